@@ -0,0 +1,17 @@
+//! Generates the gRPC service of the `grpc` feature from `proto/kenku.proto`.
+//!
+//! The proto file is compiled by protox, so building the feature does not need `protoc`.
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    #[cfg(feature = "grpc")]
+    {
+        println!("cargo:rerun-if-changed=proto/kenku.proto");
+
+        let descriptors =
+            protox::compile(["kenku.proto"], ["proto"]).expect("proto/kenku.proto should compile");
+        tonic_build::configure()
+            .compile_fds(descriptors)
+            .expect("the gRPC service should generate");
+    }
+}
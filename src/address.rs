@@ -0,0 +1,118 @@
+//! The network address of a Kenku Remote.
+use crate::KenkuError;
+use std::{
+    fmt,
+    net::{Ipv6Addr, SocketAddr, SocketAddrV4, ToSocketAddrs},
+    str::FromStr,
+    vec,
+};
+
+/// Represents the address of a Kenku Remote as a host and a port.
+///
+/// The host can be an IPv4 or IPv6 literal or any DNS name, such as `kenku-host.local`. Names are
+/// resolved by the HTTP client when a request is made, so a `KenkuAddress` never goes stale.
+///
+/// # Fields
+///
+/// * `host` - The host name or IP literal of the Kenku Remote, without brackets.
+/// * `port` - The port the Kenku Remote listens on.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KenkuAddress {
+    pub host: String,
+    pub port: u16,
+}
+
+impl KenkuAddress {
+    /// Creates a new `KenkuAddress` from a host and a port.
+    pub fn new(host: impl Into<String>, port: u16) -> KenkuAddress {
+        KenkuAddress {
+            host: host.into(),
+            port,
+        }
+    }
+
+    /// Returns the host as it must appear in a URL, with IPv6 literals wrapped in brackets.
+    pub fn url_host(&self) -> String {
+        if Ipv6Addr::from_str(&self.host).is_ok() {
+            format!("[{}]", self.host)
+        } else {
+            self.host.clone()
+        }
+    }
+}
+
+impl fmt::Display for KenkuAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.url_host(), self.port)
+    }
+}
+
+impl FromStr for KenkuAddress {
+    type Err = KenkuError;
+
+    /// Parses a `host:port` string, accepting `[ipv6]:port` for IPv6 literals.
+    fn from_str(address: &str) -> Result<Self, Self::Err> {
+        let invalid =
+            || KenkuError::InvalidInput(format!("\"{}\" is not a host:port address", address));
+        let (host, port) = address.rsplit_once(':').ok_or_else(invalid)?;
+        let host = host
+            .strip_prefix('[')
+            .and_then(|host| host.strip_suffix(']'))
+            .unwrap_or(host);
+        let port = port.parse::<u16>().map_err(|_| invalid())?;
+
+        if host.is_empty() || host.contains(char::is_whitespace) {
+            return Err(invalid());
+        }
+
+        Ok(KenkuAddress::new(host, port))
+    }
+}
+
+impl From<SocketAddrV4> for KenkuAddress {
+    fn from(address: SocketAddrV4) -> Self {
+        KenkuAddress::new(address.ip().to_string(), address.port())
+    }
+}
+
+impl From<SocketAddr> for KenkuAddress {
+    fn from(address: SocketAddr) -> Self {
+        KenkuAddress::new(address.ip().to_string(), address.port())
+    }
+}
+
+impl ToSocketAddrs for KenkuAddress {
+    type Iter = vec::IntoIter<SocketAddr>;
+
+    fn to_socket_addrs(&self) -> std::io::Result<Self::Iter> {
+        (self.host.as_str(), self.port).to_socket_addrs()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::KenkuAddress;
+    use std::str::FromStr;
+
+    #[test]
+    fn parses_host_names() {
+        let address = KenkuAddress::from_str("kenku-host.local:3333").unwrap();
+
+        assert_eq!(address, KenkuAddress::new("kenku-host.local", 3333));
+    }
+
+    #[test]
+    fn parses_bracketed_ipv6() {
+        let address = KenkuAddress::from_str("[::1]:3333").unwrap();
+
+        assert_eq!(address.host, "::1");
+        assert_eq!(address.to_string(), "[::1]:3333");
+    }
+
+    #[test]
+    fn rejects_missing_port() {
+        assert!(KenkuAddress::from_str("kenku-host.local").is_err());
+        assert!(KenkuAddress::from_str("kenku-host.local:port").is_err());
+        assert!(KenkuAddress::from_str(":3333").is_err());
+    }
+}
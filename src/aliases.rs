@@ -0,0 +1,112 @@
+//! Extra names for tracks and sounds.
+//!
+//! Titles in Kenku FM are whatever the game master typed when importing the files. An `Aliases`
+//! map gives tracks and sounds other names, such as the ones the players use in their own
+//! language, so "taverna" finds the track titled "Tavern". The map is plain data keyed by id, so
+//! it survives renames in Kenku FM and can be kept with `format::save` or in a `store::Namespace`.
+//! The `search::*_with_aliases` functions honor it.
+use crate::search::normalize;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Holds the aliases of tracks and sounds.
+///
+/// # Fields
+///
+/// * `names` - The aliases of each track or sound, by id.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct Aliases {
+    pub names: BTreeMap<String, Vec<String>>,
+}
+
+impl Aliases {
+    /// Creates an empty alias map.
+    pub fn new() -> Aliases {
+        Aliases::default()
+    }
+
+    /// Returns this map with `alias` added to the track or sound with the id `id`.
+    pub fn alias(mut self, id: impl Into<String>, alias: impl Into<String>) -> Aliases {
+        self.insert(id, alias);
+        self
+    }
+
+    /// Adds `alias` to the track or sound with the id `id`, unless it already has it.
+    pub fn insert(&mut self, id: impl Into<String>, alias: impl Into<String>) {
+        let alias = alias.into();
+        let names = self.names.entry(id.into()).or_default();
+
+        if !names.contains(&alias) {
+            names.push(alias);
+        }
+    }
+
+    /// Removes `alias` from the track or sound with the id `id`.
+    ///
+    /// # Returns
+    ///
+    /// This function returns `true` if the alias was there.
+    pub fn remove(&mut self, id: &str, alias: &str) -> bool {
+        let Some(names) = self.names.get_mut(id) else {
+            return false;
+        };
+        let count = names.len();
+        names.retain(|name| name != alias);
+        let removed = names.len() != count;
+
+        if names.is_empty() {
+            self.names.remove(id);
+        }
+
+        removed
+    }
+
+    /// Returns the aliases of the track or sound with the id `id`.
+    pub fn aliases_of(&self, id: &str) -> &[String] {
+        self.names.get(id).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Returns `title` followed by the aliases of the track or sound with the id `id`.
+    pub fn names_of<'a>(&'a self, id: &str, title: &'a str) -> Vec<&'a str> {
+        std::iter::once(title)
+            .chain(self.aliases_of(id).iter().map(String::as_str))
+            .collect()
+    }
+
+    /// Returns the ids having `alias`, compared case-insensitively and without punctuation.
+    pub fn ids_for(&self, alias: &str) -> Vec<&str> {
+        let alias = normalize(alias);
+
+        self.names
+            .iter()
+            .filter(|(_, names)| names.iter().any(|name| normalize(name) == alias))
+            .map(|(id, _)| id.as_str())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Aliases;
+
+    #[test]
+    fn aliases_are_kept_by_id() {
+        let mut aliases = Aliases::new()
+            .alias("t1", "Taverna")
+            .alias("t1", "Taverna")
+            .alias("s1", "Trovão");
+
+        assert_eq!(aliases.aliases_of("t1"), ["Taverna"]);
+        assert_eq!(aliases.names_of("t1", "Tavern"), ["Tavern", "Taverna"]);
+        assert_eq!(aliases.ids_for("trovão!"), ["s1"]);
+
+        assert!(aliases.remove("s1", "Trovão"));
+        assert!(!aliases.remove("s1", "Trovão"));
+        assert!(aliases.aliases_of("s1").is_empty());
+
+        let saved = serde_json::to_string(&aliases).unwrap();
+        assert_eq!(saved, r#"{"t1":["Taverna"]}"#);
+        assert_eq!(serde_json::from_str::<Aliases>(&saved).unwrap(), aliases);
+    }
+}
@@ -0,0 +1,167 @@
+//! Spoken callouts over the music, such as "break time" or "roll initiative".
+//!
+//! Kenku Remote can only play sounds that are already on a soundboard, so announcements are
+//! pre-rendered clips, recorded or produced by a text-to-speech tool and imported into a
+//! soundboard set aside for them. An `Announcer` maps announcement names to those sounds. It
+//! ducks the music, plays the clip, waits for it to end and puts the playback back as it was.
+use crate::{playlist::playback::fade_volume_to, Controller, KenkuError, Volume};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, time::Duration};
+
+/// The time between two volume changes while ducking.
+const DUCK_STEP: Duration = Duration::from_millis(50);
+
+/// Plays announcement clips over ducked music.
+///
+/// # Fields
+///
+/// * `clips` - The soundboard sound id of each announcement, by announcement name.
+/// * `duck_to` - The playlist volume while a clip plays. Music already quieter is left alone.
+/// * `fade` - How long the music takes to duck, and to come back.
+/// * `max_length` - How long a clip may play before the music comes back anyway.
+/// * `poll_interval` - How often the soundboard playback is checked for the end of the clip.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Announcer {
+    pub clips: BTreeMap<String, String>,
+    pub duck_to: Volume,
+    pub fade: Duration,
+    pub max_length: Duration,
+    pub poll_interval: Duration,
+}
+
+impl Default for Announcer {
+    fn default() -> Announcer {
+        Announcer {
+            clips: BTreeMap::new(),
+            duck_to: Volume::from_percent(20),
+            fade: Duration::from_millis(500),
+            max_length: Duration::from_secs(30),
+            poll_interval: Duration::from_millis(100),
+        }
+    }
+}
+
+impl Announcer {
+    /// Creates an announcer without clips, ducking the music to 20% over half a second.
+    pub fn new() -> Announcer {
+        Announcer::default()
+    }
+
+    /// Plays the soundboard sound with the id `sound` for the announcement `name`.
+    pub fn clip(mut self, name: impl Into<String>, sound: impl Into<String>) -> Announcer {
+        self.clips.insert(name.into(), sound.into());
+        self
+    }
+
+    /// Sets the playlist volume while a clip plays.
+    pub fn duck_to(mut self, volume: Volume) -> Announcer {
+        self.duck_to = volume;
+        self
+    }
+
+    /// Sets how long the music takes to duck, and to come back.
+    pub fn fade(mut self, fade: Duration) -> Announcer {
+        self.fade = fade;
+        self
+    }
+
+    /// Sets how long a clip may play before the music comes back anyway.
+    pub fn max_length(mut self, max_length: Duration) -> Announcer {
+        self.max_length = max_length;
+        self
+    }
+
+    /// Plays the clip of the announcement `name` over ducked music.
+    ///
+    /// # Returns
+    ///
+    /// This function returns `Ok(())` once the playback is back as it was, a `KenkuError::NotFound` if no clip is set for `name`, or the first `KenkuError` raised on the way.
+    pub async fn announce(&self, controller: &Controller, name: &str) -> Result<(), KenkuError> {
+        let sound = self
+            .clips
+            .get(name)
+            .ok_or_else(|| KenkuError::NotFound(format!("announcement {:?}", name)))?;
+
+        self.play_clip(controller, sound).await
+    }
+
+    /// Plays the soundboard sound with the id `sound` over ducked music.
+    ///
+    /// The playback is snapshotted first. Once the clip ended, or after `max_length`, the music
+    /// fades back and the snapshot is restored, which also stops the clip if it still plays. The
+    /// playback is restored even when ducking or playing the clip failed.
+    ///
+    /// # Returns
+    ///
+    /// This function returns `Ok(())` once the playback is back as it was, or the first `KenkuError` raised on the way.
+    pub async fn play_clip(&self, controller: &Controller, sound: &str) -> Result<(), KenkuError> {
+        let before = controller.snapshot().await?;
+        let volume = before.playlist.volume_level();
+        let steps = self.steps();
+
+        let played = async {
+            if volume > self.duck_to {
+                fade_volume_to(controller, self.duck_to, self.fade, steps).await?;
+            }
+
+            controller.play_sound_id(sound).await?;
+            self.wait_for_end(controller, sound).await
+        }
+        .await;
+
+        let restored = async {
+            if volume > self.duck_to {
+                fade_volume_to(controller, volume, self.fade, steps).await?;
+            }
+
+            controller.restore(&before).await.map(|_| ())
+        }
+        .await;
+
+        played.and(restored)
+    }
+
+    /// Waits until `sound` stopped playing, or for `max_length`.
+    async fn wait_for_end(&self, controller: &Controller, sound: &str) -> Result<(), KenkuError> {
+        let ended = async {
+            loop {
+                tokio::time::sleep(self.poll_interval).await;
+
+                if !controller
+                    .get_soundboard_playback()
+                    .await?
+                    .is_playing(sound)
+                {
+                    return Ok(());
+                }
+            }
+        };
+
+        tokio::time::timeout(self.max_length, ended)
+            .await
+            .unwrap_or(Ok(()))
+    }
+
+    fn steps(&self) -> u32 {
+        (self.fade.as_millis() / DUCK_STEP.as_millis()).max(1) as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Announcer;
+    use crate::Volume;
+    use std::time::Duration;
+
+    #[test]
+    fn announcers_load_with_defaults() {
+        let announcer: Announcer =
+            serde_json::from_str(r#"{"clips": {"initiative": "s1"}}"#).unwrap();
+
+        assert_eq!(announcer, Announcer::new().clip("initiative", "s1"));
+        assert_eq!(announcer.duck_to, Volume::from_percent(20));
+        assert_eq!(announcer.steps(), 10);
+        assert_eq!(announcer.fade(Duration::ZERO).steps(), 1);
+    }
+}
@@ -0,0 +1,190 @@
+//! The `KenkuApi` trait: the calls everything else is built on.
+//!
+//! Reading the four listings and sending a `KenkuCommandWithPayload` is all it takes to drive
+//! Kenku FM. `Controller` implements `KenkuApi` over HTTP. Code written against the trait instead
+//! of `Controller` can be unit tested with an in-memory fake, and can run over other transports.
+//!
+//! ```
+//! use kenku_control::{KenkuApi, KenkuError, KenkuPlaybackCommand};
+//!
+//! /// Pauses the music when something is playing.
+//! async fn hush(api: &impl KenkuApi) -> Result<(), KenkuError> {
+//!     if api.get_playlist_playback().await?.playing {
+//!         api.execute(&KenkuPlaybackCommand::PlaylistPlaybackPause.into())
+//!             .await?;
+//!     }
+//!
+//!     Ok(())
+//! }
+//! ```
+#[cfg(feature = "snapshot")]
+use crate::snapshot::PlaybackSnapshot;
+use crate::{
+    playlist::{PlaylistGetResponse, PlaylistPlaybackResponse},
+    soundboard::{SoundboardGetResponse, SoundboardPlaybackResponse},
+    Controller, KenkuCommandWithPayload, KenkuError,
+};
+use reqwest::StatusCode;
+use std::future::Future;
+
+/// Represents something that answers like a Kenku Remote.
+///
+/// The futures are `Send`, so implementations can be used from spawned tasks. The browser's
+/// `fetch` futures are not, so the trait is not available on wasm32.
+pub trait KenkuApi: Send + Sync {
+    /// Returns the playlists and tracks.
+    fn get_playlist(&self) -> impl Future<Output = Result<PlaylistGetResponse, KenkuError>> + Send;
+
+    /// Returns the playlist playback.
+    fn get_playlist_playback(
+        &self,
+    ) -> impl Future<Output = Result<PlaylistPlaybackResponse, KenkuError>> + Send;
+
+    /// Returns the soundboards and sounds.
+    fn get_soundboard(
+        &self,
+    ) -> impl Future<Output = Result<SoundboardGetResponse, KenkuError>> + Send;
+
+    /// Returns the sounds playing.
+    fn get_soundboard_playback(
+        &self,
+    ) -> impl Future<Output = Result<SoundboardPlaybackResponse, KenkuError>> + Send;
+
+    /// Sends `command`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is either the `StatusCode` of the response or a `KenkuError`.
+    fn execute(
+        &self,
+        command: &KenkuCommandWithPayload,
+    ) -> impl Future<Output = Result<StatusCode, KenkuError>> + Send;
+
+    /// Returns the playlist and soundboard playback, fetched at the same time.
+    #[cfg(feature = "snapshot")]
+    fn snapshot(&self) -> impl Future<Output = Result<PlaybackSnapshot, KenkuError>> + Send {
+        async move {
+            let (playlist, soundboard) =
+                tokio::try_join!(self.get_playlist_playback(), self.get_soundboard_playback())?;
+
+            Ok(PlaybackSnapshot {
+                playlist,
+                soundboard,
+            })
+        }
+    }
+}
+
+impl KenkuApi for Controller {
+    fn get_playlist(&self) -> impl Future<Output = Result<PlaylistGetResponse, KenkuError>> + Send {
+        Controller::get_playlist(self)
+    }
+
+    fn get_playlist_playback(
+        &self,
+    ) -> impl Future<Output = Result<PlaylistPlaybackResponse, KenkuError>> + Send {
+        Controller::get_playlist_playback(self)
+    }
+
+    fn get_soundboard(
+        &self,
+    ) -> impl Future<Output = Result<SoundboardGetResponse, KenkuError>> + Send {
+        Controller::get_soundboard(self)
+    }
+
+    fn get_soundboard_playback(
+        &self,
+    ) -> impl Future<Output = Result<SoundboardPlaybackResponse, KenkuError>> + Send {
+        Controller::get_soundboard_playback(self)
+    }
+
+    fn execute(
+        &self,
+        command: &KenkuCommandWithPayload,
+    ) -> impl Future<Output = Result<StatusCode, KenkuError>> + Send {
+        Controller::execute(self, command)
+    }
+
+    #[cfg(feature = "snapshot")]
+    fn snapshot(&self) -> impl Future<Output = Result<PlaybackSnapshot, KenkuError>> + Send {
+        Controller::snapshot(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::KenkuApi;
+    use crate::{
+        playlist::{PlaylistGetResponse, PlaylistPlaybackResponse},
+        soundboard::{SoundboardGetResponse, SoundboardPlaybackResponse},
+        KenkuCommandWithPayload, KenkuError, KenkuPlaybackCommand,
+    };
+    use reqwest::StatusCode;
+    use serde_json::json;
+    use std::sync::Mutex;
+
+    /// A remote that is always playing and records the commands it receives.
+    #[derive(Default)]
+    struct Fake {
+        sent: Mutex<Vec<KenkuCommandWithPayload>>,
+    }
+
+    impl KenkuApi for Fake {
+        async fn get_playlist(&self) -> Result<PlaylistGetResponse, KenkuError> {
+            Ok(serde_json::from_value(json!({"playlists": [], "tracks": []})).unwrap())
+        }
+
+        async fn get_playlist_playback(&self) -> Result<PlaylistPlaybackResponse, KenkuError> {
+            Ok(serde_json::from_value(json!({
+                "playing": true, "volume": 1.0, "muted": false, "shuffle": false, "repeat": "off"
+            }))
+            .unwrap())
+        }
+
+        async fn get_soundboard(&self) -> Result<SoundboardGetResponse, KenkuError> {
+            Ok(serde_json::from_value(json!({"soundboards": [], "sounds": []})).unwrap())
+        }
+
+        async fn get_soundboard_playback(&self) -> Result<SoundboardPlaybackResponse, KenkuError> {
+            Ok(serde_json::from_value(json!({"sounds": []})).unwrap())
+        }
+
+        async fn execute(
+            &self,
+            command: &KenkuCommandWithPayload,
+        ) -> Result<StatusCode, KenkuError> {
+            self.sent.lock().unwrap().push(command.clone());
+            Ok(StatusCode::OK)
+        }
+    }
+
+    async fn hush(api: &impl KenkuApi) -> Result<(), KenkuError> {
+        if api.get_playlist_playback().await?.playing {
+            api.execute(&KenkuPlaybackCommand::PlaylistPlaybackPause.into())
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn code_written_against_the_trait_runs_on_a_fake() {
+        let fake = Fake::default();
+
+        hush(&fake).await.unwrap();
+
+        assert_eq!(
+            *fake.sent.lock().unwrap(),
+            [KenkuPlaybackCommand::PlaylistPlaybackPause.into()]
+        );
+    }
+
+    #[cfg(feature = "snapshot")]
+    #[tokio::test]
+    async fn snapshots_join_both_playbacks() {
+        let snapshot = Fake::default().snapshot().await.unwrap();
+
+        assert!(snapshot.playlist.playing);
+        assert!(snapshot.soundboard.sounds.is_empty());
+    }
+}
@@ -0,0 +1,492 @@
+//! Deciding who may use the gateways: `rest::RestApi` and `websocket::WebSocketServer`.
+//!
+//! An `Authenticator` looks at the headers of a request and returns the `Identity` that made it,
+//! or `None` to turn it away. Three backends are provided, so a community can gate control of
+//! the table by a membership it already has:
+//!
+//! * `BearerTokens` - A fixed set of `Authorization: Bearer` tokens, each with a name.
+//! * `DiscordGuild` - Discord OAuth2 access tokens of the members of one Discord server.
+//! * `ProxyHeader` - The user a reverse proxy, such as oauth2-proxy or Authelia, logged in and
+//!   named in a header.
+//!
+//! Other schemes implement `Authenticator` themselves. Only the identities with the `admin` flag
+//! may send commands, restore snapshots and activate scenes; the others may read the playback and
+//! follow its events.
+use futures_util::future::BoxFuture;
+use http::{header::AUTHORIZATION, HeaderMap};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt,
+    net::IpAddr,
+    sync::{Arc, Mutex, RwLock},
+    time::{Duration, Instant},
+};
+
+/// How long `DiscordGuild` remembers a member by their token.
+const DISCORD_CACHE_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// How many tokens `DiscordGuild` asks Discord about for one source each minute, by default.
+const DISCORD_LOOKUPS_PER_MINUTE: u32 = 10;
+
+/// Represents who made a request.
+///
+/// # Fields
+///
+/// * `name` - The name of the client, such as the name of its token or its Discord user id.
+/// * `admin` - Whether the client may send commands, rather than only read.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Identity {
+    pub name: String,
+    pub admin: bool,
+}
+
+/// Turns the headers of a request into the `Identity` that made it.
+pub trait Authenticator: Send + Sync {
+    /// Returns who made a request with `headers` from `peer`, or `None` to turn it away.
+    ///
+    /// `peer` is the address the request came from, when the gateway knows it.
+    fn authenticate<'a>(
+        &'a self,
+        headers: &'a HeaderMap,
+        peer: Option<IpAddr>,
+    ) -> BoxFuture<'a, Option<Identity>>;
+
+    /// Stops accepting the credentials of the client called `name`, for the backends that can.
+    ///
+    /// Requests already let in, such as open WebSocket connections, are not affected; ban the
+    /// client with `clients::ClientTracker::ban` to turn those away too.
+    ///
+    /// # Returns
+    ///
+    /// This function returns `true` if credentials were revoked, and `false` if the backend knows
+    /// no credentials of `name` or cannot revoke them, which is the default.
+    fn revoke(&self, name: &str) -> bool {
+        let _ = name;
+        false
+    }
+}
+
+/// Accepts a set of bearer tokens, which can be revoked while the gateways run.
+///
+/// Clones share their tokens, so a clone kept by the application revokes the tokens of the set
+/// given to a gateway.
+///
+/// # Fields
+///
+/// * `tokens` - Each accepted token with the identity it stands for.
+#[derive(Clone, Default)]
+pub struct BearerTokens {
+    tokens: Arc<RwLock<Vec<(String, Identity)>>>,
+}
+
+/// Accepts the members of one Discord server, by the OAuth2 access token of their account.
+///
+/// The token must carry the `guilds.members.read` scope. Discord is asked whether its owner is a
+/// member of the server, and members are remembered for five minutes. The identity is named
+/// after the Discord user id. When Discord cannot be reached, requests are turned away.
+///
+/// Tokens that are not remembered are only checked with Discord ten times a minute for each
+/// source address, so a client guessing tokens cannot get the bot rate-limited by Discord. The
+/// requests beyond that are turned away. Behind a reverse proxy, every request shares its address.
+///
+/// # Fields
+///
+/// * `guild` - The id of the Discord server.
+/// * `admins` - The Discord user ids allowed to send commands.
+/// * `api` - The base URL of the Discord API.
+/// * `client` - The HTTP client Discord is asked with.
+/// * `lookups_per_minute` - How many tokens are checked with Discord for one source each minute.
+/// * `members` - The identity of each token Discord accepted, with when it was asked.
+/// * `lookups` - The tokens checked for each source in the current minute, with when it started.
+#[derive(Debug)]
+pub struct DiscordGuild {
+    guild: String,
+    admins: BTreeSet<String>,
+    api: String,
+    client: reqwest::Client,
+    lookups_per_minute: u32,
+    members: Mutex<HashMap<String, (Identity, Instant)>>,
+    lookups: Mutex<HashMap<Option<IpAddr>, (u32, Instant)>>,
+}
+
+/// Accepts the user a reverse proxy names in a header.
+///
+/// Only use it behind a proxy that logs users in and overwrites the header on every request:
+/// anyone reaching the gateway directly can set the header to any name.
+///
+/// # Fields
+///
+/// * `header` - The header holding the user name, such as `x-forwarded-user`.
+/// * `users` - The users accepted, or `None` to accept every user the proxy lets through.
+/// * `admins` - The users allowed to send commands.
+#[derive(Debug, Clone)]
+pub struct ProxyHeader {
+    header: String,
+    users: Option<BTreeSet<String>>,
+    admins: BTreeSet<String>,
+}
+
+impl BearerTokens {
+    /// Creates a set without any token, which turns every request away.
+    pub fn new() -> BearerTokens {
+        BearerTokens::default()
+    }
+
+    /// Accepts `token` for the client called `name`, which may only read.
+    pub fn token(self, token: impl Into<String>, name: impl Into<String>) -> BearerTokens {
+        self.tokens.write().unwrap().push((
+            token.into(),
+            Identity {
+                name: name.into(),
+                admin: false,
+            },
+        ));
+        self
+    }
+
+    /// Accepts `token` for the client called `name`, which may also send commands.
+    pub fn admin(self, token: impl Into<String>, name: impl Into<String>) -> BearerTokens {
+        self.tokens.write().unwrap().push((
+            token.into(),
+            Identity {
+                name: name.into(),
+                admin: true,
+            },
+        ));
+        self
+    }
+}
+
+/// Lists the names of the tokens, never the tokens themselves.
+impl fmt::Debug for BearerTokens {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(
+                self.tokens
+                    .read()
+                    .unwrap()
+                    .iter()
+                    .map(|(_, identity)| &identity.name),
+            )
+            .finish()
+    }
+}
+
+impl Authenticator for BearerTokens {
+    fn authenticate<'a>(
+        &'a self,
+        headers: &'a HeaderMap,
+        _peer: Option<IpAddr>,
+    ) -> BoxFuture<'a, Option<Identity>> {
+        let found = bearer(headers).and_then(|given| {
+            // Every token is compared, so the time taken does not tell which one was close.
+            let tokens = self.tokens.read().unwrap();
+
+            tokens.iter().fold(None, |found, (token, identity)| {
+                if same(given.as_bytes(), token.as_bytes()) {
+                    Some(identity.clone())
+                } else {
+                    found
+                }
+            })
+        });
+
+        Box::pin(std::future::ready(found))
+    }
+
+    /// Removes every token of the client called `name`.
+    fn revoke(&self, name: &str) -> bool {
+        let mut tokens = self.tokens.write().unwrap();
+        let before = tokens.len();
+        tokens.retain(|(_, identity)| identity.name != name);
+
+        tokens.len() != before
+    }
+}
+
+impl DiscordGuild {
+    /// Accepts the members of the Discord server with the id `guild`.
+    pub fn new(guild: impl Into<String>) -> DiscordGuild {
+        DiscordGuild {
+            guild: guild.into(),
+            admins: BTreeSet::new(),
+            api: "https://discord.com/api/v10".to_string(),
+            client: reqwest::Client::new(),
+            lookups_per_minute: DISCORD_LOOKUPS_PER_MINUTE,
+            members: Mutex::new(HashMap::new()),
+            lookups: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Allows the Discord users with the ids `admins` to send commands.
+    pub fn admins<I, S>(mut self, admins: I) -> DiscordGuild
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.admins.extend(admins.into_iter().map(Into::into));
+        self
+    }
+
+    /// Sends the membership checks to `api` instead of `https://discord.com/api/v10`.
+    pub fn api_base(mut self, api: impl Into<String>) -> DiscordGuild {
+        self.api = api.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Checks at most `lookups` unknown tokens with Discord for one source each minute, instead of ten.
+    pub fn lookups_per_minute(mut self, lookups: u32) -> DiscordGuild {
+        self.lookups_per_minute = lookups;
+        self
+    }
+
+    /// Counts a check with Discord for `peer`, unless it already used up those of this minute.
+    fn take_lookup(&self, peer: Option<IpAddr>) -> bool {
+        let mut lookups = self.lookups.lock().unwrap();
+        lookups.retain(|_, (_, started)| started.elapsed() < Duration::from_secs(60));
+        let (count, _) = lookups.entry(peer).or_insert((0, Instant::now()));
+
+        if *count >= self.lookups_per_minute {
+            return false;
+        }
+
+        *count += 1;
+        true
+    }
+
+    /// Asks Discord whether the owner of `token` is a member of the server.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the identity of the member, or `None` if they are not one or Discord
+    /// gave no answer.
+    async fn ask(&self, token: &str) -> Option<Identity> {
+        let url = format!("{}/users/@me/guilds/{}/member", self.api, self.guild);
+        let response = self.client.get(url).bearer_auth(token).send().await.ok()?;
+
+        if !response.status().is_success() {
+            return None;
+        }
+
+        let member: serde_json::Value = response.json().await.ok()?;
+        let id = member["user"]["id"].as_str()?.to_string();
+
+        Some(Identity {
+            admin: self.admins.contains(&id),
+            name: id,
+        })
+    }
+}
+
+impl Authenticator for DiscordGuild {
+    fn authenticate<'a>(
+        &'a self,
+        headers: &'a HeaderMap,
+        peer: Option<IpAddr>,
+    ) -> BoxFuture<'a, Option<Identity>> {
+        Box::pin(async move {
+            let token = bearer(headers)?;
+
+            if let Some((member, asked)) = self.members.lock().unwrap().get(token) {
+                if asked.elapsed() < DISCORD_CACHE_TTL {
+                    return Some(member.clone());
+                }
+            }
+
+            if !self.take_lookup(peer) {
+                return None;
+            }
+
+            // Only members are remembered, so guessed tokens do not pile up.
+            let member = self.ask(token).await?;
+            let mut members = self.members.lock().unwrap();
+            members.retain(|_, (_, asked)| asked.elapsed() < DISCORD_CACHE_TTL);
+            members.insert(token.to_string(), (member.clone(), Instant::now()));
+
+            Some(member)
+        })
+    }
+}
+
+impl ProxyHeader {
+    /// Accepts every user named in the header `header`, such as `"x-forwarded-user"`.
+    pub fn new(header: impl Into<String>) -> ProxyHeader {
+        ProxyHeader {
+            header: header.into().to_ascii_lowercase(),
+            users: None,
+            admins: BTreeSet::new(),
+        }
+    }
+
+    /// Only accepts the users in `users`, and the admins.
+    pub fn users<I, S>(mut self, users: I) -> ProxyHeader
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.users
+            .get_or_insert_with(BTreeSet::new)
+            .extend(users.into_iter().map(Into::into));
+        self
+    }
+
+    /// Allows the users in `admins` to send commands.
+    pub fn admins<I, S>(mut self, admins: I) -> ProxyHeader
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.admins.extend(admins.into_iter().map(Into::into));
+        self
+    }
+}
+
+impl Authenticator for ProxyHeader {
+    fn authenticate<'a>(
+        &'a self,
+        headers: &'a HeaderMap,
+        _peer: Option<IpAddr>,
+    ) -> BoxFuture<'a, Option<Identity>> {
+        let found = headers
+            .get(self.header.as_str())
+            .and_then(|value| value.to_str().ok())
+            .map(str::trim)
+            .filter(|user| !user.is_empty())
+            .filter(|user| {
+                self.admins.contains(*user)
+                    || self
+                        .users
+                        .as_ref()
+                        .is_none_or(|users| users.contains(*user))
+            })
+            .map(|user| Identity {
+                name: user.to_string(),
+                admin: self.admins.contains(user),
+            });
+
+        Box::pin(std::future::ready(found))
+    }
+}
+
+/// Returns the token of the `Authorization: Bearer` header, if any.
+pub(crate) fn bearer(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+}
+
+/// Compares two secrets in a time that does not depend on where they differ.
+fn same(given: &[u8], expected: &[u8]) -> bool {
+    given.len() == expected.len()
+        && given
+            .iter()
+            .zip(expected)
+            .fold(0, |difference, (a, b)| difference | (a ^ b))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{same, Authenticator, BearerTokens, Identity, ProxyHeader};
+    use http::{HeaderMap, HeaderValue};
+
+    fn headers(name: &'static str, value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(name, HeaderValue::from_static(value));
+        headers
+    }
+
+    #[test]
+    fn tokens_are_compared_whole() {
+        assert!(same(b"secret", b"secret"));
+        assert!(!same(b"secret", b"secreT"));
+        assert!(!same(b"secre", b"secret"));
+        assert!(!same(b"", b"secret"));
+    }
+
+    #[tokio::test]
+    async fn bearer_tokens_name_their_clients() {
+        let tokens = BearerTokens::new()
+            .token("player-token", "players")
+            .admin("gm-token", "gm");
+
+        assert_eq!(
+            tokens
+                .authenticate(&headers("authorization", "Bearer gm-token"), None)
+                .await,
+            Some(Identity {
+                name: "gm".to_string(),
+                admin: true
+            })
+        );
+        assert!(
+            !tokens
+                .authenticate(&headers("authorization", "Bearer player-token"), None)
+                .await
+                .unwrap()
+                .admin
+        );
+        assert_eq!(
+            tokens
+                .authenticate(&headers("authorization", "Bearer nope"), None)
+                .await,
+            None
+        );
+        assert_eq!(tokens.authenticate(&HeaderMap::new(), None).await, None);
+        assert_eq!(format!("{:?}", tokens), r#"["players", "gm"]"#);
+    }
+
+    #[tokio::test]
+    async fn revoked_tokens_are_turned_away_by_every_clone() {
+        let tokens = BearerTokens::new()
+            .token("player-token", "players")
+            .admin("gm-token", "gm");
+        let gateway = tokens.clone();
+
+        assert!(tokens.revoke("players"));
+        assert!(!tokens.revoke("players"));
+        assert_eq!(
+            gateway
+                .authenticate(&headers("authorization", "Bearer player-token"), None)
+                .await,
+            None
+        );
+        assert!(gateway
+            .authenticate(&headers("authorization", "Bearer gm-token"), None)
+            .await
+            .is_some());
+        assert!(!ProxyHeader::new("X-Forwarded-User").revoke("ana"));
+    }
+
+    #[tokio::test]
+    async fn proxy_headers_are_checked_against_the_allowed_users() {
+        let proxy = ProxyHeader::new("X-Forwarded-User")
+            .users(["ana"])
+            .admins(["bea"]);
+
+        assert_eq!(
+            proxy
+                .authenticate(&headers("x-forwarded-user", "ana"), None)
+                .await
+                .map(|identity| identity.admin),
+            Some(false)
+        );
+        assert_eq!(
+            proxy
+                .authenticate(&headers("x-forwarded-user", "bea"), None)
+                .await
+                .map(|identity| identity.admin),
+            Some(true)
+        );
+        assert_eq!(
+            proxy
+                .authenticate(&headers("x-forwarded-user", "eve"), None)
+                .await,
+            None
+        );
+        assert_eq!(proxy.authenticate(&HeaderMap::new(), None).await, None);
+    }
+}
@@ -0,0 +1,43 @@
+//! A switch that suspends automated actions while manual control keeps working.
+//!
+//! Automated components, such as schedulers, rules or background DJs, check
+//! `Controller::automation_paused` before acting on their own. Requests made directly through
+//! the controller are never affected, so the game master can always take over by hand.
+use crate::Controller;
+use std::sync::atomic::Ordering;
+
+impl Controller {
+    /// Suspends automated actions on this controller and all of its clones.
+    pub fn pause_automation(&self) {
+        self.automation_paused.store(true, Ordering::SeqCst);
+    }
+
+    /// Lets automated actions run again on this controller and all of its clones.
+    pub fn resume_automation(&self) {
+        self.automation_paused.store(false, Ordering::SeqCst);
+    }
+
+    /// Returns `true` while automated actions are suspended.
+    pub fn automation_paused(&self) -> bool {
+        self.automation_paused.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Controller;
+
+    #[test]
+    fn switch_is_shared_by_clones() {
+        let controller = Controller::new("127.0.0.1", 3333);
+        let clone = controller.clone();
+
+        assert!(!clone.automation_paused());
+
+        controller.pause_automation();
+        assert!(clone.automation_paused());
+
+        clone.resume_automation();
+        assert!(!controller.automation_paused());
+    }
+}
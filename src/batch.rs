@@ -0,0 +1,57 @@
+//! Sending a whole set of commands with one call.
+use crate::{Controller, KenkuCommandWithPayload, KenkuError};
+use reqwest::StatusCode;
+
+/// Represents the outcome of every command of a batch.
+///
+/// # Fields
+///
+/// * `results` - Each command of the batch with its result, in the order they were sent.
+#[derive(Debug)]
+pub struct BatchReport {
+    pub results: Vec<(KenkuCommandWithPayload, Result<StatusCode, KenkuError>)>,
+}
+
+impl BatchReport {
+    /// Returns `true` when every command of the batch succeeded.
+    pub fn is_success(&self) -> bool {
+        self.results.iter().all(|(_, result)| result.is_ok())
+    }
+
+    /// Returns how many commands succeeded.
+    pub fn succeeded(&self) -> usize {
+        self.results
+            .iter()
+            .filter(|(_, result)| result.is_ok())
+            .count()
+    }
+
+    /// Returns the commands that failed, with their errors.
+    pub fn failures(&self) -> Vec<(&KenkuCommandWithPayload, &KenkuError)> {
+        self.results
+            .iter()
+            .filter_map(|(command, result)| result.as_ref().err().map(|error| (command, error)))
+            .collect()
+    }
+}
+
+impl Controller {
+    /// Sends every command of `commands`, one after the other, in order.
+    ///
+    /// A failed command does not stop the batch: the remaining commands are still sent and every
+    /// result ends up in the report.
+    ///
+    /// # Returns
+    ///
+    /// A `BatchReport` holding the result of each command.
+    pub async fn execute_batch(&self, commands: Vec<KenkuCommandWithPayload>) -> BatchReport {
+        let mut results = Vec::with_capacity(commands.len());
+
+        for command in commands {
+            let result = self.execute(&command).await;
+            results.push((command, result));
+        }
+
+        BatchReport { results }
+    }
+}
@@ -0,0 +1,447 @@
+//! `kenku-ctl`, controlling Kenku FM from the shell.
+//!
+//! Every invocation sends one command and exits, so it fits shell scripts and the "run command"
+//! buttons of a Stream Deck. The connection is configured like any application built on this
+//! crate: `kenku.toml` in the working directory, or the file named by `--config` or
+//! `KENKU_CONFIG`, then `KENKU_*` variables, then flags such as `--host` or `--port`. Scenes and
+//! bindings of the configuration file can be run by name.
+//!
+//! The `admin` commands manage a running `rest::RestApi` gateway instead, named by `--gateway` or
+//! `KENKU_GATEWAY`, with the moderator token read from `KENKU_GATEWAY_TOKEN` so that it does not
+//! show in the process list.
+use kenku_control::{
+    clients::ClientSummary,
+    config::{ConfigLoader, EffectiveConfig},
+    gateway::client::GatewayClient,
+    search::{find_sound, find_track},
+    Controller, KenkuError, KenkuPlaybackCommand, Volume,
+};
+use std::{process::ExitCode, time::Duration};
+
+const USAGE: &str = "\
+usage: kenku-ctl [--config FILE] [--host HOST] [--port PORT] [--timeout-ms MS] [--retries N] COMMAND
+       kenku-ctl [--gateway URL] admin COMMAND
+
+commands:
+  play [NAME]       resume the playback, or play the track or playlist matching NAME
+  pause             pause the playback
+  next              skip to the next track
+  previous          go back to the previous track
+  volume PERCENT    set the playlist volume, from 0 to 100
+  mute              mute the playlist playback
+  unmute            unmute the playlist playback
+  sound NAME        play the soundboard sound matching NAME
+  stop NAME         stop the soundboard sound matching NAME
+  playlists         list the playlists
+  tracks            list the tracks
+  sounds            list the soundboard sounds
+  status            show what is playing
+  dashboard         open the live dashboard, when built with the `tui` feature
+  soak MINUTES [mock]
+                    watch and send harmless commands for MINUTES, flagging leaks and failures,
+                    when built with the `soak` feature; `mock` needs the `testing` feature
+  scene NAME        activate a scene of the configuration file
+  trigger NAME      run a binding of the configuration file
+  config            show the configuration and where each value came from
+
+admin commands, sent to the gateway with the token in KENKU_GATEWAY_TOKEN:
+  clients           list the clients of the gateway with their counts
+  insights          summarize the counts: totals, busiest and most refused clients
+  ban NAME SECONDS  turn away every request of a client for SECONDS
+  unban NAME        lift the ban of a client
+  automation        show whether automation is paused
+  pause-automation  pause the automated actions of the gateway
+  resume-automation resume the automated actions of the gateway
+  revoke-token NAME stop accepting the tokens of a client";
+
+/// The flags read by `ConfigLoader::args`, plus `--config` and `--gateway`, all taking a value.
+const FLAGS: &[&str] = &[
+    "config",
+    "gateway",
+    "host",
+    "port",
+    "timeout-ms",
+    "connect-timeout-ms",
+    "retries",
+];
+
+/// Represents why a command failed.
+///
+/// # Variants
+///
+/// * `Usage` - The command line is wrong. The usage is printed and the exit code is 2.
+/// * `Kenku` - The command could not be carried out. The exit code is 1.
+/// * `Anomalies` - A soak run flagged this many anomalies. The exit code is 1.
+enum Failure {
+    Usage(String),
+    Kenku(KenkuError),
+    #[cfg(feature = "soak")]
+    Anomalies(usize),
+}
+
+impl From<KenkuError> for Failure {
+    fn from(error: KenkuError) -> Failure {
+        Failure::Kenku(error)
+    }
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    if args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+
+    let (flags, command) = match split(&args) {
+        Ok(split) => split,
+        Err(error) => return usage(&error),
+    };
+
+    let Some((name, rest)) = command.split_first() else {
+        return usage("missing command");
+    };
+
+    let result = async {
+        if name == "admin" {
+            return admin(&gateway(&flags)?, rest).await;
+        }
+
+        let config = load_config(&flags)?;
+        let controller = config.config.builder().build()?;
+
+        run(&controller, &config, name, rest).await
+    }
+    .await;
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(Failure::Usage(reason)) => usage(&reason),
+        Err(Failure::Kenku(error)) => {
+            eprintln!("kenku-ctl: {}", error);
+            ExitCode::FAILURE
+        }
+        #[cfg(feature = "soak")]
+        Err(Failure::Anomalies(count)) => {
+            eprintln!("kenku-ctl: the soak run flagged {} anomalies", count);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Splits the arguments into configuration flags and the command with its arguments.
+fn split(args: &[String]) -> Result<(Vec<String>, Vec<String>), String> {
+    let mut flags = Vec::new();
+    let mut command = Vec::new();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let Some(flag) = arg.strip_prefix("--").filter(|_| command.is_empty()) else {
+            command.push(arg.clone());
+            continue;
+        };
+        let name = flag.split_once('=').map_or(flag, |(name, _)| name);
+
+        if !FLAGS.contains(&name) {
+            return Err(format!("unknown flag {}", arg));
+        }
+
+        flags.push(arg.clone());
+
+        if !flag.contains('=') {
+            let value = args.next().ok_or(format!("{} needs a value", arg))?;
+            flags.push(value.clone());
+        }
+    }
+
+    Ok((flags, command))
+}
+
+/// Returns the value of the flag `--name`, given as `--name VALUE` or `--name=VALUE`.
+fn flag(flags: &[String], name: &str) -> Option<String> {
+    let long = format!("--{}", name);
+
+    flags
+        .iter()
+        .position(|flag| *flag == long)
+        .map(|index| flags[index + 1].clone())
+        .or_else(|| {
+            flags.iter().find_map(|flag| {
+                flag.strip_prefix(&long)
+                    .and_then(|value| value.strip_prefix('='))
+                    .map(str::to_string)
+            })
+        })
+}
+
+/// Returns the client of the gateway named by `--gateway` or `KENKU_GATEWAY`, sending the token
+/// in `KENKU_GATEWAY_TOKEN` if it is set.
+fn gateway(flags: &[String]) -> Result<GatewayClient, Failure> {
+    let base = flag(flags, "gateway")
+        .or_else(|| std::env::var("KENKU_GATEWAY").ok())
+        .ok_or_else(|| Failure::Usage("admin needs --gateway or KENKU_GATEWAY".to_string()))?;
+    let gateway = GatewayClient::new(base);
+
+    Ok(match std::env::var("KENKU_GATEWAY_TOKEN") {
+        Ok(token) => gateway.token(token),
+        Err(_) => gateway,
+    })
+}
+
+/// Loads the configuration file, the environment and the flags, in that order.
+fn load_config(flags: &[String]) -> Result<EffectiveConfig, KenkuError> {
+    let path = flag(flags, "config")
+        .or_else(|| std::env::var("KENKU_CONFIG").ok())
+        .unwrap_or_else(|| "kenku.toml".to_string());
+
+    ConfigLoader::new().file(path)?.env()?.args(flags)?.load()
+}
+
+/// Runs the command `name` with its arguments `rest`.
+async fn run(
+    controller: &Controller,
+    config: &EffectiveConfig,
+    name: &str,
+    rest: &[String],
+) -> Result<(), Failure> {
+    let argument = || {
+        (!rest.is_empty())
+            .then(|| rest.join(" "))
+            .ok_or_else(|| Failure::Usage(format!("{} needs an argument", name)))
+    };
+
+    match name {
+        "play" if rest.is_empty() => {
+            controller
+                .execute(&KenkuPlaybackCommand::PlaylistPlaybackPlay.into())
+                .await?;
+        }
+        "play" => {
+            let query = argument()?;
+            let library = controller.get_playlist().await?;
+
+            if let Some(playlist) = library.playlist_by_title(&query) {
+                controller.play_playlist(&playlist.id).await?;
+            } else {
+                let found = find_track(&library, &query);
+                let track = found
+                    .first()
+                    .ok_or_else(|| KenkuError::NotFound(format!("track {:?}", query)))?;
+                track.item.play(controller).await?;
+            }
+        }
+        "pause" => playback(controller, KenkuPlaybackCommand::PlaylistPlaybackPause).await?,
+        "next" => playback(controller, KenkuPlaybackCommand::PlaylistPlaybackNext).await?,
+        "previous" => playback(controller, KenkuPlaybackCommand::PlaylistPlaybackPrevious).await?,
+        "mute" => playback(controller, KenkuPlaybackCommand::PlaylistPlaybackMute(true)).await?,
+        "unmute" => {
+            playback(
+                controller,
+                KenkuPlaybackCommand::PlaylistPlaybackMute(false),
+            )
+            .await?
+        }
+        "volume" => {
+            let value = argument()?;
+            let percent = value
+                .trim_end_matches('%')
+                .parse::<u8>()
+                .ok()
+                .filter(|percent| *percent <= 100)
+                .ok_or_else(|| {
+                    Failure::Usage(format!("volume must be from 0 to 100, not {:?}", value))
+                })?;
+
+            playback(
+                controller,
+                KenkuPlaybackCommand::PlaylistPlaybackVolume(Volume::from_percent(percent)),
+            )
+            .await?;
+        }
+        "sound" | "stop" => {
+            let query = argument()?;
+            let soundboard = controller.get_soundboard().await?;
+            let found = find_sound(&soundboard, &query);
+            let sound = found
+                .first()
+                .ok_or_else(|| KenkuError::NotFound(format!("sound {:?}", query)))?;
+
+            if name == "sound" {
+                sound.item.play(controller).await?;
+            } else {
+                sound.item.stop(controller).await?;
+            }
+        }
+        "playlists" => {
+            for playlist in controller.get_playlist().await?.playlists {
+                println!("{}\t{}", playlist.id, playlist.title);
+            }
+        }
+        "tracks" => {
+            for track in controller.get_playlist().await?.tracks {
+                println!("{}\t{}", track.id, track.title);
+            }
+        }
+        "sounds" => {
+            for sound in controller.get_soundboard().await?.sounds {
+                println!("{}\t{}", sound.id, sound.title);
+            }
+        }
+        "status" => {
+            let snapshot = controller.snapshot().await?;
+            let playlist = &snapshot.playlist;
+            let track = playlist
+                .track
+                .as_ref()
+                .map_or("nothing", |track| &track.title);
+
+            println!(
+                "{} {} at {}%{}",
+                if playlist.playing {
+                    "playing"
+                } else {
+                    "paused"
+                },
+                track,
+                playlist.volume_level().percent(),
+                if playlist.muted { " (muted)" } else { "" }
+            );
+
+            for sound in &snapshot.soundboard.sounds {
+                println!("sound {}", sound.title);
+            }
+        }
+        "scene" => {
+            let scene = argument()?;
+            config
+                .scenes
+                .get(&scene)
+                .ok_or_else(|| KenkuError::NotFound(format!("scene {:?}", scene)))?
+                .activate(controller)
+                .await?;
+        }
+        "trigger" => {
+            let report = config.trigger(controller, &argument()?).await?;
+
+            if let Some(error) = report
+                .results
+                .into_iter()
+                .find_map(|(_, result)| result.err())
+            {
+                return Err(error.into());
+            }
+        }
+        "config" => print!("{}", config),
+        #[cfg(feature = "tui")]
+        "dashboard" => kenku_control::tui::run(controller, Duration::from_millis(500))
+            .await
+            .map_err(KenkuError::from)?,
+        #[cfg(feature = "soak")]
+        "soak" => soak(controller, rest).await?,
+        _ => return Err(Failure::Usage(format!("unknown command {:?}", name))),
+    }
+
+    Ok(())
+}
+
+/// Runs the admin command `rest[0]` with its arguments against `gateway`.
+async fn admin(gateway: &GatewayClient, rest: &[String]) -> Result<(), Failure> {
+    let name = |index: usize| {
+        rest.get(index)
+            .cloned()
+            .ok_or_else(|| Failure::Usage(format!("admin {} needs a client name", rest[0])))
+    };
+
+    match rest.first().map(String::as_str) {
+        Some("clients") => {
+            for client in gateway.clients().await? {
+                println!(
+                    "{}\t{} sent\t{} refused\t{} in the last minute{}",
+                    client.name,
+                    client.sent,
+                    client.refused,
+                    client.last_minute,
+                    client
+                        .banned_secs
+                        .map(|secs| format!("\tbanned for {}s", secs))
+                        .unwrap_or_default()
+                );
+            }
+        }
+        Some("insights") => println!("{}", ClientSummary::new(&gateway.clients().await?)),
+        Some("ban") => {
+            let client = name(1)?;
+            let seconds = rest
+                .get(2)
+                .and_then(|seconds| seconds.parse::<u64>().ok())
+                .ok_or_else(|| Failure::Usage("admin ban needs a number of seconds".to_string()))?;
+
+            gateway.ban(&client, Duration::from_secs(seconds)).await?;
+        }
+        Some("unban") => gateway.unban(&name(1)?).await?,
+        Some("automation") => {
+            let paused = gateway.automation_paused().await?;
+            println!("automation {}", if paused { "paused" } else { "running" });
+        }
+        Some("pause-automation") => gateway.pause_automation().await?,
+        Some("resume-automation") => gateway.resume_automation().await?,
+        Some("revoke-token") => gateway.revoke_token(&name(1)?).await?,
+        Some(command) => {
+            return Err(Failure::Usage(format!(
+                "unknown admin command {:?}",
+                command
+            )))
+        }
+        None => return Err(Failure::Usage("missing admin command".to_string())),
+    }
+
+    Ok(())
+}
+
+async fn playback(
+    controller: &Controller,
+    command: KenkuPlaybackCommand,
+) -> Result<(), KenkuError> {
+    controller.execute(&command.into()).await.map(|_| ())
+}
+
+/// Runs a soak of `rest[0]` minutes against `controller`, or against a mock if `rest[1]` is `mock`.
+#[cfg(feature = "soak")]
+async fn soak(controller: &Controller, rest: &[String]) -> Result<(), Failure> {
+    use kenku_control::soak::Soak;
+
+    let duration = rest
+        .first()
+        .and_then(|minutes| minutes.parse::<f64>().ok())
+        .filter(|minutes| *minutes > 0.0)
+        .and_then(|minutes| Duration::try_from_secs_f64(minutes * 60.0).ok())
+        .ok_or_else(|| Failure::Usage("soak needs a number of minutes".to_string()))?;
+    let soak = Soak::new().duration(duration);
+    let print = |sample: &kenku_control::soak::SoakSample| println!("{}", sample);
+
+    let report = match rest.get(1).map(String::as_str) {
+        None => soak.run(controller, print).await,
+        #[cfg(feature = "testing")]
+        Some("mock") => {
+            let mock = kenku_control::testing::MockRemote::start().await;
+            soak.run(&mock.controller(), print).await
+        }
+        Some(target) => return Err(Failure::Usage(format!("unknown soak target {:?}", target))),
+    };
+    let anomalies = report.anomalies();
+
+    for anomaly in &anomalies {
+        println!("anomaly: {}", anomaly);
+    }
+
+    match anomalies.len() {
+        0 => Ok(()),
+        count => Err(Failure::Anomalies(count)),
+    }
+}
+
+fn usage(error: &str) -> ExitCode {
+    eprintln!("kenku-ctl: {}\n\n{}", error, USAGE);
+    ExitCode::from(2)
+}
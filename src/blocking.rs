@@ -0,0 +1,341 @@
+//! A synchronous `Controller` for applications without an async runtime.
+//!
+//! It mirrors the async API on top of `reqwest::blocking`, so every call blocks the current
+//! thread until the Kenku Remote answered. Like `reqwest::blocking`, it must not be used from
+//! inside an async runtime.
+use crate::{
+    playlist::{PlaylistGetResponse, PlaylistPlaybackResponse, Repeat},
+    soundboard::{SoundboardGetResponse, SoundboardPlaybackResponse},
+    utils::process_url,
+    KenkuAddress, KenkuCommand, KenkuError, KenkuGetCommand, KenkuPostCommand, KenkuPutCommand,
+    KenkuState, RetryPolicy, Volume,
+};
+use reqwest::{
+    blocking::{Client, Response},
+    StatusCode,
+};
+use serde::de::DeserializeOwned;
+use serde_json::json;
+use std::{
+    net::SocketAddrV4,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+/// Represents a blocking controller for the Kenku server.
+///
+/// # Fields
+///
+/// * `client` - A `reqwest::blocking::Client` used to make HTTP requests to the server.
+/// * `address` - A `KenkuAddress` holding the host and port of the server.
+/// * `kenku_remote_state` - The last known `KenkuState` of the server, shared by every clone of the controller.
+/// * `retry` - The `RetryPolicy` deciding which failed requests are sent again.
+#[derive(Debug, Clone)]
+pub struct Controller {
+    pub client: Client,
+    pub address: KenkuAddress,
+    pub kenku_remote_state: Arc<RwLock<KenkuState>>,
+    pub retry: RetryPolicy,
+}
+
+impl Controller {
+    /// Creates a new blocking `Controller` with a request timeout of 100 milliseconds.
+    ///
+    /// # Arguments
+    ///
+    /// * `host` - The IP address or DNS name of the server.
+    /// * `port` - The port number of the server.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if it is called from inside an async runtime, or if the HTTP client cannot be built.
+    pub fn new(host: impl Into<String>, port: u16) -> Controller {
+        Controller::from_address(KenkuAddress::new(host, port))
+    }
+
+    /// Creates a new blocking `Controller` for an IPv4 socket address.
+    pub fn from_ipv4(address: SocketAddrV4) -> Controller {
+        Controller::from_address(address.into())
+    }
+
+    /// Creates a new blocking `Controller` for a `KenkuAddress`.
+    pub fn from_address(address: KenkuAddress) -> Controller {
+        let client = Client::builder()
+            .timeout(Duration::from_millis(100))
+            .build()
+            .unwrap();
+
+        Controller::with_client(client, address)
+    }
+
+    /// Creates a new blocking `Controller` that sends its requests with `client`.
+    pub fn with_client(client: Client, address: KenkuAddress) -> Controller {
+        Controller {
+            client,
+            address,
+            kenku_remote_state: Arc::new(RwLock::new(KenkuState::Offline)),
+            retry: RetryPolicy::default(),
+        }
+    }
+
+    /// Returns the last known state of the server.
+    pub fn state(&self) -> KenkuState {
+        *self.kenku_remote_state.read().unwrap()
+    }
+
+    fn set_state(&self, state: KenkuState) {
+        *self.kenku_remote_state.write().unwrap() = state;
+    }
+
+    /// Checks whether the server answers HTTP requests and records the result.
+    pub fn ping(&self) -> KenkuState {
+        let url = process_url(
+            &KenkuCommand::KenkuGet(KenkuGetCommand::PlaylistPlayback),
+            &self.address,
+        );
+        let state = match self.client.get(url).send() {
+            Ok(_) => KenkuState::Online,
+            Err(_) => KenkuState::Offline,
+        };
+
+        self.set_state(state);
+
+        state
+    }
+
+    /// Fetches the soundboards and their sounds.
+    pub fn get_soundboard(&self) -> Result<SoundboardGetResponse, KenkuError> {
+        self.send_get(KenkuGetCommand::Soundboard)
+    }
+
+    /// Fetches the sounds currently playing.
+    pub fn get_soundboard_playback(&self) -> Result<SoundboardPlaybackResponse, KenkuError> {
+        self.send_get(KenkuGetCommand::SoundboardPlayback)
+    }
+
+    /// Fetches the playlists and their tracks.
+    pub fn get_playlist(&self) -> Result<PlaylistGetResponse, KenkuError> {
+        self.send_get(KenkuGetCommand::Playlist)
+    }
+
+    /// Fetches the current playlist playback.
+    pub fn get_playlist_playback(&self) -> Result<PlaylistPlaybackResponse, KenkuError> {
+        self.send_get(KenkuGetCommand::PlaylistPlayback)
+    }
+
+    /// Plays the track with the id `id`.
+    pub fn play_track(&self, id: &str) -> Result<StatusCode, KenkuError> {
+        self.send_put(KenkuPutCommand::PlaylistPlay, Some(json!({ "id": id })))
+    }
+
+    /// Plays the playlist with the id `id` from its first track.
+    pub fn play_playlist(&self, id: &str) -> Result<StatusCode, KenkuError> {
+        self.send_put(KenkuPutCommand::PlaylistPlay, Some(json!({ "id": id })))
+    }
+
+    /// Plays the soundboard sound with the id `id`.
+    pub fn play_sound(&self, id: &str) -> Result<StatusCode, KenkuError> {
+        self.send_put(KenkuPutCommand::SoundboardPlay, Some(json!({ "id": id })))
+    }
+
+    /// Stops the soundboard sound with the id `id`.
+    pub fn stop_sound(&self, id: &str) -> Result<StatusCode, KenkuError> {
+        self.send_put(KenkuPutCommand::SoundboardStop, Some(json!({ "id": id })))
+    }
+
+    /// Resumes the playlist playback.
+    pub fn playback_play(&self) -> Result<StatusCode, KenkuError> {
+        self.send_put(KenkuPutCommand::PlaylistPlaybackPlay, None)
+    }
+
+    /// Pauses the playlist playback.
+    pub fn playback_pause(&self) -> Result<StatusCode, KenkuError> {
+        self.send_put(KenkuPutCommand::PlaylistPlaybackPause, None)
+    }
+
+    /// Skips to the next track.
+    pub fn playback_next(&self) -> Result<StatusCode, KenkuError> {
+        self.send_post(KenkuPostCommand::PlaylistPlaybackNext)
+    }
+
+    /// Goes back to the previous track.
+    pub fn playback_previous(&self) -> Result<StatusCode, KenkuError> {
+        self.send_post(KenkuPostCommand::PlaylistPlaybackPrevious)
+    }
+
+    /// Mutes or unmutes the playlist playback.
+    pub fn playback_mute(&self, mute: bool) -> Result<StatusCode, KenkuError> {
+        self.send_put(
+            KenkuPutCommand::PlaylistPlaybackMute,
+            Some(json!({ "mute": mute })),
+        )
+    }
+
+    /// Sets the playlist volume.
+    pub fn playback_volume(&self, volume: Volume) -> Result<StatusCode, KenkuError> {
+        self.send_put(
+            KenkuPutCommand::PlaylistPlaybackVolume,
+            Some(json!({ "volume": volume })),
+        )
+    }
+
+    /// Turns shuffle on or off.
+    pub fn playback_shuffle(&self, shuffle: bool) -> Result<StatusCode, KenkuError> {
+        self.send_put(
+            KenkuPutCommand::PlaylistPlaybackShuffle,
+            Some(json!({ "shuffle": shuffle })),
+        )
+    }
+
+    /// Sets the repeat mode.
+    pub fn playback_repeat(&self, repeat: Repeat) -> Result<StatusCode, KenkuError> {
+        self.send_put(
+            KenkuPutCommand::PlaylistPlaybackRepeat,
+            Some(json!({ "repeat": repeat })),
+        )
+    }
+
+    fn send_get<T: DeserializeOwned>(&self, command: KenkuGetCommand) -> Result<T, KenkuError> {
+        let url = process_url(&KenkuCommand::KenkuGet(command), &self.address);
+        let response = self.send(reqwest::Method::GET, &url, None)?;
+
+        Ok(response.json::<T>()?)
+    }
+
+    fn send_put(
+        &self,
+        command: KenkuPutCommand,
+        payload: Option<serde_json::Value>,
+    ) -> Result<StatusCode, KenkuError> {
+        let url = process_url(&KenkuCommand::KenkuPut(command), &self.address);
+        let response = self.send(reqwest::Method::PUT, &url, payload.as_ref())?;
+
+        Ok(response.status())
+    }
+
+    fn send_post(&self, command: KenkuPostCommand) -> Result<StatusCode, KenkuError> {
+        let url = process_url(&KenkuCommand::KenkuPost(command), &self.address);
+        let response = self.send(reqwest::Method::POST, &url, None)?;
+
+        Ok(response.status())
+    }
+
+    /// Sends a `method` request to `url`, with `body` as the JSON body when given, retrying the failures `retry` allows.
+    fn send(
+        &self,
+        method: reqwest::Method,
+        url: &str,
+        body: Option<&serde_json::Value>,
+    ) -> Result<Response, KenkuError> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("kenku_request", method = %method, url = %url).entered();
+
+        let mut attempt = 0;
+
+        loop {
+            let request = self.client.request(method.clone(), url);
+            let request = match body {
+                Some(body) => request.json(body),
+                None => request,
+            };
+
+            #[cfg(feature = "tracing")]
+            let started = std::time::Instant::now();
+
+            let result = match request.send() {
+                Ok(response) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!(
+                        status = response.status().as_u16(),
+                        elapsed_ms = started.elapsed().as_millis() as u64,
+                        attempt,
+                        "kenku remote answered"
+                    );
+
+                    self.set_state(KenkuState::Online);
+
+                    ensure_success(response)
+                }
+                Err(error) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!(
+                        error = %error,
+                        elapsed_ms = started.elapsed().as_millis() as u64,
+                        attempt,
+                        "kenku request failed"
+                    );
+
+                    if error.is_connect() || error.is_timeout() {
+                        self.set_state(KenkuState::Offline);
+                    }
+
+                    Err(error.into())
+                }
+            };
+
+            match result {
+                Err(error)
+                    if attempt < self.retry.max_retries
+                        && self.retry.should_retry(&method, &error) =>
+                {
+                    attempt += 1;
+
+                    #[cfg(feature = "tracing")]
+                    tracing::info!(error = %error, attempt, "retrying kenku request");
+
+                    std::thread::sleep(self.retry.delay(attempt));
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// Turns a non-2xx response into a `KenkuError::Status` holding the status and the body, or a `KenkuError::Unsupported` for a missing route.
+fn ensure_success(response: Response) -> Result<Response, KenkuError> {
+    let status = response.status();
+
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let body = response.text().unwrap_or_default();
+
+    Err(crate::utils::status_error(status, body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Controller;
+    use crate::{testing::MockRemote, KenkuError, KenkuState};
+
+    /// Starts a mock remote on a runtime of its own, since the blocking controller runs outside of one.
+    fn start_remote() -> (tokio::runtime::Runtime, MockRemote) {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let remote = runtime.block_on(MockRemote::start());
+
+        (runtime, remote)
+    }
+
+    #[test]
+    fn get_parses_the_response() {
+        let (_runtime, remote) = start_remote();
+        remote.set_playing_sounds(["s1"]);
+        let controller = Controller::from_address(remote.address());
+
+        let playback = controller.get_soundboard_playback().unwrap();
+
+        assert_eq!(playback.sounds[0].id, "s1");
+        assert_eq!(controller.state(), KenkuState::Online);
+    }
+
+    #[test]
+    fn error_status_is_reported() {
+        let (_runtime, remote) = start_remote();
+        let controller = Controller::from_address(remote.address());
+
+        let error = controller.play_sound("missing").unwrap_err();
+
+        assert!(matches!(error, KenkuError::Status { status, .. } if status == 404));
+    }
+}
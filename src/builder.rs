@@ -0,0 +1,279 @@
+//! Step-by-step construction of a `Controller`.
+#[cfg(feature = "cache")]
+use crate::cache::StateCache;
+use crate::{Controller, KenkuAddress, KenkuError, KenkuState, ReconnectPolicy, RetryPolicy};
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue},
+    Client,
+};
+#[cfg(feature = "automation")]
+use std::sync::atomic::AtomicBool;
+use std::{
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+/// The request timeout used when none is configured, matching `Controller::new`.
+const DEFAULT_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Builds a `Controller` with custom timeouts, retries, headers or HTTP client.
+///
+/// ```no_run
+/// use kenku_control::ControllerBuilder;
+/// use std::time::Duration;
+///
+/// let controller = ControllerBuilder::new("kenku-host.local", 3333)
+///     .timeout(Duration::from_secs(2))
+///     .connect_timeout(Duration::from_millis(500))
+///     .retries(2)
+///     .build()
+///     .expect("failed to build the controller");
+/// ```
+#[derive(Debug)]
+pub struct ControllerBuilder {
+    address: KenkuAddress,
+    timeout: Duration,
+    connect_timeout: Option<Duration>,
+    retry: RetryPolicy,
+    #[cfg(not(target_arch = "wasm32"))]
+    reconnect: Option<ReconnectPolicy>,
+    headers: HeaderMap,
+    client: Option<Client>,
+    #[cfg(all(feature = "cache", not(target_arch = "wasm32")))]
+    cache_ttl: Duration,
+    pool_idle_timeout: Option<Duration>,
+    pool_max_idle_per_host: Option<usize>,
+    tcp_keepalive: Option<Duration>,
+}
+
+impl ControllerBuilder {
+    /// Creates a new `ControllerBuilder` for the Kenku Remote at `host:port`.
+    ///
+    /// # Arguments
+    ///
+    /// * `host` - The IP address or DNS name of the server.
+    /// * `port` - The port number of the server.
+    pub fn new(host: impl Into<String>, port: u16) -> ControllerBuilder {
+        ControllerBuilder::from_address(KenkuAddress::new(host, port))
+    }
+
+    /// Creates a new `ControllerBuilder` for `address`.
+    pub fn from_address(address: KenkuAddress) -> ControllerBuilder {
+        ControllerBuilder {
+            address,
+            timeout: DEFAULT_TIMEOUT,
+            connect_timeout: None,
+            retry: RetryPolicy::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            reconnect: None,
+            headers: HeaderMap::new(),
+            client: None,
+            #[cfg(all(feature = "cache", not(target_arch = "wasm32")))]
+            cache_ttl: Duration::ZERO,
+            pool_idle_timeout: None,
+            pool_max_idle_per_host: None,
+            tcp_keepalive: None,
+        }
+    }
+
+    /// Sets the total timeout of every request, from connecting until the body is read.
+    ///
+    /// Defaults to 100 milliseconds. Ignored on `wasm32`, where the browser decides when a request times out.
+    pub fn timeout(mut self, timeout: Duration) -> ControllerBuilder {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets the timeout for establishing the TCP connection only. Ignored on `wasm32`.
+    pub fn connect_timeout(mut self, timeout: Duration) -> ControllerBuilder {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets how many times a request is retried after a connection error or a timeout, without waiting in between.
+    ///
+    /// POST requests, such as skipping to the next track, are only retried after connection errors,
+    /// so a slow answer does not skip twice. Defaults to 0, which sends every request once. Use `retry_policy` for backoff or to retry 5xx answers.
+    pub fn retries(mut self, retries: u32) -> ControllerBuilder {
+        self.retry.max_retries = retries;
+        self
+    }
+
+    /// Sets which failed requests are sent again, and how long to wait in between.
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> ControllerBuilder {
+        self.retry = policy;
+        self
+    }
+
+    /// Makes requests wait for an offline Kenku Remote to come back instead of failing.
+    ///
+    /// Disabled by default. Not available on `wasm32`, which has no timer to wait with.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn reconnect(mut self, policy: ReconnectPolicy) -> ControllerBuilder {
+        self.reconnect = Some(policy);
+        self
+    }
+
+    /// Adds a header sent with every request.
+    pub fn header(mut self, name: HeaderName, value: HeaderValue) -> ControllerBuilder {
+        self.headers.insert(name, value);
+        self
+    }
+
+    /// Sets how long an unused connection is kept open for the next request.
+    ///
+    /// Defaults to 90 seconds. Keeping connections open spares long-running daemons a new TCP
+    /// handshake for each command. Ignored on `wasm32`.
+    pub fn pool_idle_timeout(mut self, timeout: Duration) -> ControllerBuilder {
+        self.pool_idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets how many unused connections are kept open to the Kenku Remote.
+    ///
+    /// Unlimited by default. Zero closes every connection after its request. Ignored on `wasm32`.
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> ControllerBuilder {
+        self.pool_max_idle_per_host = Some(max);
+        self
+    }
+
+    /// Enables TCP keepalive probes every `interval` on open connections, so a link that dropped
+    /// silently is noticed before the next command.
+    ///
+    /// Disabled by default. Ignored on `wasm32`.
+    pub fn tcp_keepalive(mut self, interval: Duration) -> ControllerBuilder {
+        self.tcp_keepalive = Some(interval);
+        self
+    }
+
+    /// Uses `client` for every request instead of building one.
+    ///
+    /// The timeouts, connection pool options and headers of this builder are ignored, since they are properties of the client.
+    pub fn client(mut self, client: Client) -> ControllerBuilder {
+        self.client = Some(client);
+        self
+    }
+
+    /// Answers `Controller::get_soundboard` and `Controller::get_playlist` from memory for `ttl` after each fetch.
+    ///
+    /// Defaults to zero, which disables caching. Not available on `wasm32`, where
+    /// `std::time::Instant` panics, so the cache stays disabled there.
+    #[cfg(all(feature = "cache", not(target_arch = "wasm32")))]
+    pub fn cache_ttl(mut self, ttl: Duration) -> ControllerBuilder {
+        self.cache_ttl = ttl;
+        self
+    }
+
+    /// Builds the `Controller`.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the new `Controller`, or a `KenkuError` if the HTTP client could not be built.
+    pub fn build(self) -> Result<Controller, KenkuError> {
+        let client = match self.client {
+            Some(client) => client,
+            None => {
+                let builder = Client::builder().default_headers(self.headers);
+
+                #[cfg(not(target_arch = "wasm32"))]
+                let builder = match self.connect_timeout {
+                    Some(connect_timeout) => builder
+                        .timeout(self.timeout)
+                        .connect_timeout(connect_timeout),
+                    None => builder.timeout(self.timeout),
+                };
+
+                #[cfg(not(target_arch = "wasm32"))]
+                let builder = {
+                    let builder = builder.tcp_keepalive(self.tcp_keepalive);
+                    let builder = match self.pool_idle_timeout {
+                        Some(timeout) => builder.pool_idle_timeout(timeout),
+                        None => builder,
+                    };
+
+                    match self.pool_max_idle_per_host {
+                        Some(max) => builder.pool_max_idle_per_host(max),
+                        None => builder,
+                    }
+                };
+
+                builder.build()?
+            }
+        };
+
+        Ok(Controller {
+            client,
+            address: self.address,
+            kenku_remote_state: Arc::new(RwLock::new(KenkuState::Offline)),
+            retry: self.retry,
+            #[cfg(not(target_arch = "wasm32"))]
+            reconnect: self.reconnect,
+            #[cfg(feature = "automation")]
+            automation_paused: Arc::new(AtomicBool::new(false)),
+            #[cfg(all(feature = "cache", not(target_arch = "wasm32")))]
+            cache: Arc::new(StateCache::new(self.cache_ttl)),
+            #[cfg(all(feature = "cache", target_arch = "wasm32"))]
+            cache: Arc::default(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ControllerBuilder;
+    use crate::{testing::MockRemote, KenkuAddress, ReconnectPolicy, RetryPolicy};
+    use reqwest::header::{HeaderValue, USER_AGENT};
+    use std::time::Duration;
+
+    #[test]
+    fn builder_applies_options() {
+        let controller = ControllerBuilder::new("kenku-host.local", 3333)
+            .timeout(Duration::from_secs(2))
+            .connect_timeout(Duration::from_millis(500))
+            .retries(3)
+            .reconnect(ReconnectPolicy::default())
+            .header(USER_AGENT, HeaderValue::from_static("table-bot"))
+            .pool_idle_timeout(Duration::from_secs(300))
+            .pool_max_idle_per_host(2)
+            .tcp_keepalive(Duration::from_secs(30))
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            controller.address,
+            KenkuAddress::new("kenku-host.local", 3333)
+        );
+        assert_eq!(controller.retry.max_retries, 3);
+        assert_eq!(controller.reconnect, Some(ReconnectPolicy::default()));
+    }
+
+    #[test]
+    fn builder_accepts_custom_client() {
+        let client = reqwest::Client::new();
+        let controller = ControllerBuilder::new("127.0.0.1", 3333)
+            .client(client)
+            .build()
+            .unwrap();
+
+        assert_eq!(controller.retry, RetryPolicy::default());
+    }
+
+    #[tokio::test]
+    async fn idle_pool_limits_decide_whether_connections_are_reused() {
+        for (max_idle, expected) in [(None, 1), (Some(0), 3)] {
+            let remote = MockRemote::start().await;
+            let builder = ControllerBuilder::from_address(remote.address());
+            let builder = match max_idle {
+                Some(max) => builder.pool_max_idle_per_host(max),
+                None => builder,
+            };
+            let controller = builder.build().unwrap();
+
+            for _ in 0..3 {
+                controller.get_playlist_playback().await.unwrap();
+            }
+
+            assert_eq!(remote.connections(), expected, "max idle {:?}", max_idle);
+        }
+    }
+}
@@ -0,0 +1,170 @@
+//! Caching the soundboard and playlist listings in memory.
+//!
+//! Listing the soundboards or the playlists returns the whole library, which rarely changes while
+//! a session runs. With a TTL set through `ControllerBuilder::cache_ttl`, `Controller::get_soundboard`
+//! and `Controller::get_playlist` answer from memory until the listing is older than the TTL.
+//! Playback states are never cached, since they change with every track.
+use crate::{
+    playlist::PlaylistGetResponse, soundboard::SoundboardGetResponse, Controller, KenkuError,
+};
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// A listing and the moment it was fetched.
+#[derive(Debug)]
+struct Slot<T> {
+    entry: Mutex<Option<(Instant, T)>>,
+}
+
+impl<T: Clone> Slot<T> {
+    fn new() -> Slot<T> {
+        Slot {
+            entry: Mutex::new(None),
+        }
+    }
+
+    /// Returns the stored value when it is younger than `ttl`.
+    fn get(&self, ttl: Duration) -> Option<T> {
+        match &*self.entry.lock().unwrap() {
+            Some((fetched, value)) if fetched.elapsed() < ttl => Some(value.clone()),
+            _ => None,
+        }
+    }
+
+    fn store(&self, value: &T) {
+        *self.entry.lock().unwrap() = Some((Instant::now(), value.clone()));
+    }
+
+    fn clear(&self) {
+        *self.entry.lock().unwrap() = None;
+    }
+}
+
+/// Holds the cached soundboard and playlist listings of a `Controller`.
+///
+/// Every clone of a controller shares the same cache. A TTL of zero, the default, disables caching,
+/// so every lookup asks the remote.
+#[derive(Debug)]
+pub struct StateCache {
+    ttl: Duration,
+    soundboard: Slot<SoundboardGetResponse>,
+    playlist: Slot<PlaylistGetResponse>,
+}
+
+impl StateCache {
+    /// Creates an empty cache keeping listings for `ttl`.
+    pub fn new(ttl: Duration) -> StateCache {
+        StateCache {
+            ttl,
+            soundboard: Slot::new(),
+            playlist: Slot::new(),
+        }
+    }
+
+    /// Returns how long a listing is answered from memory.
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Returns whether listings are cached at all.
+    pub fn is_enabled(&self) -> bool {
+        !self.ttl.is_zero()
+    }
+
+    /// Drops both cached listings, so the next lookups ask the remote.
+    pub fn invalidate(&self) {
+        self.soundboard.clear();
+        self.playlist.clear();
+    }
+
+    pub(crate) fn soundboard(&self) -> Option<SoundboardGetResponse> {
+        self.is_enabled()
+            .then(|| self.soundboard.get(self.ttl))
+            .flatten()
+    }
+
+    pub(crate) fn store_soundboard(&self, soundboard: &SoundboardGetResponse) {
+        if self.is_enabled() {
+            self.soundboard.store(soundboard);
+        }
+    }
+
+    pub(crate) fn playlist(&self) -> Option<PlaylistGetResponse> {
+        self.is_enabled()
+            .then(|| self.playlist.get(self.ttl))
+            .flatten()
+    }
+
+    pub(crate) fn store_playlist(&self, playlist: &PlaylistGetResponse) {
+        if self.is_enabled() {
+            self.playlist.store(playlist);
+        }
+    }
+}
+
+impl Default for StateCache {
+    fn default() -> StateCache {
+        StateCache::new(Duration::ZERO)
+    }
+}
+
+impl Controller {
+    /// Drops the cached soundboard and playlist listings, so the next lookups ask the remote.
+    ///
+    /// Call it after changing the library in Kenku FM. Does nothing when caching is disabled.
+    pub fn invalidate(&self) {
+        self.cache.invalidate();
+    }
+
+    /// Fetches the soundboard and playlist listings again, replacing the cached ones.
+    ///
+    /// # Returns
+    ///
+    /// This function returns `Ok(())` once both listings were fetched, or the first `KenkuError`. The cache is left empty for a listing that could not be fetched.
+    pub async fn refresh(&self) -> Result<(), KenkuError> {
+        self.cache.invalidate();
+        self.get_soundboard().await?;
+        self.get_playlist().await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StateCache;
+    use crate::playlist::PlaylistGetResponse;
+    use std::time::Duration;
+
+    fn library() -> PlaylistGetResponse {
+        PlaylistGetResponse {
+            playlists: Vec::new(),
+            tracks: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn listings_expire_after_the_ttl() {
+        let cache = StateCache::new(Duration::from_millis(20));
+        cache.store_playlist(&library());
+
+        assert_eq!(cache.playlist(), Some(library()));
+        std::thread::sleep(Duration::from_millis(30));
+        assert_eq!(cache.playlist(), None);
+
+        cache.store_playlist(&library());
+        cache.invalidate();
+        assert_eq!(cache.playlist(), None);
+    }
+
+    #[test]
+    fn zero_ttl_disables_caching() {
+        let cache = StateCache::default();
+        cache.store_playlist(&library());
+
+        assert!(!cache.is_enabled());
+        assert_eq!(cache.playlist(), None);
+    }
+}
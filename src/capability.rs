@@ -0,0 +1,90 @@
+//! Checking which parts of the Kenku Remote API a remote serves.
+//!
+//! Older or newer Kenku Remotes may not serve every route. A request to a missing route fails with
+//! `KenkuError::Unsupported` instead of a plain 404, and `Controller::supports` lets a UI find out
+//! ahead of time, for example to hide a button.
+use crate::{utils::format_base_url, Controller, KenkuError};
+
+/// Represents a capability of the Kenku Remote API.
+///
+/// # Variants
+///
+/// * `Playlist` - Listing the playlists and their tracks.
+/// * `PlaylistPlayback` - Reading the playlist playback state.
+/// * `Soundboard` - Listing the soundboards and their sounds.
+/// * `SoundboardPlayback` - Reading the soundboard playback state.
+/// * `Seek` - Seeking inside the current track. Kenku Remote v1 does not serve it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Feature {
+    Playlist,
+    PlaylistPlayback,
+    Soundboard,
+    SoundboardPlayback,
+    Seek,
+}
+
+impl Feature {
+    /// Returns the HTTP method and the path, relative to the API root, of the route serving this feature.
+    pub fn route(self) -> (reqwest::Method, &'static str) {
+        match self {
+            Feature::Playlist => (reqwest::Method::GET, "playlist"),
+            Feature::PlaylistPlayback => (reqwest::Method::GET, "playlist/playback"),
+            Feature::Soundboard => (reqwest::Method::GET, "soundboard"),
+            Feature::SoundboardPlayback => (reqwest::Method::GET, "soundboard/playback"),
+            Feature::Seek => (reqwest::Method::PUT, "playlist/playback/seek"),
+        }
+    }
+}
+
+impl Controller {
+    /// Checks whether the Kenku Remote serves `feature`.
+    ///
+    /// The route of the feature is probed once. Routes that change the playback are probed with an
+    /// empty JSON object, which a remote serving them rejects as invalid without acting on it.
+    ///
+    /// # Returns
+    ///
+    /// This function returns `Ok(true)` when the route exists, `Ok(false)` when the remote does not serve it, or a `KenkuError` when the remote could not be asked.
+    pub async fn supports(&self, feature: Feature) -> Result<bool, KenkuError> {
+        let (method, path) = feature.route();
+        let url = format!(
+            "{}/{}",
+            format_base_url(self.address.url_host(), self.address.port),
+            path
+        );
+        let body = serde_json::json!({});
+        let body = (method != reqwest::Method::GET).then_some(&body);
+
+        let result = self.send(method, &url, body).await;
+
+        match result {
+            Ok(_) => Ok(true),
+            Err(KenkuError::Unsupported(_)) => Ok(false),
+            Err(KenkuError::Status { status, .. }) if status.is_client_error() => Ok(true),
+            Err(error) => Err(error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{utils::status_error, KenkuError};
+    use reqwest::StatusCode;
+
+    #[test]
+    fn missing_route_is_unsupported() {
+        let body = r#"{"message":"Route PUT:/v1/playlist/playback/seek not found","error":"Not Found","statusCode":404}"#;
+
+        assert!(matches!(
+            status_error(StatusCode::NOT_FOUND, body.to_string()),
+            KenkuError::Unsupported(route) if route == "PUT:/v1/playlist/playback/seek"
+        ));
+        assert!(matches!(
+            status_error(
+                StatusCode::NOT_FOUND,
+                r#"{"error":"not found"}"#.to_string()
+            ),
+            KenkuError::Status { .. }
+        ));
+    }
+}
@@ -0,0 +1,433 @@
+//! A fault-injecting proxy for resilience testing.
+//!
+//! `ChaosProxy` sits between a `Controller` and a Kenku Remote (real or mocked) and makes the
+//! connection misbehave: it adds latency, drops connections, answers with bursts of server errors
+//! and corrupts JSON bodies, at configurable rates. Point the controller at `ChaosProxy::address`
+//! to check that retries, reconnection and the event stream recover from realistic failures.
+//!
+//! ```no_run
+//! # async fn soak() -> Result<(), kenku_control::KenkuError> {
+//! use kenku_control::{chaos::{ChaosConfig, ChaosProxy}, Controller, KenkuAddress};
+//! use std::time::Duration;
+//!
+//! let config = ChaosConfig {
+//!     drop_rate: 0.05,
+//!     error_rate: 0.02,
+//!     latency: (Duration::from_millis(5), Duration::from_millis(80)),
+//!     ..ChaosConfig::default()
+//! };
+//! let proxy = ChaosProxy::start(KenkuAddress::new("127.0.0.1", 3333), config).await?;
+//! let controller = Controller::builder(proxy.address().host, proxy.address().port)
+//!     .retries(3)
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+use crate::{KenkuAddress, KenkuError};
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    task::JoinHandle,
+};
+
+/// Represents how often and how badly a `ChaosProxy` misbehaves.
+///
+/// Rates are probabilities from 0.0 (never) to 1.0 (every request).
+///
+/// # Fields
+///
+/// * `latency` - The range the delay added before each answer is drawn from.
+/// * `drop_rate` - How often the connection is closed without an answer.
+/// * `error_rate` - How often a burst of `503 Service Unavailable` answers starts.
+/// * `error_burst` - How many requests in a row a burst of errors lasts.
+/// * `malformed_rate` - How often a successful answer has its JSON body cut in half.
+/// * `seed` - The seed of the random generator, to replay the same sequence of faults. `None` seeds from the clock.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChaosConfig {
+    pub latency: (Duration, Duration),
+    pub drop_rate: f64,
+    pub error_rate: f64,
+    pub error_burst: u32,
+    pub malformed_rate: f64,
+    pub seed: Option<u64>,
+}
+
+impl Default for ChaosConfig {
+    /// A configuration that forwards every request untouched.
+    fn default() -> Self {
+        ChaosConfig {
+            latency: (Duration::ZERO, Duration::ZERO),
+            drop_rate: 0.0,
+            error_rate: 0.0,
+            error_burst: 1,
+            malformed_rate: 0.0,
+            seed: None,
+        }
+    }
+}
+
+/// Counts what a `ChaosProxy` did to the requests it received.
+///
+/// # Fields
+///
+/// * `requests` - The requests received.
+/// * `dropped` - The requests whose connection was closed without an answer.
+/// * `errors` - The requests answered with an injected server error.
+/// * `malformed` - The answers whose body was corrupted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChaosStats {
+    pub requests: u64,
+    pub dropped: u64,
+    pub errors: u64,
+    pub malformed: u64,
+}
+
+/// What the proxy decided to do with one request.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Verdict {
+    Forward { delay: Duration, malformed: bool },
+    Drop,
+    Error { delay: Duration },
+}
+
+/// The mutable state shared by the connections of a proxy.
+#[derive(Debug)]
+struct Chaos {
+    config: ChaosConfig,
+    rng: u64,
+    burst_left: u32,
+    stats: ChaosStats,
+}
+
+impl Chaos {
+    fn new(config: ChaosConfig) -> Chaos {
+        let seed = config.seed.unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_nanos() as u64)
+        });
+
+        Chaos {
+            config,
+            // xorshift gets stuck on zero.
+            rng: seed | 1,
+            burst_left: 0,
+            stats: ChaosStats::default(),
+        }
+    }
+
+    /// Returns a pseudo-random number in `[0, 1)`.
+    fn next_f64(&mut self) -> f64 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+
+        (self.rng >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn chance(&mut self, rate: f64) -> bool {
+        rate > 0.0 && self.next_f64() < rate
+    }
+
+    fn delay(&mut self) -> Duration {
+        let (min, max) = self.config.latency;
+
+        if max <= min {
+            return min;
+        }
+
+        min + (max - min).mul_f64(self.next_f64())
+    }
+
+    /// Decides the fate of the next request and records it in the stats.
+    fn verdict(&mut self) -> Verdict {
+        self.stats.requests += 1;
+
+        if self.chance(self.config.drop_rate) {
+            self.stats.dropped += 1;
+            return Verdict::Drop;
+        }
+
+        let delay = self.delay();
+
+        if self.burst_left == 0 && self.chance(self.config.error_rate) {
+            self.burst_left = self.config.error_burst.max(1);
+        }
+
+        if self.burst_left > 0 {
+            self.burst_left -= 1;
+            self.stats.errors += 1;
+            return Verdict::Error { delay };
+        }
+
+        let malformed = self.chance(self.config.malformed_rate);
+
+        if malformed {
+            self.stats.malformed += 1;
+        }
+
+        Verdict::Forward { delay, malformed }
+    }
+}
+
+/// A running fault-injecting proxy. It stops when dropped.
+#[derive(Debug)]
+pub struct ChaosProxy {
+    address: KenkuAddress,
+    chaos: Arc<Mutex<Chaos>>,
+    handle: JoinHandle<()>,
+}
+
+impl ChaosProxy {
+    /// Starts a proxy on a random local port that forwards to `upstream`.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the running `ChaosProxy`, or a `KenkuError::Io` if no local port could be bound.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if it is called outside of a tokio runtime.
+    pub async fn start(
+        upstream: KenkuAddress,
+        config: ChaosConfig,
+    ) -> Result<ChaosProxy, KenkuError> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let address = KenkuAddress::from(listener.local_addr()?);
+        let chaos = Arc::new(Mutex::new(Chaos::new(config)));
+        let shared = chaos.clone();
+
+        let handle = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(relay(stream, upstream.clone(), shared.clone()));
+            }
+        });
+
+        Ok(ChaosProxy {
+            address,
+            chaos,
+            handle,
+        })
+    }
+
+    /// Returns the address to point a `Controller` at.
+    pub fn address(&self) -> KenkuAddress {
+        self.address.clone()
+    }
+
+    /// Returns what the proxy did so far.
+    pub fn stats(&self) -> ChaosStats {
+        self.chaos.lock().unwrap().stats
+    }
+
+    /// Replaces the configuration, for example to stop injecting faults halfway through a test.
+    pub fn set_config(&self, config: ChaosConfig) {
+        let mut chaos = self.chaos.lock().unwrap();
+        chaos.config = config;
+        chaos.burst_left = 0;
+    }
+}
+
+impl Drop for ChaosProxy {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+/// Forwards one request from `client` to `upstream`, misbehaving as the chaos decides.
+async fn relay(client: TcpStream, upstream: KenkuAddress, chaos: Arc<Mutex<Chaos>>) {
+    let mut reader = BufReader::new(client);
+    let Some(request) = read_request(&mut reader).await else {
+        return;
+    };
+    let verdict = chaos.lock().unwrap().verdict();
+    let mut client = reader.into_inner();
+
+    let response = match verdict {
+        Verdict::Drop => return,
+        Verdict::Error { delay } => {
+            tokio::time::sleep(delay).await;
+            http_response("503 Service Unavailable", "", b"injected failure")
+        }
+        Verdict::Forward { delay, malformed } => {
+            tokio::time::sleep(delay).await;
+
+            let Some(response) = forward(&request, &upstream).await else {
+                return;
+            };
+
+            if malformed {
+                corrupt(&response)
+            } else {
+                close_after(&response)
+            }
+        }
+    };
+
+    let _ = client.write_all(&response).await;
+    let _ = client.shutdown().await;
+}
+
+/// Reads one request and returns it, rewritten to ask the upstream to close the connection.
+async fn read_request(reader: &mut BufReader<TcpStream>) -> Option<Vec<u8>> {
+    let mut head = String::new();
+    let mut content_length = 0;
+
+    if reader.read_line(&mut head).await.ok()? == 0 {
+        return None;
+    }
+
+    loop {
+        let mut header = String::new();
+
+        if reader.read_line(&mut header).await.ok()? == 0 || header.trim().is_empty() {
+            break;
+        }
+
+        let name = header.split(':').next().unwrap_or_default().trim();
+
+        if name.eq_ignore_ascii_case("connection") {
+            continue;
+        }
+
+        if name.eq_ignore_ascii_case("content-length") {
+            content_length = header.split(':').nth(1)?.trim().parse().ok()?;
+        }
+
+        head.push_str(&header);
+    }
+
+    head.push_str("connection: close\r\n\r\n");
+
+    let mut request = head.into_bytes();
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).await.ok()?;
+    request.extend_from_slice(&body);
+
+    Some(request)
+}
+
+/// Sends `request` to `upstream` and reads the whole response.
+async fn forward(request: &[u8], upstream: &KenkuAddress) -> Option<Vec<u8>> {
+    let mut stream = TcpStream::connect((upstream.host.as_str(), upstream.port))
+        .await
+        .ok()?;
+    stream.write_all(request).await.ok()?;
+
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response).await.ok()?;
+
+    Some(response)
+}
+
+/// Splits a raw response into its status line, its headers without `connection` and `content-length`, and its body.
+fn split_response(response: &[u8]) -> (String, String, Vec<u8>) {
+    let end = response
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .unwrap_or(response.len());
+    let head = String::from_utf8_lossy(&response[..end]);
+    let body = response.get(end + 4..).unwrap_or_default().to_vec();
+    let mut lines = head.split("\r\n");
+    let status = lines
+        .next()
+        .and_then(|line| line.split_once(' '))
+        .map_or("502 Bad Gateway", |(_, status)| status)
+        .to_string();
+    let headers = lines
+        .filter(|line| {
+            let name = line.split(':').next().unwrap_or_default().trim();
+            !name.eq_ignore_ascii_case("connection") && !name.eq_ignore_ascii_case("content-length")
+        })
+        .map(|line| format!("{}\r\n", line))
+        .collect();
+
+    (status, headers, body)
+}
+
+/// Rewrites the response so the client closes the connection after reading it.
+fn close_after(response: &[u8]) -> Vec<u8> {
+    let (status, headers, body) = split_response(response);
+
+    http_response(&status, &headers, &body)
+}
+
+/// Cuts the body of the response in half, which breaks any JSON document.
+fn corrupt(response: &[u8]) -> Vec<u8> {
+    let (status, headers, body) = split_response(response);
+    let half = &body[..body.len() / 2];
+
+    http_response(&status, &headers, if half.is_empty() { b"{" } else { half })
+}
+
+fn http_response(status: &str, headers: &str, body: &[u8]) -> Vec<u8> {
+    let mut response = format!(
+        "HTTP/1.1 {}\r\n{}connection: close\r\ncontent-length: {}\r\n\r\n",
+        status,
+        headers,
+        body.len()
+    )
+    .into_bytes();
+    response.extend_from_slice(body);
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Chaos, ChaosConfig, Verdict};
+    use std::time::Duration;
+
+    #[test]
+    fn default_config_forwards_everything() {
+        let mut chaos = Chaos::new(ChaosConfig::default());
+
+        for _ in 0..100 {
+            assert_eq!(
+                chaos.verdict(),
+                Verdict::Forward {
+                    delay: Duration::ZERO,
+                    malformed: false
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn errors_come_in_bursts() {
+        let mut chaos = Chaos::new(ChaosConfig {
+            error_rate: 1.0,
+            error_burst: 3,
+            seed: Some(7),
+            ..ChaosConfig::default()
+        });
+
+        for _ in 0..6 {
+            assert!(matches!(chaos.verdict(), Verdict::Error { .. }));
+        }
+        assert_eq!(chaos.stats.errors, 6);
+    }
+
+    #[test]
+    fn same_seed_replays_the_same_faults() {
+        let config = ChaosConfig {
+            drop_rate: 0.3,
+            malformed_rate: 0.3,
+            latency: (Duration::from_millis(1), Duration::from_millis(50)),
+            seed: Some(42),
+            ..ChaosConfig::default()
+        };
+        let mut first = Chaos::new(config.clone());
+        let mut second = Chaos::new(config);
+
+        for _ in 0..50 {
+            assert_eq!(first.verdict(), second.verdict());
+        }
+        assert!(first.stats.dropped > 0);
+        assert!(first.stats.malformed > 0);
+    }
+}
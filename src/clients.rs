@@ -0,0 +1,458 @@
+//! Command rates, rate limits and temporary bans for the clients of the gateways.
+//!
+//! A `ClientTracker` counts the commands each `auth::Identity` sends through `rest::RestApi` and
+//! `websocket::WebSocketServer`, refuses the commands beyond an optional limit per minute, and
+//! keeps clients banned for a while. Share one tracker between both gateways, with
+//! `RestApi::clients` and `WebSocketServer::start_with_clients`, so a ban applies everywhere.
+//!
+//! Moderators, named with `ClientTracker::moderator`, are never limited or banned, and are the
+//! only clients allowed on the `/admin` routes and `/metrics` of `RestApi`.
+//!
+//! `ClientSummary` condenses the counts into the few numbers worth reading mid-session, as printed
+//! by `kenku-ctl admin insights`. Nothing leaves the gateway but what its moderators ask for.
+use crate::auth::Identity;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    fmt,
+    fmt::Write,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// The window command rates are counted over.
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// The longest ban, about thirty years.
+const MAX_BAN: Duration = Duration::from_secs(30 * 365 * 24 * 60 * 60);
+
+/// A metric served by `ClientTracker::metrics`: its name, type, help and value for a client.
+type Metric = (
+    &'static str,
+    &'static str,
+    &'static str,
+    fn(&ClientStats) -> u64,
+);
+
+/// Counts, limits and bans the clients of the gateways.
+///
+/// Clones share their counts and bans.
+///
+/// # Fields
+///
+/// * `limit` - How many commands a client may send each minute, or `None` for no limit.
+/// * `moderators` - The names of the clients allowed to moderate the others.
+/// * `clients` - What is known of each client, by name.
+#[derive(Debug, Clone, Default)]
+pub struct ClientTracker {
+    limit: Option<u32>,
+    moderators: BTreeSet<String>,
+    clients: Arc<Mutex<BTreeMap<String, Client>>>,
+}
+
+/// What a tracker knows of one client.
+///
+/// # Fields
+///
+/// * `sent` - How many of its commands were let through.
+/// * `refused` - How many of its commands were refused.
+/// * `recent` - When its commands of the last minute were let through.
+/// * `banned_until` - When its ban ends, if it is banned.
+#[derive(Debug, Default)]
+struct Client {
+    sent: u64,
+    refused: u64,
+    recent: VecDeque<Instant>,
+    banned_until: Option<Instant>,
+}
+
+/// Represents the counts of one client, as served by `GET /admin/clients`.
+///
+/// # Fields
+///
+/// * `name` - The name of the client's `Identity`.
+/// * `sent` - How many of its commands were let through.
+/// * `refused` - How many of its commands were refused, for the limit or a ban.
+/// * `last_minute` - How many commands it sent in the last minute.
+/// * `banned_secs` - How many seconds are left of its ban, if it is banned.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClientStats {
+    pub name: String,
+    pub sent: u64,
+    pub refused: u64,
+    pub last_minute: usize,
+    pub banned_secs: Option<u64>,
+}
+
+/// Represents a summary of the counts of every client.
+///
+/// # Fields
+///
+/// * `clients` - How many clients were counted.
+/// * `sent` - How many commands were let through, for every client.
+/// * `refused` - How many commands were refused, for every client.
+/// * `banned` - How many clients are banned.
+/// * `busiest` - The client that sent the most commands, with its count, if any sent one.
+/// * `most_refused` - The client with the most refused commands, with its count, if any was refused.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientSummary {
+    pub clients: usize,
+    pub sent: u64,
+    pub refused: u64,
+    pub banned: usize,
+    pub busiest: Option<(String, u64)>,
+    pub most_refused: Option<(String, u64)>,
+}
+
+/// Represents why the commands of a client were refused.
+///
+/// # Variants
+///
+/// * `Banned` - The client is banned, for the time held.
+/// * `RateLimited` - The client already sent as many commands as allowed this minute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Refusal {
+    Banned(Duration),
+    RateLimited,
+}
+
+impl ClientTracker {
+    /// Creates a tracker without a limit, moderators or bans.
+    pub fn new() -> ClientTracker {
+        ClientTracker::default()
+    }
+
+    /// Refuses the commands of a client beyond `commands` in a minute.
+    pub fn limit(mut self, commands: u32) -> ClientTracker {
+        self.limit = Some(commands);
+        self
+    }
+
+    /// Allows the client called `name` to moderate the others.
+    pub fn moderator(mut self, name: impl Into<String>) -> ClientTracker {
+        self.moderators.insert(name.into());
+        self
+    }
+
+    /// Returns `true` if `identity` is a moderator.
+    pub fn is_moderator(&self, identity: &Identity) -> bool {
+        self.moderators.contains(&identity.name)
+    }
+
+    /// Checks that `identity` is not banned, without counting a command.
+    pub fn check(&self, identity: &Identity) -> Result<(), Refusal> {
+        if self.is_moderator(identity) {
+            return Ok(());
+        }
+
+        let clients = self.clients.lock().unwrap();
+
+        match clients.get(&identity.name).and_then(Client::ban_left) {
+            Some(left) => Err(Refusal::Banned(left)),
+            None => Ok(()),
+        }
+    }
+
+    /// Counts `commands` commands from `identity`, unless it is banned or they would go over the limit.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the `Refusal` if the commands must not be sent. Refused commands are
+    /// counted too.
+    pub fn record(&self, identity: &Identity, commands: usize) -> Result<(), Refusal> {
+        if self.is_moderator(identity) {
+            return Ok(());
+        }
+
+        let mut clients = self.clients.lock().unwrap();
+        let client = clients.entry(identity.name.clone()).or_default();
+        let now = Instant::now();
+
+        while client
+            .recent
+            .front()
+            .is_some_and(|sent| now.duration_since(*sent) >= RATE_WINDOW)
+        {
+            client.recent.pop_front();
+        }
+
+        let refusal = match client.ban_left() {
+            Some(left) => Some(Refusal::Banned(left)),
+            None if self
+                .limit
+                .is_some_and(|limit| client.recent.len() + commands > limit as usize) =>
+            {
+                Some(Refusal::RateLimited)
+            }
+            None => None,
+        };
+
+        match refusal {
+            Some(refusal) => {
+                client.refused += commands as u64;
+                Err(refusal)
+            }
+            None => {
+                client.sent += commands as u64;
+                client.recent.extend(std::iter::repeat_n(now, commands));
+                Ok(())
+            }
+        }
+    }
+
+    /// Bans the client called `name` for `duration`, replacing any ban it had.
+    ///
+    /// Bans longer than thirty years are shortened to thirty years.
+    pub fn ban(&self, name: &str, duration: Duration) {
+        let mut clients = self.clients.lock().unwrap();
+        let client = clients.entry(name.to_string()).or_default();
+
+        client.banned_until = Some(Instant::now() + duration.min(MAX_BAN));
+    }
+
+    /// Lifts the ban of the client called `name`.
+    ///
+    /// # Returns
+    ///
+    /// This function returns `true` if the client was banned.
+    pub fn unban(&self, name: &str) -> bool {
+        let mut clients = self.clients.lock().unwrap();
+
+        clients
+            .get_mut(name)
+            .and_then(|client| client.banned_until.take())
+            .is_some_and(|until| until > Instant::now())
+    }
+
+    /// Returns the counts of every client seen or banned, ordered by name.
+    pub fn clients(&self) -> Vec<ClientStats> {
+        let clients = self.clients.lock().unwrap();
+        let now = Instant::now();
+
+        clients
+            .iter()
+            .map(|(name, client)| ClientStats {
+                name: name.clone(),
+                sent: client.sent,
+                refused: client.refused,
+                last_minute: client
+                    .recent
+                    .iter()
+                    .filter(|sent| now.duration_since(**sent) < RATE_WINDOW)
+                    .count(),
+                banned_secs: client.ban_left().map(|left| left.as_secs()),
+            })
+            .collect()
+    }
+
+    /// Returns the counts of every client in the Prometheus text format, as served by `GET /metrics`.
+    pub fn metrics(&self) -> String {
+        let clients = self.clients();
+        let mut text = String::new();
+        let metrics: [Metric; 4] = [
+            (
+                "kenku_gateway_commands_total",
+                "counter",
+                "Commands let through the gateways.",
+                |client| client.sent,
+            ),
+            (
+                "kenku_gateway_commands_refused_total",
+                "counter",
+                "Commands refused for the rate limit or a ban.",
+                |client| client.refused,
+            ),
+            (
+                "kenku_gateway_commands_last_minute",
+                "gauge",
+                "Commands let through in the last minute.",
+                |client| client.last_minute as u64,
+            ),
+            (
+                "kenku_gateway_client_banned",
+                "gauge",
+                "Whether the client is banned.",
+                |client| u64::from(client.banned_secs.is_some()),
+            ),
+        ];
+
+        for (metric, kind, help, value) in metrics {
+            let _ = writeln!(text, "# HELP {} {}", metric, help);
+            let _ = writeln!(text, "# TYPE {} {}", metric, kind);
+
+            for client in &clients {
+                let _ = writeln!(
+                    text,
+                    "{}{{client=\"{}\"}} {}",
+                    metric,
+                    label(&client.name),
+                    value(client)
+                );
+            }
+        }
+
+        text
+    }
+}
+
+impl ClientSummary {
+    /// Summarizes `clients`, as returned by `ClientTracker::clients` or `GET /admin/clients`.
+    ///
+    /// Ties for the busiest or most refused client go to the first one in `clients`.
+    pub fn new(clients: &[ClientStats]) -> ClientSummary {
+        let top = |count: fn(&ClientStats) -> u64| {
+            clients
+                .iter()
+                .filter(|client| count(client) > 0)
+                .rev()
+                .max_by_key(|client| count(client))
+                .map(|client| (client.name.clone(), count(client)))
+        };
+
+        ClientSummary {
+            clients: clients.len(),
+            sent: clients.iter().map(|client| client.sent).sum(),
+            refused: clients.iter().map(|client| client.refused).sum(),
+            banned: clients
+                .iter()
+                .filter(|client| client.banned_secs.is_some())
+                .count(),
+            busiest: top(|client| client.sent),
+            most_refused: top(|client| client.refused),
+        }
+    }
+
+    /// Returns the share of the commands that were refused, from 0 to 1, or 0 without commands.
+    pub fn refused_ratio(&self) -> f64 {
+        match self.sent + self.refused {
+            0 => 0.0,
+            total => self.refused as f64 / total as f64,
+        }
+    }
+}
+
+impl Client {
+    /// Returns how long the ban of the client still lasts, if it is banned.
+    fn ban_left(&self) -> Option<Duration> {
+        self.banned_until?
+            .checked_duration_since(Instant::now())
+            .filter(|left| !left.is_zero())
+    }
+}
+
+impl fmt::Display for Refusal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Refusal::Banned(left) => write!(f, "banned for {} more seconds", left.as_secs() + 1),
+            Refusal::RateLimited => write!(f, "too many commands, try again in a minute"),
+        }
+    }
+}
+
+impl std::error::Error for Refusal {}
+
+impl fmt::Display for ClientSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} clients, {} commands sent, {} refused ({:.0}%), {} banned",
+            self.clients,
+            self.sent,
+            self.refused,
+            self.refused_ratio() * 100.0,
+            self.banned
+        )?;
+
+        if let Some((name, sent)) = &self.busiest {
+            write!(f, "\nbusiest: {} ({} sent)", name, sent)?;
+        }
+
+        if let Some((name, refused)) = &self.most_refused {
+            write!(f, "\nmost refused: {} ({} refused)", name, refused)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Escapes `value` for a Prometheus label.
+fn label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ClientSummary, ClientTracker, Refusal};
+    use crate::auth::Identity;
+    use std::time::Duration;
+
+    fn client(name: &str) -> Identity {
+        Identity {
+            name: name.to_string(),
+            admin: true,
+        }
+    }
+
+    #[test]
+    fn commands_beyond_the_limit_are_refused_and_counted() {
+        let tracker = ClientTracker::new().limit(3).moderator("gm");
+
+        assert_eq!(tracker.record(&client("ana"), 2), Ok(()));
+        assert_eq!(tracker.record(&client("ana"), 2), Err(Refusal::RateLimited));
+        assert_eq!(tracker.record(&client("ana"), 1), Ok(()));
+        assert_eq!(tracker.record(&client("gm"), 10), Ok(()));
+
+        let ana = &tracker.clients()[0];
+        assert_eq!((ana.sent, ana.refused, ana.last_minute), (3, 2, 3));
+        assert!(tracker
+            .metrics()
+            .contains("kenku_gateway_commands_refused_total{client=\"ana\"} 2\n"));
+    }
+
+    #[test]
+    fn banned_clients_are_refused_until_unbanned() {
+        let tracker = ClientTracker::new().moderator("gm");
+
+        tracker.ban("ana", Duration::from_secs(60));
+        tracker.ban("gm", Duration::MAX);
+
+        assert!(matches!(
+            tracker.record(&client("ana"), 1),
+            Err(Refusal::Banned(_))
+        ));
+        assert!(tracker.check(&client("ana")).is_err());
+        assert_eq!(tracker.check(&client("gm")), Ok(()));
+        assert_eq!(tracker.clients()[0].banned_secs, Some(59));
+
+        assert!(tracker.unban("ana"));
+        assert!(!tracker.unban("ana"));
+        assert_eq!(tracker.record(&client("ana"), 1), Ok(()));
+    }
+
+    #[test]
+    fn summaries_name_the_busiest_and_most_refused_clients() {
+        let tracker = ClientTracker::new().limit(3);
+
+        assert_eq!(tracker.record(&client("ana"), 3), Ok(()));
+        assert_eq!(tracker.record(&client("bob"), 1), Ok(()));
+        assert_eq!(tracker.record(&client("bob"), 3), Err(Refusal::RateLimited));
+        tracker.ban("cid", Duration::from_secs(60));
+
+        let summary = ClientSummary::new(&tracker.clients());
+        assert_eq!(summary.busiest, Some(("ana".to_string(), 3)));
+        assert_eq!(summary.most_refused, Some(("bob".to_string(), 3)));
+        assert_eq!(
+            summary.to_string(),
+            "3 clients, 4 commands sent, 3 refused (43%), 1 banned\n\
+             busiest: ana (3 sent)\n\
+             most refused: bob (3 refused)"
+        );
+        assert_eq!(
+            ClientSummary::new(&[]).to_string(),
+            "0 clients, 0 commands sent, 0 refused (0%), 0 banned"
+        );
+    }
+}
@@ -0,0 +1,660 @@
+//! Layered configuration for applications built on this crate.
+//!
+//! A `Config` is put together from up to four layers, each one overriding the previous:
+//! the defaults, a file, the environment and command-line flags. `EffectiveConfig` remembers
+//! which layer every value came from, so `kenku config show --effective` style output is a
+//! `println!("{}", effective)` away.
+//!
+//! Configuration files can also name `Scene`s and `Binding`s, so CLIs and bots built on this
+//! crate share one file format. With the `toml` feature, a `kenku.toml` can read:
+//!
+//! ```toml
+//! host = "table.local"
+//! timeout_ms = 500
+//!
+//! [scenes.tavern]
+//! name = "tavern"
+//! track = "track-id"
+//! volume = 0.6
+//! sounds = ["fire-id", "crowd-id"]
+//!
+//! [bindings]
+//! f1 = { scene = "tavern" }
+//! f2 = { execute = [{ Playback = "PlaylistPlaybackPause" }] }
+//! ```
+//!
+//! ```no_run
+//! # fn load() -> Result<(), kenku_control::KenkuError> {
+//! use kenku_control::config::ConfigLoader;
+//!
+//! let effective = ConfigLoader::new()
+//!     .file("kenku.json")?
+//!     .env()?
+//!     .args(std::env::args().skip(1))?
+//!     .load()?;
+//!
+//! println!("{}", effective);
+//! let controller = effective.config.builder().build()?;
+//! # Ok(())
+//! # }
+//! ```
+use crate::{
+    batch::BatchReport, format, scenes::Scene, Controller, ControllerBuilder,
+    KenkuCommandWithPayload, KenkuError,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt, path::Path, time::Duration};
+
+/// The prefix of the environment variables read by `ConfigLoader::env`.
+pub const ENV_PREFIX: &str = "KENKU_";
+
+/// Represents the settings needed to reach a Kenku Remote.
+///
+/// # Fields
+///
+/// * `host` - The IP address or DNS name of the Kenku Remote.
+/// * `port` - The port of the Kenku Remote.
+/// * `timeout_ms` - The total timeout of every request, in milliseconds.
+/// * `connect_timeout_ms` - The timeout for establishing the connection, in milliseconds, if any.
+/// * `retries` - How many times a request is retried after a connection error or a timeout.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Config {
+    pub host: String,
+    pub port: u16,
+    pub timeout_ms: u64,
+    pub connect_timeout_ms: Option<u64>,
+    pub retries: u32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            host: "127.0.0.1".to_string(),
+            port: 3333,
+            timeout_ms: 100,
+            connect_timeout_ms: None,
+            retries: 0,
+        }
+    }
+}
+
+impl Config {
+    /// Checks that every value is usable.
+    ///
+    /// # Returns
+    ///
+    /// This function returns `Ok(())`, or a `KenkuError::InvalidInput` naming the first invalid value.
+    pub fn validate(&self) -> Result<(), KenkuError> {
+        if self.host.trim().is_empty() {
+            return Err(invalid("host", "must not be empty"));
+        }
+
+        if self.port == 0 {
+            return Err(invalid("port", "must be between 1 and 65535"));
+        }
+
+        if self.timeout_ms == 0 {
+            return Err(invalid("timeout_ms", "must be greater than zero"));
+        }
+
+        if self.connect_timeout_ms == Some(0) {
+            return Err(invalid("connect_timeout_ms", "must be greater than zero"));
+        }
+
+        Ok(())
+    }
+
+    /// Returns a `ControllerBuilder` set up with this configuration.
+    pub fn builder(&self) -> ControllerBuilder {
+        let builder = ControllerBuilder::new(self.host.clone(), self.port)
+            .timeout(Duration::from_millis(self.timeout_ms))
+            .retries(self.retries);
+
+        match self.connect_timeout_ms {
+            Some(connect_timeout) => {
+                builder.connect_timeout(Duration::from_millis(connect_timeout))
+            }
+            None => builder,
+        }
+    }
+}
+
+/// Represents where a configuration value came from.
+///
+/// # Variants
+///
+/// * `Default` - The built-in default.
+/// * `File` - A configuration file, with its path.
+/// * `Env` - An environment variable, with its name.
+/// * `Cli` - A command-line flag, with its name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    Default,
+    File(String),
+    Env(String),
+    Cli(String),
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Default => write!(f, "default"),
+            Source::File(path) => write!(f, "file {}", path),
+            Source::Env(name) => write!(f, "env {}", name),
+            Source::Cli(flag) => write!(f, "flag {}", flag),
+        }
+    }
+}
+
+/// Represents what a named binding, such as a hotkey or a chat command, does.
+///
+/// # Variants
+///
+/// * `Scene` - Activates the scene with the given name.
+/// * `Execute` - Sends the commands, in order.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Binding {
+    Scene(String),
+    Execute(Vec<KenkuCommandWithPayload>),
+}
+
+/// Represents a loaded `Config` and the layer every value came from.
+///
+/// # Fields
+///
+/// * `config` - The validated configuration.
+/// * `sources` - The source of each value, keyed by field name.
+/// * `scenes` - The scenes of every configuration file, by name. A later file replaces a scene of the same name.
+/// * `bindings` - The bindings of every configuration file, by name. A later file replaces a binding of the same name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EffectiveConfig {
+    pub config: Config,
+    pub sources: BTreeMap<&'static str, Source>,
+    pub scenes: BTreeMap<String, Scene>,
+    pub bindings: BTreeMap<String, Binding>,
+}
+
+impl EffectiveConfig {
+    /// Runs the binding `name` on `controller`.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the `BatchReport` of the commands sent, a `KenkuError::NotFound` if there is no binding `name` or it names a missing scene, or the `KenkuError` that stopped a scene activation.
+    pub async fn trigger(
+        &self,
+        controller: &Controller,
+        name: &str,
+    ) -> Result<BatchReport, KenkuError> {
+        let binding = self
+            .bindings
+            .get(name)
+            .ok_or_else(|| KenkuError::NotFound(format!("binding {:?}", name)))?;
+
+        match binding {
+            Binding::Scene(scene) => {
+                self.scenes
+                    .get(scene)
+                    .ok_or_else(|| KenkuError::NotFound(format!("scene {:?}", scene)))?
+                    .activate(controller)
+                    .await
+            }
+            Binding::Execute(commands) => Ok(controller.execute_batch(commands.clone()).await),
+        }
+    }
+}
+
+impl fmt::Display for EffectiveConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let values = [
+            ("host", self.config.host.clone()),
+            ("port", self.config.port.to_string()),
+            ("timeout_ms", self.config.timeout_ms.to_string()),
+            (
+                "connect_timeout_ms",
+                self.config
+                    .connect_timeout_ms
+                    .map_or("none".to_string(), |value| value.to_string()),
+            ),
+            ("retries", self.config.retries.to_string()),
+        ];
+
+        for (key, value) in values {
+            let source = self.sources.get(key).unwrap_or(&Source::Default);
+            writeln!(f, "{} = {}  ({})", key, value, source)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Represents one layer of configuration, as written in a configuration file.
+///
+/// Every value is optional, and unknown keys are rejected. The environment and command-line
+/// layers only set the connection values.
+///
+/// # Fields
+///
+/// * `host`, `port`, `timeout_ms`, `connect_timeout_ms`, `retries` - Override the `Config` value of the same name.
+/// * `scenes` - Named scenes, such as `[scenes.tavern]` in TOML.
+/// * `bindings` - Named bindings, such as `f1 = { scene = "tavern" }` under `[bindings]` in TOML.
+#[serde_with::skip_serializing_none]
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigFile {
+    pub host: Option<String>,
+    pub port: Option<u16>,
+    pub timeout_ms: Option<u64>,
+    pub connect_timeout_ms: Option<u64>,
+    pub retries: Option<u32>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub scenes: BTreeMap<String, Scene>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub bindings: BTreeMap<String, Binding>,
+}
+
+/// Builds an `EffectiveConfig` from the defaults and any number of layers, applied in order.
+#[derive(Debug, Clone)]
+pub struct ConfigLoader {
+    config: Config,
+    sources: BTreeMap<&'static str, Source>,
+    scenes: BTreeMap<String, Scene>,
+    bindings: BTreeMap<String, Binding>,
+}
+
+impl Default for ConfigLoader {
+    fn default() -> Self {
+        ConfigLoader::new()
+    }
+}
+
+impl ConfigLoader {
+    /// Creates a `ConfigLoader` starting from `Config::default`.
+    pub fn new() -> ConfigLoader {
+        ConfigLoader {
+            config: Config::default(),
+            sources: BTreeMap::new(),
+            scenes: BTreeMap::new(),
+            bindings: BTreeMap::new(),
+        }
+    }
+
+    /// Applies the configuration file at `path`, in the format matching its extension.
+    ///
+    /// A missing file is skipped. Unknown keys are rejected. Scenes and bindings are added to those of earlier files, replacing the ones of the same name.
+    pub fn file(self, path: impl AsRef<Path>) -> Result<ConfigLoader, KenkuError> {
+        let path = path.as_ref();
+
+        if !path.exists() {
+            return Ok(self);
+        }
+
+        let layer: ConfigFile = format::load(path)?;
+        let source = path.display().to_string();
+
+        Ok(self.apply(layer, |_| Source::File(source.clone())))
+    }
+
+    /// Applies the `KENKU_*` environment variables of the current process.
+    ///
+    /// Other variables are skipped even when they are not valid UTF-8, but a `KENKU_*` variable
+    /// that is not is rejected with a `KenkuError::InvalidInput`.
+    pub fn env(self) -> Result<ConfigLoader, KenkuError> {
+        let mut vars = Vec::new();
+
+        for (name, value) in std::env::vars_os() {
+            if !name.as_encoded_bytes().starts_with(ENV_PREFIX.as_bytes()) {
+                continue;
+            }
+
+            match (name.into_string(), value.into_string()) {
+                (Ok(name), Ok(value)) => vars.push((name, value)),
+                (Ok(name), Err(_)) => {
+                    return Err(KenkuError::InvalidInput(format!(
+                        "the value of {} is not valid UTF-8",
+                        name
+                    )))
+                }
+                (Err(name), _) => {
+                    return Err(KenkuError::InvalidInput(format!(
+                        "the variable {:?} is not valid UTF-8",
+                        name
+                    )))
+                }
+            }
+        }
+
+        self.env_vars(vars)
+    }
+
+    /// Applies the `KENKU_*` variables of `vars`, such as `KENKU_PORT=3334`. Other variables are ignored.
+    pub fn env_vars(
+        self,
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<ConfigLoader, KenkuError> {
+        let mut layer = ConfigFile::default();
+        let mut names = BTreeMap::new();
+
+        for (name, value) in vars {
+            let Some(key) = name.strip_prefix(ENV_PREFIX) else {
+                continue;
+            };
+            let key = key.to_ascii_lowercase();
+
+            if let Some(field) = set(&mut layer, &key, &value, &name)? {
+                names.insert(field, name);
+            }
+        }
+
+        Ok(self.apply(layer, |field| Source::Env(names[field].clone())))
+    }
+
+    /// Applies command-line flags such as `--port 3334` or `--timeout-ms=500`.
+    ///
+    /// Arguments that are not configuration flags are ignored, so the full argument list of a
+    /// program can be passed.
+    pub fn args<S: AsRef<str>>(
+        self,
+        args: impl IntoIterator<Item = S>,
+    ) -> Result<ConfigLoader, KenkuError> {
+        let args: Vec<String> = args
+            .into_iter()
+            .map(|arg| arg.as_ref().to_string())
+            .collect();
+        let mut layer = ConfigFile::default();
+        let mut flags = BTreeMap::new();
+        let mut index = 0;
+
+        while index < args.len() {
+            let Some(flag) = args[index].strip_prefix("--") else {
+                index += 1;
+                continue;
+            };
+            let (name, inline) = match flag.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (flag, None),
+            };
+            let key = name.replace('-', "_");
+
+            if !FIELDS.contains(&key.as_str()) {
+                index += 1;
+                continue;
+            }
+
+            let value = match inline {
+                Some(value) => value,
+                None => {
+                    index += 1;
+                    args.get(index).cloned().ok_or_else(|| {
+                        KenkuError::InvalidInput(format!("--{} needs a value", name))
+                    })?
+                }
+            };
+
+            if let Some(field) = set(&mut layer, &key, &value, &format!("--{}", name))? {
+                flags.insert(field, format!("--{}", name));
+            }
+
+            index += 1;
+        }
+
+        Ok(self.apply(layer, |field| Source::Cli(flags[field].clone())))
+    }
+
+    /// Validates the result of every layer.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the `EffectiveConfig`, or a `KenkuError::InvalidInput` if a value is invalid.
+    pub fn load(self) -> Result<EffectiveConfig, KenkuError> {
+        self.config.validate()?;
+
+        Ok(EffectiveConfig {
+            config: self.config,
+            sources: self.sources,
+            scenes: self.scenes,
+            bindings: self.bindings,
+        })
+    }
+
+    /// Overrides every value set in `layer`, recording `source` for it.
+    fn apply(mut self, layer: ConfigFile, source: impl Fn(&'static str) -> Source) -> ConfigLoader {
+        self.scenes.extend(layer.scenes);
+        self.bindings.extend(layer.bindings);
+
+        if let Some(host) = layer.host {
+            self.config.host = host;
+            self.sources.insert("host", source("host"));
+        }
+
+        if let Some(port) = layer.port {
+            self.config.port = port;
+            self.sources.insert("port", source("port"));
+        }
+
+        if let Some(timeout) = layer.timeout_ms {
+            self.config.timeout_ms = timeout;
+            self.sources.insert("timeout_ms", source("timeout_ms"));
+        }
+
+        if let Some(connect_timeout) = layer.connect_timeout_ms {
+            self.config.connect_timeout_ms = Some(connect_timeout);
+            self.sources
+                .insert("connect_timeout_ms", source("connect_timeout_ms"));
+        }
+
+        if let Some(retries) = layer.retries {
+            self.config.retries = retries;
+            self.sources.insert("retries", source("retries"));
+        }
+
+        self
+    }
+}
+
+/// The names of the configuration fields.
+const FIELDS: [&str; 5] = [
+    "host",
+    "port",
+    "timeout_ms",
+    "connect_timeout_ms",
+    "retries",
+];
+
+/// Parses `value` into the field `key` of `layer`.
+///
+/// # Returns
+///
+/// This function returns the field name, `None` when `key` is not a field, or a `KenkuError::InvalidInput` naming `origin` when `value` does not parse.
+fn set(
+    layer: &mut ConfigFile,
+    key: &str,
+    value: &str,
+    origin: &str,
+) -> Result<Option<&'static str>, KenkuError> {
+    let parse_error = |field: &str| {
+        KenkuError::InvalidInput(format!("{}: invalid {} {:?}", origin, field, value))
+    };
+
+    let field = match key {
+        "host" => {
+            layer.host = Some(value.to_string());
+            "host"
+        }
+        "port" => {
+            layer.port = Some(value.parse().map_err(|_| parse_error("port"))?);
+            "port"
+        }
+        "timeout_ms" => {
+            layer.timeout_ms = Some(value.parse().map_err(|_| parse_error("timeout_ms"))?);
+            "timeout_ms"
+        }
+        "connect_timeout_ms" => {
+            layer.connect_timeout_ms = Some(
+                value
+                    .parse()
+                    .map_err(|_| parse_error("connect_timeout_ms"))?,
+            );
+            "connect_timeout_ms"
+        }
+        "retries" => {
+            layer.retries = Some(value.parse().map_err(|_| parse_error("retries"))?);
+            "retries"
+        }
+        _ => return Ok(None),
+    };
+
+    Ok(Some(field))
+}
+
+fn invalid(field: &str, reason: &str) -> KenkuError {
+    KenkuError::InvalidInput(format!("config {} {}", field, reason))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ConfigLoader, Source};
+
+    fn env(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+        vars.iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unrelated_variables_that_are_not_utf8_are_skipped() {
+        use std::os::unix::ffi::OsStrExt;
+
+        std::env::set_var(
+            "KENKU_CONTROL_TEST_UNRELATED",
+            std::ffi::OsStr::from_bytes(b"\xff"),
+        );
+        std::env::set_var(
+            "OTHER_KENKU_CONTROL_TEST",
+            std::ffi::OsStr::from_bytes(b"\xff"),
+        );
+        let rejected = ConfigLoader::new().env();
+        std::env::remove_var("KENKU_CONTROL_TEST_UNRELATED");
+        let accepted = ConfigLoader::new().env();
+        std::env::remove_var("OTHER_KENKU_CONTROL_TEST");
+
+        assert!(rejected.is_err());
+        assert!(accepted.is_ok());
+    }
+
+    #[test]
+    fn later_layers_win() {
+        let path =
+            std::env::temp_dir().join(format!("kenku_control_config_{}.json", std::process::id()));
+        std::fs::write(
+            &path,
+            r#"{"host": "table.local", "port": 4000, "retries": 1}"#,
+        )
+        .unwrap();
+
+        let effective = ConfigLoader::new()
+            .file(&path)
+            .unwrap()
+            .env_vars(env(&[("KENKU_PORT", "4001"), ("HOME", "/root")]))
+            .unwrap()
+            .args(["kenku", "--retries", "3", "--timeout-ms=250"])
+            .unwrap()
+            .load()
+            .unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(effective.config.host, "table.local");
+        assert_eq!(effective.config.port, 4001);
+        assert_eq!(effective.config.retries, 3);
+        assert_eq!(effective.config.timeout_ms, 250);
+        assert_eq!(
+            effective.sources["port"],
+            Source::Env("KENKU_PORT".to_string())
+        );
+        assert_eq!(
+            effective.sources["retries"],
+            Source::Cli("--retries".to_string())
+        );
+        assert!(effective
+            .to_string()
+            .contains("connect_timeout_ms = none  (default)"));
+    }
+
+    #[test]
+    fn invalid_values_are_rejected() {
+        assert!(ConfigLoader::new()
+            .env_vars(env(&[("KENKU_PORT", "many")]))
+            .is_err());
+        assert!(ConfigLoader::new()
+            .args(["--port", "0"])
+            .unwrap()
+            .load()
+            .is_err());
+        assert!(ConfigLoader::new().args(["--host"]).is_err());
+    }
+
+    #[test]
+    fn unknown_file_keys_are_rejected() {
+        let path = std::env::temp_dir().join(format!(
+            "kenku_control_config_unknown_{}.json",
+            std::process::id()
+        ));
+        std::fs::write(&path, r#"{"hots": "table.local"}"#).unwrap();
+
+        let result = ConfigLoader::new().file(&path);
+        let _ = std::fs::remove_file(&path);
+
+        assert!(result.is_err());
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn toml_files_name_scenes_and_bindings() {
+        use super::Binding;
+        use crate::{scenes::Scene, KenkuPlaybackCommand, Volume};
+
+        let path =
+            std::env::temp_dir().join(format!("kenku_control_config_{}.toml", std::process::id()));
+        std::fs::write(
+            &path,
+            r#"
+host = "table.local"
+timeout_ms = 500
+
+[scenes.tavern]
+name = "tavern"
+track = "t1"
+volume = 0.6
+sounds = ["fire", "crowd"]
+
+[bindings]
+f1 = { scene = "tavern" }
+f2 = { execute = [{ Playback = "PlaylistPlaybackPause" }] }
+"#,
+        )
+        .unwrap();
+
+        let effective = ConfigLoader::new().file(&path).unwrap().load().unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(effective.config.host, "table.local");
+        assert_eq!(effective.config.timeout_ms, 500);
+        assert_eq!(
+            effective.scenes["tavern"],
+            Scene::new("tavern")
+                .track("t1")
+                .volume(Volume::from_percent(60))
+                .sound("fire")
+                .sound("crowd")
+        );
+        assert_eq!(
+            effective.bindings["f1"],
+            Binding::Scene("tavern".to_string())
+        );
+        assert_eq!(
+            effective.bindings["f2"],
+            Binding::Execute(vec![KenkuPlaybackCommand::PlaylistPlaybackPause.into()])
+        );
+    }
+}
@@ -0,0 +1,277 @@
+//! Finding Kenku Remote instances on the local network.
+//!
+//! Kenku Remote does not announce itself on the network, so browsing with mDNS only finds hosts
+//! where the service was published, for example with
+//! `avahi-publish -s "Kenku" _kenku-remote._tcp 3333 version=1`. On networks without mDNS,
+//! `scan_subnet` probes every host of a subnet instead.
+use crate::{KenkuAddress, KenkuError};
+#[cfg(feature = "mdns")]
+use futures_util::Stream;
+use futures_util::StreamExt;
+use std::{
+    net::{Ipv4Addr, SocketAddr, SocketAddrV4},
+    time::Duration,
+};
+use tokio::net::TcpStream;
+
+/// How many hosts `scan_subnet` probes at the same time.
+pub const DEFAULT_SCAN_CONCURRENCY: usize = 64;
+
+/// The largest subnet `scan_subnet` accepts, as a prefix length.
+const MIN_SCAN_PREFIX: u8 = 16;
+
+/// The mDNS service type browsed by default.
+#[cfg(feature = "mdns")]
+pub const KENKU_SERVICE_TYPE: &str = "_kenku-remote._tcp.local.";
+
+/// Represents a Kenku Remote found on the network.
+///
+/// # Fields
+///
+/// * `name` - The name the remote was announced under, or its address when it was found by scanning.
+/// * `address` - The address to build a `Controller` with.
+/// * `version` - The version announced in the `version` TXT property, if any.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiscoveredRemote {
+    pub name: String,
+    pub address: KenkuAddress,
+    pub version: Option<String>,
+}
+
+/// Browses the local network for Kenku Remotes announced over mDNS.
+///
+/// The returned stream yields every remote as soon as it is resolved and keeps browsing until it
+/// is dropped. Consume it with `futures_util::StreamExt`.
+///
+/// # Arguments
+///
+/// * `service_type` - The fully qualified service type to browse, usually `KENKU_SERVICE_TYPE`.
+///
+/// # Returns
+///
+/// This function returns the stream of discovered remotes, or a `KenkuError::Io` if the mDNS daemon could not be started.
+#[cfg(feature = "mdns")]
+pub fn browse_mdns(service_type: &str) -> Result<impl Stream<Item = DiscoveredRemote>, KenkuError> {
+    let daemon = mdns_sd::ServiceDaemon::new().map_err(mdns_error)?;
+    let receiver = daemon.browse(service_type).map_err(mdns_error)?;
+    let browser = Browser { daemon, receiver };
+
+    Ok(futures_util::stream::unfold(
+        browser,
+        |browser| async move {
+            loop {
+                match browser.receiver.recv_async().await {
+                    Ok(mdns_sd::ServiceEvent::ServiceResolved(info)) => {
+                        if let Some(remote) = remote_from_service(&info) {
+                            return Some((remote, browser));
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(_) => return None,
+                }
+            }
+        },
+    ))
+}
+
+/// Keeps the mDNS daemon alive while its stream is in use and shuts it down afterwards.
+#[cfg(feature = "mdns")]
+struct Browser {
+    daemon: mdns_sd::ServiceDaemon,
+    receiver: mdns_sd::Receiver<mdns_sd::ServiceEvent>,
+}
+
+#[cfg(feature = "mdns")]
+impl Drop for Browser {
+    fn drop(&mut self) {
+        let _ = self.daemon.shutdown();
+    }
+}
+
+/// Converts a resolved mDNS service into a `DiscoveredRemote`, preferring an IPv4 address.
+#[cfg(feature = "mdns")]
+fn remote_from_service(info: &mdns_sd::ServiceInfo) -> Option<DiscoveredRemote> {
+    let addresses = info.get_addresses();
+    let ip = addresses
+        .iter()
+        .find(|ip| ip.is_ipv4())
+        .or_else(|| addresses.iter().next())?;
+    let name = info
+        .get_fullname()
+        .strip_suffix(info.get_type())
+        .map_or(info.get_fullname(), |name| name.trim_end_matches('.'));
+
+    Some(DiscoveredRemote {
+        name: name.to_string(),
+        address: KenkuAddress::new(ip.to_string(), info.get_port()),
+        version: info.get_property_val_str("version").map(str::to_string),
+    })
+}
+
+/// Probes every host of an IPv4 subnet for a Kenku Remote listening on `port`.
+///
+/// A host counts as a Kenku Remote when it accepts a TCP connection on `port` within `timeout`,
+/// the same check `check_kenku_server_state` does with a one-second timeout. At most `DEFAULT_SCAN_CONCURRENCY` hosts are probed at once.
+///
+/// ```no_run
+/// # async fn scan() -> Result<(), kenku_control::KenkuError> {
+/// use kenku_control::discovery::scan_subnet;
+/// use std::time::Duration;
+///
+/// for remote in scan_subnet("192.168.1.0/24", 3333, Duration::from_millis(200)).await? {
+///     println!("found a Kenku Remote at {}", remote.address);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Arguments
+///
+/// * `cidr` - The subnet to scan, such as `192.168.1.0/24`. Prefixes shorter than `/16` are refused.
+/// * `port` - The port Kenku Remote listens on.
+/// * `timeout` - How long to wait for each host to accept the connection.
+///
+/// # Returns
+///
+/// This function returns the reachable remotes ordered by address, or a `KenkuError::InvalidInput` if `cidr` is not a valid subnet.
+pub async fn scan_subnet(
+    cidr: &str,
+    port: u16,
+    timeout: Duration,
+) -> Result<Vec<DiscoveredRemote>, KenkuError> {
+    scan_subnet_with_concurrency(cidr, port, timeout, DEFAULT_SCAN_CONCURRENCY).await
+}
+
+/// Probes every host of an IPv4 subnet like `scan_subnet`, with at most `concurrency` probes at once.
+pub async fn scan_subnet_with_concurrency(
+    cidr: &str,
+    port: u16,
+    timeout: Duration,
+    concurrency: usize,
+) -> Result<Vec<DiscoveredRemote>, KenkuError> {
+    let hosts = subnet_hosts(cidr)?;
+
+    let mut found: Vec<Ipv4Addr> = futures_util::stream::iter(hosts)
+        .map(|ip| async move {
+            let address = SocketAddr::V4(SocketAddrV4::new(ip, port));
+
+            match tokio::time::timeout(timeout, TcpStream::connect(address)).await {
+                Ok(Ok(_)) => Some(ip),
+                _ => None,
+            }
+        })
+        .buffer_unordered(concurrency.max(1))
+        .filter_map(|ip| async move { ip })
+        .collect()
+        .await;
+
+    found.sort();
+
+    Ok(found
+        .into_iter()
+        .map(|ip| DiscoveredRemote {
+            name: ip.to_string(),
+            address: KenkuAddress::from(SocketAddrV4::new(ip, port)),
+            version: None,
+        })
+        .collect())
+}
+
+/// Lists the host addresses of an IPv4 subnet, leaving out the network and broadcast addresses.
+fn subnet_hosts(cidr: &str) -> Result<Vec<Ipv4Addr>, KenkuError> {
+    let invalid = || KenkuError::InvalidInput(format!("invalid IPv4 subnet: {}", cidr));
+    let (ip, prefix) = cidr.split_once('/').ok_or_else(invalid)?;
+    let ip: Ipv4Addr = ip.trim().parse().map_err(|_| invalid())?;
+    let prefix: u8 = prefix.trim().parse().map_err(|_| invalid())?;
+
+    if prefix > 32 {
+        return Err(invalid());
+    }
+
+    if prefix < MIN_SCAN_PREFIX {
+        return Err(KenkuError::InvalidInput(format!(
+            "refusing to scan {}, subnets larger than /{} are not supported",
+            cidr, MIN_SCAN_PREFIX
+        )));
+    }
+
+    let mask = u32::MAX.checked_shl(32 - u32::from(prefix)).unwrap_or(0);
+    let network = u32::from(ip) & mask;
+    let broadcast = network | !mask;
+
+    let hosts = if prefix >= 31 {
+        network..=broadcast
+    } else {
+        network + 1..=broadcast - 1
+    };
+
+    Ok(hosts.map(Ipv4Addr::from).collect())
+}
+
+#[cfg(feature = "mdns")]
+fn mdns_error(error: mdns_sd::Error) -> KenkuError {
+    KenkuError::Io(std::io::Error::other(error.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{scan_subnet, subnet_hosts};
+    use crate::KenkuAddress;
+    use std::{net::Ipv4Addr, time::Duration};
+
+    #[test]
+    fn subnet_hosts_skip_network_and_broadcast() {
+        let hosts = subnet_hosts("192.168.1.77/30").unwrap();
+
+        assert_eq!(
+            hosts,
+            [
+                Ipv4Addr::new(192, 168, 1, 77),
+                Ipv4Addr::new(192, 168, 1, 78)
+            ]
+        );
+        assert_eq!(subnet_hosts("10.0.0.0/24").unwrap().len(), 254);
+        assert_eq!(subnet_hosts("10.0.0.5/32").unwrap().len(), 1);
+        assert!(subnet_hosts("10.0.0.0/8").is_err());
+        assert!(subnet_hosts("10.0.0.0").is_err());
+    }
+
+    #[tokio::test]
+    async fn scan_finds_listening_hosts() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let remotes = scan_subnet("127.0.0.1/32", port, Duration::from_millis(200))
+            .await
+            .unwrap();
+
+        assert_eq!(remotes.len(), 1);
+        assert_eq!(remotes[0].address, KenkuAddress::new("127.0.0.1", port));
+    }
+}
+
+#[cfg(all(test, feature = "mdns"))]
+mod mdns_tests {
+    use super::{remote_from_service, KENKU_SERVICE_TYPE};
+    use crate::KenkuAddress;
+    use mdns_sd::ServiceInfo;
+
+    #[test]
+    fn resolved_service_becomes_remote() {
+        let info = ServiceInfo::new(
+            KENKU_SERVICE_TYPE,
+            "Game Table",
+            "table.local.",
+            "192.168.1.20",
+            3333,
+            &[("version", "1.2.0")][..],
+        )
+        .unwrap();
+
+        let remote = remote_from_service(&info).unwrap();
+
+        assert_eq!(remote.name, "Game Table");
+        assert_eq!(remote.address, KenkuAddress::new("192.168.1.20", 3333));
+        assert_eq!(remote.version.as_deref(), Some("1.2.0"));
+    }
+}
@@ -0,0 +1,184 @@
+//! Now-playing text, for chat bots and OBS text sources.
+//!
+//! `NowPlaying` holds the pieces of the current playback already formatted for people: times as
+//! `m:ss`, the volume as a percentage. Its `Display` puts them on one line, such as
+//! `▶ Tavern · Town · 1:05 / 3:00 · 80% · 2 sounds`, ready to post to Discord or to write to the
+//! file an OBS text source reads. Build other layouts from the fields.
+use crate::{
+    playlist::PlaylistPlaybackResponse, soundboard::SoundboardPlaybackResponse, Controller,
+    KenkuError,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Represents the current playback, formatted for display.
+///
+/// # Fields
+///
+/// * `playing` - Whether the playlist is currently playing.
+/// * `title` - The title of the current track, if any.
+/// * `playlist` - The title of the current playlist, if any.
+/// * `progress` - How far the current track is, as `m:ss`, if known.
+/// * `duration` - The length of the current track, as `m:ss`, if known.
+/// * `volume` - The playlist volume as a percentage, such as `80%`.
+/// * `muted` - Whether the playlist is muted.
+/// * `active_sounds` - How many soundboard sounds are playing.
+#[serde_with::skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct NowPlaying {
+    pub playing: bool,
+    pub title: Option<String>,
+    pub playlist: Option<String>,
+    pub progress: Option<String>,
+    pub duration: Option<String>,
+    pub volume: String,
+    pub muted: bool,
+    pub active_sounds: usize,
+}
+
+impl NowPlaying {
+    /// Builds a `NowPlaying` from the playlist and soundboard playback responses.
+    pub fn from_playback(
+        playlist: &PlaylistPlaybackResponse,
+        soundboard: &SoundboardPlaybackResponse,
+    ) -> NowPlaying {
+        let track = playlist.track.as_ref();
+
+        NowPlaying {
+            playing: playlist.playing,
+            title: track.map(|track| track.title.clone()),
+            playlist: playlist.playlist.as_ref().map(|list| list.title.clone()),
+            progress: track.and_then(|track| track.progress).map(clock),
+            duration: track.and_then(|track| track.duration).map(clock),
+            volume: playlist.volume_level().to_string(),
+            muted: playlist.muted,
+            active_sounds: soundboard.sounds.len(),
+        }
+    }
+}
+
+/// Formats the playback on one line, such as `▶ Tavern · Town · 1:05 / 3:00 · 80% · 2 sounds`.
+///
+/// The line starts with `▶` while playing, `⏸` while paused and `⏹` when no track is loaded.
+/// Unknown parts are left out, and the volume reads `muted` while muted.
+impl fmt::Display for NowPlaying {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+
+        match &self.title {
+            Some(title) if self.playing => parts.push(format!("▶ {}", title)),
+            Some(title) => parts.push(format!("⏸ {}", title)),
+            None => parts.push("⏹ Nothing playing".to_string()),
+        }
+
+        if let Some(playlist) = &self.playlist {
+            parts.push(playlist.clone());
+        }
+
+        match (&self.progress, &self.duration) {
+            (Some(progress), Some(duration)) => parts.push(format!("{} / {}", progress, duration)),
+            (Some(time), None) | (None, Some(time)) => parts.push(time.clone()),
+            (None, None) => {}
+        }
+
+        parts.push(if self.muted {
+            "muted".to_string()
+        } else {
+            self.volume.clone()
+        });
+
+        match self.active_sounds {
+            0 => {}
+            1 => parts.push("1 sound".to_string()),
+            count => parts.push(format!("{} sounds", count)),
+        }
+
+        write!(f, "{}", parts.join(" · "))
+    }
+}
+
+/// Formats `millis` as `m:ss`.
+pub fn clock(millis: u32) -> String {
+    let seconds = millis / 1000;
+
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+impl Controller {
+    /// Fetches the playlist and soundboard playback and formats them into a `NowPlaying`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is either a `NowPlaying` or a `KenkuError`.
+    pub async fn now_playing(&self) -> Result<NowPlaying, KenkuError> {
+        let playlist = self.get_playlist_playback().await?;
+        let soundboard = self.get_soundboard_playback().await?;
+
+        Ok(NowPlaying::from_playback(&playlist, &soundboard))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{clock, NowPlaying};
+    use crate::{
+        playlist::{Playlist, PlaylistPlaybackResponse, Track},
+        soundboard::SoundboardPlaybackResponse,
+        testing,
+    };
+
+    fn now_playing(playback: PlaylistPlaybackResponse, sounds: usize) -> NowPlaying {
+        NowPlaying::from_playback(
+            &playback,
+            &SoundboardPlaybackResponse {
+                sounds: vec![testing::sound("s1"); sounds],
+            },
+        )
+    }
+
+    #[test]
+    fn playback_is_formatted_on_one_line() {
+        let track = Track {
+            title: "Tavern".to_string(),
+            duration: Some(180_000),
+            progress: Some(65_400),
+            ..testing::track("t1")
+        };
+        let playing = now_playing(
+            PlaylistPlaybackResponse {
+                playing: true,
+                volume: 0.8,
+                playlist: Some(Playlist {
+                    title: "Town".to_string(),
+                    ..testing::playlist("p1", &["t1"])
+                }),
+                ..testing::playback(Some(track))
+            },
+            2,
+        );
+
+        assert_eq!(playing.progress.as_deref(), Some("1:05"));
+        assert_eq!(playing.volume, "80%");
+        assert_eq!(
+            playing.to_string(),
+            "▶ Tavern · Town · 1:05 / 3:00 · 80% · 2 sounds"
+        );
+
+        let stopped = now_playing(
+            PlaylistPlaybackResponse {
+                volume: 0.5,
+                muted: true,
+                ..testing::playback(None)
+            },
+            1,
+        );
+
+        assert_eq!(stopped.to_string(), "⏹ Nothing playing · muted · 1 sound");
+    }
+
+    #[test]
+    fn clock_shows_minutes_and_seconds() {
+        assert_eq!(clock(183_200), "3:03");
+        assert_eq!(clock(0), "0:00");
+    }
+}
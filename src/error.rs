@@ -0,0 +1,135 @@
+//! The error type returned by every call to the Kenku Remote.
+use reqwest::StatusCode;
+use std::fmt;
+
+/// Represents everything that can go wrong while talking to the Kenku Remote.
+///
+/// # Variants
+///
+/// * `Connection` - The Kenku Remote could not be reached.
+/// * `Timeout` - The Kenku Remote took longer than the client timeout to answer.
+/// * `Deserialization` - The response body could not be parsed into the expected type.
+/// * `Status` - The Kenku Remote answered with a non-2xx status. The response body is kept for context.
+/// * `InvalidInput` - An argument was rejected before any request was sent.
+/// * `NotFound` - A lookup by title or id did not match anything.
+/// * `Request` - Any other failure reported by the HTTP client.
+/// * `Io` - Reading or writing local files failed.
+/// * `Serialization` - Local data could not be encoded or decoded.
+/// * `Unsupported` - The Kenku Remote does not serve the requested route. Holds the route, such as `PUT:/v1/playlist/playback/seek`.
+/// * `QueueClosed` - A `queue::CommandQueue` stopped before sending a command it had accepted.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum KenkuError {
+    Connection(reqwest::Error),
+    Timeout(reqwest::Error),
+    Deserialization(reqwest::Error),
+    Status { status: StatusCode, body: String },
+    InvalidInput(String),
+    NotFound(String),
+    Request(reqwest::Error),
+    Io(std::io::Error),
+    Serialization(String),
+    Unsupported(String),
+    QueueClosed,
+}
+
+impl fmt::Display for KenkuError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KenkuError::Connection(error) => {
+                write!(f, "failed to connect to the Kenku Remote: {}", error)
+            }
+            KenkuError::Timeout(error) => write!(f, "the Kenku Remote timed out: {}", error),
+            KenkuError::Deserialization(error) => {
+                write!(f, "failed to parse the Kenku Remote response: {}", error)
+            }
+            KenkuError::Status { status, body } if body.is_empty() => {
+                write!(f, "the Kenku Remote answered with {}", status)
+            }
+            KenkuError::Status { status, body } => {
+                write!(f, "the Kenku Remote answered with {}: {}", status, body)
+            }
+            KenkuError::InvalidInput(reason) => write!(f, "invalid input: {}", reason),
+            KenkuError::NotFound(what) => write!(f, "{} was not found", what),
+            KenkuError::Request(error) => write!(f, "request failed: {}", error),
+            KenkuError::Io(error) => write!(f, "i/o error: {}", error),
+            KenkuError::Serialization(reason) => write!(f, "serialization failed: {}", reason),
+            KenkuError::Unsupported(route) => {
+                write!(f, "the Kenku Remote does not support {}", route)
+            }
+            KenkuError::QueueClosed => {
+                write!(f, "the command queue stopped before sending the command")
+            }
+        }
+    }
+}
+
+impl std::error::Error for KenkuError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            KenkuError::Connection(error)
+            | KenkuError::Timeout(error)
+            | KenkuError::Deserialization(error)
+            | KenkuError::Request(error) => Some(error),
+            KenkuError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for KenkuError {
+    fn from(error: reqwest::Error) -> Self {
+        if error.is_timeout() {
+            KenkuError::Timeout(error)
+        } else if crate::utils::is_connect_error(&error) {
+            KenkuError::Connection(error)
+        } else if error.is_decode() {
+            KenkuError::Deserialization(error)
+        } else if let (true, Some(status)) = (error.is_status(), error.status()) {
+            KenkuError::Status {
+                status,
+                body: String::new(),
+            }
+        } else {
+            KenkuError::Request(error)
+        }
+    }
+}
+
+impl From<std::io::Error> for KenkuError {
+    fn from(error: std::io::Error) -> Self {
+        KenkuError::Io(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::KenkuError;
+    use reqwest::StatusCode;
+
+    #[test]
+    fn status_error_includes_body() {
+        let error = KenkuError::Status {
+            status: StatusCode::NOT_FOUND,
+            body: "no track with that id".to_string(),
+        };
+
+        assert_eq!(
+            error.to_string(),
+            "the Kenku Remote answered with 404 Not Found: no track with that id"
+        );
+    }
+
+    #[test]
+    fn status_error_without_body() {
+        let error = KenkuError::Status {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            body: String::new(),
+        };
+
+        assert_eq!(
+            error.to_string(),
+            "the Kenku Remote answered with 500 Internal Server Error"
+        );
+    }
+}
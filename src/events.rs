@@ -0,0 +1,329 @@
+//! A stream of playback changes, computed by polling the Kenku Remote.
+//!
+//! Kenku Remote has no push API, so `Controller::events` fetches the playlist and soundboard
+//! playback on an interval and compares every response with the previous one.
+use crate::{
+    playlist::{Playlist, PlaylistPlaybackResponse, Repeat, Track},
+    snapshot::PlaybackSnapshot,
+    soundboard::Sounds,
+    Controller, KenkuError,
+};
+use futures_util::Stream;
+use serde::{Serialize, Serializer};
+use std::{collections::VecDeque, time::Duration};
+use tokio::time::{interval, Interval, MissedTickBehavior};
+
+/// Represents a change observed on the Kenku Remote.
+///
+/// This enum has a variant for each kind of change:
+/// * `TrackChanged`: The current track changed. Holds the new track, if any.
+/// * `PlaylistChanged`: The current playlist changed. Holds the new playlist, if any.
+/// * `PlaybackPaused`: The playlist stopped playing.
+/// * `PlaybackResumed`: The playlist started playing.
+/// * `VolumeChanged`: The playlist volume changed. Holds the new volume.
+/// * `MuteChanged`: The playlist was muted or unmuted. Holds the new mute state.
+/// * `ShuffleChanged`: Shuffle was turned on or off. Holds the new shuffle state.
+/// * `RepeatChanged`: The repeat mode changed. Holds the new mode.
+/// * `SoundStarted`: A soundboard sound started playing.
+/// * `SoundStopped`: A soundboard sound stopped playing.
+/// * `Disconnected`: The Kenku Remote could not be reached. Holds the error of the failed poll.
+/// * `Reconnected`: The Kenku Remote answered again after being disconnected.
+/// * `AutomationPaused`: Automated actions were suspended with `Controller::pause_automation`.
+/// * `AutomationResumed`: Automated actions were allowed again with `Controller::resume_automation`.
+#[derive(Debug)]
+pub enum KenkuEvent {
+    TrackChanged(Option<Track>),
+    PlaylistChanged(Option<Playlist>),
+    PlaybackPaused,
+    PlaybackResumed,
+    VolumeChanged(f64),
+    MuteChanged(bool),
+    ShuffleChanged(bool),
+    RepeatChanged(Repeat),
+    SoundStarted(Sounds),
+    SoundStopped(Sounds),
+    Disconnected(KenkuError),
+    Reconnected,
+    AutomationPaused,
+    AutomationResumed,
+}
+
+/// The JSON form of a `KenkuEvent`, borrowing from it.
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum EventJson<'a> {
+    TrackChanged { track: Option<&'a Track> },
+    PlaylistChanged { playlist: Option<&'a Playlist> },
+    PlaybackPaused,
+    PlaybackResumed,
+    VolumeChanged { volume: f64 },
+    MuteChanged { muted: bool },
+    ShuffleChanged { shuffle: bool },
+    RepeatChanged { repeat: &'a Repeat },
+    SoundStarted { sound: &'a Sounds },
+    SoundStopped { sound: &'a Sounds },
+    Disconnected { error: String },
+    Reconnected,
+    AutomationPaused,
+    AutomationResumed,
+}
+
+/// Serializes an event as an object whose `type` is the snake-case name of the variant, such as
+/// `{"type": "volume_changed", "volume": 0.5}`. The error of `Disconnected` is kept as its message.
+impl Serialize for KenkuEvent {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let json = match self {
+            KenkuEvent::TrackChanged(track) => EventJson::TrackChanged {
+                track: track.as_ref(),
+            },
+            KenkuEvent::PlaylistChanged(playlist) => EventJson::PlaylistChanged {
+                playlist: playlist.as_ref(),
+            },
+            KenkuEvent::PlaybackPaused => EventJson::PlaybackPaused,
+            KenkuEvent::PlaybackResumed => EventJson::PlaybackResumed,
+            KenkuEvent::VolumeChanged(volume) => EventJson::VolumeChanged { volume: *volume },
+            KenkuEvent::MuteChanged(muted) => EventJson::MuteChanged { muted: *muted },
+            KenkuEvent::ShuffleChanged(shuffle) => EventJson::ShuffleChanged { shuffle: *shuffle },
+            KenkuEvent::RepeatChanged(repeat) => EventJson::RepeatChanged { repeat },
+            KenkuEvent::SoundStarted(sound) => EventJson::SoundStarted { sound },
+            KenkuEvent::SoundStopped(sound) => EventJson::SoundStopped { sound },
+            KenkuEvent::Disconnected(error) => EventJson::Disconnected {
+                error: error.to_string(),
+            },
+            KenkuEvent::Reconnected => EventJson::Reconnected,
+            KenkuEvent::AutomationPaused => EventJson::AutomationPaused,
+            KenkuEvent::AutomationResumed => EventJson::AutomationResumed,
+        };
+
+        json.serialize(serializer)
+    }
+}
+
+impl PlaybackSnapshot {
+    /// Lists the changes between `previous` and this snapshot, in the order of `KenkuEvent`'s variants.
+    pub fn changes_since(&self, previous: &PlaybackSnapshot) -> Vec<KenkuEvent> {
+        let mut events = Vec::new();
+        let (old, new) = (&previous.playlist, &self.playlist);
+
+        let track_id = |playback: &PlaylistPlaybackResponse| {
+            playback.track.as_ref().map(|track| track.id.clone())
+        };
+        let playlist_id = |playback: &PlaylistPlaybackResponse| {
+            playback
+                .playlist
+                .as_ref()
+                .map(|playlist| playlist.id.clone())
+        };
+
+        if track_id(old) != track_id(new) {
+            events.push(KenkuEvent::TrackChanged(new.track.clone()));
+        }
+
+        if playlist_id(old) != playlist_id(new) {
+            events.push(KenkuEvent::PlaylistChanged(new.playlist.clone()));
+        }
+
+        match (old.playing, new.playing) {
+            (true, false) => events.push(KenkuEvent::PlaybackPaused),
+            (false, true) => events.push(KenkuEvent::PlaybackResumed),
+            _ => {}
+        }
+
+        if old.volume != new.volume {
+            events.push(KenkuEvent::VolumeChanged(new.volume));
+        }
+
+        if old.muted != new.muted {
+            events.push(KenkuEvent::MuteChanged(new.muted));
+        }
+
+        if old.shuffle != new.shuffle {
+            events.push(KenkuEvent::ShuffleChanged(new.shuffle));
+        }
+
+        if old.repeat != new.repeat {
+            events.push(KenkuEvent::RepeatChanged(new.repeat.clone()));
+        }
+
+        let (old_sounds, new_sounds) = (&previous.soundboard.sounds, &self.soundboard.sounds);
+
+        for sound in new_sounds {
+            if !old_sounds.iter().any(|old| old.id == sound.id) {
+                events.push(KenkuEvent::SoundStarted(sound.clone()));
+            }
+        }
+
+        for sound in old_sounds {
+            if !new_sounds.iter().any(|new| new.id == sound.id) {
+                events.push(KenkuEvent::SoundStopped(sound.clone()));
+            }
+        }
+
+        events
+    }
+}
+
+/// The state carried between two items of the event stream.
+struct Poller {
+    controller: Controller,
+    interval: Interval,
+    last: Option<PlaybackSnapshot>,
+    connected: bool,
+    automation_paused: bool,
+    pending: VecDeque<KenkuEvent>,
+}
+
+impl Poller {
+    /// Fetches a snapshot and queues the events it produces.
+    async fn poll(&mut self) {
+        let automation_paused = self.controller.automation_paused();
+
+        if automation_paused != self.automation_paused {
+            self.automation_paused = automation_paused;
+            self.pending.push_back(match automation_paused {
+                true => KenkuEvent::AutomationPaused,
+                false => KenkuEvent::AutomationResumed,
+            });
+        }
+
+        match self.controller.snapshot().await {
+            Ok(snapshot) => {
+                if !self.connected {
+                    self.connected = true;
+                    self.pending.push_back(KenkuEvent::Reconnected);
+                }
+
+                if let Some(last) = &self.last {
+                    self.pending.extend(snapshot.changes_since(last));
+                }
+
+                self.last = Some(snapshot);
+            }
+            Err(error) => {
+                if self.connected {
+                    self.connected = false;
+                    self.pending.push_back(KenkuEvent::Disconnected(error));
+                }
+            }
+        }
+    }
+}
+
+impl Controller {
+    /// Polls the Kenku Remote every `poll_interval` and yields what changed between two polls.
+    ///
+    /// The first poll only records the current playback, so the stream starts with the first
+    /// change after it. A failed poll yields `KenkuEvent::Disconnected` once, and the next
+    /// successful one yields `KenkuEvent::Reconnected` followed by whatever changed meanwhile.
+    /// The stream never ends; drop it to stop polling.
+    ///
+    /// ```no_run
+    /// # async fn watch() {
+    /// use futures_util::StreamExt;
+    /// use kenku_control::{events::KenkuEvent, Controller};
+    /// use std::time::Duration;
+    ///
+    /// let controller = Controller::new("127.0.0.1", 3333);
+    /// let mut events = std::pin::pin!(controller.events(Duration::from_millis(500)));
+    ///
+    /// while let Some(event) = events.next().await {
+    ///     if let KenkuEvent::TrackChanged(Some(track)) = event {
+    ///         println!("now playing {}", track.title);
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// # Arguments
+    ///
+    /// * `poll_interval` - The time between two polls, at least a millisecond. Polls that fall behind are delayed, not bunched up.
+    pub fn events(&self, poll_interval: Duration) -> impl Stream<Item = KenkuEvent> {
+        let mut ticks = interval(poll_interval.max(Duration::from_millis(1)));
+        ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        let poller = Poller {
+            controller: self.clone(),
+            interval: ticks,
+            last: None,
+            connected: true,
+            automation_paused: self.automation_paused(),
+            pending: VecDeque::new(),
+        };
+
+        futures_util::stream::unfold(poller, |mut poller| async move {
+            loop {
+                if let Some(event) = poller.pending.pop_front() {
+                    return Some((event, poller));
+                }
+
+                poller.interval.tick().await;
+                poller.poll().await;
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{KenkuEvent, PlaybackSnapshot};
+    use crate::{playlist::PlaylistPlaybackResponse, testing, Controller, KenkuError};
+    use serde_json::json;
+    use std::time::Duration;
+
+    fn snapshot(playing: bool, track: &str, volume: f64, sounds: &[&str]) -> PlaybackSnapshot {
+        let playlist = PlaylistPlaybackResponse {
+            playing,
+            volume,
+            playlist: Some(testing::playlist("p1", &[track])),
+            ..testing::playback(Some(testing::track(track)))
+        };
+
+        testing::snapshot(playlist, sounds)
+    }
+
+    #[test]
+    fn identical_snapshots_have_no_changes() {
+        let current = snapshot(true, "t1", 1.0, &["rain"]);
+
+        assert!(current.changes_since(&current.clone()).is_empty());
+    }
+
+    #[test]
+    fn changes_are_reported_in_order() {
+        let previous = snapshot(true, "t1", 1.0, &["rain"]);
+        let current = snapshot(false, "t2", 0.5, &["thunder"]);
+
+        let events = current.changes_since(&previous);
+
+        assert!(matches!(&events[0], KenkuEvent::TrackChanged(Some(track)) if track.id == "t2"));
+        assert!(matches!(events[1], KenkuEvent::PlaybackPaused));
+        assert!(matches!(events[2], KenkuEvent::VolumeChanged(volume) if volume == 0.5));
+        assert!(matches!(&events[3], KenkuEvent::SoundStarted(sound) if sound.id == "thunder"));
+        assert!(matches!(&events[4], KenkuEvent::SoundStopped(sound) if sound.id == "rain"));
+        assert_eq!(events.len(), 5);
+    }
+
+    #[test]
+    fn events_serialize_with_a_type_tag() {
+        assert_eq!(
+            serde_json::to_value(KenkuEvent::VolumeChanged(0.5)).unwrap(),
+            json!({"type": "volume_changed", "volume": 0.5})
+        );
+        assert_eq!(
+            serde_json::to_value(KenkuEvent::PlaybackPaused).unwrap(),
+            json!({"type": "playback_paused"})
+        );
+        assert_eq!(
+            serde_json::to_value(KenkuEvent::Disconnected(KenkuError::NotFound(
+                "remote".to_string()
+            )))
+            .unwrap(),
+            json!({"type": "disconnected", "error": "remote was not found"})
+        );
+    }
+
+    #[tokio::test]
+    async fn zero_poll_intervals_do_not_panic() {
+        let _events = Controller::new("127.0.0.1", 3333).events(Duration::ZERO);
+    }
+}
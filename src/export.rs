@@ -0,0 +1,233 @@
+//! Exporting the Kenku FM library to files other applications read.
+//!
+//! `m3u` and `playlist_m3u` write extended M3U (M3U8) playlists, which most media players open
+//! as is. `LibraryExport` is a plain JSON document of the playlists and their tracks, with titles,
+//! URLs and durations, meant for backups and for sharing a library outside of Kenku FM. Both are
+//! built from a `PlaylistGetResponse`, so they reflect the library as the Kenku Remote reports it.
+use crate::{
+    playlist::{Playlist, PlaylistGetResponse, Track},
+    KenkuError,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// Represents an exported track.
+///
+/// # Fields
+///
+/// * `id` - The id of the track in Kenku FM.
+/// * `title` - The title of the track.
+/// * `url` - The URL where the track file is located.
+/// * `duration_ms` - The duration of the track in milliseconds, when the Kenku Remote reported it.
+#[serde_with::skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ExportedTrack {
+    pub id: String,
+    pub title: String,
+    pub url: String,
+    pub duration_ms: Option<u32>,
+}
+
+/// Represents an exported playlist.
+///
+/// # Fields
+///
+/// * `id` - The id of the playlist in Kenku FM.
+/// * `title` - The title of the playlist.
+/// * `background` - The background image of the playlist, if any.
+/// * `tracks` - The tracks of the playlist, in order.
+#[serde_with::skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ExportedPlaylist {
+    pub id: String,
+    pub title: String,
+    pub background: Option<String>,
+    #[serde(default)]
+    pub tracks: Vec<ExportedTrack>,
+}
+
+/// Represents an exported library.
+///
+/// # Fields
+///
+/// * `playlists` - Every playlist, in the order of the Kenku Remote.
+/// * `unlisted` - The tracks that belong to no playlist.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct LibraryExport {
+    pub playlists: Vec<ExportedPlaylist>,
+    pub unlisted: Vec<ExportedTrack>,
+}
+
+impl From<&Track> for ExportedTrack {
+    fn from(track: &Track) -> ExportedTrack {
+        ExportedTrack {
+            id: track.id.clone(),
+            title: track.title.clone(),
+            url: track.url.clone(),
+            duration_ms: track.duration,
+        }
+    }
+}
+
+impl From<&PlaylistGetResponse> for LibraryExport {
+    fn from(library: &PlaylistGetResponse) -> LibraryExport {
+        let playlists = library
+            .playlists
+            .iter()
+            .map(|playlist| ExportedPlaylist {
+                id: playlist.id.clone(),
+                title: playlist.title.clone(),
+                background: playlist.background.clone(),
+                tracks: tracks_of(library, playlist)
+                    .map(ExportedTrack::from)
+                    .collect(),
+            })
+            .collect();
+
+        let unlisted = library
+            .tracks
+            .iter()
+            .filter(|track| library.playlists_containing(track).is_empty())
+            .map(ExportedTrack::from)
+            .collect();
+
+        LibraryExport {
+            playlists,
+            unlisted,
+        }
+    }
+}
+
+impl LibraryExport {
+    /// Encodes this export as indented JSON.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the JSON document, or a `KenkuError::Serialization` if it cannot be encoded.
+    pub fn to_json(&self) -> Result<String, KenkuError> {
+        serde_json::to_string_pretty(self)
+            .map_err(|error| KenkuError::Serialization(error.to_string()))
+    }
+}
+
+/// Writes the whole library as one M3U8 playlist.
+///
+/// Every track is listed once, in playlist order, followed by the tracks that belong to no
+/// playlist. A `#EXTGRP` line marks where each playlist starts.
+pub fn m3u(library: &PlaylistGetResponse) -> String {
+    let mut out = String::from("#EXTM3U\n");
+    let mut group = None;
+
+    for entry in library.library_in_playlist_order() {
+        let playlist = entry.playlists.first().map(|playlist| &playlist.title);
+
+        if playlist != group {
+            if let Some(title) = playlist {
+                let _ = writeln!(out, "#EXTGRP:{}", one_line(title));
+            }
+            group = playlist;
+        }
+
+        push_track(&mut out, entry.track);
+    }
+
+    out
+}
+
+/// Writes `playlist` as an M3U8 playlist, with its tracks in order.
+///
+/// Track ids missing from `library` are skipped.
+pub fn playlist_m3u(library: &PlaylistGetResponse, playlist: &Playlist) -> String {
+    let mut out = format!("#EXTM3U\n#PLAYLIST:{}\n", one_line(&playlist.title));
+
+    for track in tracks_of(library, playlist) {
+        push_track(&mut out, track);
+    }
+
+    out
+}
+
+/// Returns the tracks of `playlist` found in `library`, in order.
+pub(crate) fn tracks_of<'a>(
+    library: &'a PlaylistGetResponse,
+    playlist: &'a Playlist,
+) -> impl Iterator<Item = &'a Track> {
+    playlist
+        .tracks
+        .iter()
+        .flatten()
+        .filter_map(|id| library.track_by_id(id))
+}
+
+/// Appends the `#EXTINF` line and the URL of `track`. Unknown durations are written as `-1`.
+fn push_track(out: &mut String, track: &Track) {
+    let seconds = track
+        .duration
+        .map_or(-1, |duration| i64::from(duration.div_ceil(1000)));
+
+    let _ = writeln!(out, "#EXTINF:{},{}", seconds, one_line(&track.title));
+    let _ = writeln!(out, "{}", one_line(&track.url));
+}
+
+/// Replaces line breaks, which would end an M3U entry early, with spaces.
+fn one_line(text: &str) -> String {
+    text.replace(['\r', '\n'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{m3u, playlist_m3u, LibraryExport};
+    use crate::playlist::PlaylistGetResponse;
+    use serde_json::json;
+
+    fn library() -> PlaylistGetResponse {
+        serde_json::from_value(json!({
+            "playlists": [
+                {"id": "p1", "title": "Tavern", "tracks": ["t1", "t2", "gone"]},
+                {"id": "p2", "title": "Battle", "tracks": ["t2"]}
+            ],
+            "tracks": [
+                {"id": "t1", "url": "file:///busy.mp3", "title": "Busy\ntavern", "duration": 183_200},
+                {"id": "t2", "url": "file:///quiet.ogg", "title": "Quiet night"},
+                {"id": "t3", "url": "file:///drums.mp3", "title": "Drums", "duration": 60_000}
+            ]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn playlists_export_to_m3u() {
+        let library = library();
+
+        assert_eq!(
+            playlist_m3u(&library, &library.playlists[0]),
+            "#EXTM3U\n#PLAYLIST:Tavern\n\
+             #EXTINF:184,Busy tavern\nfile:///busy.mp3\n\
+             #EXTINF:-1,Quiet night\nfile:///quiet.ogg\n"
+        );
+        assert_eq!(
+            m3u(&library),
+            "#EXTM3U\n#EXTGRP:Tavern\n\
+             #EXTINF:184,Busy tavern\nfile:///busy.mp3\n\
+             #EXTINF:-1,Quiet night\nfile:///quiet.ogg\n\
+             #EXTINF:60,Drums\nfile:///drums.mp3\n"
+        );
+    }
+
+    #[test]
+    fn library_exports_to_json() {
+        let export = LibraryExport::from(&library());
+
+        assert_eq!(export.playlists[0].tracks.len(), 2);
+        assert_eq!(export.playlists[1].tracks[0].title, "Quiet night");
+        assert_eq!(export.unlisted[0].id, "t3");
+
+        let saved = export.to_json().unwrap();
+        assert!(saved.contains(r#""duration_ms": 183200"#));
+        assert_eq!(
+            serde_json::from_str::<LibraryExport>(&saved).unwrap(),
+            export
+        );
+    }
+}
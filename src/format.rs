@@ -0,0 +1,219 @@
+//! Storing data in a format chosen by the user.
+//!
+//! JSON is always available. TOML, RON and MessagePack are enabled by the `toml`, `ron` and
+//! `msgpack` features. `save` and `load` pick the format from the file extension, so snapshots and
+//! other saved data can be kept as hand-edited TOML or compact MessagePack alike.
+use crate::KenkuError;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{fs, path::Path};
+
+/// Represents a serialization format.
+///
+/// # Variants
+///
+/// * `Json` - JSON, always available.
+/// * `Toml` - TOML, for files edited by hand. Needs the `toml` feature.
+/// * `Ron` - Rusty Object Notation. Needs the `ron` feature.
+/// * `MessagePack` - Compact binary MessagePack. Needs the `msgpack` feature.
+///
+/// The variants depend on the enabled features, so matches outside this crate need a wildcard arm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Format {
+    Json,
+    #[cfg(feature = "toml")]
+    Toml,
+    #[cfg(feature = "ron")]
+    Ron,
+    #[cfg(feature = "msgpack")]
+    MessagePack,
+}
+
+impl Format {
+    /// Returns every format enabled in this build.
+    pub fn available() -> &'static [Format] {
+        &[
+            Format::Json,
+            #[cfg(feature = "toml")]
+            Format::Toml,
+            #[cfg(feature = "ron")]
+            Format::Ron,
+            #[cfg(feature = "msgpack")]
+            Format::MessagePack,
+        ]
+    }
+
+    /// Returns the usual file extension of this format, without the dot.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Json => "json",
+            #[cfg(feature = "toml")]
+            Format::Toml => "toml",
+            #[cfg(feature = "ron")]
+            Format::Ron => "ron",
+            #[cfg(feature = "msgpack")]
+            Format::MessagePack => "msgpack",
+        }
+    }
+
+    /// Returns `true` for formats whose encoding is always valid UTF-8 text.
+    pub fn is_text(self) -> bool {
+        match self {
+            #[cfg(feature = "msgpack")]
+            Format::MessagePack => false,
+            _ => true,
+        }
+    }
+
+    /// Finds the enabled format using `extension`, ignoring case. `mpk` is accepted for MessagePack.
+    pub fn from_extension(extension: &str) -> Option<Format> {
+        let extension = extension.to_ascii_lowercase();
+
+        #[cfg(feature = "msgpack")]
+        if extension == "mpk" {
+            return Some(Format::MessagePack);
+        }
+
+        Format::available()
+            .iter()
+            .copied()
+            .find(|format| format.extension() == extension)
+    }
+
+    /// Finds the enabled format matching the extension of `path`.
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Format> {
+        path.as_ref()
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(Format::from_extension)
+    }
+
+    /// Encodes `value` in this format.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the encoded bytes, or a `KenkuError::Serialization` if `value` cannot be represented in this format.
+    pub fn encode<T: Serialize + ?Sized>(self, value: &T) -> Result<Vec<u8>, KenkuError> {
+        match self {
+            Format::Json => serde_json::to_vec_pretty(value).map_err(serialization_error),
+            #[cfg(feature = "toml")]
+            Format::Toml => toml::to_string_pretty(value)
+                .map(String::into_bytes)
+                .map_err(serialization_error),
+            #[cfg(feature = "ron")]
+            Format::Ron => ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())
+                .map(String::into_bytes)
+                .map_err(serialization_error),
+            #[cfg(feature = "msgpack")]
+            Format::MessagePack => rmp_serde::to_vec_named(value).map_err(serialization_error),
+        }
+    }
+
+    /// Decodes a `T` from `bytes` encoded in this format.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the decoded value, or a `KenkuError::Serialization` if `bytes` is malformed.
+    pub fn decode<T: DeserializeOwned>(self, bytes: &[u8]) -> Result<T, KenkuError> {
+        match self {
+            Format::Json => serde_json::from_slice(bytes).map_err(serialization_error),
+            #[cfg(feature = "toml")]
+            Format::Toml => toml::from_str(text(bytes)?).map_err(serialization_error),
+            #[cfg(feature = "ron")]
+            Format::Ron => ron::de::from_bytes(bytes).map_err(serialization_error),
+            #[cfg(feature = "msgpack")]
+            Format::MessagePack => rmp_serde::from_slice(bytes).map_err(serialization_error),
+        }
+    }
+}
+
+/// Writes `value` to `path`, in the format matching its extension.
+///
+/// # Returns
+///
+/// This function returns `Ok(())` once the file is written, a `KenkuError::InvalidInput` if no enabled format matches the extension, or the error raised while encoding or writing.
+pub fn save<T: Serialize + ?Sized>(path: impl AsRef<Path>, value: &T) -> Result<(), KenkuError> {
+    let path = path.as_ref();
+    let bytes = format_of(path)?.encode(value)?;
+
+    fs::write(path, bytes)?;
+
+    Ok(())
+}
+
+/// Reads a `T` from `path`, in the format matching its extension.
+///
+/// # Returns
+///
+/// This function returns the decoded value, a `KenkuError::InvalidInput` if no enabled format matches the extension, or the error raised while reading or decoding.
+pub fn load<T: DeserializeOwned>(path: impl AsRef<Path>) -> Result<T, KenkuError> {
+    let path = path.as_ref();
+    let format = format_of(path)?;
+
+    format.decode(&fs::read(path)?)
+}
+
+fn format_of(path: &Path) -> Result<Format, KenkuError> {
+    Format::from_path(path).ok_or_else(|| {
+        KenkuError::InvalidInput(format!(
+            "no enabled format matches the extension of {}",
+            path.display()
+        ))
+    })
+}
+
+#[cfg(feature = "toml")]
+fn text(bytes: &[u8]) -> Result<&str, KenkuError> {
+    std::str::from_utf8(bytes).map_err(serialization_error)
+}
+
+fn serialization_error(error: impl std::fmt::Display) -> KenkuError {
+    KenkuError::Serialization(error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{load, save, Format};
+    use crate::playlist::Repeat;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, PartialEq, Deserialize, Serialize)]
+    struct Scene {
+        title: String,
+        volume: f64,
+        repeat: Repeat,
+        tracks: Vec<String>,
+    }
+
+    fn scene() -> Scene {
+        Scene {
+            title: "Tavern".to_string(),
+            volume: 0.5,
+            repeat: Repeat::Playlist,
+            tracks: vec!["t1".to_string(), "t2".to_string()],
+        }
+    }
+
+    #[test]
+    fn every_available_format_round_trips() {
+        for format in Format::available() {
+            let bytes = format.encode(&scene()).unwrap();
+
+            assert_eq!(format.decode::<Scene>(&bytes).unwrap(), scene());
+            assert_eq!(Format::from_extension(format.extension()), Some(*format));
+        }
+    }
+
+    #[test]
+    fn files_use_the_format_of_their_extension() {
+        let path =
+            std::env::temp_dir().join(format!("kenku_control_format_{}.json", std::process::id()));
+
+        save(&path, &scene()).unwrap();
+        let loaded: Scene = load(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(loaded, scene());
+        assert!(save("scene.unknown", &scene()).is_err());
+    }
+}
@@ -0,0 +1,234 @@
+//! The current time inside the game world.
+//!
+//! Ambience that follows the time of day needs to know what time it is in the game, which has
+//! little to do with the wall clock. `GameTimeProvider` abstracts where that time comes from:
+//! set by hand with `ManualClock`, running at a fixed ratio of real time with `RatioClock`, or
+//! pushed by a virtual tabletop with `FedClock`.
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Represents a moment in the game world, counted from the start of the campaign.
+///
+/// Days are 24 hours long and numbered from 0.
+///
+/// # Fields
+///
+/// * `elapsed` - The in-game time since the start of the campaign.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct GameTime {
+    pub elapsed: Duration,
+}
+
+/// Represents a part of the day, for ambience that changes with the light.
+///
+/// # Variants
+///
+/// * `Dawn` - From 05:00 to 08:00.
+/// * `Day` - From 08:00 to 18:00.
+/// * `Dusk` - From 18:00 to 21:00.
+/// * `Night` - From 21:00 to 05:00.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TimeOfDay {
+    Dawn,
+    Day,
+    Dusk,
+    Night,
+}
+
+impl GameTime {
+    /// Creates a `GameTime` at `hour:minute` on day `day`.
+    pub fn at(day: u64, hour: u32, minute: u32) -> GameTime {
+        GameTime {
+            elapsed: Duration::from_secs(
+                day * SECONDS_PER_DAY + u64::from(hour) * 3600 + u64::from(minute) * 60,
+            ),
+        }
+    }
+
+    /// Returns the day, starting at 0.
+    pub fn day(&self) -> u64 {
+        self.elapsed.as_secs() / SECONDS_PER_DAY
+    }
+
+    /// Returns the hour of the day, from 0 to 23.
+    pub fn hour(&self) -> u32 {
+        (self.elapsed.as_secs() % SECONDS_PER_DAY / 3600) as u32
+    }
+
+    /// Returns the minute of the hour, from 0 to 59.
+    pub fn minute(&self) -> u32 {
+        (self.elapsed.as_secs() % 3600 / 60) as u32
+    }
+
+    /// Returns the part of the day this time falls in.
+    pub fn time_of_day(&self) -> TimeOfDay {
+        match self.hour() {
+            5..=7 => TimeOfDay::Dawn,
+            8..=17 => TimeOfDay::Day,
+            18..=20 => TimeOfDay::Dusk,
+            _ => TimeOfDay::Night,
+        }
+    }
+}
+
+/// Provides the current in-game time.
+pub trait GameTimeProvider: Send + Sync {
+    /// Returns the current in-game time.
+    fn now(&self) -> GameTime;
+}
+
+/// An in-game clock that only moves when told to.
+#[derive(Debug, Default)]
+pub struct ManualClock {
+    time: Mutex<GameTime>,
+}
+
+impl ManualClock {
+    /// Creates a `ManualClock` showing `time`.
+    pub fn new(time: GameTime) -> ManualClock {
+        ManualClock {
+            time: Mutex::new(time),
+        }
+    }
+
+    /// Sets the clock to `time`.
+    pub fn set(&self, time: GameTime) {
+        *self.time.lock().unwrap() = time;
+    }
+
+    /// Moves the clock forward by `by`, for example after a long rest.
+    pub fn advance(&self, by: Duration) {
+        self.time.lock().unwrap().elapsed += by;
+    }
+}
+
+impl GameTimeProvider for ManualClock {
+    fn now(&self) -> GameTime {
+        *self.time.lock().unwrap()
+    }
+}
+
+/// An in-game clock running at a fixed ratio of real time.
+///
+/// A ratio of 24 makes one real hour last one game day.
+#[derive(Debug)]
+pub struct RatioClock {
+    start: GameTime,
+    started: Instant,
+    ratio: f64,
+}
+
+impl RatioClock {
+    /// Creates a `RatioClock` showing `start` now and running `ratio` times faster than real time.
+    pub fn new(start: GameTime, ratio: f64) -> RatioClock {
+        RatioClock {
+            start,
+            started: Instant::now(),
+            ratio,
+        }
+    }
+}
+
+impl GameTimeProvider for RatioClock {
+    fn now(&self) -> GameTime {
+        GameTime {
+            elapsed: run(self.start.elapsed, self.started.elapsed(), self.ratio),
+        }
+    }
+}
+
+/// An in-game clock kept in sync by an outside source, such as a virtual tabletop.
+///
+/// Between two updates the clock runs at `ratio` times real time, so a source that only reports
+/// now and then still gives a moving clock. A ratio of 0 holds the last reported time.
+#[derive(Debug)]
+pub struct FedClock {
+    last: Mutex<(GameTime, Instant)>,
+    ratio: f64,
+}
+
+impl FedClock {
+    /// Creates a `FedClock` showing `start` until the first update.
+    pub fn new(start: GameTime, ratio: f64) -> FedClock {
+        FedClock {
+            last: Mutex::new((start, Instant::now())),
+            ratio,
+        }
+    }
+
+    /// Records `time` as the current in-game time, as reported by the source.
+    pub fn update(&self, time: GameTime) {
+        *self.last.lock().unwrap() = (time, Instant::now());
+    }
+}
+
+impl GameTimeProvider for FedClock {
+    fn now(&self) -> GameTime {
+        let (time, received) = *self.last.lock().unwrap();
+
+        GameTime {
+            elapsed: run(time.elapsed, received.elapsed(), self.ratio),
+        }
+    }
+}
+
+/// Returns the in-game time `real` after `from` at `ratio` times real time.
+///
+/// A negative ratio holds the clock, and times too large for a `Duration` saturate.
+fn run(from: Duration, real: Duration, ratio: f64) -> Duration {
+    let ran =
+        Duration::try_from_secs_f64(real.as_secs_f64() * ratio.max(0.0)).unwrap_or(Duration::MAX);
+
+    from.saturating_add(ran)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FedClock, GameTime, GameTimeProvider, ManualClock, RatioClock, TimeOfDay};
+    use std::time::Duration;
+
+    #[test]
+    fn game_time_splits_into_day_and_time() {
+        let time = GameTime::at(3, 19, 45);
+
+        assert_eq!((time.day(), time.hour(), time.minute()), (3, 19, 45));
+        assert_eq!(time.time_of_day(), TimeOfDay::Dusk);
+        assert_eq!(GameTime::at(0, 2, 0).time_of_day(), TimeOfDay::Night);
+    }
+
+    #[test]
+    fn manual_clock_moves_when_told() {
+        let clock = ManualClock::new(GameTime::at(0, 22, 0));
+
+        clock.advance(Duration::from_secs(8 * 3600));
+
+        assert_eq!(clock.now(), GameTime::at(1, 6, 0));
+    }
+
+    #[test]
+    fn ratio_and_fed_clocks_run_between_updates() {
+        let ratio = RatioClock::new(GameTime::at(0, 12, 0), 3600.0);
+        let fed = FedClock::new(GameTime::default(), 0.0);
+
+        std::thread::sleep(Duration::from_millis(20));
+        fed.update(GameTime::at(5, 8, 0));
+
+        assert!(ratio.now() >= GameTime::at(0, 12, 1));
+        assert_eq!(fed.now(), GameTime::at(5, 8, 0));
+    }
+
+    #[test]
+    fn huge_ratios_saturate() {
+        let infinite = RatioClock::new(GameTime::at(0, 12, 0), f64::INFINITY);
+        let huge = FedClock::new(GameTime::default(), 1e300);
+
+        std::thread::sleep(Duration::from_millis(1));
+
+        assert_eq!(infinite.now().elapsed, Duration::MAX);
+        assert_eq!(huge.now().elapsed, Duration::MAX);
+    }
+}
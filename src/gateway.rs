@@ -0,0 +1,21 @@
+//! Talking to a `rest::RestApi` gateway from another process or machine.
+//!
+//! A gateway runs next to Kenku FM and serves the Kenku Remote, with authentication, scenes and
+//! snapshots, beyond localhost. `client::GatewayClient` lets Rust applications drive it with the
+//! same typed API as a local `Controller`, so a bot or a Stream Deck plugin can stay a thin client
+//! while the bridge holds the controller. `federation::Federation` drives the gateways of several
+//! rooms from one place.
+//!
+//! ```no_run
+//! # async fn example() -> Result<(), kenku_control::KenkuError> {
+//! use kenku_control::gateway::client::GatewayClient;
+//!
+//! let gateway = GatewayClient::new("http://bridge.local:8080").token("hunter2");
+//!
+//! gateway.play_sound_id("s1").await?;
+//! let playback = gateway.get_playlist_playback().await?;
+//! # Ok(())
+//! # }
+//! ```
+pub mod client;
+pub mod federation;
@@ -0,0 +1,335 @@
+//! The typed client of a `rest::RestApi` gateway.
+use crate::{
+    clients::ClientStats,
+    playlist::{PlaylistGetResponse, PlaylistPlaybackResponse},
+    snapshot::PlaybackSnapshot,
+    soundboard::{SoundboardGetResponse, SoundboardPlaybackResponse},
+    KenkuCommandWithPayload, KenkuError, KenkuPlaybackCommand,
+};
+use reqwest::{Method, StatusCode, Url};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::time::Duration;
+
+/// A client of a `rest::RestApi` gateway.
+///
+/// Cloning a client is cheap: the HTTP client and its connection pool are shared.
+///
+/// # Fields
+///
+/// * `client` - The `reqwest::Client` sending the requests.
+/// * `base` - The URL the gateway is served under, such as `http://bridge.local:8080`.
+/// * `token` - The bearer token sent with every request, if the gateway needs one.
+#[derive(Debug, Clone)]
+pub struct GatewayClient {
+    client: reqwest::Client,
+    base: String,
+    token: Option<String>,
+}
+
+/// Represents the answer of a gateway to commands, as `BatchReport` does for a `Controller`.
+///
+/// # Fields
+///
+/// * `sent` - How many commands the gateway sent to the Kenku Remote.
+/// * `failures` - The commands that failed, with their errors.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct GatewayReport {
+    pub sent: usize,
+    pub failures: Vec<GatewayFailure>,
+}
+
+/// Represents a command the gateway could not send.
+///
+/// # Fields
+///
+/// * `command` - The command.
+/// * `error` - Why it failed, as the gateway describes its `KenkuError`.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct GatewayFailure {
+    pub command: KenkuCommandWithPayload,
+    pub error: String,
+}
+
+impl GatewayReport {
+    /// Returns `true` when every command succeeded.
+    pub fn is_success(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    /// Returns how many commands succeeded.
+    pub fn succeeded(&self) -> usize {
+        self.sent.saturating_sub(self.failures.len())
+    }
+}
+
+impl GatewayClient {
+    /// Creates a client of the gateway served under `base`, such as `"http://bridge.local:8080"`.
+    pub fn new(base: impl Into<String>) -> GatewayClient {
+        GatewayClient {
+            client: reqwest::Client::new(),
+            base: base.into(),
+            token: None,
+        }
+    }
+
+    /// Sends `token` as a bearer token with every request.
+    pub fn token(mut self, token: impl Into<String>) -> GatewayClient {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// Sends the requests with `client`, to set timeouts, proxies or TLS roots.
+    pub fn http_client(mut self, client: reqwest::Client) -> GatewayClient {
+        self.client = client;
+        self
+    }
+
+    /// Returns the soundboards and sounds, from `GET /soundboard`.
+    pub async fn get_soundboard(&self) -> Result<SoundboardGetResponse, KenkuError> {
+        self.get(&["soundboard"]).await
+    }
+
+    /// Returns the sounds playing, from `GET /soundboard/playback`.
+    pub async fn get_soundboard_playback(&self) -> Result<SoundboardPlaybackResponse, KenkuError> {
+        self.get(&["soundboard", "playback"]).await
+    }
+
+    /// Returns the playlists and tracks, from `GET /playlist`.
+    pub async fn get_playlist(&self) -> Result<PlaylistGetResponse, KenkuError> {
+        self.get(&["playlist"]).await
+    }
+
+    /// Returns the playlist playback, from `GET /playlist/playback`.
+    pub async fn get_playlist_playback(&self) -> Result<PlaylistPlaybackResponse, KenkuError> {
+        self.get(&["playlist", "playback"]).await
+    }
+
+    /// Plays the playlist with the id `id` from its first track.
+    pub async fn play_playlist(&self, id: &str) -> Result<(), KenkuError> {
+        self.execute(&KenkuCommandWithPayload::PlayPlaylist(id.to_string()))
+            .await
+    }
+
+    /// Plays the track with the id `id`.
+    pub async fn play_track_id(&self, id: &str) -> Result<(), KenkuError> {
+        self.execute(&KenkuCommandWithPayload::PlayTrack(id.to_string()))
+            .await
+    }
+
+    /// Plays the soundboard sound with the id `id`.
+    pub async fn play_sound_id(&self, id: &str) -> Result<(), KenkuError> {
+        self.execute(&KenkuCommandWithPayload::PlaySound(id.to_string()))
+            .await
+    }
+
+    /// Stops the soundboard sound with the id `id`.
+    pub async fn stop_sound_id(&self, id: &str) -> Result<(), KenkuError> {
+        self.execute(&KenkuCommandWithPayload::StopSound(id.to_string()))
+            .await
+    }
+
+    /// Controls the playlist playback, as `playlist::playback` does for a `Controller`.
+    pub async fn playback(&self, command: KenkuPlaybackCommand) -> Result<(), KenkuError> {
+        self.execute(&KenkuCommandWithPayload::Playback(command))
+            .await
+    }
+
+    /// Has the gateway send `command`.
+    ///
+    /// # Returns
+    ///
+    /// This function returns a `KenkuError::Status` holding the gateway's answer if the gateway
+    /// refused the command, or with `502 Bad Gateway` and the error if the Kenku Remote failed it.
+    pub async fn execute(&self, command: &KenkuCommandWithPayload) -> Result<(), KenkuError> {
+        let report: GatewayReport = self
+            .send(Method::POST, &["commands"], Some(&json!(command)))
+            .await?;
+
+        match report.failures.into_iter().next() {
+            Some(failure) => Err(KenkuError::Status {
+                status: StatusCode::BAD_GATEWAY,
+                body: failure.error,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Has the gateway send every command of `commands`, one after the other, in order.
+    ///
+    /// As with `Controller::execute_batch`, a failed command does not stop the batch.
+    pub async fn execute_batch(
+        &self,
+        commands: &[KenkuCommandWithPayload],
+    ) -> Result<GatewayReport, KenkuError> {
+        self.send(Method::POST, &["commands"], Some(&json!(commands)))
+            .await
+    }
+
+    /// Returns the current playback, from `GET /snapshot`.
+    pub async fn snapshot(&self) -> Result<PlaybackSnapshot, KenkuError> {
+        self.get(&["snapshot"]).await
+    }
+
+    /// Has the gateway restore `snapshot`.
+    pub async fn restore(&self, snapshot: &PlaybackSnapshot) -> Result<GatewayReport, KenkuError> {
+        self.send(Method::PUT, &["snapshot"], Some(&json!(snapshot)))
+            .await
+    }
+
+    /// Returns the names of the scenes the gateway serves.
+    pub async fn scenes(&self) -> Result<Vec<String>, KenkuError> {
+        self.get(&["scenes"]).await
+    }
+
+    /// Has the gateway activate the scene called `name`.
+    pub async fn activate_scene(&self, name: &str) -> Result<GatewayReport, KenkuError> {
+        self.send(Method::POST, &["scenes", name], None).await
+    }
+
+    /// Returns the counts of every client, from `GET /admin/clients`. Needs a moderator's token.
+    pub async fn clients(&self) -> Result<Vec<ClientStats>, KenkuError> {
+        self.get(&["admin", "clients"]).await
+    }
+
+    /// Has the gateway turn away every request of the client called `name` for `duration`.
+    ///
+    /// The gateway counts whole seconds. Needs a moderator's token.
+    pub async fn ban(&self, name: &str, duration: Duration) -> Result<(), KenkuError> {
+        let body = json!({"seconds": duration.as_secs()});
+
+        self.request(Method::PUT, &["admin", "bans", name], Some(&body))
+            .await
+            .map(drop)
+    }
+
+    /// Lifts the ban of the client called `name`. Needs a moderator's token.
+    ///
+    /// # Returns
+    ///
+    /// This function returns a `KenkuError::Status` with `404 Not Found` if the client was not
+    /// banned.
+    pub async fn unban(&self, name: &str) -> Result<(), KenkuError> {
+        self.request(Method::DELETE, &["admin", "bans", name], None)
+            .await
+            .map(drop)
+    }
+
+    /// Returns `true` while the automated actions of the gateway's controller are paused. Needs a
+    /// moderator's token.
+    pub async fn automation_paused(&self) -> Result<bool, KenkuError> {
+        let answer: Value = self.get(&["admin", "automation"]).await?;
+
+        Ok(answer["paused"].as_bool().unwrap_or_default())
+    }
+
+    /// Pauses the automated actions of the gateway's controller. Needs a moderator's token.
+    pub async fn pause_automation(&self) -> Result<(), KenkuError> {
+        self.request(Method::PUT, &["admin", "automation"], None)
+            .await
+            .map(drop)
+    }
+
+    /// Resumes the automated actions of the gateway's controller. Needs a moderator's token.
+    pub async fn resume_automation(&self) -> Result<(), KenkuError> {
+        self.request(Method::DELETE, &["admin", "automation"], None)
+            .await
+            .map(drop)
+    }
+
+    /// Has the gateway stop accepting the credentials of the client called `name`. Needs a
+    /// moderator's token.
+    ///
+    /// # Returns
+    ///
+    /// This function returns a `KenkuError::Status` with `404 Not Found` if the gateway's
+    /// authenticator has no credentials of `name` to revoke.
+    pub async fn revoke_token(&self, name: &str) -> Result<(), KenkuError> {
+        self.request(Method::DELETE, &["admin", "tokens", name], None)
+            .await
+            .map(drop)
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &[&str]) -> Result<T, KenkuError> {
+        self.send(Method::GET, path, None).await
+    }
+
+    /// Sends a `method` request to the route made of the `path` segments, with `body` as the JSON
+    /// body when given, and parses the answer into `T`.
+    async fn send<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &[&str],
+        body: Option<&Value>,
+    ) -> Result<T, KenkuError> {
+        let response = self.request(method, path, body).await?;
+
+        Ok(response.json::<T>().await?)
+    }
+
+    /// Sends a `method` request to the route made of the `path` segments, with `body` as the JSON
+    /// body when given.
+    ///
+    /// Answers other than 2xx become a `KenkuError::Status` holding the `error` of their body.
+    async fn request(
+        &self,
+        method: Method,
+        path: &[&str],
+        body: Option<&Value>,
+    ) -> Result<reqwest::Response, KenkuError> {
+        let mut request = self.client.request(method, self.url(path)?);
+
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+
+        let response = request.send().await?;
+        let status = response.status();
+
+        if status.is_success() {
+            return Ok(response);
+        }
+
+        let body = response.text().await.unwrap_or_default();
+        let body = serde_json::from_str::<Value>(&body)
+            .ok()
+            .and_then(|answer| answer["error"].as_str().map(str::to_string))
+            .unwrap_or(body);
+
+        Err(KenkuError::Status { status, body })
+    }
+
+    /// Returns the URL of the route made of the `path` segments, which are percent-encoded.
+    fn url(&self, path: &[&str]) -> Result<Url, KenkuError> {
+        let mut url = Url::parse(&self.base).map_err(|error| {
+            KenkuError::InvalidInput(format!("{:?} is not a gateway URL: {}", self.base, error))
+        })?;
+
+        url.path_segments_mut()
+            .map_err(|_| KenkuError::InvalidInput(format!("{:?} is not a gateway URL", self.base)))?
+            .pop_if_empty()
+            .extend(path);
+
+        Ok(url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GatewayClient;
+
+    #[test]
+    fn routes_are_appended_to_the_base_and_encoded() {
+        let gateway = GatewayClient::new("http://bridge.local:8080/kenku/");
+
+        assert_eq!(
+            gateway.url(&["scenes", "boss fight/2"]).unwrap().as_str(),
+            "http://bridge.local:8080/kenku/scenes/boss%20fight%2F2"
+        );
+        assert!(GatewayClient::new("bridge.local").url(&["scenes"]).is_err());
+    }
+}
@@ -0,0 +1,136 @@
+//! Several gateways driven from one control seat, for events spread over rooms.
+//!
+//! A venue running a main hall and a side room has one bridge per room, each next to its own
+//! Kenku FM. A `Federation` names the `GatewayClient` of every room, reads them all at once as a
+//! merged view, and forwards commands to the one room they are meant for. Served through
+//! `rest::RestApi::federation`, it lets one gateway proxy the others under `/rooms`.
+//!
+//! ```no_run
+//! # async fn example() -> Result<(), kenku_control::KenkuError> {
+//! use kenku_control::{
+//!     gateway::{client::GatewayClient, federation::Federation},
+//!     KenkuCommandWithPayload,
+//! };
+//!
+//! let federation = Federation::new()
+//!     .room("hall", GatewayClient::new("http://hall.local:8080").token("hall-token"))
+//!     .room("side", GatewayClient::new("http://side.local:8080").token("side-token"));
+//!
+//! for (room, snapshot) in federation.snapshots().await {
+//!     println!("{}: {:?}", room, snapshot.map(|snapshot| snapshot.playlist.playing));
+//! }
+//!
+//! federation
+//!     .execute_batch("side", &[KenkuCommandWithPayload::PlaySound("s1".to_string())])
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+use crate::{
+    gateway::client::{GatewayClient, GatewayReport},
+    snapshot::PlaybackSnapshot,
+    KenkuCommandWithPayload, KenkuError,
+};
+use futures_util::future::join_all;
+use std::collections::BTreeMap;
+
+/// The gateways of several rooms, by name.
+///
+/// Cloning a federation is cheap: the HTTP clients of the gateways are shared.
+///
+/// # Fields
+///
+/// * `rooms` - The gateway of each room, by name.
+#[derive(Debug, Clone, Default)]
+pub struct Federation {
+    rooms: BTreeMap<String, GatewayClient>,
+}
+
+impl Federation {
+    /// Creates a federation without rooms.
+    pub fn new() -> Federation {
+        Federation::default()
+    }
+
+    /// Adds the room called `name`, served by `gateway`, replacing any room with the same name.
+    pub fn room(mut self, name: impl Into<String>, gateway: GatewayClient) -> Federation {
+        self.rooms.insert(name.into(), gateway);
+        self
+    }
+
+    /// Returns the names of the rooms, in order.
+    pub fn rooms(&self) -> Vec<String> {
+        self.rooms.keys().cloned().collect()
+    }
+
+    /// Returns the gateway of the room called `room`.
+    ///
+    /// # Returns
+    ///
+    /// This function returns a `KenkuError::NotFound` if there is no such room.
+    pub fn gateway(&self, room: &str) -> Result<&GatewayClient, KenkuError> {
+        self.rooms
+            .get(room)
+            .ok_or_else(|| KenkuError::NotFound(format!("room {:?}", room)))
+    }
+
+    /// Reads the playback of every room at once.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the snapshot of each room by name, or the error of the rooms that
+    /// could not be read, so one room being down does not hide the others.
+    pub async fn snapshots(&self) -> BTreeMap<String, Result<PlaybackSnapshot, KenkuError>> {
+        let snapshots = join_all(self.rooms.values().map(GatewayClient::snapshot)).await;
+
+        self.rooms.keys().cloned().zip(snapshots).collect()
+    }
+
+    /// Has the gateway of the room called `room` send every command of `commands`, in order.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the report of the room's gateway, or a `KenkuError::NotFound` if
+    /// there is no such room.
+    pub async fn execute_batch(
+        &self,
+        room: &str,
+        commands: &[KenkuCommandWithPayload],
+    ) -> Result<GatewayReport, KenkuError> {
+        self.gateway(room)?.execute_batch(commands).await
+    }
+
+    /// Has the gateway of the room called `room` activate its scene called `scene`.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the report of the room's gateway, or a `KenkuError::NotFound` if
+    /// there is no such room.
+    pub async fn activate_scene(
+        &self,
+        room: &str,
+        scene: &str,
+    ) -> Result<GatewayReport, KenkuError> {
+        self.gateway(room)?.activate_scene(scene).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Federation;
+    use crate::{gateway::client::GatewayClient, KenkuError};
+
+    #[test]
+    fn unknown_rooms_are_not_found() {
+        let federation = Federation::new()
+            .room("side", GatewayClient::new("http://side.local:8080"))
+            .room("hall", GatewayClient::new("http://hall.local:8080"));
+
+        assert_eq!(federation.rooms(), ["hall", "side"]);
+        assert!(federation.gateway("hall").is_ok());
+        assert!(matches!(
+            federation.gateway("attic"),
+            Err(KenkuError::NotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,343 @@
+//! A gRPC facade over the controller, for microservices and clients generated in other languages.
+//!
+//! The service is defined in `proto/kenku.proto`, which other languages can generate their
+//! clients from. It has three calls:
+//!
+//! * `Playback` - Runs a playlist playback action, such as `pause` or `volume`, when one is set,
+//!   then returns the playlist playback.
+//! * `Soundboard` - Plays or stops a sound, or every sound, when asked to, then returns the sounds
+//!   playing.
+//! * `Library` - Returns the playlists, tracks, soundboards and sounds.
+//!
+//! Failures are answered with a gRPC status that follows the `KenkuError`: `INVALID_ARGUMENT`
+//! for invalid input, `NOT_FOUND` for unknown ids, `DEADLINE_EXCEEDED` when the Kenku Remote
+//! timed out and `UNAVAILABLE` when it could not be reached.
+use crate::{
+    playlist::{Playlist, PlaylistPlaybackResponse, Repeat, Track},
+    soundboard::{self, Soundboards, Sounds},
+    Controller, KenkuCommandWithPayload, KenkuError, KenkuPlaybackCommand, Volume,
+};
+use std::net::SocketAddr;
+use tokio::{
+    net::{TcpListener, ToSocketAddrs},
+    task::JoinHandle,
+};
+use tonic::{
+    transport::{server::TcpIncoming, Server},
+    Request, Response, Status,
+};
+
+/// The messages, client and server generated from `proto/kenku.proto`.
+#[allow(clippy::all)]
+pub mod proto {
+    tonic::include_proto!("kenku.v1");
+}
+
+use proto::{
+    kenku_control_server::{KenkuControl, KenkuControlServer},
+    playback_request, soundboard_request,
+};
+
+/// The `KenkuControl` service, answering with `controller`.
+#[derive(Debug, Clone)]
+pub struct KenkuControlService {
+    controller: Controller,
+}
+
+/// A running `KenkuControlService`. It stops when dropped.
+#[derive(Debug)]
+pub struct GrpcServer {
+    address: SocketAddr,
+    handle: JoinHandle<()>,
+}
+
+impl KenkuControlService {
+    /// Creates the service of `controller`.
+    pub fn new(controller: &Controller) -> KenkuControlService {
+        KenkuControlService {
+            controller: controller.clone(),
+        }
+    }
+
+    /// Returns the service, to be added to a `tonic::transport::Server` next to other services.
+    pub fn into_server(self) -> KenkuControlServer<KenkuControlService> {
+        KenkuControlServer::new(self)
+    }
+
+    /// Serves the service alone on `bind`, such as `"127.0.0.1:50051"`.
+    ///
+    /// The service does not authenticate its callers, so it only listens on a loopback address.
+    /// To serve other hosts, add `into_server` to a `tonic::transport::Server` behind an
+    /// interceptor or a proxy that checks them.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the running `GrpcServer`, a `KenkuError::InvalidInput` if `bind` is
+    /// not a loopback address, or a `KenkuError::Io` if `bind` could not be bound.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if it is called outside of a tokio runtime.
+    pub async fn start(self, bind: impl ToSocketAddrs) -> Result<GrpcServer, KenkuError> {
+        let listener = TcpListener::bind(bind).await?;
+        let address = listener.local_addr()?;
+
+        if !address.ip().is_loopback() {
+            return Err(KenkuError::InvalidInput(format!(
+                "refusing to serve {} without authentication",
+                address
+            )));
+        }
+
+        let incoming = TcpIncoming::from_listener(listener, true, None)
+            .map_err(|error| KenkuError::Io(std::io::Error::other(error.to_string())))?;
+        let server = Server::builder().add_service(self.into_server());
+
+        let handle = tokio::spawn(async move {
+            let _ = server.serve_with_incoming(incoming).await;
+        });
+
+        Ok(GrpcServer { address, handle })
+    }
+}
+
+impl GrpcServer {
+    /// Returns the address the server listens on.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.address
+    }
+}
+
+impl Drop for GrpcServer {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+#[tonic::async_trait]
+impl KenkuControl for KenkuControlService {
+    async fn playback(
+        &self,
+        request: Request<proto::PlaybackRequest>,
+    ) -> Result<Response<proto::PlaybackState>, Status> {
+        if let Some(action) = request.into_inner().action {
+            let command = playback_command(action).map_err(status)?;
+            self.controller.execute(&command).await.map_err(status)?;
+        }
+
+        let playback = self
+            .controller
+            .get_playlist_playback()
+            .await
+            .map_err(status)?;
+
+        Ok(Response::new(playback.into()))
+    }
+
+    async fn soundboard(
+        &self,
+        request: Request<proto::SoundboardRequest>,
+    ) -> Result<Response<proto::SoundboardState>, Status> {
+        let command = match request.into_inner().action {
+            Some(soundboard_request::Action::Play(id)) => {
+                Some(KenkuCommandWithPayload::PlaySound(id))
+            }
+            Some(soundboard_request::Action::Stop(id)) => {
+                Some(KenkuCommandWithPayload::StopSound(id))
+            }
+            Some(soundboard_request::Action::StopAll(_)) => {
+                soundboard::playback::stop_all(&self.controller)
+                    .await
+                    .map_err(status)?;
+                None
+            }
+            None => None,
+        };
+
+        if let Some(command) = command {
+            self.controller.execute(&command).await.map_err(status)?;
+        }
+
+        let playback = self
+            .controller
+            .get_soundboard_playback()
+            .await
+            .map_err(status)?;
+
+        Ok(Response::new(proto::SoundboardState {
+            sounds: playback.sounds.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    async fn library(
+        &self,
+        _: Request<proto::LibraryRequest>,
+    ) -> Result<Response<proto::Library>, Status> {
+        let (playlist, soundboard) = tokio::try_join!(
+            self.controller.get_playlist(),
+            self.controller.get_soundboard()
+        )
+        .map_err(status)?;
+
+        Ok(Response::new(proto::Library {
+            playlists: playlist.playlists.into_iter().map(Into::into).collect(),
+            tracks: playlist.tracks.into_iter().map(Into::into).collect(),
+            soundboards: soundboard.soundboards.into_iter().map(Into::into).collect(),
+            sounds: soundboard.sounds.into_iter().map(Into::into).collect(),
+        }))
+    }
+}
+
+/// Turns a playback action into the command it stands for.
+fn playback_command(
+    action: playback_request::Action,
+) -> Result<KenkuCommandWithPayload, KenkuError> {
+    use playback_request::Action;
+
+    let command = match action {
+        Action::Play(_) => KenkuPlaybackCommand::PlaylistPlaybackPlay,
+        Action::Pause(_) => KenkuPlaybackCommand::PlaylistPlaybackPause,
+        Action::Next(_) => KenkuPlaybackCommand::PlaylistPlaybackNext,
+        Action::Previous(_) => KenkuPlaybackCommand::PlaylistPlaybackPrevious,
+        Action::Volume(level) => {
+            KenkuPlaybackCommand::PlaylistPlaybackVolume(Volume::try_new(level)?)
+        }
+        Action::Mute(muted) => KenkuPlaybackCommand::PlaylistPlaybackMute(muted),
+        Action::Shuffle(shuffle) => KenkuPlaybackCommand::PlaylistPlaybackShuffle(shuffle),
+        Action::Repeat(repeat) => {
+            let repeat = match proto::Repeat::try_from(repeat) {
+                Ok(proto::Repeat::Off) => Repeat::Off,
+                Ok(proto::Repeat::Track) => Repeat::Track,
+                Ok(proto::Repeat::Playlist) => Repeat::Playlist,
+                Err(_) => {
+                    return Err(KenkuError::InvalidInput(format!(
+                        "{} is not a repeat mode",
+                        repeat
+                    )))
+                }
+            };
+
+            KenkuPlaybackCommand::PlaylistPlaybackRepeat(repeat)
+        }
+        Action::PlayPlaylist(id) => return Ok(KenkuCommandWithPayload::PlayPlaylist(id)),
+        Action::PlayTrack(id) => return Ok(KenkuCommandWithPayload::PlayTrack(id)),
+    };
+
+    Ok(command.into())
+}
+
+/// Turns an error into the gRPC status closest to it.
+fn status(error: KenkuError) -> Status {
+    let message = error.to_string();
+
+    match error {
+        KenkuError::InvalidInput(_) => Status::invalid_argument(message),
+        KenkuError::NotFound(_) => Status::not_found(message),
+        KenkuError::Timeout(_) => Status::deadline_exceeded(message),
+        KenkuError::Connection(_) => Status::unavailable(message),
+        KenkuError::Unsupported(_) => Status::unimplemented(message),
+        KenkuError::Status { status, .. } if status.as_u16() == 404 => Status::not_found(message),
+        _ => Status::internal(message),
+    }
+}
+
+impl From<Repeat> for proto::Repeat {
+    fn from(repeat: Repeat) -> proto::Repeat {
+        match repeat {
+            Repeat::Off => proto::Repeat::Off,
+            Repeat::Track => proto::Repeat::Track,
+            Repeat::Playlist => proto::Repeat::Playlist,
+        }
+    }
+}
+
+impl From<PlaylistPlaybackResponse> for proto::PlaybackState {
+    fn from(playback: PlaylistPlaybackResponse) -> proto::PlaybackState {
+        proto::PlaybackState {
+            playing: playback.playing,
+            volume: playback.volume,
+            muted: playback.muted,
+            shuffle: playback.shuffle,
+            repeat: proto::Repeat::from(playback.repeat).into(),
+            track: playback.track.map(Into::into),
+            playlist: playback.playlist.map(Into::into),
+        }
+    }
+}
+
+impl From<Playlist> for proto::Playlist {
+    fn from(playlist: Playlist) -> proto::Playlist {
+        proto::Playlist {
+            id: playlist.id,
+            title: playlist.title,
+            tracks: playlist.tracks.unwrap_or_default(),
+            background: playlist.background,
+        }
+    }
+}
+
+impl From<Track> for proto::Track {
+    fn from(track: Track) -> proto::Track {
+        proto::Track {
+            id: track.id,
+            title: track.title,
+            url: track.url,
+            duration: track.duration,
+            progress: track.progress,
+        }
+    }
+}
+
+impl From<Soundboards> for proto::Soundboard {
+    fn from(soundboard: Soundboards) -> proto::Soundboard {
+        proto::Soundboard {
+            id: soundboard.id,
+            title: soundboard.title,
+            sounds: soundboard.sounds,
+            background: soundboard.background,
+        }
+    }
+}
+
+impl From<Sounds> for proto::Sound {
+    fn from(sound: Sounds) -> proto::Sound {
+        proto::Sound {
+            id: sound.id,
+            title: sound.title,
+            url: sound.url,
+            r#loop: sound._loop,
+            volume: sound.volume,
+            fade_in: sound.fade_in,
+            fade_out: sound.fade_out,
+            duration: sound.duration,
+            progress: sound.progress,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{playback_command, proto::playback_request::Action, status};
+    use crate::{playlist::Repeat, KenkuPlaybackCommand, Volume};
+    use tonic::Code;
+
+    #[test]
+    fn actions_become_commands() {
+        assert_eq!(
+            playback_command(Action::Volume(0.25)).unwrap(),
+            KenkuPlaybackCommand::PlaylistPlaybackVolume(Volume::from_percent(25)).into()
+        );
+        assert_eq!(
+            playback_command(Action::Repeat(2)).unwrap(),
+            KenkuPlaybackCommand::PlaylistPlaybackRepeat(Repeat::Playlist).into()
+        );
+        assert_eq!(
+            status(playback_command(Action::Volume(1.5)).unwrap_err()).code(),
+            Code::InvalidArgument
+        );
+        assert_eq!(
+            status(playback_command(Action::Repeat(7)).unwrap_err()).code(),
+            Code::InvalidArgument
+        );
+    }
+}
@@ -0,0 +1,98 @@
+//! A background task that keeps the remote state of a `Controller` fresh.
+use crate::Controller;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+/// A handle to a running health monitor.
+///
+/// The monitor stops when `HealthMonitor::stop` is called or when the handle is dropped.
+#[derive(Debug)]
+pub struct HealthMonitor {
+    handle: JoinHandle<()>,
+}
+
+impl HealthMonitor {
+    /// Stops the monitor. The last recorded state stays readable through `Controller::state`.
+    pub fn stop(self) {}
+}
+
+impl Drop for HealthMonitor {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+impl Controller {
+    /// Spawns a task that pings the server every `interval` and records its state.
+    ///
+    /// The state is shared by every clone of this controller, so any of them can read it with
+    /// `Controller::state` at no cost.
+    ///
+    /// # Arguments
+    ///
+    /// * `interval` - The time between two pings, at least a millisecond.
+    ///
+    /// # Returns
+    ///
+    /// This function returns a `HealthMonitor`; the task runs until it is stopped or dropped.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if it is called outside of a tokio runtime.
+    pub fn spawn_health_monitor(&self, interval: Duration) -> HealthMonitor {
+        let controller = self.clone();
+
+        let handle = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval.max(Duration::from_millis(1)));
+
+            loop {
+                ticker.tick().await;
+                controller.ping().await;
+            }
+        });
+
+        HealthMonitor { handle }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{testing::MockRemote, Controller, KenkuAddress, KenkuState};
+    use std::{net::TcpListener, time::Duration};
+
+    #[tokio::test]
+    async fn monitor_updates_state_of_every_clone() {
+        let remote = MockRemote::start().await;
+        let controller = remote.controller();
+        let clone = controller.clone();
+        let monitor = controller.spawn_health_monitor(Duration::from_millis(10));
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        monitor.stop();
+
+        assert_eq!(clone.state(), KenkuState::Online);
+    }
+
+    #[tokio::test]
+    async fn zero_intervals_still_ping() {
+        let remote = MockRemote::start().await;
+        let controller = remote.controller();
+        let _monitor = controller.spawn_health_monitor(Duration::ZERO);
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        assert_eq!(controller.state(), KenkuState::Online);
+    }
+
+    #[tokio::test]
+    async fn ping_reports_offline_remote() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = KenkuAddress::from(listener.local_addr().unwrap());
+        drop(listener);
+
+        let controller = Controller::from_address(address);
+
+        assert_eq!(controller.ping().await, KenkuState::Offline);
+        assert_eq!(controller.state(), KenkuState::Offline);
+    }
+}
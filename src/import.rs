@@ -0,0 +1,492 @@
+//! Checking a library description against the Kenku Remote.
+//!
+//! A `LibraryDescription` lists the playlists, tracks, soundboards and sounds a campaign expects,
+//! written by hand in JSON or TOML or produced by `export::LibraryExport`, whose JSON loads as a
+//! description. `LibraryDescription::diff` compares it with what the Kenku Remote reports and
+//! returns a `LibraryDiff`, so a game master can check their prep before the session starts.
+//!
+//! Entries are matched by id when the description gives one the remote knows, and by title,
+//! compared case-insensitively and without punctuation, otherwise. A description without
+//! soundboards, such as an export, leaves the soundboards unchecked, and likewise for playlists.
+use crate::{
+    export::tracks_of, format, playlist::PlaylistGetResponse, search::normalize,
+    soundboard::SoundboardGetResponse, Controller, KenkuError,
+};
+use serde::{Deserialize, Serialize};
+use std::{fmt, path::Path};
+
+/// Represents a track or sound of a description.
+///
+/// # Fields
+///
+/// * `id` - The id of the entry in Kenku FM, if known.
+/// * `title` - The title of the entry.
+#[serde_with::skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct EntryDescription {
+    #[serde(default)]
+    pub id: Option<String>,
+    pub title: String,
+}
+
+/// Represents a playlist of a description.
+///
+/// # Fields
+///
+/// * `id` - The id of the playlist in Kenku FM, if known.
+/// * `title` - The title of the playlist.
+/// * `tracks` - The tracks the playlist should hold.
+#[serde_with::skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct PlaylistDescription {
+    #[serde(default)]
+    pub id: Option<String>,
+    pub title: String,
+    #[serde(default)]
+    pub tracks: Vec<EntryDescription>,
+}
+
+/// Represents a soundboard of a description.
+///
+/// # Fields
+///
+/// * `id` - The id of the soundboard in Kenku FM, if known.
+/// * `title` - The title of the soundboard.
+/// * `sounds` - The sounds the soundboard should hold.
+#[serde_with::skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct SoundboardDescription {
+    #[serde(default)]
+    pub id: Option<String>,
+    pub title: String,
+    #[serde(default)]
+    pub sounds: Vec<EntryDescription>,
+}
+
+/// Represents the library a campaign expects.
+///
+/// # Fields
+///
+/// * `playlists` - The expected playlists.
+/// * `soundboards` - The expected soundboards.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct LibraryDescription {
+    pub playlists: Vec<PlaylistDescription>,
+    pub soundboards: Vec<SoundboardDescription>,
+}
+
+/// Represents the kind of a library entry.
+///
+/// # Variants
+///
+/// * `Playlist` - A playlist.
+/// * `Track` - A track of a playlist.
+/// * `Soundboard` - A soundboard.
+/// * `Sound` - A sound of a soundboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EntryKind {
+    Playlist,
+    Track,
+    Soundboard,
+    Sound,
+}
+
+/// Represents one difference between a description and the Kenku Remote.
+///
+/// `parent` is the title of the playlist or soundboard holding the entry, as described, and is
+/// `None` for playlists and soundboards themselves.
+///
+/// # Variants
+///
+/// * `Missing` - The entry is described but the remote does not have it.
+/// * `Extra` - The remote has the entry but it is not described.
+/// * `Retitled` - The entry was matched by id but its title differs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LibraryChange {
+    Missing {
+        kind: EntryKind,
+        parent: Option<String>,
+        title: String,
+    },
+    Extra {
+        kind: EntryKind,
+        parent: Option<String>,
+        id: String,
+        title: String,
+    },
+    Retitled {
+        kind: EntryKind,
+        id: String,
+        expected: String,
+        actual: String,
+    },
+}
+
+/// Represents the differences between a description and the Kenku Remote.
+///
+/// # Fields
+///
+/// * `changes` - The differences: playlists, then the tracks of each playlist, then soundboards and their sounds. Described entries come before extra ones at every level.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LibraryDiff {
+    pub changes: Vec<LibraryChange>,
+}
+
+impl LibraryDiff {
+    /// Returns `true` when the remote matches the description.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Returns the entries of `kind` the remote is missing.
+    pub fn missing(&self, kind: EntryKind) -> impl Iterator<Item = &LibraryChange> {
+        self.changes.iter().filter(
+            move |change| matches!(change, LibraryChange::Missing { kind: k, .. } if *k == kind),
+        )
+    }
+}
+
+impl LibraryDescription {
+    /// Reads a description from `path`, in the format matching its extension.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the description, or a `KenkuError` if the file cannot be read or decoded.
+    pub fn load(path: impl AsRef<Path>) -> Result<LibraryDescription, KenkuError> {
+        format::load(path)
+    }
+
+    /// Compares this description with the library and soundboards reported by the remote.
+    pub fn diff(
+        &self,
+        library: &PlaylistGetResponse,
+        soundboards: &SoundboardGetResponse,
+    ) -> LibraryDiff {
+        let mut changes = Vec::new();
+
+        if !self.playlists.is_empty() {
+            self.diff_playlists(library, &mut changes);
+        }
+
+        if !self.soundboards.is_empty() {
+            self.diff_soundboards(soundboards, &mut changes);
+        }
+
+        LibraryDiff { changes }
+    }
+
+    fn diff_playlists(&self, library: &PlaylistGetResponse, changes: &mut Vec<LibraryChange>) {
+        let described: Vec<_> = self
+            .playlists
+            .iter()
+            .map(|playlist| (playlist.id.as_deref(), playlist.title.as_str()))
+            .collect();
+        let remote: Vec<_> = library
+            .playlists
+            .iter()
+            .map(|playlist| (playlist.id.as_str(), playlist.title.as_str()))
+            .collect();
+        let matches = compare(EntryKind::Playlist, None, &described, &remote, changes);
+
+        for (playlist, index) in self.playlists.iter().zip(matches) {
+            let Some(index) = index else { continue };
+            let tracks: Vec<_> = tracks_of(library, &library.playlists[index])
+                .map(|track| (track.id.as_str(), track.title.as_str()))
+                .collect();
+
+            compare(
+                EntryKind::Track,
+                Some(&playlist.title),
+                &entries(&playlist.tracks),
+                &tracks,
+                changes,
+            );
+        }
+    }
+
+    fn diff_soundboards(
+        &self,
+        soundboards: &SoundboardGetResponse,
+        changes: &mut Vec<LibraryChange>,
+    ) {
+        let described: Vec<_> = self
+            .soundboards
+            .iter()
+            .map(|soundboard| (soundboard.id.as_deref(), soundboard.title.as_str()))
+            .collect();
+        let remote: Vec<_> = soundboards
+            .soundboards
+            .iter()
+            .map(|soundboard| (soundboard.id.as_str(), soundboard.title.as_str()))
+            .collect();
+        let matches = compare(EntryKind::Soundboard, None, &described, &remote, changes);
+
+        for (soundboard, index) in self.soundboards.iter().zip(matches) {
+            let Some(index) = index else { continue };
+            let sounds: Vec<_> = soundboards
+                .sounds_of_soundboard(&soundboards.soundboards[index])
+                .into_iter()
+                .map(|sound| (sound.id.as_str(), sound.title.as_str()))
+                .collect();
+
+            compare(
+                EntryKind::Sound,
+                Some(&soundboard.title),
+                &entries(&soundboard.sounds),
+                &sounds,
+                changes,
+            );
+        }
+    }
+}
+
+impl Controller {
+    /// Compares `description` with the library and soundboards of the Kenku Remote.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the `LibraryDiff`, or the `KenkuError` raised while fetching the library or the soundboards.
+    pub async fn diff_library(
+        &self,
+        description: &LibraryDescription,
+    ) -> Result<LibraryDiff, KenkuError> {
+        let library = self.get_playlist().await?;
+        let soundboards = self.get_soundboard().await?;
+
+        Ok(description.diff(&library, &soundboards))
+    }
+}
+
+impl fmt::Display for EntryKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            EntryKind::Playlist => "playlist",
+            EntryKind::Track => "track",
+            EntryKind::Soundboard => "soundboard",
+            EntryKind::Sound => "sound",
+        };
+
+        f.write_str(name)
+    }
+}
+
+impl fmt::Display for LibraryChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parent_kind = |kind: &EntryKind| match kind {
+            EntryKind::Track => "playlist",
+            _ => "soundboard",
+        };
+
+        match self {
+            LibraryChange::Missing {
+                kind,
+                parent,
+                title,
+            } => {
+                write!(f, "missing {} {:?}", kind, title)?;
+                match parent {
+                    Some(parent) => write!(f, " in {} {:?}", parent_kind(kind), parent),
+                    None => Ok(()),
+                }
+            }
+            LibraryChange::Extra {
+                kind,
+                parent,
+                id,
+                title,
+            } => {
+                write!(f, "extra {} {:?} ({})", kind, title, id)?;
+                match parent {
+                    Some(parent) => write!(f, " in {} {:?}", parent_kind(kind), parent),
+                    None => Ok(()),
+                }
+            }
+            LibraryChange::Retitled {
+                kind,
+                id,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "{} {} is titled {:?}, expected {:?}",
+                kind, id, actual, expected
+            ),
+        }
+    }
+}
+
+impl fmt::Display for LibraryDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for change in &self.changes {
+            writeln!(f, "{}", change)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn entries(described: &[EntryDescription]) -> Vec<(Option<&str>, &str)> {
+    described
+        .iter()
+        .map(|entry| (entry.id.as_deref(), entry.title.as_str()))
+        .collect()
+}
+
+/// Matches the `(id, title)` pairs of `described` with those of `remote`, recording the changes.
+///
+/// # Returns
+///
+/// The index in `remote` of each described entry, or `None` when it is missing.
+fn compare(
+    kind: EntryKind,
+    parent: Option<&str>,
+    described: &[(Option<&str>, &str)],
+    remote: &[(&str, &str)],
+    changes: &mut Vec<LibraryChange>,
+) -> Vec<Option<usize>> {
+    let mut used = vec![false; remote.len()];
+    let mut matches = Vec::with_capacity(described.len());
+
+    for (id, title) in described {
+        let by_id = id
+            .and_then(|id| (0..remote.len()).find(|index| !used[*index] && remote[*index].0 == id));
+        let by_title = || {
+            let title = normalize(title);
+            (0..remote.len()).find(|index| !used[*index] && normalize(remote[*index].1) == title)
+        };
+
+        let found = by_id.or_else(by_title);
+
+        match found {
+            Some(index) => {
+                used[index] = true;
+
+                if by_id.is_some() && remote[index].1 != *title {
+                    changes.push(LibraryChange::Retitled {
+                        kind,
+                        id: remote[index].0.to_string(),
+                        expected: title.to_string(),
+                        actual: remote[index].1.to_string(),
+                    });
+                }
+            }
+            None => changes.push(LibraryChange::Missing {
+                kind,
+                parent: parent.map(str::to_string),
+                title: title.to_string(),
+            }),
+        }
+
+        matches.push(found);
+    }
+
+    for ((id, title), _) in remote.iter().zip(used).filter(|(_, used)| !used) {
+        changes.push(LibraryChange::Extra {
+            kind,
+            parent: parent.map(str::to_string),
+            id: id.to_string(),
+            title: title.to_string(),
+        });
+    }
+
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EntryKind, LibraryChange, LibraryDescription};
+    use crate::{
+        export::LibraryExport,
+        playlist::{Playlist, PlaylistGetResponse, Track},
+        soundboard::{SoundboardGetResponse, Soundboards, Sounds},
+        testing,
+    };
+    use serde_json::json;
+
+    fn library() -> PlaylistGetResponse {
+        let track = |id: &str, title: &str| Track {
+            title: title.to_string(),
+            ..testing::track(id)
+        };
+
+        PlaylistGetResponse {
+            playlists: vec![Playlist {
+                title: "Town".to_string(),
+                ..testing::playlist("p1", &["t1", "t2"])
+            }],
+            tracks: vec![track("t1", "Tavern"), track("t2", "Market")],
+        }
+    }
+
+    fn soundboards() -> SoundboardGetResponse {
+        SoundboardGetResponse {
+            soundboards: vec![Soundboards {
+                title: "Weather".to_string(),
+                ..testing::soundboard("b1", &["s1"])
+            }],
+            sounds: vec![Sounds {
+                title: "Rain".to_string(),
+                _loop: true,
+                ..testing::sound("s1")
+            }],
+        }
+    }
+
+    #[test]
+    fn descriptions_match_by_id_then_title() {
+        let description: LibraryDescription = serde_json::from_value(json!({
+            "playlists": [
+                {"title": "town", "tracks": [
+                    {"id": "t1", "title": "The Tavern"},
+                    {"title": "Blacksmith"}
+                ]},
+                {"title": "Dungeon"}
+            ]
+        }))
+        .unwrap();
+
+        let diff = description.diff(&library(), &soundboards());
+
+        assert_eq!(
+            diff.changes,
+            vec![
+                LibraryChange::Missing {
+                    kind: EntryKind::Playlist,
+                    parent: None,
+                    title: "Dungeon".to_string()
+                },
+                LibraryChange::Retitled {
+                    kind: EntryKind::Track,
+                    id: "t1".to_string(),
+                    expected: "The Tavern".to_string(),
+                    actual: "Tavern".to_string()
+                },
+                LibraryChange::Missing {
+                    kind: EntryKind::Track,
+                    parent: Some("town".to_string()),
+                    title: "Blacksmith".to_string()
+                },
+                LibraryChange::Extra {
+                    kind: EntryKind::Track,
+                    parent: Some("town".to_string()),
+                    id: "t2".to_string(),
+                    title: "Market".to_string()
+                },
+            ]
+        );
+        assert_eq!(diff.missing(EntryKind::Track).count(), 1);
+        assert_eq!(
+            diff.changes[3].to_string(),
+            r#"extra track "Market" (t2) in playlist "town""#
+        );
+    }
+
+    #[test]
+    fn exports_load_as_descriptions() {
+        let export = LibraryExport::from(&library()).to_json().unwrap();
+        let description: LibraryDescription = serde_json::from_str(&export).unwrap();
+
+        assert!(description.diff(&library(), &soundboards()).is_empty());
+    }
+}
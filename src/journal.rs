@@ -0,0 +1,568 @@
+//! A bounded, append-only journal of events with replay cursors.
+//!
+//! Every entry gets a monotonically increasing id. A client that lost its connection can ask for
+//! `Journal::since(last_seen_id)` to catch up instead of losing what happened in between, as the
+//! clients of `websocket::WebSocketServer::start_with_journal` do with `{"since": N}`. Entries
+//! are written as JSON lines by default, so the journal survives restarts of the process, even
+//! one that crashed halfway through an append. Other formats, such as MessagePack for compact
+//! journals, are chosen with `Journal::open_with_format`.
+//!
+//! With the `zstd` feature, `Journal::set_compression` stores the file compressed. Compressed
+//! journals are recognized when they are opened, so reading them needs no extra call. A
+//! `RotationPolicy` moves the file aside once it grows past a size, keeping a few old files.
+use crate::{format::Format, KenkuError};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    collections::{HashMap, VecDeque},
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Represents one entry of the journal.
+///
+/// # Fields
+///
+/// * `id` - The id of the entry. Ids start at 1 and increase by one per entry.
+/// * `timestamp_ms` - When the entry was appended, in milliseconds since the Unix epoch.
+/// * `event` - The journaled event.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct JournalEntry<T> {
+    pub id: u64,
+    pub timestamp_ms: u64,
+    pub event: T,
+}
+
+/// The magic number every zstd frame starts with.
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// Represents when the file of a journal is rotated.
+///
+/// Once the file is larger than `max_bytes`, it is renamed with a `.1` suffix, older rotated
+/// files are shifted to `.2`, `.3` and so on, and a new file holding the retained entries is
+/// written. `max_bytes` should leave room for well over `capacity` entries, or the journal
+/// rotates on every append.
+///
+/// # Fields
+///
+/// * `max_bytes` - The size of the file, in bytes, above which it is rotated.
+/// * `keep` - How many rotated files to keep. The oldest one is deleted. Zero keeps none.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RotationPolicy {
+    pub max_bytes: u64,
+    pub keep: usize,
+}
+
+/// A bounded, append-only journal persisted as JSON lines.
+///
+/// Only the newest `capacity` entries are kept. When the file grows past twice that, it is
+/// rewritten with the retained entries only. With a format other than JSON, every entry is
+/// stored as a 4-byte little-endian length followed by the encoded entry.
+#[derive(Debug)]
+pub struct Journal<T> {
+    path: PathBuf,
+    format: Format,
+    capacity: usize,
+    compressed: bool,
+    rotation: Option<RotationPolicy>,
+    entries: VecDeque<JournalEntry<T>>,
+    lines_on_disk: usize,
+    bytes_on_disk: u64,
+    next_id: u64,
+    cursors: HashMap<String, u64>,
+}
+
+impl<T: Serialize + DeserializeOwned + Clone> Journal<T> {
+    /// Opens the journal stored at `path`, creating it when it does not exist.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The file the journal is stored in.
+    /// * `capacity` - The maximum number of entries to keep. Must be greater than zero.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the opened `Journal`, or a `KenkuError` if the file cannot be read or holds malformed entries before its last one.
+    pub fn open(path: impl AsRef<Path>, capacity: usize) -> Result<Journal<T>, KenkuError> {
+        Journal::open_with_format(path, capacity, Format::Json)
+    }
+
+    /// Opens the journal stored at `path` in `format`, creating it when it does not exist.
+    ///
+    /// A journal must always be reopened with the format it was written in. A last entry that
+    /// cannot be decoded was cut short by a crash while it was appended: it is dropped, and the
+    /// file is rewritten without it so that the next entries are appended after the valid ones.
+    pub fn open_with_format(
+        path: impl AsRef<Path>,
+        capacity: usize,
+        format: Format,
+    ) -> Result<Journal<T>, KenkuError> {
+        if capacity == 0 {
+            return Err(KenkuError::InvalidInput(
+                "journal capacity must be greater than zero".to_string(),
+            ));
+        }
+
+        let path = path.as_ref().to_path_buf();
+        let mut entries = VecDeque::new();
+        let mut lines_on_disk = 0;
+        let mut bytes_on_disk = 0;
+        let mut compressed = false;
+        let mut torn = false;
+
+        if path.exists() {
+            let mut contents = fs::read(&path)?;
+            bytes_on_disk = contents.len() as u64;
+
+            if contents.starts_with(&ZSTD_MAGIC) {
+                contents = decompress(&contents)?;
+                compressed = true;
+            }
+
+            // A JSON line whose newline was never written would have the next entry glued to it.
+            torn = format == Format::Json && !contents.is_empty() && !contents.ends_with(b"\n");
+            let records = records(&contents, format);
+            let last = records.len().saturating_sub(1);
+
+            for (index, record) in records.into_iter().enumerate() {
+                let entry = match record.and_then(|record| format.decode::<JournalEntry<T>>(record))
+                {
+                    Ok(entry) => entry,
+                    Err(_) if index == last => {
+                        torn = true;
+                        break;
+                    }
+                    Err(error) => {
+                        return Err(KenkuError::Serialization(format!(
+                            "malformed journal entry in {}: {}",
+                            path.display(),
+                            error
+                        )))
+                    }
+                };
+
+                entries.push_back(entry);
+                lines_on_disk += 1;
+
+                if entries.len() > capacity {
+                    entries.pop_front();
+                }
+            }
+        }
+
+        let next_id = entries.back().map_or(1, |entry| entry.id + 1);
+
+        let mut journal = Journal {
+            path,
+            format,
+            capacity,
+            compressed,
+            rotation: None,
+            entries,
+            lines_on_disk,
+            bytes_on_disk,
+            next_id,
+            cursors: HashMap::new(),
+        };
+
+        if torn {
+            journal.compact()?;
+        }
+
+        Ok(journal)
+    }
+
+    /// Appends `event` to the journal and returns its id.
+    pub fn append(&mut self, event: T) -> Result<u64, KenkuError> {
+        let entry = JournalEntry {
+            id: self.next_id,
+            timestamp_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_millis() as u64),
+            event,
+        };
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        let record = self.to_disk(to_record(&entry, self.format)?)?;
+        file.write_all(&record)?;
+
+        self.next_id += 1;
+        self.lines_on_disk += 1;
+        self.bytes_on_disk += record.len() as u64;
+        self.entries.push_back(entry);
+
+        if self.entries.len() > self.capacity {
+            self.entries.pop_front();
+        }
+
+        match &self.rotation {
+            Some(policy) if self.bytes_on_disk > policy.max_bytes => self.rotate()?,
+            _ if self.lines_on_disk > self.capacity * 2 => self.compact()?,
+            _ => {}
+        }
+
+        Ok(self.next_id - 1)
+    }
+
+    /// Turns compression of the file on or off and rewrites the file accordingly.
+    #[cfg(feature = "zstd")]
+    pub fn set_compression(&mut self, enabled: bool) -> Result<(), KenkuError> {
+        self.compressed = enabled;
+
+        self.compact()
+    }
+
+    /// Returns `true` when the file is stored compressed.
+    pub fn is_compressed(&self) -> bool {
+        self.compressed
+    }
+
+    /// Sets when the file is rotated. `None`, the default, never rotates it.
+    pub fn set_rotation(&mut self, policy: Option<RotationPolicy>) {
+        self.rotation = policy;
+    }
+
+    /// Returns the path of the rotated file number `index`, such as `events.jsonl.1`.
+    pub fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{}", index));
+
+        PathBuf::from(path)
+    }
+
+    /// Returns every retained entry with an id greater than `id`.
+    ///
+    /// Passing `0` returns everything still in the journal. When older entries were already
+    /// dropped, the result starts at the oldest retained entry.
+    pub fn since(&self, id: u64) -> Vec<JournalEntry<T>> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.id > id)
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the id of the newest entry, or `0` when the journal is empty.
+    pub fn last_id(&self) -> u64 {
+        self.next_id - 1
+    }
+
+    /// Returns the entries the consumer `name` has not read yet and moves its cursor past them.
+    ///
+    /// A new consumer starts at the beginning of the retained entries.
+    pub fn read(&mut self, name: &str) -> Vec<JournalEntry<T>> {
+        let cursor = self.cursors.get(name).copied().unwrap_or(0);
+        let entries = self.since(cursor);
+
+        self.cursors.insert(name.to_string(), self.last_id());
+
+        entries
+    }
+
+    /// Moves the cursor of the consumer `name` to `id`.
+    pub fn seek(&mut self, name: &str, id: u64) {
+        self.cursors.insert(name.to_string(), id);
+    }
+
+    /// Rewrites the file so it only holds the retained entries.
+    fn compact(&mut self) -> Result<(), KenkuError> {
+        let temporary = self.path.with_extension("compacting");
+        let mut contents = Vec::new();
+
+        for entry in &self.entries {
+            contents.extend(to_record(entry, self.format)?);
+        }
+
+        let contents = self.to_disk(contents)?;
+        fs::write(&temporary, &contents)?;
+        fs::rename(&temporary, &self.path)?;
+        self.lines_on_disk = self.entries.len();
+        self.bytes_on_disk = contents.len() as u64;
+
+        Ok(())
+    }
+
+    /// Moves the file aside as rotated file number 1 and writes a new one with the retained entries.
+    fn rotate(&mut self) -> Result<(), KenkuError> {
+        let keep = self.rotation.as_ref().map_or(0, |policy| policy.keep);
+
+        if keep > 0 {
+            let _ = fs::remove_file(self.rotated_path(keep));
+
+            for index in (1..keep).rev() {
+                let from = self.rotated_path(index);
+
+                if from.exists() {
+                    fs::rename(from, self.rotated_path(index + 1))?;
+                }
+            }
+
+            fs::rename(&self.path, self.rotated_path(1))?;
+        }
+
+        self.compact()
+    }
+
+    /// Compresses `bytes` when the journal is compressed, or returns them unchanged.
+    fn to_disk(&self, bytes: Vec<u8>) -> Result<Vec<u8>, KenkuError> {
+        if self.compressed {
+            compress(&bytes)
+        } else {
+            Ok(bytes)
+        }
+    }
+}
+
+#[cfg(feature = "zstd")]
+fn compress(bytes: &[u8]) -> Result<Vec<u8>, KenkuError> {
+    Ok(zstd::stream::encode_all(bytes, 0)?)
+}
+
+#[cfg(not(feature = "zstd"))]
+fn compress(_: &[u8]) -> Result<Vec<u8>, KenkuError> {
+    Err(zstd_missing())
+}
+
+/// Decompresses the zstd frames of a compressed journal file.
+#[cfg(feature = "zstd")]
+fn decompress(bytes: &[u8]) -> Result<Vec<u8>, KenkuError> {
+    Ok(zstd::stream::decode_all(bytes)?)
+}
+
+#[cfg(not(feature = "zstd"))]
+fn decompress(_: &[u8]) -> Result<Vec<u8>, KenkuError> {
+    Err(zstd_missing())
+}
+
+#[cfg(not(feature = "zstd"))]
+fn zstd_missing() -> KenkuError {
+    KenkuError::Serialization("compressed journals need the `zstd` feature".to_string())
+}
+
+/// Encodes `entry` as one record of a journal file in `format`.
+fn to_record<T: Serialize>(entry: &JournalEntry<T>, format: Format) -> Result<Vec<u8>, KenkuError> {
+    if format == Format::Json {
+        let mut line = serde_json::to_vec(entry)
+            .map_err(|error| KenkuError::Serialization(error.to_string()))?;
+        line.push(b'\n');
+
+        return Ok(line);
+    }
+
+    let encoded = format.encode(entry)?;
+    let mut record = (encoded.len() as u32).to_le_bytes().to_vec();
+    record.extend(encoded);
+
+    Ok(record)
+}
+
+/// Splits the contents of a journal file in `format` into its records.
+fn records(contents: &[u8], format: Format) -> Vec<Result<&[u8], KenkuError>> {
+    if format == Format::Json {
+        return contents
+            .split(|byte| *byte == b'\n')
+            .filter(|line| !line.trim_ascii().is_empty())
+            .map(Ok)
+            .collect();
+    }
+
+    let mut records = Vec::new();
+    let mut rest = contents;
+
+    while !rest.is_empty() {
+        let Some((length, tail)) = rest.split_first_chunk::<4>() else {
+            records.push(Err(KenkuError::Serialization(
+                "truncated record length".to_string(),
+            )));
+            break;
+        };
+        let length = u32::from_le_bytes(*length) as usize;
+
+        if tail.len() < length {
+            records.push(Err(KenkuError::Serialization(
+                "truncated record".to_string(),
+            )));
+            break;
+        }
+
+        let (record, tail) = tail.split_at(length);
+        records.push(Ok(record));
+        rest = tail;
+    }
+
+    records
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Journal, RotationPolicy};
+    use crate::format::Format;
+    use std::path::PathBuf;
+
+    fn temporary_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "kenku_control_journal_{}_{}.jsonl",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn since_returns_entries_after_id() {
+        let path = temporary_path("since");
+        let mut journal = Journal::open(&path, 10).unwrap();
+
+        for event in ["a", "b", "c"] {
+            journal.append(event.to_string()).unwrap();
+        }
+
+        let events: Vec<String> = journal.since(1).into_iter().map(|e| e.event).collect();
+        assert_eq!(events, ["b", "c"]);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn journal_is_bounded_and_survives_reopening() {
+        let path = temporary_path("bounded");
+        let mut journal = Journal::open(&path, 2).unwrap();
+
+        for number in 0..7u32 {
+            journal.append(number).unwrap();
+        }
+
+        let reopened: Journal<u32> = Journal::open(&path, 2).unwrap();
+        let events: Vec<u32> = reopened.since(0).into_iter().map(|e| e.event).collect();
+
+        assert_eq!(events, [5, 6]);
+        assert_eq!(reopened.last_id(), 7);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn journal_reopens_in_every_format() {
+        for format in Format::available() {
+            let path = temporary_path(&format!("format_{}", format.extension()));
+            let mut journal = Journal::open_with_format(&path, 2, *format).unwrap();
+
+            for number in 0..5u32 {
+                journal.append(number).unwrap();
+            }
+
+            let reopened: Journal<u32> = Journal::open_with_format(&path, 2, *format).unwrap();
+            let events: Vec<u32> = reopened.since(0).into_iter().map(|e| e.event).collect();
+
+            assert_eq!(events, [3, 4]);
+            let _ = std::fs::remove_file(path);
+        }
+    }
+
+    #[test]
+    fn torn_last_entries_are_dropped_in_every_format() {
+        for format in Format::available() {
+            let path = temporary_path(&format!("torn_{}", format.extension()));
+            let mut journal = Journal::open_with_format(&path, 10, *format).unwrap();
+
+            for number in 0..3u32 {
+                journal.append(number).unwrap();
+            }
+
+            let contents = std::fs::read(&path).unwrap();
+            std::fs::write(&path, &contents[..contents.len() - 2]).unwrap();
+
+            let mut reopened: Journal<u32> = Journal::open_with_format(&path, 10, *format).unwrap();
+            assert_eq!(reopened.last_id(), 2);
+            reopened.append(3).unwrap();
+
+            let reopened: Journal<u32> = Journal::open_with_format(&path, 10, *format).unwrap();
+            let events: Vec<u32> = reopened.since(0).into_iter().map(|e| e.event).collect();
+
+            assert_eq!(events, [0, 1, 3]);
+            let _ = std::fs::remove_file(path);
+        }
+    }
+
+    #[test]
+    fn malformed_entries_before_the_last_one_are_refused() {
+        let path = temporary_path("malformed");
+        let mut journal = Journal::open(&path, 10).unwrap();
+
+        journal.append(1u32).unwrap();
+        let contents = std::fs::read(&path).unwrap();
+        std::fs::write(&path, [b"not json\n".as_slice(), &contents].concat()).unwrap();
+
+        assert!(matches!(
+            Journal::<u32>::open(&path, 10),
+            Err(crate::KenkuError::Serialization(_))
+        ));
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn compressed_journal_is_reopened_transparently() {
+        let path = temporary_path("compressed");
+        let mut journal = Journal::open(&path, 10).unwrap();
+
+        journal.append("before".to_string()).unwrap();
+        journal.set_compression(true).unwrap();
+        journal.append("after".to_string()).unwrap();
+
+        let reopened: Journal<String> = Journal::open(&path, 10).unwrap();
+        let events: Vec<String> = reopened.since(0).into_iter().map(|e| e.event).collect();
+
+        assert!(reopened.is_compressed());
+        assert_eq!(events, ["before", "after"]);
+        assert!(std::fs::read(&path)
+            .unwrap()
+            .starts_with(&super::ZSTD_MAGIC));
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn rotation_keeps_old_files() {
+        let path = temporary_path("rotation");
+        let mut journal = Journal::open(&path, 2).unwrap();
+        journal.set_rotation(Some(RotationPolicy {
+            max_bytes: 200,
+            keep: 2,
+        }));
+
+        for number in 0..20u32 {
+            journal.append(number).unwrap();
+        }
+
+        let reopened: Journal<u32> = Journal::open(&path, 2).unwrap();
+        let events: Vec<u32> = reopened.since(0).into_iter().map(|e| e.event).collect();
+
+        assert_eq!(events, [18, 19]);
+        assert!(journal.rotated_path(1).exists());
+        assert!(journal.rotated_path(2).exists());
+        assert!(!journal.rotated_path(3).exists());
+        assert!(std::fs::metadata(&path).unwrap().len() <= 200);
+
+        for index in 1..=2 {
+            let _ = std::fs::remove_file(journal.rotated_path(index));
+        }
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn cursors_track_each_consumer() {
+        let path = temporary_path("cursors");
+        let mut journal = Journal::open(&path, 10).unwrap();
+
+        journal.append(1u32).unwrap();
+        assert_eq!(journal.read("overlay").len(), 1);
+
+        journal.append(2u32).unwrap();
+        assert_eq!(journal.read("overlay").len(), 1);
+        assert_eq!(journal.read("bot").len(), 2);
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,355 @@
+//! Laying soundboard sounds out on fixed-size button grids.
+//!
+//! Control surfaces such as a 15-key Stream Deck or an 8×8 Launchpad have a fixed number of
+//! buttons. A `GridLayout` assigns every sound a `Cell` on a page of such a grid and keeps the
+//! assignment when the library changes, so a sound stays under the same button. The layout
+//! serializes to a map from sound id to cell, which can be saved with `format::save` and loaded
+//! back in the next session.
+use crate::soundboard::{SoundboardGetResponse, Soundboards, Sounds};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+
+/// Represents the dimensions of a button grid.
+///
+/// # Fields
+///
+/// * `columns` - The number of buttons in a row.
+/// * `rows` - The number of rows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct GridSize {
+    pub columns: u8,
+    pub rows: u8,
+}
+
+impl GridSize {
+    /// The 15-key Stream Deck: 5 columns and 3 rows.
+    pub const STREAM_DECK: GridSize = GridSize::new(5, 3);
+    /// The Launchpad pad matrix: 8 columns and 8 rows.
+    pub const LAUNCHPAD: GridSize = GridSize::new(8, 8);
+
+    /// Creates a grid of `columns` by `rows` buttons.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `columns` or `rows` is zero.
+    pub const fn new(columns: u8, rows: u8) -> GridSize {
+        assert!(columns > 0 && rows > 0, "a grid needs at least one button");
+
+        GridSize { columns, rows }
+    }
+
+    /// Returns the number of buttons on a page.
+    pub fn cells(self) -> usize {
+        self.columns as usize * self.rows as usize
+    }
+
+    /// Returns the cell at position `index`, counting row by row across pages.
+    pub fn cell(self, index: usize) -> Cell {
+        let within = index % self.cells();
+
+        Cell {
+            page: index / self.cells(),
+            row: (within / self.columns as usize) as u8,
+            column: (within % self.columns as usize) as u8,
+        }
+    }
+
+    /// Returns the position of `cell`, counting row by row across pages, or `None` when the cell lies outside this grid.
+    pub fn index(self, cell: Cell) -> Option<usize> {
+        (cell.row < self.rows && cell.column < self.columns).then(|| {
+            cell.page * self.cells()
+                + cell.row as usize * self.columns as usize
+                + cell.column as usize
+        })
+    }
+}
+
+/// Represents a button of a grid.
+///
+/// # Fields
+///
+/// * `page` - The page the button is on, starting at 0.
+/// * `row` - The row of the button, starting at 0 at the top.
+/// * `column` - The column of the button, starting at 0 on the left.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+pub struct Cell {
+    pub page: usize,
+    pub row: u8,
+    pub column: u8,
+}
+
+/// Represents a sound placed on a button, with what the button should show.
+///
+/// # Fields
+///
+/// * `cell` - The button the sound is on.
+/// * `sound` - The sound.
+/// * `soundboard` - The first soundboard containing the sound, if any.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GridKey<'a> {
+    pub cell: Cell,
+    pub sound: &'a Sounds,
+    pub soundboard: Option<&'a Soundboards>,
+}
+
+impl GridKey<'_> {
+    /// Returns the title of the sound shortened to `max_chars` characters, ending with `…` when cut.
+    pub fn label(&self, max_chars: usize) -> String {
+        label(&self.sound.title, max_chars)
+    }
+
+    /// Returns up to two capital letters for the sound, for buttons too small for a label.
+    pub fn icon(&self) -> String {
+        icon(&self.sound.title)
+    }
+}
+
+/// Assigns the sounds of a library to the buttons of a grid.
+///
+/// # Fields
+///
+/// * `size` - The grid the sounds are laid out on.
+/// * `assignments` - The cell of every placed sound, by sound id.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct GridLayout {
+    pub size: GridSize,
+    pub assignments: BTreeMap<String, Cell>,
+}
+
+impl GridLayout {
+    /// Creates a layout with no sounds placed.
+    pub fn new(size: GridSize) -> GridLayout {
+        GridLayout {
+            size,
+            assignments: BTreeMap::new(),
+        }
+    }
+
+    /// Creates a layout for `soundboard`, placing its sounds in soundboard order.
+    pub fn for_soundboard(size: GridSize, soundboard: &SoundboardGetResponse) -> GridLayout {
+        let mut layout = GridLayout::new(size);
+        layout.arrange(soundboard);
+        layout
+    }
+
+    /// Brings the layout in line with `soundboard`.
+    ///
+    /// Sounds that are already placed keep their cell. Sounds that are gone from the library free
+    /// their cell, as do sounds on a cell outside the grid. Sounds without a cell are then placed
+    /// on the first free cells, soundboard by soundboard, followed by the sounds of no soundboard.
+    pub fn arrange(&mut self, soundboard: &SoundboardGetResponse) {
+        let size = self.size;
+        let known: HashSet<&str> = soundboard
+            .sounds
+            .iter()
+            .map(|sound| sound.id.as_str())
+            .collect();
+
+        self.assignments
+            .retain(|id, cell| known.contains(id.as_str()) && size.index(*cell).is_some());
+
+        let mut taken: HashSet<usize> = self
+            .assignments
+            .values()
+            .filter_map(|cell| size.index(*cell))
+            .collect();
+        let mut next = 0;
+
+        for sound in sounds_in_order(soundboard) {
+            if self.assignments.contains_key(&sound.id) {
+                continue;
+            }
+
+            while !taken.insert(next) {
+                next += 1;
+            }
+
+            self.assignments.insert(sound.id.clone(), size.cell(next));
+        }
+    }
+
+    /// Returns the cell of the sound with the id `id`, if it is placed.
+    pub fn cell_of(&self, id: &str) -> Option<Cell> {
+        self.assignments.get(id).copied()
+    }
+
+    /// Returns the id of the sound on `cell`, if any.
+    pub fn sound_at(&self, cell: Cell) -> Option<&str> {
+        self.assignments
+            .iter()
+            .find(|(_, assigned)| **assigned == cell)
+            .map(|(id, _)| id.as_str())
+    }
+
+    /// Returns the number of pages holding at least one sound, and at least one.
+    pub fn page_count(&self) -> usize {
+        self.assignments
+            .values()
+            .map(|cell| cell.page + 1)
+            .max()
+            .unwrap_or(1)
+    }
+
+    /// Returns the buttons of page `page`, row by row.
+    ///
+    /// # Arguments
+    ///
+    /// * `page` - The page, starting at 0.
+    /// * `soundboard` - The library the sounds are looked up in.
+    ///
+    /// # Returns
+    ///
+    /// A vector with one entry per button of the page, `None` for buttons without a sound or whose sound is missing from `soundboard`.
+    pub fn page<'a>(
+        &self,
+        page: usize,
+        soundboard: &'a SoundboardGetResponse,
+    ) -> Vec<Option<GridKey<'a>>> {
+        let mut keys = vec![None; self.size.cells()];
+
+        for (id, cell) in &self.assignments {
+            if cell.page != page {
+                continue;
+            }
+
+            let (Some(index), Some(sound)) = (self.size.index(*cell), soundboard.sound_by_id(id))
+            else {
+                continue;
+            };
+
+            keys[index % self.size.cells()] = Some(GridKey {
+                cell: *cell,
+                sound,
+                soundboard: soundboard
+                    .soundboards
+                    .iter()
+                    .find(|board| board.sounds.contains(id)),
+            });
+        }
+
+        keys
+    }
+}
+
+/// Returns every sound of `soundboard` once, soundboard by soundboard, followed by the sounds of no soundboard.
+fn sounds_in_order(soundboard: &SoundboardGetResponse) -> Vec<&Sounds> {
+    let mut seen = HashSet::new();
+
+    soundboard
+        .soundboards
+        .iter()
+        .flat_map(|board| soundboard.sounds_of_soundboard(board))
+        .chain(&soundboard.sounds)
+        .filter(|sound| seen.insert(sound.id.as_str()))
+        .collect()
+}
+
+/// Shortens `title` to `max_chars` characters, replacing the last one with `…` when it is cut.
+pub fn label(title: &str, max_chars: usize) -> String {
+    let title = title.trim();
+
+    if title.chars().count() <= max_chars {
+        return title.to_string();
+    }
+
+    let mut label: String = title.chars().take(max_chars.saturating_sub(1)).collect();
+    label.truncate(label.trim_end().len());
+
+    if max_chars > 0 {
+        label.push('…');
+    }
+
+    label
+}
+
+/// Returns the capitalized initials of the first two words of `title`, or its first two letters when it is a single word.
+pub fn icon(title: &str) -> String {
+    let words: Vec<&str> = title
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect();
+
+    let letters: String = match words.as_slice() {
+        [] => String::new(),
+        [word] => word.chars().take(2).collect(),
+        [first, second, ..] => first
+            .chars()
+            .take(1)
+            .chain(second.chars().take(1))
+            .collect(),
+    };
+
+    letters.to_uppercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{icon, label, Cell, GridLayout, GridSize};
+    use crate::soundboard::SoundboardGetResponse;
+    use serde_json::json;
+
+    fn library(ids: &[&str]) -> SoundboardGetResponse {
+        let sounds: Vec<_> = ids
+            .iter()
+            .map(|id| {
+                json!({"id": id, "url": "", "title": format!("Sound {id}"), "loop": false, "volume": 1.0, "fadeIn": 0, "fadeOut": 0})
+            })
+            .collect();
+
+        serde_json::from_value(json!({
+            "soundboards": [{"id": "b1", "title": "Combat", "background": "", "sounds": ids}],
+            "sounds": sounds
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn sounds_fill_pages_row_by_row() {
+        let ids: Vec<String> = (0..17).map(|i| format!("s{i}")).collect();
+        let ids: Vec<&str> = ids.iter().map(String::as_str).collect();
+        let library = library(&ids);
+        let layout = GridLayout::for_soundboard(GridSize::STREAM_DECK, &library);
+
+        assert_eq!(layout.page_count(), 2);
+        assert_eq!(
+            layout.cell_of("s6"),
+            Some(Cell {
+                page: 0,
+                row: 1,
+                column: 1
+            })
+        );
+
+        let second = layout.page(1, &library);
+        assert_eq!(second.len(), 15);
+        assert_eq!(second[1].as_ref().unwrap().sound.id, "s16");
+        assert_eq!(second[1].as_ref().unwrap().soundboard.unwrap().id, "b1");
+        assert!(second[2].is_none());
+    }
+
+    #[test]
+    fn placed_sounds_keep_their_cell() {
+        let mut layout =
+            GridLayout::for_soundboard(GridSize::new(2, 2), &library(&["a", "b", "c"]));
+        let c = layout.cell_of("c");
+
+        layout.arrange(&library(&["d", "c"]));
+
+        assert_eq!(layout.cell_of("c"), c);
+        assert_eq!(layout.cell_of("a"), None);
+        assert_eq!(layout.sound_at(GridSize::new(2, 2).cell(0)), Some("d"));
+
+        let saved = serde_json::to_string(&layout).unwrap();
+        assert_eq!(serde_json::from_str::<GridLayout>(&saved).unwrap(), layout);
+    }
+
+    #[test]
+    fn labels_and_icons_come_from_titles() {
+        assert_eq!(label("Dragon Roar", 20), "Dragon Roar");
+        assert_eq!(label("Dragon Roar", 6), "Drago…");
+        assert_eq!(label("Dragon Roar", 7), "Dragon…");
+        assert_eq!(icon("dragon roar (loud)"), "DR");
+        assert_eq!(icon("thunder"), "TH");
+        assert_eq!(icon("  "), "");
+    }
+}
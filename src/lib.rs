@@ -1,24 +1,146 @@
 //! # Kenku Control
 //!
 //! `Kenku Control` is a API to manage your Kenku FM using Rust.
-use reqwest::{self, Client};
+use reqwest::{self, Client, StatusCode};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+#[cfg(feature = "automation")]
+use std::sync::atomic::AtomicBool;
 use std::{
-    net::{Ipv4Addr, SocketAddrV4},
-    str::FromStr,
-    time::Duration,
+    net::SocketAddrV4,
+    sync::{Arc, RwLock},
 };
 use utils::*;
 
+pub use address::KenkuAddress;
+#[cfg(not(target_arch = "wasm32"))]
+pub use api::KenkuApi;
+pub use builder::ControllerBuilder;
+pub use error::KenkuError;
+#[cfg(not(target_arch = "wasm32"))]
+pub use reconnect::ReconnectPolicy;
+pub use retry::RetryPolicy;
+pub use volume::Volume;
+
+pub mod address;
+#[cfg(feature = "search")]
+pub mod aliases;
+#[cfg(feature = "announce")]
+pub mod announce;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod api;
+#[cfg(feature = "auth")]
+pub mod auth;
+#[cfg(feature = "automation")]
+pub mod automation;
+#[cfg(feature = "batch")]
+pub mod batch;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub mod builder;
+#[cfg(feature = "cache")]
+pub mod cache;
+#[cfg(feature = "capability")]
+pub mod capability;
+#[cfg(feature = "chaos")]
+pub mod chaos;
+#[cfg(feature = "auth")]
+pub mod clients;
+#[cfg(feature = "config")]
+pub mod config;
+#[cfg(feature = "discovery")]
+pub mod discovery;
+#[cfg(feature = "display")]
+pub mod display;
+pub mod error;
+#[cfg(feature = "events")]
+pub mod events;
+#[cfg(feature = "export")]
+pub mod export;
+#[cfg(feature = "format")]
+pub mod format;
+#[cfg(feature = "game-time")]
+pub mod game_time;
+#[cfg(feature = "gateway")]
+pub mod gateway;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+#[cfg(feature = "health-monitor")]
+pub mod health;
+#[cfg(feature = "import")]
+pub mod import;
+#[cfg(feature = "journal")]
+pub mod journal;
+#[cfg(feature = "layout")]
+pub mod layout;
+#[cfg(feature = "library")]
+pub mod library;
+#[cfg(feature = "log-filter")]
+pub mod log_filter;
+#[cfg(feature = "macros")]
+pub mod macros;
+#[cfg(feature = "mqtt")]
+pub mod mqtt;
+#[cfg(feature = "obs")]
+pub mod obs;
+#[cfg(feature = "osc")]
+pub mod osc;
+#[cfg(feature = "outro")]
+pub mod outro;
+#[cfg(feature = "overlay")]
+pub mod overlay;
+#[cfg(feature = "packs")]
+pub mod packs;
 pub mod playlist;
+pub mod prelude;
+#[cfg(feature = "progress")]
+pub mod progress;
+#[cfg(feature = "queue")]
+pub mod queue;
+#[cfg(feature = "quick")]
+pub mod quick;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod reconnect;
+#[cfg(feature = "rest")]
+pub mod rest;
+pub mod retry;
+#[cfg(feature = "scenes")]
+pub mod scenes;
+#[cfg(feature = "scheduler")]
+pub mod scheduler;
+#[cfg(feature = "search")]
+pub mod search;
+#[cfg(feature = "snapshot")]
+pub mod snapshot;
+#[cfg(feature = "soak")]
+pub mod soak;
 pub mod soundboard;
+#[cfg(feature = "store")]
+pub mod store;
+#[cfg(feature = "supervisor")]
+pub mod supervisor;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "transitions")]
+pub mod transitions;
+#[cfg(feature = "tui")]
+pub mod tui;
 pub mod utils;
+pub mod volume;
+#[cfg(feature = "timezone")]
+pub mod wall_clock;
+#[cfg(feature = "watch")]
+pub mod watch;
+#[cfg(feature = "webhooks")]
+pub mod webhooks;
+#[cfg(feature = "websocket")]
+pub mod websocket;
 
 /// Represents the state of the Kenku server.
 ///
 /// This enum has two variants:
 /// * `Online`: Represents that the Kenku server is online and reachable.
 /// * `Offline`: Represents that the Kenku server is offline or not reachable.
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 pub enum KenkuState {
     Online,
     Offline,
@@ -40,28 +162,54 @@ pub enum KenkuState {
 ///
 /// This function will panic if the client builder fails to build the client.
 fn build_client(milisseconds: u64) -> Client {
-    Client::builder()
-        .timeout(Duration::from_millis(milisseconds))
-        .build()
-        .unwrap()
+    let builder = Client::builder();
+
+    // The browser's fetch API has no request timeout, so it is left to the browser on wasm32.
+    #[cfg(not(target_arch = "wasm32"))]
+    let builder = builder.timeout(std::time::Duration::from_millis(milisseconds));
+    #[cfg(target_arch = "wasm32")]
+    let _ = milisseconds;
+
+    builder.build().unwrap()
 }
 
 /// Represents a command to control the playback of a playlist.
 ///
 /// This enum has variants for each possible playback command, including play, pause, next, previous, mute, volume, shuffle, and repeat.
 /// The `PlaylistPlaybackMute`, `PlaylistPlaybackVolume`, `PlaylistPlaybackShuffle`, and `PlaylistPlaybackRepeat` variants carry additional data.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub enum KenkuPlaybackCommand {
     PlaylistPlaybackPlay,
     PlaylistPlaybackPause,
     PlaylistPlaybackNext,
     PlaylistPlaybackPrevious,
     PlaylistPlaybackMute(bool),
-    PlaylistPlaybackVolume(f64),
+    PlaylistPlaybackVolume(Volume),
     PlaylistPlaybackShuffle(bool),
     PlaylistPlaybackRepeat(playlist::Repeat),
 }
 
+/// Represents a complete command for the Kenku server, together with the data it needs.
+///
+/// Unlike `KenkuPutCommand` and `KenkuPostCommand`, which only name a route, a `KenkuCommandWithPayload`
+/// can be stored, queued or scheduled and sent later with `Controller::execute`.
+///
+/// # Variants
+///
+/// * `PlayPlaylist` - Plays the playlist with the given id from its first track.
+/// * `PlayTrack` - Plays the track with the given id.
+/// * `PlaySound` - Plays the soundboard sound with the given id.
+/// * `StopSound` - Stops the soundboard sound with the given id.
+/// * `Playback` - Controls the playlist playback.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub enum KenkuCommandWithPayload {
+    PlayPlaylist(String),
+    PlayTrack(String),
+    PlaySound(String),
+    StopSound(String),
+    Playback(KenkuPlaybackCommand),
+}
+
 /// Represents a command to be sent to the Kenku server.
 ///
 /// The `KenkuCommand` enum is used to encapsulate different types of commands that can be sent to the Kenku server, such as GET, PUT, and POST commands.
@@ -128,6 +276,12 @@ pub enum KenkuGetCommand {
     PlaylistPlayback,
 }
 
+impl From<KenkuPlaybackCommand> for KenkuCommandWithPayload {
+    fn from(command: KenkuPlaybackCommand) -> Self {
+        KenkuCommandWithPayload::Playback(command)
+    }
+}
+
 /// Represents a response from the soundboard or playlist API.
 ///
 /// This enum can hold a response of any type, including `SoundboardGetResponse`, `SoundboardPlaybackResponse`, `PlaylistGetResponse`, and `PlaylistPlaybackResponse`.
@@ -140,19 +294,31 @@ pub enum KenkuResponse {
 
 /// Represents a controller for the Kenku server.
 ///
-/// This struct is used to model a controller for the Kenku server. It includes a HTTP client, the IP address and port of the server, and the current state of the server.
+/// This struct is used to model a controller for the Kenku server. It includes a HTTP client, the address of the server, and the current state of the server.
+///
+/// Cloning a controller is cheap: the HTTP client and its connection pool, the remote state, the automation flag and the cache are reference-counted, so every clone shares them. `Controller` is `Send` and `Sync`, so one controller can be kept in the shared state of a web server and used from every handler, or cloned into spawned tasks.
 ///
 /// # Fields
 ///
 /// * `client` - A `reqwest::Client` used to make HTTP requests to the server.
-/// * `ip` - A string representing the IP address of the server.
-/// * `port` - A string representing the port number of the server.
-/// * `kenku_remote_state` - A `KenkuState` representing the current state of the server.
-#[derive(Debug)]
+/// * `address` - A `KenkuAddress` holding the host and port of the server.
+/// * `kenku_remote_state` - The last known `KenkuState` of the server, shared by every clone of the controller. It is updated by each request and by the health monitor; read it with `Controller::state`.
+/// * `retry` - The `RetryPolicy` deciding which failed requests are sent again and how long to wait in between. Its delays are ignored on `wasm32`.
+/// * `reconnect` - An optional `ReconnectPolicy`. When set, a request that cannot reach the server waits for it to come back online and is then sent again. Not available on `wasm32`.
+/// * `automation_paused` - Whether automated actions are suspended, shared by every clone of the controller. Toggle it with `Controller::pause_automation` and `Controller::resume_automation`. Needs the `automation` feature.
+/// * `cache` - The `StateCache` holding the soundboard and playlist listings, shared by every clone of the controller. Disabled unless `ControllerBuilder::cache_ttl` is set. Needs the `cache` feature.
+#[derive(Debug, Clone)]
 pub struct Controller {
     pub client: Client,
-    pub address: SocketAddrV4,
-    pub kenku_remote_state: KenkuState,
+    pub address: KenkuAddress,
+    pub kenku_remote_state: Arc<RwLock<KenkuState>>,
+    pub retry: RetryPolicy,
+    #[cfg(not(target_arch = "wasm32"))]
+    pub reconnect: Option<ReconnectPolicy>,
+    #[cfg(feature = "automation")]
+    pub automation_paused: Arc<AtomicBool>,
+    #[cfg(feature = "cache")]
+    pub cache: Arc<cache::StateCache>,
 }
 
 /// Provides methods for `Controller`.
@@ -161,62 +327,107 @@ pub struct Controller {
 impl Controller {
     /// Creates a new `Controller`.
     ///
-    /// This function takes an IP address and a port, builds a new HTTP client with a timeout of 20 milliseconds, and returns a new `Controller` with the client, IP address, port, and an initial server state of `KenkuState::Offline`.
+    /// This function takes a host and a port, builds a new HTTP client with a timeout of 100 milliseconds, and returns a new `Controller` with the client, address, and an initial server state of `KenkuState::Offline`.
     ///
     /// # Arguments
     ///
-    /// * `ip` - A string that holds the IP address of the server.
-    /// * `port` - A string that holds the port number of the server.
+    /// * `host` - The IP address or DNS name of the server, e.g. `"127.0.0.1"` or `"kenku-host.local"`. Names are resolved by the HTTP client on each request.
+    /// * `port` - The port number of the server.
     ///
     /// # Returns
     ///
-    /// This function returns a new `Controller` with the specified IP address, port, and an initial server state of `KenkuState::Offline`.
-    pub fn new(ip: String, port: u16) -> Controller {
+    /// This function returns a new `Controller` with the specified address and an initial server state of `KenkuState::Offline`.
+    pub fn new(host: impl Into<String>, port: u16) -> Controller {
+        Controller::from_address(KenkuAddress::new(host, port))
+    }
+
+    /// Creates a new `Controller` for an IPv4 socket address.
+    pub fn from_ipv4(address: SocketAddrV4) -> Controller {
+        Controller::from_address(address.into())
+    }
+
+    /// Creates a new `Controller` for a `KenkuAddress`.
+    pub fn from_address(address: KenkuAddress) -> Controller {
         let client = build_client(100);
-        let ip = Ipv4Addr::from_str(ip.as_str()).expect("failed to convert String ip to Ipv4Addr.");
-        let address = SocketAddrV4::new(ip, port);
 
         Controller {
             client,
             address,
-            kenku_remote_state: KenkuState::Offline,
+            kenku_remote_state: Arc::new(RwLock::new(KenkuState::Offline)),
+            retry: RetryPolicy::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            reconnect: None,
+            #[cfg(feature = "automation")]
+            automation_paused: Arc::new(AtomicBool::new(false)),
+            #[cfg(feature = "cache")]
+            cache: Arc::default(),
         }
     }
 
-    pub fn from_ipv4(address: SocketAddrV4) -> Controller {
-        let client = build_client(100);
+    /// Returns a `ControllerBuilder` for the Kenku Remote at `host:port`, to configure timeouts, retries, headers or the HTTP client.
+    pub fn builder(host: impl Into<String>, port: u16) -> ControllerBuilder {
+        ControllerBuilder::new(host, port)
+    }
 
-        Controller {
-            client,
-            address,
-            kenku_remote_state: KenkuState::Offline,
+    /// Returns the last known state of the server.
+    ///
+    /// The state starts as `KenkuState::Offline` and is refreshed by every request, by `Controller::ping`, and by the health monitor when one is running.
+    pub fn state(&self) -> KenkuState {
+        *self.kenku_remote_state.read().unwrap()
+    }
+
+    /// Stores `state` as the last known state of the server.
+    pub(crate) fn set_state(&self, state: KenkuState) {
+        let mut current = self.kenku_remote_state.write().unwrap();
+
+        #[cfg(feature = "tracing")]
+        if *current != state {
+            tracing::info!(address = %self.address, previous = ?*current, current = ?state, "kenku remote state changed");
         }
+
+        *current = state;
     }
 
-    /// Sends a GET request to the soundboard API and returns a `SoundboardGetResponse`.
+    /// Checks whether the server answers HTTP requests and records the result.
     ///
-    /// This function constructs the URL for the request using the `process_url` function with the `KenkuGetCommand::Soundboard` command and the IP address and port of the server.
+    /// Unlike the other requests, a ping is sent once, without retries or reconnection, and any answer counts as online.
     ///
     /// # Returns
     ///
-    /// A `Result` which is either a `SoundboardGetResponse` or a `reqwest::Error`.
-    pub async fn get_soundboard(
-        &self,
-    ) -> Result<soundboard::SoundboardGetResponse, reqwest::Error> {
+    /// This function returns the new `KenkuState` of the server.
+    pub async fn ping(&self) -> KenkuState {
         let url = process_url(
-            &KenkuCommand::KenkuGet(KenkuGetCommand::Soundboard),
-            self.address,
+            &KenkuCommand::KenkuGet(KenkuGetCommand::PlaylistPlayback),
+            &self.address,
         );
+        let state = match self.client.get(url).send().await {
+            Ok(_) => KenkuState::Online,
+            Err(_) => KenkuState::Offline,
+        };
 
-        let response = self
-            .client
-            .get(url)
-            .send()
-            .await?
-            .json::<soundboard::SoundboardGetResponse>()
-            .await?;
+        self.set_state(state);
 
-        Ok(response)
+        state
+    }
+
+    /// Sends a GET request to the soundboard API and returns a `SoundboardGetResponse`.
+    ///
+    /// This function constructs the URL for the request using the `process_url` function with the `KenkuGetCommand::Soundboard` command and the IP address and port of the server. When the controller caches listings, a listing younger than the cache TTL is returned without a request.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is either a `SoundboardGetResponse` or a `KenkuError`.
+    pub async fn get_soundboard(&self) -> Result<soundboard::SoundboardGetResponse, KenkuError> {
+        #[cfg(feature = "cache")]
+        if let Some(soundboard) = self.cache.soundboard() {
+            return Ok(soundboard);
+        }
+
+        let soundboard = self.send_get(KenkuGetCommand::Soundboard).await?;
+        #[cfg(feature = "cache")]
+        self.cache.store_soundboard(&soundboard);
+
+        Ok(soundboard)
     }
 
     /// Sends a GET request to the soundboard API to get the current playback state.
@@ -225,44 +436,31 @@ impl Controller {
     ///
     /// # Returns
     ///
-    /// A `Result` which is either a `SoundboardPlaybackResponse` or a `reqwest::Error`.
+    /// A `Result` which is either a `SoundboardPlaybackResponse` or a `KenkuError`.
     pub async fn get_soundboard_playback(
         &self,
-    ) -> Result<soundboard::SoundboardPlaybackResponse, reqwest::Error> {
-        let url = process_url(
-            &KenkuCommand::KenkuGet(KenkuGetCommand::SoundboardPlayback),
-            self.address,
-        );
-        let response = self
-            .client
-            .get(url)
-            .send()
-            .await?
-            .json::<soundboard::SoundboardPlaybackResponse>()
-            .await?;
-        Ok(response)
+    ) -> Result<soundboard::SoundboardPlaybackResponse, KenkuError> {
+        self.send_get(KenkuGetCommand::SoundboardPlayback).await
     }
 
     /// Sends a GET request to the playlist API and returns a `PlaylistGetResponse`.
     ///
-    /// This function constructs the URL for the request using the `process_url` function with the `KenkuGetCommand::Playlist` command and the IP address and port of the server.
+    /// This function constructs the URL for the request using the `process_url` function with the `KenkuGetCommand::Playlist` command and the IP address and port of the server. When the controller caches listings, a listing younger than the cache TTL is returned without a request.
     ///
     /// # Returns
     ///
-    /// A `Result` which is either a `PlaylistGetResponse` or a `reqwest::Error`.
-    pub async fn get_playlist(&self) -> Result<playlist::PlaylistGetResponse, reqwest::Error> {
-        let url = process_url(
-            &KenkuCommand::KenkuGet(KenkuGetCommand::Playlist),
-            self.address,
-        );
-        let response = self
-            .client
-            .get(url)
-            .send()
-            .await?
-            .json::<playlist::PlaylistGetResponse>()
-            .await?;
-        Ok(response)
+    /// A `Result` which is either a `PlaylistGetResponse` or a `KenkuError`.
+    pub async fn get_playlist(&self) -> Result<playlist::PlaylistGetResponse, KenkuError> {
+        #[cfg(feature = "cache")]
+        if let Some(playlist) = self.cache.playlist() {
+            return Ok(playlist);
+        }
+
+        let playlist = self.send_get(KenkuGetCommand::Playlist).await?;
+        #[cfg(feature = "cache")]
+        self.cache.store_playlist(&playlist);
+
+        Ok(playlist)
     }
 
     /// Sends a GET request to the playlist API to get the current playback state.
@@ -271,46 +469,268 @@ impl Controller {
     ///
     /// # Returns
     ///
-    /// A `Result` which is either a `PlaylistPlaybackResponse` or a `reqwest::Error`.
+    /// A `Result` which is either a `PlaylistPlaybackResponse` or a `KenkuError`.
     pub async fn get_playlist_playback(
         &self,
-    ) -> Result<playlist::PlaylistPlaybackResponse, reqwest::Error> {
-        let url = process_url(
-            &KenkuCommand::KenkuGet(KenkuGetCommand::PlaylistPlayback),
-            self.address,
-        );
+    ) -> Result<playlist::PlaylistPlaybackResponse, KenkuError> {
+        self.send_get(KenkuGetCommand::PlaylistPlayback).await
+    }
+
+    /// Sends a request to play the playlist with the id `id` from its first track.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is either the `StatusCode` of the response or a `KenkuError`.
+    pub async fn play_playlist(&self, id: &str) -> Result<StatusCode, KenkuError> {
+        self.send_put(
+            KenkuPutCommand::PlaylistPlay,
+            Some(serde_json::json!({ "id": id })),
+        )
+        .await
+    }
+
+    /// Sends a request to play the track with the id `id`, without fetching the playlists first.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is either the `StatusCode` of the response or a `KenkuError`.
+    pub async fn play_track_id(&self, id: &str) -> Result<StatusCode, KenkuError> {
+        self.send_put(
+            KenkuPutCommand::PlaylistPlay,
+            Some(serde_json::json!({ "id": id })),
+        )
+        .await
+    }
+
+    /// Sends a request to play the soundboard sound with the id `id`, without fetching the soundboards first.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is either the `StatusCode` of the response or a `KenkuError`.
+    pub async fn play_sound_id(&self, id: &str) -> Result<StatusCode, KenkuError> {
+        self.send_put(
+            KenkuPutCommand::SoundboardPlay,
+            Some(serde_json::json!({ "id": id })),
+        )
+        .await
+    }
+
+    /// Sends a request to stop the soundboard sound with the id `id`, without fetching the soundboards first.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is either the `StatusCode` of the response or a `KenkuError`.
+    pub async fn stop_sound_id(&self, id: &str) -> Result<StatusCode, KenkuError> {
+        self.send_put(
+            KenkuPutCommand::SoundboardStop,
+            Some(serde_json::json!({ "id": id })),
+        )
+        .await
+    }
+
+    /// Sends `command` to the server.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is either the `StatusCode` of the response or a `KenkuError`.
+    pub async fn execute(
+        &self,
+        command: &KenkuCommandWithPayload,
+    ) -> Result<StatusCode, KenkuError> {
+        use playlist::playback;
+
+        match command {
+            KenkuCommandWithPayload::PlayPlaylist(id) => self.play_playlist(id).await,
+            KenkuCommandWithPayload::PlayTrack(id) => self.play_track_id(id).await,
+            KenkuCommandWithPayload::PlaySound(id) => self.play_sound_id(id).await,
+            KenkuCommandWithPayload::StopSound(id) => self.stop_sound_id(id).await,
+            KenkuCommandWithPayload::Playback(command) => match command {
+                KenkuPlaybackCommand::PlaylistPlaybackPlay => playback::playback_play(self).await,
+                KenkuPlaybackCommand::PlaylistPlaybackPause => playback::playback_pause(self).await,
+                KenkuPlaybackCommand::PlaylistPlaybackNext => playback::playback_next(self).await,
+                KenkuPlaybackCommand::PlaylistPlaybackPrevious => {
+                    playback::playback_previous(self).await
+                }
+                KenkuPlaybackCommand::PlaylistPlaybackMute(mute) => {
+                    playback::playback_mute(self, *mute).await
+                }
+                KenkuPlaybackCommand::PlaylistPlaybackVolume(volume) => {
+                    playback::playback_volume(self, *volume).await
+                }
+                KenkuPlaybackCommand::PlaylistPlaybackShuffle(shuffle) => {
+                    playback::playback_shuffle(self, *shuffle).await
+                }
+                KenkuPlaybackCommand::PlaylistPlaybackRepeat(repeat) => {
+                    playback::playback_repeat(self, repeat.clone()).await
+                }
+            },
+        }
+    }
+
+    /// Sends a GET request for `command` and parses the JSON body into `T`.
+    pub(crate) async fn send_get<T: DeserializeOwned>(
+        &self,
+        command: KenkuGetCommand,
+    ) -> Result<T, KenkuError> {
+        let url = process_url(&KenkuCommand::KenkuGet(command), &self.address);
+        let response = self.send(reqwest::Method::GET, &url, None).await?;
+
+        Ok(response.json::<T>().await?)
+    }
+
+    /// Sends a PUT request for `command`, with `payload` as the JSON body when given.
+    pub(crate) async fn send_put(
+        &self,
+        command: KenkuPutCommand,
+        payload: Option<serde_json::Value>,
+    ) -> Result<StatusCode, KenkuError> {
+        let url = process_url(&KenkuCommand::KenkuPut(command), &self.address);
         let response = self
-            .client
-            .get(url)
-            .send()
-            .await?
-            .json::<playlist::PlaylistPlaybackResponse>()
+            .send(reqwest::Method::PUT, &url, payload.as_ref())
             .await?;
-        Ok(response)
+
+        Ok(response.status())
+    }
+
+    /// Sends a POST request for `command`.
+    pub(crate) async fn send_post(
+        &self,
+        command: KenkuPostCommand,
+    ) -> Result<StatusCode, KenkuError> {
+        let url = process_url(&KenkuCommand::KenkuPost(command), &self.address);
+        let response = self.send(reqwest::Method::POST, &url, None).await?;
+
+        Ok(response.status())
+    }
+
+    /// Sends a `method` request to `url`, with `body` as the JSON body when given.
+    ///
+    /// Failures allowed by `retry` are retried up to `retry.max_retries` times. After that, a
+    /// connection error waits for the server according to `reconnect`, when set, and starts over.
+    /// With the `tracing` feature, the request runs in a `kenku_request` span.
+    async fn send(
+        &self,
+        method: reqwest::Method,
+        url: &str,
+        body: Option<&serde_json::Value>,
+    ) -> Result<reqwest::Response, KenkuError> {
+        #[cfg(feature = "tracing")]
+        let span = tracing::debug_span!("kenku_request", method = %method, url = %url);
+
+        let attempts = self.send_attempts(method, url, body);
+
+        #[cfg(feature = "tracing")]
+        let attempts = tracing::Instrument::instrument(attempts, span);
+
+        attempts.await
+    }
+
+    /// Sends the request of `send`, retrying and reconnecting as configured.
+    async fn send_attempts(
+        &self,
+        method: reqwest::Method,
+        url: &str,
+        body: Option<&serde_json::Value>,
+    ) -> Result<reqwest::Response, KenkuError> {
+        let mut attempt = 0;
+
+        loop {
+            let request = self.client.request(method.clone(), url);
+            let request = match body {
+                Some(body) => request.json(body),
+                None => request,
+            };
+
+            #[cfg(feature = "tracing")]
+            let started = std::time::Instant::now();
+
+            let result = match request.send().await {
+                Ok(response) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!(
+                        status = response.status().as_u16(),
+                        elapsed_ms = started.elapsed().as_millis() as u64,
+                        attempt,
+                        "kenku remote answered"
+                    );
+
+                    self.set_state(KenkuState::Online);
+
+                    ensure_success(response).await
+                }
+                Err(error) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!(
+                        error = %error,
+                        elapsed_ms = started.elapsed().as_millis() as u64,
+                        attempt,
+                        "kenku request failed"
+                    );
+
+                    if is_connect_error(&error) || error.is_timeout() {
+                        self.set_state(KenkuState::Offline);
+                    }
+
+                    Err(error.into())
+                }
+            };
+
+            match result {
+                Err(error)
+                    if attempt < self.retry.max_retries
+                        && self.retry.should_retry(&method, &error) =>
+                {
+                    attempt += 1;
+
+                    // wasm32 has no tokio timer, so retries are sent there at once.
+                    #[cfg(not(target_arch = "wasm32"))]
+                    {
+                        let delay = self.retry.delay(attempt);
+
+                        #[cfg(feature = "tracing")]
+                        tracing::info!(
+                            error = %error,
+                            attempt,
+                            delay_ms = delay.as_millis() as u64,
+                            "retrying kenku request"
+                        );
+
+                        if !delay.is_zero() {
+                            tokio::time::sleep(delay).await;
+                        }
+                    }
+                }
+                #[cfg(not(target_arch = "wasm32"))]
+                Err(KenkuError::Connection(error)) => match &self.reconnect {
+                    Some(policy) if policy.wait_until_online(&self.address).await => {
+                        attempt = 0;
+                    }
+                    _ => return Err(KenkuError::Connection(error)),
+                },
+                result => return result,
+            }
+        }
     }
 }
 
 #[cfg(test)]
 mod kenku_commands {
-    use super::{process_url, KenkuCommand, KenkuGetCommand, KenkuPostCommand, KenkuPutCommand};
-    use std::{
-        net::{Ipv4Addr, SocketAddrV4},
-        str::FromStr,
+    use super::{
+        process_url, KenkuAddress, KenkuCommand, KenkuGetCommand, KenkuPostCommand, KenkuPutCommand,
     };
 
-    fn get_default_address() -> SocketAddrV4 {
-        SocketAddrV4::new(Ipv4Addr::from_str("127.0.0.1").unwrap(), 3333)
+    fn get_default_address() -> KenkuAddress {
+        KenkuAddress::new("127.0.0.1", 3333)
     }
 
     #[test]
     fn get_soundboard_link_creation() {
         let command = KenkuCommand::KenkuGet(KenkuGetCommand::Soundboard);
         let default_address = get_default_address();
-        let url = process_url(&command, default_address);
+        let url = process_url(&command, &default_address);
         let expected_url = format!(
             "http://{}:{}/v1/soundboard",
-            default_address.ip(),
-            default_address.port()
+            default_address.host, default_address.port
         );
         assert_eq!(url, expected_url);
     }
@@ -319,11 +739,10 @@ mod kenku_commands {
     fn get_playlist_link_creation() {
         let command = KenkuCommand::KenkuGet(KenkuGetCommand::Playlist);
         let default_address = get_default_address();
-        let url = process_url(&command, default_address);
+        let url = process_url(&command, &default_address);
         let expected_url = format!(
             "http://{}:{}/v1/playlist",
-            default_address.ip(),
-            default_address.port()
+            default_address.host, default_address.port
         );
         assert_eq!(url, expected_url);
     }
@@ -332,11 +751,10 @@ mod kenku_commands {
     fn get_soundboard_playback_link_creation() {
         let command = KenkuCommand::KenkuGet(KenkuGetCommand::SoundboardPlayback);
         let default_address = get_default_address();
-        let url = process_url(&command, default_address);
+        let url = process_url(&command, &default_address);
         let expected_url = format!(
             "http://{}:{}/v1/soundboard/playback",
-            default_address.ip(),
-            default_address.port()
+            default_address.host, default_address.port
         );
         assert_eq!(url, expected_url);
     }
@@ -345,11 +763,10 @@ mod kenku_commands {
     fn get_playlist_playback_link_creation() {
         let command = KenkuCommand::KenkuGet(KenkuGetCommand::PlaylistPlayback);
         let default_address = get_default_address();
-        let url = process_url(&command, default_address);
+        let url = process_url(&command, &default_address);
         let expected_url = format!(
             "http://{}:{}/v1/playlist/playback",
-            default_address.ip(),
-            default_address.port()
+            default_address.host, default_address.port
         );
         assert_eq!(url, expected_url);
     }
@@ -358,11 +775,10 @@ mod kenku_commands {
     fn put_playlist_play_link_creation() {
         let command = KenkuCommand::KenkuPut(KenkuPutCommand::PlaylistPlay);
         let default_address = get_default_address();
-        let url = process_url(&command, default_address);
+        let url = process_url(&command, &default_address);
         let expected_url = format!(
             "http://{}:{}/v1/playlist/play",
-            default_address.ip(),
-            default_address.port()
+            default_address.host, default_address.port
         );
         assert_eq!(url, expected_url);
     }
@@ -371,11 +787,10 @@ mod kenku_commands {
     fn put_playlist_playback_mute_link_creation() {
         let command = KenkuCommand::KenkuPut(KenkuPutCommand::PlaylistPlaybackMute);
         let default_address = get_default_address();
-        let url = process_url(&command, default_address);
+        let url = process_url(&command, &default_address);
         let expected_url = format!(
             "http://{}:{}/v1/playlist/playback/mute",
-            default_address.ip(),
-            default_address.port()
+            default_address.host, default_address.port
         );
         assert_eq!(url, expected_url);
     }
@@ -384,11 +799,10 @@ mod kenku_commands {
     fn put_playlist_playback_pause_link_creation() {
         let command = KenkuCommand::KenkuPut(KenkuPutCommand::PlaylistPlaybackPause);
         let default_address = get_default_address();
-        let url = process_url(&command, default_address);
+        let url = process_url(&command, &default_address);
         let expected_url = format!(
             "http://{}:{}/v1/playlist/playback/pause",
-            default_address.ip(),
-            default_address.port()
+            default_address.host, default_address.port
         );
         assert_eq!(url, expected_url);
     }
@@ -397,11 +811,10 @@ mod kenku_commands {
     fn put_playlist_playback_play_link_creation() {
         let command = KenkuCommand::KenkuPut(KenkuPutCommand::PlaylistPlaybackPlay);
         let default_address = get_default_address();
-        let url = process_url(&command, default_address);
+        let url = process_url(&command, &default_address);
         let expected_url = format!(
             "http://{}:{}/v1/playlist/playback/play",
-            default_address.ip(),
-            default_address.port()
+            default_address.host, default_address.port
         );
         assert_eq!(url, expected_url);
     }
@@ -410,11 +823,10 @@ mod kenku_commands {
     fn put_playlist_playback_repeat_link_creation() {
         let command = KenkuCommand::KenkuPut(KenkuPutCommand::PlaylistPlaybackRepeat);
         let default_address = get_default_address();
-        let url = process_url(&command, default_address);
+        let url = process_url(&command, &default_address);
         let expected_url = format!(
             "http://{}:{}/v1/playlist/playback/repeat",
-            default_address.ip(),
-            default_address.port()
+            default_address.host, default_address.port
         );
         assert_eq!(url, expected_url);
     }
@@ -423,11 +835,10 @@ mod kenku_commands {
     fn put_playlist_playback_shuffle_link_creation() {
         let command = KenkuCommand::KenkuPut(KenkuPutCommand::PlaylistPlaybackShuffle);
         let default_address = get_default_address();
-        let url = process_url(&command, default_address);
+        let url = process_url(&command, &default_address);
         let expected_url = format!(
             "http://{}:{}/v1/playlist/playback/shuffle",
-            default_address.ip(),
-            default_address.port()
+            default_address.host, default_address.port
         );
         assert_eq!(url, expected_url);
     }
@@ -436,11 +847,10 @@ mod kenku_commands {
     fn put_playlist_playback_volume_link_creation() {
         let command = KenkuCommand::KenkuPut(KenkuPutCommand::PlaylistPlaybackVolume);
         let default_address = get_default_address();
-        let url = process_url(&command, default_address);
+        let url = process_url(&command, &default_address);
         let expected_url = format!(
             "http://{}:{}/v1/playlist/playback/volume",
-            default_address.ip(),
-            default_address.port()
+            default_address.host, default_address.port
         );
         assert_eq!(url, expected_url);
     }
@@ -449,11 +859,10 @@ mod kenku_commands {
     fn put_soundboard_play_link_creation() {
         let command = KenkuCommand::KenkuPut(KenkuPutCommand::SoundboardPlay);
         let default_address = get_default_address();
-        let url = process_url(&command, default_address);
+        let url = process_url(&command, &default_address);
         let expected_url = format!(
             "http://{}:{}/v1/soundboard/play",
-            default_address.ip(),
-            default_address.port()
+            default_address.host, default_address.port
         );
         assert_eq!(url, expected_url);
     }
@@ -462,11 +871,10 @@ mod kenku_commands {
     fn put_soundboard_stop_link_creation() {
         let command = KenkuCommand::KenkuPut(KenkuPutCommand::SoundboardStop);
         let default_address = get_default_address();
-        let url = process_url(&command, default_address);
+        let url = process_url(&command, &default_address);
         let expected_url = format!(
             "http://{}:{}/v1/soundboard/stop",
-            default_address.ip(),
-            default_address.port()
+            default_address.host, default_address.port
         );
         assert_eq!(url, expected_url);
     }
@@ -475,11 +883,10 @@ mod kenku_commands {
     fn post_playlist_playback_next_link_creation() {
         let command = KenkuCommand::KenkuPost(KenkuPostCommand::PlaylistPlaybackNext);
         let default_address = get_default_address();
-        let url = process_url(&command, default_address);
+        let url = process_url(&command, &default_address);
         let expected_url = format!(
             "http://{}:{}/v1/playlist/playback/next",
-            default_address.ip(),
-            default_address.port()
+            default_address.host, default_address.port
         );
         assert_eq!(url, expected_url);
     }
@@ -488,12 +895,64 @@ mod kenku_commands {
     fn post_playlist_playback_previous_link_creation() {
         let command = KenkuCommand::KenkuPost(KenkuPostCommand::PlaylistPlaybackPrevious);
         let default_address = get_default_address();
-        let url = process_url(&command, default_address);
+        let url = process_url(&command, &default_address);
         let expected_url = format!(
             "http://{}:{}/v1/playlist/playback/previous",
-            default_address.ip(),
-            default_address.port()
+            default_address.host, default_address.port
         );
         assert_eq!(url, expected_url);
     }
+
+    #[test]
+    fn host_name_link_creation() {
+        let command = KenkuCommand::KenkuGet(KenkuGetCommand::Playlist);
+        let url = process_url(&command, &KenkuAddress::new("kenku-host.local", 3333));
+
+        assert_eq!(url, "http://kenku-host.local:3333/v1/playlist");
+    }
+
+    #[test]
+    fn ipv6_link_creation() {
+        let command = KenkuCommand::KenkuGet(KenkuGetCommand::Playlist);
+        let url = process_url(&command, &KenkuAddress::new("::1", 3333));
+
+        assert_eq!(url, "http://[::1]:3333/v1/playlist");
+    }
+}
+
+#[cfg(test)]
+mod sharing {
+    use super::{Controller, KenkuState};
+    use std::sync::Arc;
+
+    fn assert_shareable<T: Clone + Send + Sync + 'static>() {}
+
+    fn assert_send<T: Send>(_: &T) {}
+
+    #[test]
+    fn controllers_are_send_and_sync() {
+        assert_shareable::<Controller>();
+
+        let controller = Controller::new("127.0.0.1", 3333);
+        assert_send(&controller.get_playlist_playback());
+        assert_send(
+            &controller.execute(&super::KenkuCommandWithPayload::PlaySound("s1".to_string())),
+        );
+    }
+
+    #[test]
+    fn clones_share_their_state() {
+        let controller = Controller::new("127.0.0.1", 3333);
+        let clone = controller.clone();
+
+        assert!(Arc::ptr_eq(
+            &controller.kenku_remote_state,
+            &clone.kenku_remote_state
+        ));
+        #[cfg(feature = "cache")]
+        assert!(Arc::ptr_eq(&controller.cache, &clone.cache));
+
+        clone.set_state(KenkuState::Online);
+        assert_eq!(controller.state(), KenkuState::Online);
+    }
 }
@@ -0,0 +1,235 @@
+//! Planning Kenku FM playlists and soundboards from a folder of audio files.
+//!
+//! Kenku Remote cannot create playlists or soundboards, so the import itself still happens in the
+//! Kenku FM app. This module turns an asset folder into a `LibraryPlan` listing what to create and
+//! which file goes where, which a GM can follow by hand or hand to other tools as JSON.
+//!
+//! The folder is expected to look like this; other folders and non-audio files are ignored:
+//!
+//! ```text
+//! ambience-pack/
+//! ├── playlists/
+//! │   └── Tavern/
+//! │       ├── 01_busy_tavern.mp3
+//! │       └── 02_quiet_night.ogg
+//! └── soundboards/
+//!     └── Weather/
+//!         ├── rain.wav
+//!         └── thunder.flac
+//! ```
+use crate::KenkuError;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// The file extensions, compared case-insensitively, that are treated as audio.
+pub const AUDIO_EXTENSIONS: &[&str] = &["mp3", "ogg", "wav", "flac", "m4a", "webm"];
+
+/// Represents the playlists and soundboards to create in Kenku FM.
+///
+/// # Fields
+///
+/// * `playlists` - The playlists to create, ordered by title.
+/// * `soundboards` - The soundboards to create, ordered by title.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+pub struct LibraryPlan {
+    pub playlists: Vec<PlannedCollection>,
+    pub soundboards: Vec<PlannedCollection>,
+}
+
+/// Represents one playlist or soundboard of a `LibraryPlan`.
+///
+/// # Fields
+///
+/// * `title` - The title, taken from the folder name.
+/// * `items` - The tracks or sounds, ordered by file name.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct PlannedCollection {
+    pub title: String,
+    pub items: Vec<PlannedItem>,
+}
+
+/// Represents one track or sound of a `PlannedCollection`.
+///
+/// # Fields
+///
+/// * `title` - The title, taken from the file name without its extension and leading numbering.
+/// * `url` - The `file://` URL to add in Kenku FM.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct PlannedItem {
+    pub title: String,
+    pub url: String,
+}
+
+impl LibraryPlan {
+    /// Scans `root` for `playlists/<title>/` and `soundboards/<title>/` folders of audio files.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the `LibraryPlan`, or a `KenkuError::Io` if a folder cannot be read.
+    pub fn scan_folder(root: impl AsRef<Path>) -> Result<LibraryPlan, KenkuError> {
+        let root = fs::canonicalize(root)?;
+
+        Ok(LibraryPlan {
+            playlists: scan_collections(&root.join("playlists"))?,
+            soundboards: scan_collections(&root.join("soundboards"))?,
+        })
+    }
+
+    /// Reads a `LibraryPlan` from a JSON manifest, as written by `LibraryPlan::to_json`.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the `LibraryPlan`, or a `KenkuError` if the file cannot be read or is not a valid manifest.
+    pub fn from_manifest(path: impl AsRef<Path>) -> Result<LibraryPlan, KenkuError> {
+        let contents = fs::read_to_string(path)?;
+
+        serde_json::from_str(&contents)
+            .map_err(|error| KenkuError::Serialization(error.to_string()))
+    }
+
+    /// Writes the plan as pretty-printed JSON.
+    pub fn to_json(&self) -> Result<String, KenkuError> {
+        serde_json::to_string_pretty(self)
+            .map_err(|error| KenkuError::Serialization(error.to_string()))
+    }
+}
+
+/// Turns every sub-folder of `folder` holding audio files into a `PlannedCollection`.
+fn scan_collections(folder: &Path) -> Result<Vec<PlannedCollection>, KenkuError> {
+    if !folder.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut collections = Vec::new();
+
+    for directory in sorted_entries(folder)? {
+        if !directory.is_dir() {
+            continue;
+        }
+
+        let items: Vec<PlannedItem> = sorted_entries(&directory)?
+            .into_iter()
+            .filter(|file| file.is_file() && is_audio(file))
+            .map(|file| PlannedItem {
+                title: title_from_file(&file),
+                url: file_url(&file),
+            })
+            .collect();
+
+        if !items.is_empty() {
+            collections.push(PlannedCollection {
+                title: file_name(&directory),
+                items,
+            });
+        }
+    }
+
+    Ok(collections)
+}
+
+fn sorted_entries(folder: &Path) -> Result<Vec<PathBuf>, KenkuError> {
+    let mut entries = fs::read_dir(folder)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    entries.sort();
+
+    Ok(entries)
+}
+
+fn is_audio(file: &Path) -> bool {
+    file.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| {
+            AUDIO_EXTENSIONS
+                .iter()
+                .any(|audio| audio.eq_ignore_ascii_case(extension))
+        })
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Turns `01_busy-tavern.mp3` into `busy tavern`.
+fn title_from_file(file: &Path) -> String {
+    let stem = file
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let without_numbering = stem.trim_start_matches(|c: char| c.is_ascii_digit() || c == ' ');
+    let without_numbering = without_numbering.trim_start_matches(['_', '-', '.', ' ']);
+    let title = if without_numbering.is_empty() {
+        stem.as_str()
+    } else {
+        without_numbering
+    };
+
+    title.replace(['_', '-'], " ").trim().to_string()
+}
+
+fn file_url(file: &Path) -> String {
+    let path = file.to_string_lossy().replace('\\', "/");
+    let encoded = path
+        .replace('%', "%25")
+        .replace(' ', "%20")
+        .replace('#', "%23");
+
+    if encoded.starts_with('/') {
+        format!("file://{}", encoded)
+    } else {
+        format!("file:///{}", encoded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{title_from_file, LibraryPlan};
+    use std::{fs, path::Path};
+
+    #[test]
+    fn titles_drop_numbering_and_separators() {
+        assert_eq!(
+            title_from_file(Path::new("01_busy-tavern.mp3")),
+            "busy tavern"
+        );
+        assert_eq!(title_from_file(Path::new("rain.ogg")), "rain");
+        assert_eq!(title_from_file(Path::new("1984.mp3")), "1984");
+    }
+
+    #[test]
+    fn folder_becomes_plan() {
+        let root =
+            std::env::temp_dir().join(format!("kenku_control_library_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("playlists/Tavern")).unwrap();
+        fs::create_dir_all(root.join("soundboards/Weather")).unwrap();
+        fs::write(root.join("playlists/Tavern/02_quiet night.ogg"), b"").unwrap();
+        fs::write(root.join("playlists/Tavern/01_busy_tavern.mp3"), b"").unwrap();
+        fs::write(root.join("playlists/Tavern/cover.png"), b"").unwrap();
+        fs::write(root.join("soundboards/Weather/rain.WAV"), b"").unwrap();
+
+        let plan = LibraryPlan::scan_folder(&root).unwrap();
+        let _ = fs::remove_dir_all(&root);
+
+        let titles: Vec<&str> = plan.playlists[0]
+            .items
+            .iter()
+            .map(|item| item.title.as_str())
+            .collect();
+        assert_eq!(plan.playlists[0].title, "Tavern");
+        assert_eq!(titles, ["busy tavern", "quiet night"]);
+        assert!(plan.playlists[0].items[1]
+            .url
+            .ends_with("02_quiet%20night.ogg"));
+        assert_eq!(plan.soundboards[0].items[0].title, "rain");
+
+        let manifest: LibraryPlan = serde_json::from_str(&plan.to_json().unwrap()).unwrap();
+        assert_eq!(manifest, plan);
+    }
+}
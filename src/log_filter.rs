@@ -0,0 +1,189 @@
+//! Tracing filter directives changed while the process runs.
+//!
+//! A gateway or bot runs for a whole session, and restarting it to see debug logs would cut the
+//! audio bridge mid-session. `LogFilter::layer` returns an `EnvFilter` layer to install first on
+//! a `tracing_subscriber::registry()`, and a `LogFilter` that swaps its directives at runtime:
+//! by hand with `LogFilter::set`, from a file on every SIGHUP with `LogFilter::reload_on_hangup`,
+//! or through the `/admin/log-filter` route of `rest::RestApi::log_filter`.
+//!
+//! ```no_run
+//! use kenku_control::log_filter::LogFilter;
+//! use tracing_subscriber::prelude::*;
+//!
+//! let (layer, filter) = LogFilter::layer("kenku_control=info").unwrap();
+//! tracing_subscriber::registry().with(layer).init();
+//!
+//! filter.set("kenku_control=debug").unwrap();
+//! ```
+use crate::KenkuError;
+use tracing_subscriber::{reload, EnvFilter, Registry};
+
+#[cfg(unix)]
+use std::path::PathBuf;
+#[cfg(unix)]
+use tokio::task::JoinHandle;
+
+/// The filter layer returned by `LogFilter::layer`, to be added to a `tracing_subscriber::registry()`.
+pub type FilterLayer = reload::Layer<EnvFilter, Registry>;
+
+/// A handle changing the directives of a `FilterLayer`.
+///
+/// Clones change the same layer.
+#[derive(Debug, Clone)]
+pub struct LogFilter {
+    handle: reload::Handle<EnvFilter, Registry>,
+}
+
+/// A handle to the task reloading a `LogFilter` on SIGHUP.
+///
+/// The task stops when `FilterReloader::stop` is called or when the handle is dropped.
+#[cfg(unix)]
+#[derive(Debug)]
+pub struct FilterReloader {
+    handle: JoinHandle<()>,
+}
+
+impl LogFilter {
+    /// Creates a filter layer starting with `directives`, such as `"kenku_control=debug,warn"`.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the layer and the `LogFilter` changing it, or a
+    /// `KenkuError::InvalidInput` if `directives` cannot be parsed.
+    pub fn layer(directives: &str) -> Result<(FilterLayer, LogFilter), KenkuError> {
+        let (layer, handle) = reload::Layer::new(parse(directives)?);
+
+        Ok((layer, LogFilter { handle }))
+    }
+
+    /// Returns the directives in effect, or `None` if the layer was dropped.
+    pub fn directives(&self) -> Option<String> {
+        self.handle.with_current(EnvFilter::to_string).ok()
+    }
+
+    /// Replaces the directives of the layer with `directives`.
+    ///
+    /// # Returns
+    ///
+    /// This function returns a `KenkuError::InvalidInput` if `directives` cannot be parsed or the
+    /// layer was dropped; the directives in effect are then kept.
+    pub fn set(&self, directives: &str) -> Result<(), KenkuError> {
+        self.handle.reload(parse(directives)?).map_err(|error| {
+            KenkuError::InvalidInput(format!("cannot change the filter: {}", error))
+        })?;
+
+        tracing::info!(directives, "log filter changed");
+
+        Ok(())
+    }
+
+    /// Spawns a task that reads the directives from the file at `path` on every SIGHUP.
+    ///
+    /// The file holds one directive per line or comma-separated directives; blank lines and lines
+    /// starting with `#` are skipped. A file that cannot be read or parsed is reported with a
+    /// warning and leaves the directives in effect.
+    ///
+    /// # Returns
+    ///
+    /// This function returns a `FilterReloader`, or a `KenkuError::Io` if SIGHUP cannot be
+    /// listened for.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if it is called outside of a tokio runtime.
+    #[cfg(unix)]
+    pub fn reload_on_hangup(&self, path: impl Into<PathBuf>) -> Result<FilterReloader, KenkuError> {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut hangups = signal(SignalKind::hangup())?;
+        let filter = self.clone();
+        let path = path.into();
+
+        let handle = tokio::spawn(async move {
+            while hangups.recv().await.is_some() {
+                let reloaded = std::fs::read_to_string(&path)
+                    .map_err(KenkuError::from)
+                    .and_then(|text| filter.set(&file_directives(&text)));
+
+                if let Err(error) = reloaded {
+                    tracing::warn!(
+                        path = %path.display(),
+                        error = %error,
+                        "log filter not reloaded"
+                    );
+                }
+            }
+        });
+
+        Ok(FilterReloader { handle })
+    }
+}
+
+#[cfg(unix)]
+impl FilterReloader {
+    /// Stops reloading the filter. The directives in effect are kept.
+    pub fn stop(self) {}
+}
+
+#[cfg(unix)]
+impl Drop for FilterReloader {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+/// Parses `directives` into a filter.
+fn parse(directives: &str) -> Result<EnvFilter, KenkuError> {
+    EnvFilter::builder().parse(directives).map_err(|error| {
+        KenkuError::InvalidInput(format!(
+            "{:?} is not a tracing filter: {}",
+            directives, error
+        ))
+    })
+}
+
+/// Joins the directives of a filter file, skipping blank lines and comments.
+#[cfg(unix)]
+fn file_directives(text: &str) -> String {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LogFilter;
+    use crate::KenkuError;
+    use tracing_subscriber::prelude::*;
+
+    #[test]
+    fn directives_are_swapped_while_the_subscriber_runs() {
+        let (layer, filter) = LogFilter::layer("warn").unwrap();
+        let subscriber = tracing_subscriber::registry().with(layer);
+
+        tracing::subscriber::with_default(subscriber, || {
+            assert!(!tracing::enabled!(target: "kenku_control", tracing::Level::DEBUG));
+
+            filter.set("kenku_control=debug").unwrap();
+            assert!(tracing::enabled!(target: "kenku_control", tracing::Level::DEBUG));
+            assert!(!tracing::enabled!(target: "other", tracing::Level::DEBUG));
+
+            assert!(matches!(
+                filter.set("kenku_control=loud"),
+                Err(KenkuError::InvalidInput(_))
+            ));
+            assert_eq!(filter.directives().unwrap(), "kenku_control=debug");
+        });
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn filter_files_skip_comments_and_blank_lines() {
+        assert_eq!(
+            super::file_directives("# debugging the bridge\nkenku_control=debug\n\nwarn\n"),
+            "kenku_control=debug,warn"
+        );
+    }
+}
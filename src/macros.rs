@@ -0,0 +1,254 @@
+//! Recording commands as macros and replaying them, for intros and other set pieces.
+//!
+//! A `Recorder` sends commands through a controller like `Controller::execute` does and notes
+//! when each one was issued. `Recorder::finish` turns what was sent into a `Macro`, which is plain
+//! data and can be kept with `format::save`. `Controller::replay` sends the commands of a macro
+//! again with the same spacing, or faster or slower with a `speed` other than 1.
+//!
+//! ```no_run
+//! # async fn example() -> Result<(), kenku_control::KenkuError> {
+//! use kenku_control::{macros::Recorder, Controller, KenkuCommandWithPayload};
+//! use std::time::Duration;
+//!
+//! let controller = Controller::new("127.0.0.1", 3333);
+//!
+//! // Rehearse the intro once...
+//! let recorder = Recorder::new(&controller);
+//! recorder.execute(&KenkuCommandWithPayload::PlayPlaylist("intro".to_string())).await?;
+//! tokio::time::sleep(Duration::from_secs(4)).await;
+//! recorder.execute(&KenkuCommandWithPayload::PlaySound("thunder".to_string())).await?;
+//! tokio::time::sleep(Duration::from_secs(2)).await;
+//! recorder.execute(&KenkuCommandWithPayload::PlaySound("door-slam".to_string())).await?;
+//! let intro = recorder.finish();
+//!
+//! // ...then play it live.
+//! controller.replay(&intro, 1.0).await?;
+//! # Ok(())
+//! # }
+//! ```
+use crate::{
+    batch::BatchReport,
+    playlist::{PlaylistGetResponse, PlaylistPlaybackResponse},
+    soundboard::{SoundboardGetResponse, SoundboardPlaybackResponse},
+    Controller, KenkuApi, KenkuCommandWithPayload, KenkuError,
+};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::{
+    future::Future,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Represents one command of a macro.
+///
+/// # Fields
+///
+/// * `offset_ms` - When the command is sent, in milliseconds after the start of the macro.
+/// * `command` - The command sent.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct MacroStep {
+    pub offset_ms: u64,
+    pub command: KenkuCommandWithPayload,
+}
+
+/// Represents a recorded sequence of commands.
+///
+/// # Fields
+///
+/// * `steps` - The commands of the macro, ordered by `offset_ms`.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct Macro {
+    pub steps: Vec<MacroStep>,
+}
+
+/// Sends commands through a controller and records them into a `Macro`.
+///
+/// The offsets of the macro are counted from the creation of the recorder. Commands are recorded
+/// whether or not the Kenku Remote accepted them, so a rehearsal against a remote that is not
+/// running still yields the whole sequence.
+#[derive(Debug)]
+pub struct Recorder {
+    controller: Controller,
+    started: Instant,
+    steps: Mutex<Vec<MacroStep>>,
+}
+
+impl Macro {
+    /// Returns the offset of the last command, or zero for an empty macro.
+    pub fn duration(&self) -> Duration {
+        Duration::from_millis(self.steps.last().map_or(0, |step| step.offset_ms))
+    }
+}
+
+impl Recorder {
+    /// Creates a recorder sending through `controller`, starting the clock of the macro now.
+    pub fn new(controller: &Controller) -> Recorder {
+        Recorder {
+            controller: controller.clone(),
+            started: Instant::now(),
+            steps: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Records `command` as issued now and sends it.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is either the `StatusCode` of the response or a `KenkuError`.
+    pub async fn execute(
+        &self,
+        command: &KenkuCommandWithPayload,
+    ) -> Result<StatusCode, KenkuError> {
+        self.steps.lock().unwrap().push(MacroStep {
+            offset_ms: self.started.elapsed().as_millis() as u64,
+            command: command.clone(),
+        });
+
+        self.controller.execute(command).await
+    }
+
+    /// Returns the commands recorded so far, leaving the recorder running.
+    pub fn recorded(&self) -> Macro {
+        Macro {
+            steps: self.steps.lock().unwrap().clone(),
+        }
+    }
+
+    /// Stops recording.
+    ///
+    /// # Returns
+    ///
+    /// The `Macro` holding every command recorded.
+    pub fn finish(self) -> Macro {
+        Macro {
+            steps: self.steps.into_inner().unwrap(),
+        }
+    }
+}
+
+/// Records the commands sent by code written against `KenkuApi`. Reads are not recorded.
+impl KenkuApi for Recorder {
+    fn get_playlist(&self) -> impl Future<Output = Result<PlaylistGetResponse, KenkuError>> + Send {
+        self.controller.get_playlist()
+    }
+
+    fn get_playlist_playback(
+        &self,
+    ) -> impl Future<Output = Result<PlaylistPlaybackResponse, KenkuError>> + Send {
+        self.controller.get_playlist_playback()
+    }
+
+    fn get_soundboard(
+        &self,
+    ) -> impl Future<Output = Result<SoundboardGetResponse, KenkuError>> + Send {
+        self.controller.get_soundboard()
+    }
+
+    fn get_soundboard_playback(
+        &self,
+    ) -> impl Future<Output = Result<SoundboardPlaybackResponse, KenkuError>> + Send {
+        self.controller.get_soundboard_playback()
+    }
+
+    fn execute(
+        &self,
+        command: &KenkuCommandWithPayload,
+    ) -> impl Future<Output = Result<StatusCode, KenkuError>> + Send {
+        Recorder::execute(self, command)
+    }
+}
+
+impl Controller {
+    /// Sends the commands of `recording` again, keeping their spacing divided by `speed`.
+    ///
+    /// A `speed` of 2 plays the macro twice as fast, and 0.5 half as fast. The steps are timed
+    /// from the start of the replay, so a slow answer does not delay the steps after it. A failed
+    /// command does not stop the replay: every result ends up in the report.
+    ///
+    /// # Arguments
+    ///
+    /// * `recording` - The macro to replay.
+    /// * `speed` - How much faster than recorded to replay, greater than zero.
+    ///
+    /// # Returns
+    ///
+    /// A `BatchReport` holding the result of each command, or a `KenkuError::InvalidInput`, before
+    /// any command is sent, if `speed` is not a positive, finite number or is so slow that a step
+    /// would fall beyond what a timer can wait for.
+    pub async fn replay(&self, recording: &Macro, speed: f64) -> Result<BatchReport, KenkuError> {
+        if !(speed.is_finite() && speed > 0.0) {
+            return Err(KenkuError::InvalidInput(format!(
+                "{} is not a replay speed",
+                speed
+            )));
+        }
+
+        let started = tokio::time::Instant::now();
+        let deadlines = recording
+            .steps
+            .iter()
+            .map(|step| {
+                Duration::try_from_secs_f64(step.offset_ms as f64 / 1000.0 / speed)
+                    .ok()
+                    .and_then(|offset| started.checked_add(offset))
+                    .ok_or_else(|| {
+                        KenkuError::InvalidInput(format!("{} is too slow a replay speed", speed))
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut results = Vec::with_capacity(recording.steps.len());
+
+        for (step, deadline) in recording.steps.iter().zip(deadlines) {
+            tokio::time::sleep_until(deadline).await;
+
+            let result = self.execute(&step.command).await;
+            results.push((step.command.clone(), result));
+        }
+
+        Ok(BatchReport { results })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Macro, MacroStep};
+    use crate::{Controller, KenkuCommandWithPayload, KenkuError, KenkuPlaybackCommand};
+    use std::time::Duration;
+
+    fn intro() -> Macro {
+        Macro {
+            steps: vec![
+                MacroStep {
+                    offset_ms: 0,
+                    command: KenkuCommandWithPayload::PlayPlaylist("p1".to_string()),
+                },
+                MacroStep {
+                    offset_ms: 1500,
+                    command: KenkuPlaybackCommand::PlaylistPlaybackPause.into(),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn macros_round_trip_through_json() {
+        let saved = serde_json::to_string(&intro()).unwrap();
+
+        assert_eq!(serde_json::from_str::<Macro>(&saved).unwrap(), intro());
+        assert_eq!(intro().duration(), Duration::from_millis(1500));
+        assert_eq!(Macro::default().duration(), Duration::ZERO);
+    }
+
+    #[tokio::test]
+    async fn replay_speed_must_be_positive() {
+        let controller = Controller::new("127.0.0.1", 1);
+
+        for speed in [0.0, -1.0, f64::NAN, f64::INFINITY, 1e-300] {
+            assert!(matches!(
+                controller.replay(&intro(), speed).await,
+                Err(KenkuError::InvalidInput(_))
+            ));
+        }
+    }
+}
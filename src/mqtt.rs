@@ -0,0 +1,695 @@
+//! Controlling Kenku FM over MQTT, for home-automation setups.
+//!
+//! `MqttBridge::run` connects to a broker, turns messages on command topics into commands for the
+//! Kenku Remote, and polls the playback to publish state topics whenever a value changes. With the
+//! default `kenku` prefix, the topics are:
+//!
+//! | Command topic              | Payload                                   |
+//! | -------------------------- | ----------------------------------------- |
+//! | `kenku/playlist/play`      | Empty to resume, or a playlist id to play |
+//! | `kenku/playlist/pause`     | Ignored                                   |
+//! | `kenku/playlist/next`      | Ignored                                   |
+//! | `kenku/playlist/previous`  | Ignored                                   |
+//! | `kenku/playlist/volume`    | A volume from `0` to `100`                |
+//! | `kenku/playlist/mute`      | `true`/`false`, `on`/`off` or `1`/`0`     |
+//! | `kenku/playlist/shuffle`   | `true`/`false`, `on`/`off` or `1`/`0`     |
+//! | `kenku/playlist/repeat`    | `off`, `track` or `playlist`              |
+//! | `kenku/track/play`         | A track id                                |
+//! | `kenku/sound/play`         | A sound id                                |
+//! | `kenku/sound/stop`         | A sound id                                |
+//! | `kenku/sound/<id>/set`     | `true`/`false` to play or stop the sound  |
+//! | `kenku/command`            | A `KenkuCommandWithPayload` as JSON       |
+//!
+//! State is published, retained, on `kenku/state/online`, `playing`, `volume`, `muted`,
+//! `shuffle`, `repeat`, `track`, `track_id`, `playlist`, `sounds` (a JSON array of the titles
+//! playing) and `sound_ids` (the same for their ids). `kenku/bridge` is `online` while the bridge
+//! is connected and `offline` once it is gone. Commands that fail are reported on `kenku/error`.
+//!
+//! # Home Assistant
+//!
+//! With `MqttBridge::home_assistant`, the bridge also publishes MQTT discovery payloads when it
+//! connects, so Kenku FM shows up in Home Assistant as a device without any YAML. Home
+//! Assistant's MQTT integration has no `media_player` platform, so the playback is made of the
+//! entities it does support: buttons for play, pause, next and previous, a number for the volume,
+//! switches for mute and shuffle, a select for the repeat mode, a binary sensor for whether music
+//! is playing, a sensor for the track, and one switch per soundboard sound. Their state comes
+//! from the state topics above, kept in sync by the poller. Sounds added to Kenku FM later show
+//! up once the bridge reconnects.
+use crate::{
+    playlist::Repeat, snapshot::PlaybackSnapshot, soundboard::SoundboardGetResponse, Controller,
+    KenkuCommandWithPayload, KenkuError, KenkuPlaybackCommand, Volume,
+};
+use rumqttc::{AsyncClient, Event, LastWill, MqttOptions, Packet, QoS};
+use serde_json::{json, Value};
+use std::{collections::BTreeMap, time::Duration};
+use tokio::time::{interval, MissedTickBehavior};
+
+/// Connects a Kenku Remote to an MQTT broker.
+///
+/// # Fields
+///
+/// * `host` - The host name or IP address of the broker.
+/// * `port` - The port of the broker, usually 1883.
+/// * `client_id` - The MQTT client id of the bridge.
+/// * `prefix` - The first level of every topic.
+/// * `credentials` - The user name and password, if the broker needs them.
+/// * `poll_interval` - How often the playback is polled for state changes.
+/// * `discovery_prefix` - The Home Assistant discovery prefix, usually `homeassistant`, or `None` to publish no discovery payloads.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MqttBridge {
+    pub host: String,
+    pub port: u16,
+    pub client_id: String,
+    pub prefix: String,
+    pub credentials: Option<(String, String)>,
+    pub poll_interval: Duration,
+    pub discovery_prefix: Option<String>,
+}
+
+impl MqttBridge {
+    /// Creates a bridge to the broker at `host:port`, with the `kenku` prefix and a one second poll interval.
+    pub fn new(host: impl Into<String>, port: u16) -> MqttBridge {
+        MqttBridge {
+            host: host.into(),
+            port,
+            client_id: "kenku_control".to_string(),
+            prefix: "kenku".to_string(),
+            credentials: None,
+            poll_interval: Duration::from_secs(1),
+            discovery_prefix: None,
+        }
+    }
+
+    /// Sets the MQTT client id, which must be unique on the broker.
+    pub fn client_id(mut self, client_id: impl Into<String>) -> MqttBridge {
+        self.client_id = client_id.into();
+        self
+    }
+
+    /// Sets the first level of every topic, such as `table-2` for `table-2/playlist/play`.
+    pub fn prefix(mut self, prefix: impl Into<String>) -> MqttBridge {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Logs into the broker as `user`.
+    pub fn credentials(
+        mut self,
+        user: impl Into<String>,
+        password: impl Into<String>,
+    ) -> MqttBridge {
+        self.credentials = Some((user.into(), password.into()));
+        self
+    }
+
+    /// Sets how often the playback is polled for state changes. Intervals under a millisecond are
+    /// taken as a millisecond.
+    pub fn poll_interval(mut self, poll_interval: Duration) -> MqttBridge {
+        self.poll_interval = poll_interval.max(Duration::from_millis(1));
+        self
+    }
+
+    /// Publishes Home Assistant discovery payloads under `discovery_prefix`, usually
+    /// `homeassistant`, every time the bridge connects.
+    pub fn home_assistant(mut self, discovery_prefix: impl Into<String>) -> MqttBridge {
+        self.discovery_prefix = Some(discovery_prefix.into());
+        self
+    }
+
+    /// Returns the full topic for `suffix`, such as `kenku/state/volume` for `state/volume`.
+    pub fn topic(&self, suffix: &str) -> String {
+        format!("{}/{}", self.prefix, suffix)
+    }
+
+    /// Turns a message received on `topic` into a command.
+    ///
+    /// # Returns
+    ///
+    /// This function returns `None` for topics that are not command topics, such as the state
+    /// topics the bridge publishes itself, the command otherwise, or a `KenkuError::InvalidInput`
+    /// if the payload does not fit the topic.
+    pub fn command_for(
+        &self,
+        topic: &str,
+        payload: &[u8],
+    ) -> Option<Result<KenkuCommandWithPayload, KenkuError>> {
+        let suffix = topic.strip_prefix(&self.prefix)?.strip_prefix('/')?;
+        let payload = String::from_utf8_lossy(payload);
+        let payload = payload.trim();
+
+        let command = match suffix {
+            "playlist/play" if payload.is_empty() => {
+                Ok(KenkuPlaybackCommand::PlaylistPlaybackPlay.into())
+            }
+            "playlist/play" => Ok(KenkuCommandWithPayload::PlayPlaylist(payload.to_string())),
+            "playlist/pause" => Ok(KenkuPlaybackCommand::PlaylistPlaybackPause.into()),
+            "playlist/next" => Ok(KenkuPlaybackCommand::PlaylistPlaybackNext.into()),
+            "playlist/previous" => Ok(KenkuPlaybackCommand::PlaylistPlaybackPrevious.into()),
+            "playlist/volume" => payload
+                .parse::<u8>()
+                .ok()
+                .filter(|percent| *percent <= 100)
+                .map(|percent| {
+                    KenkuPlaybackCommand::PlaylistPlaybackVolume(Volume::from_percent(percent))
+                        .into()
+                })
+                .ok_or_else(|| invalid(topic, payload)),
+            "playlist/mute" => parse_bool(payload)
+                .map(|muted| KenkuPlaybackCommand::PlaylistPlaybackMute(muted).into())
+                .ok_or_else(|| invalid(topic, payload)),
+            "playlist/shuffle" => parse_bool(payload)
+                .map(|shuffle| KenkuPlaybackCommand::PlaylistPlaybackShuffle(shuffle).into())
+                .ok_or_else(|| invalid(topic, payload)),
+            "playlist/repeat" => serde_json::from_value::<Repeat>(payload.to_lowercase().into())
+                .map(|repeat| KenkuPlaybackCommand::PlaylistPlaybackRepeat(repeat).into())
+                .map_err(|_| invalid(topic, payload)),
+            "track/play" | "sound/play" | "sound/stop" if payload.is_empty() => {
+                Err(invalid(topic, payload))
+            }
+            "track/play" => Ok(KenkuCommandWithPayload::PlayTrack(payload.to_string())),
+            "sound/play" => Ok(KenkuCommandWithPayload::PlaySound(payload.to_string())),
+            "sound/stop" => Ok(KenkuCommandWithPayload::StopSound(payload.to_string())),
+            "command" => serde_json::from_str(payload).map_err(|_| invalid(topic, payload)),
+            _ => {
+                let id = suffix.strip_prefix("sound/")?.strip_suffix("/set")?;
+
+                parse_bool(payload)
+                    .map(|play| match play {
+                        true => KenkuCommandWithPayload::PlaySound(id.to_string()),
+                        false => KenkuCommandWithPayload::StopSound(id.to_string()),
+                    })
+                    .ok_or_else(|| invalid(topic, payload))
+            }
+        };
+
+        Some(command)
+    }
+
+    /// Lists the state topics and their payloads for `playback`, or for an unreachable remote when it is `None`.
+    pub fn state_messages(&self, playback: Option<&PlaybackSnapshot>) -> Vec<(String, String)> {
+        let Some(playback) = playback else {
+            return vec![(self.topic("state/online"), "false".to_string())];
+        };
+        let playlist = &playback.playlist;
+        let repeat = match playlist.repeat {
+            Repeat::Off => "off",
+            Repeat::Track => "track",
+            Repeat::Playlist => "playlist",
+        };
+        let sounds: Vec<&str> = playback
+            .soundboard
+            .sounds
+            .iter()
+            .map(|sound| sound.title.as_str())
+            .collect();
+        let sound_ids: Vec<&str> = playback
+            .soundboard
+            .sounds
+            .iter()
+            .map(|sound| sound.id.as_str())
+            .collect();
+
+        [
+            ("online", "true".to_string()),
+            ("playing", playlist.playing.to_string()),
+            ("volume", playlist.volume_level().percent().to_string()),
+            ("muted", playlist.muted.to_string()),
+            ("shuffle", playlist.shuffle.to_string()),
+            ("repeat", repeat.to_string()),
+            (
+                "track",
+                playlist
+                    .track
+                    .as_ref()
+                    .map_or(String::new(), |track| track.title.clone()),
+            ),
+            (
+                "track_id",
+                playlist
+                    .track
+                    .as_ref()
+                    .map_or(String::new(), |track| track.id.clone()),
+            ),
+            (
+                "playlist",
+                playlist
+                    .playlist
+                    .as_ref()
+                    .map_or(String::new(), |playlist| playlist.title.clone()),
+            ),
+            ("sounds", json!(sounds).to_string()),
+            ("sound_ids", json!(sound_ids).to_string()),
+        ]
+        .into_iter()
+        .map(|(name, payload)| (self.topic(&format!("state/{}", name)), payload))
+        .collect()
+    }
+
+    /// Lists the Home Assistant discovery topics and their payloads, with a switch for every
+    /// sound of `soundboard`.
+    ///
+    /// # Returns
+    ///
+    /// This function returns an empty list when `discovery_prefix` is `None`.
+    pub fn discovery_messages(&self, soundboard: &SoundboardGetResponse) -> Vec<(String, String)> {
+        let Some(discovery_prefix) = &self.discovery_prefix else {
+            return Vec::new();
+        };
+        let node = object_id(&self.client_id);
+        let device = json!({
+            "identifiers": [self.client_id],
+            "name": "Kenku FM",
+            "manufacturer": "Kenku FM",
+            "model": "Kenku Remote",
+        });
+        let availability = json!([
+            {"topic": self.topic("bridge")},
+            {
+                "topic": self.topic("state/online"),
+                "payload_available": "true",
+                "payload_not_available": "false",
+            },
+        ]);
+        let switch = |state: &str, command: &str| {
+            json!({
+                "state_topic": self.topic(state),
+                "command_topic": self.topic(command),
+                "state_on": "true",
+                "state_off": "false",
+                "payload_on": "true",
+                "payload_off": "false",
+            })
+        };
+
+        let mut entities = vec![
+            (
+                "button",
+                "play".to_string(),
+                "Play",
+                json!({"command_topic": self.topic("playlist/play"), "payload_press": ""}),
+            ),
+            (
+                "button",
+                "pause".to_string(),
+                "Pause",
+                json!({"command_topic": self.topic("playlist/pause")}),
+            ),
+            (
+                "button",
+                "next".to_string(),
+                "Next",
+                json!({"command_topic": self.topic("playlist/next")}),
+            ),
+            (
+                "button",
+                "previous".to_string(),
+                "Previous",
+                json!({"command_topic": self.topic("playlist/previous")}),
+            ),
+            (
+                "number",
+                "volume".to_string(),
+                "Volume",
+                json!({
+                    "state_topic": self.topic("state/volume"),
+                    "command_topic": self.topic("playlist/volume"),
+                    "min": 0,
+                    "max": 100,
+                    "unit_of_measurement": "%",
+                }),
+            ),
+            (
+                "switch",
+                "muted".to_string(),
+                "Muted",
+                switch("state/muted", "playlist/mute"),
+            ),
+            (
+                "switch",
+                "shuffle".to_string(),
+                "Shuffle",
+                switch("state/shuffle", "playlist/shuffle"),
+            ),
+            (
+                "select",
+                "repeat".to_string(),
+                "Repeat",
+                json!({
+                    "state_topic": self.topic("state/repeat"),
+                    "command_topic": self.topic("playlist/repeat"),
+                    "options": ["off", "track", "playlist"],
+                }),
+            ),
+            (
+                "binary_sensor",
+                "playing".to_string(),
+                "Playing",
+                json!({
+                    "state_topic": self.topic("state/playing"),
+                    "payload_on": "true",
+                    "payload_off": "false",
+                }),
+            ),
+            (
+                "sensor",
+                "track".to_string(),
+                "Track",
+                json!({"state_topic": self.topic("state/track")}),
+            ),
+        ];
+
+        for sound in &soundboard.sounds {
+            let mut config = switch("state/sound_ids", &format!("sound/{}/set", sound.id));
+            config["value_template"] = json!(format!(
+                "{{{{ 'true' if {} in value_json else 'false' }}}}",
+                json!(sound.id)
+            ));
+            entities.push((
+                "switch",
+                format!("sound_{}", object_id(&sound.id)),
+                sound.title.as_str(),
+                config,
+            ));
+        }
+
+        entities
+            .into_iter()
+            .map(|(component, object, name, mut config)| {
+                let unique_id = format!("{}_{}", node, object);
+                let topic = format!(
+                    "{}/{}/{}/{}/config",
+                    discovery_prefix, component, node, object
+                );
+
+                if let Value::Object(fields) = &mut config {
+                    fields.insert("name".to_string(), json!(name));
+                    fields.insert("unique_id".to_string(), json!(unique_id));
+                    fields.insert("availability".to_string(), availability.clone());
+                    fields.insert("availability_mode".to_string(), json!("all"));
+                    fields.insert("device".to_string(), device.clone());
+                }
+
+                (topic, config.to_string())
+            })
+            .collect()
+    }
+
+    /// Fetches the soundboard and publishes the Home Assistant discovery payloads in the background.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the `KenkuError` of fetching the soundboard, in which case nothing is published.
+    async fn publish_discovery(
+        &self,
+        client: &AsyncClient,
+        controller: &Controller,
+    ) -> Result<(), KenkuError> {
+        let messages = self.discovery_messages(&controller.get_soundboard().await?);
+        let client = client.clone();
+
+        // A large soundboard would fill the request channel, so the payloads are sent while the
+        // bridge keeps polling the connection.
+        tokio::spawn(async move {
+            for (topic, payload) in messages {
+                let _ = client.publish(topic, QoS::AtLeastOnce, true, payload).await;
+            }
+        });
+
+        Ok(())
+    }
+
+    /// Runs the bridge until the broker connection fails.
+    ///
+    /// Every command topic is subscribed to once connected. The playback is polled every
+    /// `poll_interval`, and state topics are published, retained, when their value changed since
+    /// the last poll; state and errors that do not fit in the request channel are dropped, and the
+    /// state is published again on a later poll. To keep a bridge up across broker restarts, run
+    /// it under a `supervisor::Supervisor`. When the Kenku Remote cannot be reached on connection,
+    /// the Home Assistant discovery payloads are published once a later poll reaches it, and the
+    /// error is reported on `kenku/error`.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the `KenkuError::Io` describing why the broker connection failed.
+    pub async fn run(&self, controller: &Controller) -> Result<(), KenkuError> {
+        let bridge = self.topic("bridge");
+        let mut options = MqttOptions::new(&self.client_id, &self.host, self.port);
+        options.set_keep_alive(Duration::from_secs(30));
+        options.set_last_will(LastWill::new(&bridge, "offline", QoS::AtLeastOnce, true));
+
+        if let Some((user, password)) = &self.credentials {
+            options.set_credentials(user, password);
+        }
+
+        let (client, mut connection) = AsyncClient::new(options, 64);
+        let mut published: BTreeMap<String, String> = BTreeMap::new();
+        let mut ticks = interval(self.poll_interval);
+        ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut discovery_pending = false;
+
+        loop {
+            tokio::select! {
+                notification = connection.poll() => match notification.map_err(mqtt_error)? {
+                    Event::Incoming(Packet::ConnAck(_)) => {
+                        client
+                            .try_subscribe(self.topic("#"), QoS::AtLeastOnce)
+                            .map_err(mqtt_error)?;
+                        client
+                            .try_publish(&bridge, QoS::AtLeastOnce, true, "online")
+                            .map_err(mqtt_error)?;
+                        published.clear();
+                        discovery_pending = self.discovery_prefix.is_some();
+
+                        if discovery_pending {
+                            match self.publish_discovery(&client, controller).await {
+                                Ok(()) => discovery_pending = false,
+                                Err(error) => {
+                                    let topic = self.topic("error");
+                                    let error = error.to_string();
+                                    let _ = client.try_publish(topic, QoS::AtLeastOnce, false, error);
+                                }
+                            }
+                        }
+                    }
+                    Event::Incoming(Packet::Publish(message)) => {
+                        let Some(command) = self.command_for(&message.topic, &message.payload)
+                        else {
+                            continue;
+                        };
+                        let result = match command {
+                            Ok(command) => controller.execute(&command).await.map(|_| ()),
+                            Err(error) => Err(error),
+                        };
+
+                        if let Err(error) = result {
+                            let topic = self.topic("error");
+                            let error = error.to_string();
+                            let _ = client.try_publish(topic, QoS::AtLeastOnce, false, error);
+                        }
+                    }
+                    _ => {}
+                },
+                _ = ticks.tick() => {
+                    let playback = controller.snapshot().await.ok();
+
+                    if discovery_pending
+                        && playback.is_some()
+                        && self.publish_discovery(&client, controller).await.is_ok()
+                    {
+                        discovery_pending = false;
+                    }
+
+                    for (topic, payload) in self.state_messages(playback.as_ref()) {
+                        if published.get(&topic) == Some(&payload) {
+                            continue;
+                        }
+
+                        // While the discovery payloads fill the request channel, the rest of
+                        // the state is left for a later poll.
+                        if client
+                            .try_publish(&topic, QoS::AtLeastOnce, true, payload.clone())
+                            .is_err()
+                        {
+                            break;
+                        }
+                        published.insert(topic, payload);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Parses the boolean payloads home-automation tools send.
+fn parse_bool(payload: &str) -> Option<bool> {
+    match payload.to_ascii_lowercase().as_str() {
+        "true" | "on" | "1" => Some(true),
+        "false" | "off" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+/// Keeps the characters Home Assistant accepts in object ids.
+fn object_id(id: &str) -> String {
+    id.chars()
+        .map(|c| match c.is_ascii_alphanumeric() || c == '-' {
+            true => c,
+            false => '_',
+        })
+        .collect()
+}
+
+fn invalid(topic: &str, payload: &str) -> KenkuError {
+    KenkuError::InvalidInput(format!(
+        "{:?} is not a valid payload for {}",
+        payload, topic
+    ))
+}
+
+fn mqtt_error(error: impl std::fmt::Display) -> KenkuError {
+    KenkuError::Io(std::io::Error::other(error.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MqttBridge;
+    use crate::{
+        playlist::{PlaylistPlaybackResponse, Repeat, Track},
+        soundboard::{SoundboardGetResponse, Sounds},
+        testing, KenkuCommandWithPayload, KenkuError, KenkuPlaybackCommand, Volume,
+    };
+    use serde_json::json;
+    use std::time::Duration;
+
+    #[test]
+    fn zero_poll_intervals_are_taken_as_a_millisecond() {
+        let bridge = MqttBridge::new("localhost", 1883).poll_interval(Duration::ZERO);
+
+        assert_eq!(bridge.poll_interval, Duration::from_millis(1));
+    }
+
+    #[test]
+    fn command_topics_become_commands() {
+        let bridge = MqttBridge::new("localhost", 1883).prefix("table");
+        let command = |topic: &str, payload: &str| {
+            bridge
+                .command_for(topic, payload.as_bytes())
+                .map(|command| command.unwrap())
+        };
+
+        assert_eq!(
+            command("table/playlist/volume", "40"),
+            Some(KenkuPlaybackCommand::PlaylistPlaybackVolume(Volume::from_percent(40)).into())
+        );
+        assert_eq!(
+            command("table/playlist/mute", "ON"),
+            Some(KenkuPlaybackCommand::PlaylistPlaybackMute(true).into())
+        );
+        assert_eq!(
+            command("table/playlist/repeat", "Track"),
+            Some(KenkuPlaybackCommand::PlaylistPlaybackRepeat(Repeat::Track).into())
+        );
+        assert_eq!(
+            command("table/playlist/play", ""),
+            Some(KenkuPlaybackCommand::PlaylistPlaybackPlay.into())
+        );
+        assert_eq!(
+            command("table/sound/play", "s1"),
+            Some(KenkuCommandWithPayload::PlaySound("s1".to_string()))
+        );
+        assert_eq!(
+            command("table/command", r#"{"PlayTrack": "t1"}"#),
+            Some(KenkuCommandWithPayload::PlayTrack("t1".to_string()))
+        );
+
+        assert_eq!(
+            command("table/sound/s2/set", "false"),
+            Some(KenkuCommandWithPayload::StopSound("s2".to_string()))
+        );
+
+        assert!(command("table/state/volume", "40").is_none());
+        assert!(command("kenku/playlist/pause", "").is_none());
+        assert!(matches!(
+            bridge.command_for("table/playlist/volume", b"loud"),
+            Some(Err(KenkuError::InvalidInput(_)))
+        ));
+    }
+
+    #[test]
+    fn playback_becomes_state_topics() {
+        let bridge = MqttBridge::new("localhost", 1883);
+        let track = Track {
+            title: "Tavern".to_string(),
+            ..testing::track("t1")
+        };
+        let playlist = PlaylistPlaybackResponse {
+            playing: true,
+            volume: 0.5,
+            shuffle: true,
+            repeat: Repeat::Playlist,
+            ..testing::playback(Some(track))
+        };
+        let mut playback = testing::snapshot(playlist, &["s1"]);
+        playback.soundboard.sounds[0].title = "Rain".to_string();
+
+        let state = bridge.state_messages(Some(&playback));
+        let value = |name: &str| {
+            state
+                .iter()
+                .find(|(topic, _)| *topic == format!("kenku/state/{}", name))
+                .map(|(_, payload)| payload.as_str())
+        };
+
+        assert_eq!(value("online"), Some("true"));
+        assert_eq!(value("volume"), Some("50"));
+        assert_eq!(value("repeat"), Some("playlist"));
+        assert_eq!(value("track"), Some("Tavern"));
+        assert_eq!(value("playlist"), Some(""));
+        assert_eq!(value("sounds"), Some(r#"["Rain"]"#));
+        assert_eq!(value("sound_ids"), Some(r#"["s1"]"#));
+
+        assert_eq!(
+            bridge.state_messages(None),
+            [("kenku/state/online".to_string(), "false".to_string())]
+        );
+    }
+
+    #[test]
+    fn home_assistant_discovers_playback_and_sounds() {
+        let soundboard = SoundboardGetResponse {
+            soundboards: Vec::new(),
+            sounds: vec![Sounds {
+                title: "Rain".to_string(),
+                ..testing::sound("s1")
+            }],
+        };
+
+        assert!(MqttBridge::new("localhost", 1883)
+            .discovery_messages(&soundboard)
+            .is_empty());
+
+        let bridge = MqttBridge::new("localhost", 1883).home_assistant("homeassistant");
+        let messages = bridge.discovery_messages(&soundboard);
+        let config = |topic: &str| {
+            messages
+                .iter()
+                .find(|(found, _)| found == topic)
+                .map(|(_, payload)| serde_json::from_str::<serde_json::Value>(payload).unwrap())
+                .unwrap()
+        };
+
+        let volume = config("homeassistant/number/kenku_control/volume/config");
+        assert_eq!(volume["command_topic"], json!("kenku/playlist/volume"));
+        assert_eq!(volume["state_topic"], json!("kenku/state/volume"));
+        assert_eq!(volume["unique_id"], json!("kenku_control_volume"));
+        assert_eq!(volume["device"]["identifiers"], json!(["kenku_control"]));
+
+        let rain = config("homeassistant/switch/kenku_control/sound_s1/config");
+        assert_eq!(rain["name"], json!("Rain"));
+        assert_eq!(rain["command_topic"], json!("kenku/sound/s1/set"));
+        assert_eq!(
+            rain["value_template"],
+            json!("{{ 'true' if \"s1\" in value_json else 'false' }}")
+        );
+    }
+}
@@ -0,0 +1,211 @@
+//! Switching the ambience when OBS switches scenes, for streamers.
+//!
+//! `ObsBridge::run` connects to OBS Studio through obs-websocket (version 5, built into OBS 28
+//! and later), and activates the Kenku `Scene` mapped to each OBS scene that goes on air. A
+//! `Scene` can name music, playback settings and soundboard sounds, so switching to a "Tavern"
+//! camera can start the tavern music and the crowd sounds at once. OBS scenes without a mapping
+//! leave the audio alone.
+//!
+//! ```no_run
+//! # async fn example() -> Result<(), kenku_control::KenkuError> {
+//! use kenku_control::{obs::ObsBridge, scenes::Scene, Controller};
+//!
+//! let controller = Controller::new("127.0.0.1", 3333);
+//! ObsBridge::new("ws://127.0.0.1:4455")
+//!     .password("from the obs-websocket settings")
+//!     .on_scene("Tavern", Scene::new("tavern").playlist("p1").sound("crowd"))
+//!     .on_scene("Be right back", Scene::new("break").muted(true))
+//!     .run(&controller)
+//!     .await
+//! # }
+//! ```
+use crate::{scenes::Scene, Controller, KenkuError};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use tokio_tungstenite::tungstenite::Message;
+
+/// The obs-websocket message types used by the bridge.
+const HELLO: u64 = 0;
+const IDENTIFY: u64 = 1;
+const IDENTIFIED: u64 = 2;
+const EVENT: u64 = 5;
+const REQUEST: u64 = 6;
+const REQUEST_RESPONSE: u64 = 7;
+
+/// The event subscription for scene events.
+const SCENES: u64 = 1 << 2;
+
+/// Connects OBS Studio scenes to Kenku scenes.
+///
+/// # Fields
+///
+/// * `url` - The obs-websocket address, usually `ws://127.0.0.1:4455`.
+/// * `password` - The obs-websocket password, if authentication is enabled.
+/// * `scenes` - The Kenku scene activated for each OBS scene name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObsBridge {
+    pub url: String,
+    pub password: Option<String>,
+    pub scenes: BTreeMap<String, Scene>,
+}
+
+impl ObsBridge {
+    /// Creates a bridge to the obs-websocket server at `url`, without a password or mappings.
+    pub fn new(url: impl Into<String>) -> ObsBridge {
+        ObsBridge {
+            url: url.into(),
+            password: None,
+            scenes: BTreeMap::new(),
+        }
+    }
+
+    /// Sets the obs-websocket password.
+    pub fn password(mut self, password: impl Into<String>) -> ObsBridge {
+        self.password = Some(password.into());
+        self
+    }
+
+    /// Activates `scene` whenever the OBS scene named `obs_scene` goes on air.
+    pub fn on_scene(mut self, obs_scene: impl Into<String>, scene: Scene) -> ObsBridge {
+        self.scenes.insert(obs_scene.into(), scene);
+        self
+    }
+
+    /// Runs the bridge until OBS closes the connection.
+    ///
+    /// Once identified, the scene currently on air is activated, then every program scene change
+    /// is. A scene that fails to activate does not stop the bridge. To keep a bridge up while OBS
+    /// restarts, run it under a `supervisor::Supervisor`.
+    ///
+    /// # Returns
+    ///
+    /// This function returns `Ok(())` when OBS closed the connection, a `KenkuError::InvalidInput`
+    /// if OBS asks for a password and none is set, or a `KenkuError::Io` if the connection failed.
+    pub async fn run(&self, controller: &Controller) -> Result<(), KenkuError> {
+        let (mut socket, _) = tokio_tungstenite::connect_async(self.url.as_str())
+            .await
+            .map_err(obs_error)?;
+
+        while let Some(message) = socket.next().await {
+            let text = match message.map_err(obs_error)? {
+                Message::Text(text) => text,
+                Message::Close(_) => break,
+                _ => continue,
+            };
+            let Ok(message) = serde_json::from_str::<Value>(&text) else {
+                continue;
+            };
+            let data = &message["d"];
+
+            let reply = match message["op"].as_u64() {
+                Some(HELLO) => Some(self.identify(data)?),
+                Some(IDENTIFIED) => Some(json!({
+                    "op": REQUEST,
+                    "d": {"requestType": "GetCurrentProgramScene", "requestId": "current"},
+                })),
+                Some(EVENT) if data["eventType"] == "CurrentProgramSceneChanged" => {
+                    self.activate(controller, &data["eventData"]["sceneName"])
+                        .await;
+                    None
+                }
+                Some(REQUEST_RESPONSE) if data["requestId"] == "current" => {
+                    self.activate(controller, &data["responseData"]["currentProgramSceneName"])
+                        .await;
+                    None
+                }
+                _ => None,
+            };
+
+            if let Some(reply) = reply {
+                socket
+                    .send(Message::text(reply.to_string()))
+                    .await
+                    .map_err(obs_error)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Answers the `Hello` of OBS, authenticating when it asks to.
+    fn identify(&self, hello: &Value) -> Result<Value, KenkuError> {
+        let mut identify = json!({"rpcVersion": 1, "eventSubscriptions": SCENES});
+
+        if let Some(auth) = hello.get("authentication") {
+            let password = self.password.as_deref().ok_or_else(|| {
+                KenkuError::InvalidInput("OBS asks for a password and none is set".to_string())
+            })?;
+            let salt = auth["salt"].as_str().unwrap_or_default();
+            let challenge = auth["challenge"].as_str().unwrap_or_default();
+
+            identify["authentication"] = json!(authentication(password, salt, challenge));
+        }
+
+        Ok(json!({"op": IDENTIFY, "d": identify}))
+    }
+
+    /// Activates the scene mapped to `obs_scene`, if any.
+    async fn activate(&self, controller: &Controller, obs_scene: &Value) {
+        if let Some(scene) = obs_scene.as_str().and_then(|name| self.scenes.get(name)) {
+            let _ = scene.activate(controller).await;
+        }
+    }
+}
+
+/// Computes the obs-websocket authentication string from the password and the `Hello` values.
+fn authentication(password: &str, salt: &str, challenge: &str) -> String {
+    let secret = STANDARD.encode(Sha256::digest(format!("{}{}", password, salt)));
+
+    STANDARD.encode(Sha256::digest(format!("{}{}", secret, challenge)))
+}
+
+fn obs_error(error: impl std::fmt::Display) -> KenkuError {
+    KenkuError::Io(std::io::Error::other(error.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{authentication, ObsBridge};
+    use crate::KenkuError;
+    use serde_json::json;
+
+    #[test]
+    fn authentication_follows_the_protocol() {
+        // The example of the obs-websocket protocol documentation.
+        assert_eq!(
+            authentication(
+                "supersecretpassword",
+                "lM1GncleQOaCu9lT1yeUZhFYnqhsLLP1G5lAGo3ixaI=",
+                "+IxH4CnCiqpX1rM9scsNynZzbOe4KhDeYcTNS3PDaeY="
+            ),
+            "1Ct943GAT+6YQUUX47Ia/ncufilbe6+oD6lY+5kaCu4="
+        );
+    }
+
+    #[test]
+    fn hello_is_answered_with_identify() {
+        let hello = json!({"obsWebSocketVersion": "5.0.0", "rpcVersion": 1});
+        let identify = ObsBridge::new("ws://localhost:4455")
+            .identify(&hello)
+            .unwrap();
+
+        assert_eq!(
+            identify,
+            json!({"op": 1, "d": {"rpcVersion": 1, "eventSubscriptions": 4}})
+        );
+
+        let hello = json!({"rpcVersion": 1, "authentication": {"challenge": "c", "salt": "s"}});
+        assert!(matches!(
+            ObsBridge::new("ws://localhost:4455").identify(&hello),
+            Err(KenkuError::InvalidInput(_))
+        ));
+        assert!(ObsBridge::new("ws://localhost:4455")
+            .password("secret")
+            .identify(&hello)
+            .unwrap()["d"]["authentication"]
+            .is_string());
+    }
+}
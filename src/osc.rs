@@ -0,0 +1,298 @@
+//! Driving Kenku FM from TouchOSC, lighting consoles and other OSC senders.
+//!
+//! `OscServer` listens for OSC messages over UDP and turns them into commands for the Kenku
+//! Remote. With the default `/kenku` prefix, the addresses are:
+//!
+//! | Address                          | Arguments                                        |
+//! | -------------------------------- | ------------------------------------------------ |
+//! | `/kenku/playlist/play`           | None to resume, or a playlist id                 |
+//! | `/kenku/playlist/play/<id>`      | None; plays the playlist `<id>`                  |
+//! | `/kenku/playlist/pause`          | None                                             |
+//! | `/kenku/playlist/next`           | None                                             |
+//! | `/kenku/playlist/previous`       | None                                             |
+//! | `/kenku/playlist/volume`         | A float from `0` to `1`, or an int from 0 to 100 |
+//! | `/kenku/playlist/mute`           | A bool, or a number where non-zero is `true`     |
+//! | `/kenku/playlist/shuffle`        | A bool, or a number where non-zero is `true`     |
+//! | `/kenku/playlist/repeat`         | `off`, `track` or `playlist`                     |
+//! | `/kenku/track/play`              | A track id                                       |
+//! | `/kenku/track/play/<id>`         | None; plays the track `<id>`                     |
+//! | `/kenku/soundboard/play`         | A sound id                                       |
+//! | `/kenku/soundboard/play/<id>`    | None; plays the sound `<id>`                     |
+//! | `/kenku/soundboard/stop`         | A sound id                                       |
+//! | `/kenku/soundboard/stop/<id>`    | None; stops the sound `<id>`                     |
+//!
+//! Buttons on control surfaces send `1` when pressed and `0` when released, so the addresses
+//! that take no argument ignore messages whose first argument is a zero number or `false`.
+//! Messages inside bundles are handled in order, and messages that match nothing are ignored.
+use crate::{
+    playlist::Repeat, Controller, KenkuCommandWithPayload, KenkuError, KenkuPlaybackCommand, Volume,
+};
+use rosc::{OscMessage, OscPacket, OscType};
+use std::net::SocketAddr;
+use tokio::{
+    net::{ToSocketAddrs, UdpSocket},
+    task::JoinHandle,
+};
+
+/// The default first part of every address.
+pub const DEFAULT_PREFIX: &str = "/kenku";
+
+/// A running OSC server. It stops when dropped.
+#[derive(Debug)]
+pub struct OscServer {
+    address: SocketAddr,
+    handle: JoinHandle<()>,
+}
+
+impl OscServer {
+    /// Starts a server on `bind`, such as `"0.0.0.0:8000"`, sending commands to `controller`.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the running `OscServer`, or a `KenkuError::Io` if `bind` could not be bound.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if it is called outside of a tokio runtime.
+    pub async fn start(
+        bind: impl ToSocketAddrs,
+        controller: &Controller,
+    ) -> Result<OscServer, KenkuError> {
+        OscServer::start_with_prefix(bind, DEFAULT_PREFIX, controller).await
+    }
+
+    /// Starts a server like `start`, with `prefix`, such as `"/table2"`, instead of `/kenku`.
+    pub async fn start_with_prefix(
+        bind: impl ToSocketAddrs,
+        prefix: impl Into<String>,
+        controller: &Controller,
+    ) -> Result<OscServer, KenkuError> {
+        let socket = UdpSocket::bind(bind).await?;
+        let address = socket.local_addr()?;
+        let prefix = prefix.into();
+        let controller = controller.clone();
+
+        let handle = tokio::spawn(async move {
+            let mut buffer = vec![0; rosc::decoder::MTU];
+
+            while let Ok((length, _)) = socket.recv_from(&mut buffer).await {
+                let Ok((_, packet)) = rosc::decoder::decode_udp(&buffer[..length]) else {
+                    continue;
+                };
+
+                for message in messages(packet) {
+                    if let Some(Ok(command)) = command_for(&prefix, &message) {
+                        let _ = controller.execute(&command).await;
+                    }
+                }
+            }
+        });
+
+        Ok(OscServer { address, handle })
+    }
+
+    /// Returns the address the server listens on.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.address
+    }
+}
+
+impl Drop for OscServer {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+/// Turns an OSC message into a command.
+///
+/// # Returns
+///
+/// This function returns `None` for addresses outside of `prefix` or not listed in the module
+/// documentation, and for button releases. It returns a `KenkuError::InvalidInput` if the
+/// arguments do not fit the address, and the command otherwise.
+pub fn command_for(
+    prefix: &str,
+    message: &OscMessage,
+) -> Option<Result<KenkuCommandWithPayload, KenkuError>> {
+    let path = message.addr.strip_prefix(prefix)?.strip_prefix('/')?;
+    let args = &message.args;
+    let (path, id) = match path.split('/').collect::<Vec<_>>()[..] {
+        [group, action, id] => (format!("{}/{}", group, action), Some(id.to_string())),
+        _ => (path.to_string(), None),
+    };
+    let invalid = || {
+        KenkuError::InvalidInput(format!(
+            "{:?} are not valid arguments for {}",
+            args, message.addr
+        ))
+    };
+
+    let command = match (path.as_str(), id) {
+        ("playlist/play", Some(id)) => trigger(args, KenkuCommandWithPayload::PlayPlaylist(id))?,
+        ("playlist/play", None) => match args.first() {
+            Some(OscType::String(id)) => Ok(KenkuCommandWithPayload::PlayPlaylist(id.clone())),
+            _ => trigger(args, KenkuPlaybackCommand::PlaylistPlaybackPlay.into())?,
+        },
+        ("playlist/pause", None) => {
+            trigger(args, KenkuPlaybackCommand::PlaylistPlaybackPause.into())?
+        }
+        ("playlist/next", None) => {
+            trigger(args, KenkuPlaybackCommand::PlaylistPlaybackNext.into())?
+        }
+        ("playlist/previous", None) => {
+            trigger(args, KenkuPlaybackCommand::PlaylistPlaybackPrevious.into())?
+        }
+        ("playlist/volume", None) => {
+            let volume = match args.first() {
+                Some(OscType::Float(level)) => Volume::try_new(f64::from(*level)).ok(),
+                Some(OscType::Double(level)) => Volume::try_new(*level).ok(),
+                Some(OscType::Int(percent)) => u8::try_from(*percent)
+                    .ok()
+                    .filter(|percent| *percent <= 100)
+                    .map(Volume::from_percent),
+                _ => None,
+            };
+
+            volume
+                .map(|volume| KenkuPlaybackCommand::PlaylistPlaybackVolume(volume).into())
+                .ok_or_else(invalid)
+        }
+        ("playlist/mute", None) => switch(args)
+            .map(|muted| KenkuPlaybackCommand::PlaylistPlaybackMute(muted).into())
+            .ok_or_else(invalid),
+        ("playlist/shuffle", None) => switch(args)
+            .map(|shuffle| KenkuPlaybackCommand::PlaylistPlaybackShuffle(shuffle).into())
+            .ok_or_else(invalid),
+        ("playlist/repeat", None) => match args.first() {
+            Some(OscType::String(mode)) => {
+                serde_json::from_value::<Repeat>(mode.to_lowercase().into())
+                    .map(|repeat| KenkuPlaybackCommand::PlaylistPlaybackRepeat(repeat).into())
+                    .map_err(|_| invalid())
+            }
+            _ => Err(invalid()),
+        },
+        ("track/play" | "soundboard/play" | "soundboard/stop", id) => {
+            let command = match path.as_str() {
+                "track/play" => KenkuCommandWithPayload::PlayTrack,
+                "soundboard/play" => KenkuCommandWithPayload::PlaySound,
+                _ => KenkuCommandWithPayload::StopSound,
+            };
+
+            match (id, args.first()) {
+                (Some(id), _) => trigger(args, command(id))?,
+                (None, Some(OscType::String(id))) => Ok(command(id.clone())),
+                (None, _) => Err(invalid()),
+            }
+        }
+        _ => return None,
+    };
+
+    Some(command)
+}
+
+/// Returns every message of `packet`, in order, opening bundles.
+fn messages(packet: OscPacket) -> Vec<OscMessage> {
+    match packet {
+        OscPacket::Message(message) => vec![message],
+        OscPacket::Bundle(bundle) => bundle.content.into_iter().flat_map(messages).collect(),
+    }
+}
+
+/// Returns `command` when a trigger was pressed: sent without arguments, or with a first argument
+/// that is non-zero or `true`. Releases and arguments that make no sense for a trigger give `None`.
+fn trigger(
+    args: &[OscType],
+    command: KenkuCommandWithPayload,
+) -> Option<Result<KenkuCommandWithPayload, KenkuError>> {
+    let pressed = match args.first() {
+        None => true,
+        Some(_) => switch(args)?,
+    };
+
+    pressed.then_some(Ok(command))
+}
+
+/// Reads the first argument as an on/off switch.
+fn switch(args: &[OscType]) -> Option<bool> {
+    match args.first()? {
+        OscType::Bool(on) => Some(*on),
+        OscType::Int(value) => Some(*value != 0),
+        OscType::Long(value) => Some(*value != 0),
+        OscType::Float(value) => Some(*value != 0.0),
+        OscType::Double(value) => Some(*value != 0.0),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{command_for, DEFAULT_PREFIX};
+    use crate::{playlist::Repeat, KenkuCommandWithPayload, KenkuPlaybackCommand, Volume};
+    use rosc::{OscMessage, OscType};
+
+    fn command(addr: &str, args: Vec<OscType>) -> Option<KenkuCommandWithPayload> {
+        let message = OscMessage {
+            addr: addr.to_string(),
+            args,
+        };
+
+        command_for(DEFAULT_PREFIX, &message).map(|command| command.unwrap())
+    }
+
+    #[test]
+    fn addresses_become_commands() {
+        assert_eq!(
+            command("/kenku/playlist/volume", vec![OscType::Float(0.5)]),
+            Some(KenkuPlaybackCommand::PlaylistPlaybackVolume(Volume::from_percent(50)).into())
+        );
+        assert_eq!(
+            command("/kenku/playlist/volume", vec![OscType::Int(30)]),
+            Some(KenkuPlaybackCommand::PlaylistPlaybackVolume(Volume::from_percent(30)).into())
+        );
+        assert_eq!(
+            command(
+                "/kenku/playlist/repeat",
+                vec![OscType::String("track".into())]
+            ),
+            Some(KenkuPlaybackCommand::PlaylistPlaybackRepeat(Repeat::Track).into())
+        );
+        assert_eq!(
+            command("/kenku/soundboard/play", vec![OscType::String("s1".into())]),
+            Some(KenkuCommandWithPayload::PlaySound("s1".to_string()))
+        );
+        assert_eq!(
+            command("/kenku/soundboard/stop/s2", vec![OscType::Float(1.0)]),
+            Some(KenkuCommandWithPayload::StopSound("s2".to_string()))
+        );
+        assert_eq!(
+            command("/kenku/playlist/play/p1", vec![]),
+            Some(KenkuCommandWithPayload::PlayPlaylist("p1".to_string()))
+        );
+        assert_eq!(
+            command("/kenku/playlist/mute", vec![OscType::Int(1)]),
+            Some(KenkuPlaybackCommand::PlaylistPlaybackMute(true).into())
+        );
+    }
+
+    #[test]
+    fn releases_and_other_addresses_are_ignored() {
+        assert_eq!(
+            command("/kenku/playlist/next", vec![OscType::Float(0.0)]),
+            None
+        );
+        assert_eq!(
+            command("/kenku/soundboard/play/s1", vec![OscType::Bool(false)]),
+            None
+        );
+        assert_eq!(command("/lights/dimmer", vec![OscType::Float(1.0)]), None);
+        assert!(command_for(
+            DEFAULT_PREFIX,
+            &OscMessage {
+                addr: "/kenku/playlist/volume".to_string(),
+                args: vec![OscType::Float(2.0)],
+            }
+        )
+        .unwrap()
+        .is_err());
+    }
+}
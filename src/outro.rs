@@ -0,0 +1,272 @@
+//! Actions run when a given track ends.
+//!
+//! `OutroHooks` maps track ids to the actions to run once that track played to its end: pausing,
+//! activating a scene, sending commands or calling a webhook. The hooks are plain data, so they
+//! can be kept with `format::save` or in a `store::Namespace` next to the rest of a campaign.
+//! `Controller::outros` polls the playlist playback and runs the hooks of every track that ends.
+//!
+//! Kenku Remote does not report when a track ends, so an end is inferred: the track was within
+//! two poll intervals of its duration on the last poll, and `playlist::playback::wait_for_track_end`
+//! would consider it over now. A track the game master skips halfway through does not trigger its
+//! hooks. Webhooks are posted like those of the `webhooks` feature, without the controller's
+//! default headers.
+use crate::{
+    playlist::{playback, PlaylistPlaybackResponse, Track},
+    scenes::Scene,
+    utils::post_webhook,
+    Controller, KenkuCommandWithPayload, KenkuError, KenkuPlaybackCommand,
+};
+use futures_util::Stream;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, time::Duration};
+use tokio::time::{interval, MissedTickBehavior};
+
+/// Represents something to do when a track ends.
+///
+/// # Variants
+///
+/// * `Pause` - Pauses the playlist playback, holding on the silence after the track.
+/// * `Execute` - Sends the commands, in order.
+/// * `Webhook` - Sends a POST request to the URL, with `{"event": "outro", "track": ...}` as JSON body.
+/// * `Scene` - Activates the scene.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutroAction {
+    Pause,
+    Execute(Vec<KenkuCommandWithPayload>),
+    Webhook(String),
+    Scene(Scene),
+}
+
+/// Holds the outro actions of each track.
+///
+/// # Fields
+///
+/// * `tracks` - The actions to run, in order, by track id.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct OutroHooks {
+    pub tracks: BTreeMap<String, Vec<OutroAction>>,
+}
+
+impl OutroHooks {
+    /// Creates an empty set of hooks.
+    pub fn new() -> OutroHooks {
+        OutroHooks::default()
+    }
+
+    /// Adds `action` to the actions run when the track with the id `track` ends.
+    pub fn on_end(mut self, track: impl Into<String>, action: OutroAction) -> OutroHooks {
+        self.tracks.entry(track.into()).or_default().push(action);
+        self
+    }
+
+    /// Removes every action of the track with the id `track`.
+    pub fn clear(&mut self, track: &str) {
+        self.tracks.remove(track);
+    }
+
+    /// Returns the actions run when the track with the id `track` ends.
+    pub fn actions_for(&self, track: &str) -> &[OutroAction] {
+        self.tracks
+            .get(track)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+}
+
+/// Represents a track that ended, with the outcome of each of its outro actions.
+///
+/// # Fields
+///
+/// * `track` - The track that ended, as last seen playing.
+/// * `results` - Each action with its result, in the order they ran.
+#[derive(Debug)]
+pub struct OutroFired {
+    pub track: Track,
+    pub results: Vec<(OutroAction, Result<(), KenkuError>)>,
+}
+
+/// Returns the track of `previous` that ended by the time of `current`, if any.
+///
+/// A track ended when it was playing with at most `tolerance` left in `previous`, and is over in
+/// `current` the way `playlist::playback::wait_for_track_end` sees it: another track or no track
+/// is loaded, the same track started over, or it stopped at its end.
+pub fn ended_track<'a>(
+    previous: &'a PlaylistPlaybackResponse,
+    current: &PlaylistPlaybackResponse,
+    tolerance: Duration,
+) -> Option<&'a Track> {
+    let track = previous.track.as_ref().filter(|_| previous.playing)?;
+    let remaining = track.duration?.saturating_sub(track.progress?);
+
+    if u128::from(remaining) > tolerance.as_millis() {
+        return None;
+    }
+
+    playback::moved_on(previous, current, &track.id).then_some(track)
+}
+
+impl Controller {
+    /// Runs `action`, reporting `track` to a webhook when asked to.
+    pub async fn run_outro_action(
+        &self,
+        action: &OutroAction,
+        track: &Track,
+    ) -> Result<(), KenkuError> {
+        match action {
+            OutroAction::Pause => {
+                self.execute(&KenkuPlaybackCommand::PlaylistPlaybackPause.into())
+                    .await?;
+            }
+            OutroAction::Execute(commands) => {
+                for command in commands {
+                    self.execute(command).await?;
+                }
+            }
+            OutroAction::Scene(scene) => {
+                scene.activate(self).await?;
+            }
+            OutroAction::Webhook(url) => {
+                let body = serde_json::json!({ "event": "outro", "track": track });
+                post_webhook(url, &body).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Polls the playlist playback every `poll_interval` and runs the outro actions of every track that ends.
+    ///
+    /// Tracks without actions are ignored. While automation is paused, ended tracks are not
+    /// acted on. Failed polls are skipped. The stream never ends; drop it to stop. A
+    /// `poll_interval` under a millisecond is taken as a millisecond.
+    ///
+    /// # Returns
+    ///
+    /// A stream yielding an `OutroFired` for each track whose actions ran.
+    pub fn outros(
+        &self,
+        hooks: OutroHooks,
+        poll_interval: Duration,
+    ) -> impl Stream<Item = OutroFired> {
+        let poll_interval = poll_interval.max(Duration::from_millis(1));
+        let mut ticks = interval(poll_interval);
+        ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        let state = (self.clone(), hooks, ticks, None);
+
+        futures_util::stream::unfold(
+            state,
+            move |(controller, hooks, mut ticks, mut last)| async move {
+                loop {
+                    ticks.tick().await;
+
+                    let Ok(current) = controller.get_playlist_playback().await else {
+                        continue;
+                    };
+                    let previous: Option<PlaylistPlaybackResponse> = last.replace(current.clone());
+
+                    let Some(track) = previous
+                        .as_ref()
+                        .and_then(|previous| ended_track(previous, &current, poll_interval * 2))
+                    else {
+                        continue;
+                    };
+
+                    let actions = hooks.actions_for(&track.id);
+
+                    if actions.is_empty() || controller.automation_paused() {
+                        continue;
+                    }
+
+                    let mut results = Vec::with_capacity(actions.len());
+
+                    for action in actions {
+                        let result = controller.run_outro_action(action, track).await;
+                        results.push((action.clone(), result));
+                    }
+
+                    let fired = OutroFired {
+                        track: track.clone(),
+                        results,
+                    };
+
+                    return Some((fired, (controller, hooks, ticks, last)));
+                }
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ended_track, OutroAction, OutroHooks, Scene};
+    use crate::{
+        playlist::{PlaylistPlaybackResponse, Track},
+        testing, Controller, KenkuCommandWithPayload,
+    };
+    use std::time::Duration;
+
+    fn playback(track: Option<&str>, playing: bool, progress: u32) -> PlaylistPlaybackResponse {
+        let track = track.map(|id| Track {
+            duration: Some(60_000),
+            progress: Some(progress),
+            ..testing::track(id)
+        });
+
+        PlaylistPlaybackResponse {
+            playing,
+            ..testing::playback(track)
+        }
+    }
+
+    #[test]
+    fn only_tracks_played_to_their_end_have_ended() {
+        let tolerance = Duration::from_secs(2);
+        let near_end = playback(Some("t1"), true, 59_000);
+
+        assert_eq!(
+            ended_track(&near_end, &playback(Some("t2"), true, 0), tolerance).map(|t| &t.id[..]),
+            Some("t1")
+        );
+        assert!(ended_track(&near_end, &playback(None, false, 0), tolerance).is_some());
+        assert!(ended_track(&near_end, &playback(Some("t1"), true, 500), tolerance).is_some());
+        assert!(ended_track(&near_end, &playback(Some("t1"), true, 59_900), tolerance).is_none());
+        assert!(ended_track(&near_end, &playback(Some("t1"), false, 60_000), tolerance).is_some());
+
+        let halfway = playback(Some("t1"), true, 30_000);
+        assert!(ended_track(&halfway, &playback(Some("t2"), true, 0), tolerance).is_none());
+    }
+
+    #[test]
+    fn hooks_round_trip_through_json() {
+        let hooks = OutroHooks::new()
+            .on_end("t1", OutroAction::Pause)
+            .on_end(
+                "t1",
+                OutroAction::Execute(vec![KenkuCommandWithPayload::PlayPlaylist(
+                    "p2".to_string(),
+                )]),
+            )
+            .on_end(
+                "t2",
+                OutroAction::Scene(Scene::new("ambush").sound("drums")),
+            )
+            .on_end(
+                "t2",
+                OutroAction::Webhook("http://127.0.0.1/hook".to_string()),
+            );
+
+        let saved = serde_json::to_string(&hooks).unwrap();
+
+        assert_eq!(serde_json::from_str::<OutroHooks>(&saved).unwrap(), hooks);
+        assert_eq!(hooks.actions_for("t1").len(), 2);
+        assert!(hooks.actions_for("t3").is_empty());
+    }
+
+    #[tokio::test]
+    async fn zero_poll_intervals_do_not_panic() {
+        let controller = Controller::new("127.0.0.1", 3333);
+        let _outros = controller.outros(OutroHooks::new(), Duration::ZERO);
+    }
+}
@@ -0,0 +1,146 @@
+//! A compact status payload for stream overlays and other frequent pollers.
+use crate::{
+    playlist::PlaylistPlaybackResponse, soundboard::SoundboardPlaybackResponse, Controller,
+    KenkuError,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+};
+
+/// Represents what an overlay needs to show about the current playback.
+///
+/// # Fields
+///
+/// * `playing` - Whether the playlist is currently playing.
+/// * `title` - The title of the current track, if any.
+/// * `playlist` - The title of the current playlist, if any.
+/// * `progress` - How far the current track is, from 0.0 to 1.0, rounded to three decimals. `None` when the duration is unknown.
+/// * `artwork` - The background image of the current playlist, if any.
+/// * `active_sounds` - How many soundboard sounds are playing.
+#[serde_with::skip_serializing_none]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct OverlayStatus {
+    pub playing: bool,
+    pub title: Option<String>,
+    pub playlist: Option<String>,
+    pub progress: Option<f64>,
+    pub artwork: Option<String>,
+    pub active_sounds: usize,
+}
+
+impl OverlayStatus {
+    /// Builds an `OverlayStatus` from the playlist and soundboard playback responses.
+    pub fn from_playback(
+        playlist: &PlaylistPlaybackResponse,
+        soundboard: &SoundboardPlaybackResponse,
+    ) -> OverlayStatus {
+        let progress = playlist
+            .track
+            .as_ref()
+            .and_then(|track| track.percent_complete())
+            .map(|percent| (percent * 10.0).round() / 1000.0);
+
+        OverlayStatus {
+            playing: playlist.playing,
+            title: playlist.track.as_ref().map(|track| track.title.clone()),
+            playlist: playlist.playlist.as_ref().map(|list| list.title.clone()),
+            progress,
+            artwork: playlist
+                .playlist
+                .as_ref()
+                .and_then(|list| list.background.clone()),
+            active_sounds: soundboard.sounds.len(),
+        }
+    }
+
+    /// Returns a strong HTTP entity tag for this status.
+    ///
+    /// The tag only changes when the status does, so a server can answer `304 Not Modified` to
+    /// pollers sending it back in `If-None-Match`.
+    pub fn etag(&self) -> String {
+        let mut hasher = DefaultHasher::new();
+
+        self.playing.hash(&mut hasher);
+        self.title.hash(&mut hasher);
+        self.playlist.hash(&mut hasher);
+        self.progress.map(f64::to_bits).hash(&mut hasher);
+        self.artwork.hash(&mut hasher);
+        self.active_sounds.hash(&mut hasher);
+
+        format!("\"{:016x}\"", hasher.finish())
+    }
+
+    /// Returns `true` when `if_none_match`, the value of an `If-None-Match` header, matches this status.
+    pub fn matches(&self, if_none_match: &str) -> bool {
+        let etag = self.etag();
+
+        if_none_match
+            .split(',')
+            .map(str::trim)
+            .any(|candidate| candidate == "*" || candidate.trim_start_matches("W/") == etag)
+    }
+}
+
+impl Controller {
+    /// Fetches the playlist and soundboard playback and condenses them into an `OverlayStatus`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is either an `OverlayStatus` or a `KenkuError`.
+    pub async fn get_overlay_status(&self) -> Result<OverlayStatus, KenkuError> {
+        let playlist = self.get_playlist_playback().await?;
+        let soundboard = self.get_soundboard_playback().await?;
+
+        Ok(OverlayStatus::from_playback(&playlist, &soundboard))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OverlayStatus;
+    use crate::{playlist::PlaylistPlaybackResponse, soundboard::SoundboardPlaybackResponse};
+    use serde_json::json;
+
+    fn playlist_playback(progress: u32) -> PlaylistPlaybackResponse {
+        serde_json::from_value(json!({
+            "playing": true,
+            "volume": 1.0,
+            "muted": false,
+            "shuffle": false,
+            "repeat": "playlist",
+            "track": {"id": "t1", "url": "", "title": "Tavern", "duration": 4000, "progress": progress},
+            "playlist": {"id": "p1", "title": "Town", "background": "town.png"}
+        }))
+        .unwrap()
+    }
+
+    fn soundboard_playback() -> SoundboardPlaybackResponse {
+        serde_json::from_value(json!({"sounds": []})).unwrap()
+    }
+
+    #[test]
+    fn status_is_built_from_playback() {
+        let status = OverlayStatus::from_playback(&playlist_playback(1000), &soundboard_playback());
+
+        assert_eq!(status.title.as_deref(), Some("Tavern"));
+        assert_eq!(status.playlist.as_deref(), Some("Town"));
+        assert_eq!(status.artwork.as_deref(), Some("town.png"));
+        assert_eq!(status.progress, Some(0.25));
+        assert_eq!(status.active_sounds, 0);
+    }
+
+    #[test]
+    fn etag_follows_the_status() {
+        let soundboard = soundboard_playback();
+        let first = OverlayStatus::from_playback(&playlist_playback(1000), &soundboard);
+        let same = OverlayStatus::from_playback(&playlist_playback(1000), &soundboard);
+        let later = OverlayStatus::from_playback(&playlist_playback(2000), &soundboard);
+
+        assert_eq!(first.etag(), same.etag());
+        assert_ne!(first.etag(), later.etag());
+        assert!(first.matches(&format!("W/{}", same.etag())));
+        assert!(!first.matches(&later.etag()));
+    }
+}
@@ -0,0 +1,243 @@
+//! Content packs: scenes, tags and gain maps shipped alongside a published audio pack.
+//!
+//! Ids are given by each Kenku FM installation, so a creator cannot ship scenes that name them. A
+//! `Pack` names tracks, playlists and sounds by their title or URL instead, which stay the same
+//! wherever the audio pack is imported. `Pack::install` resolves those names against the library
+//! of a Kenku Remote and keeps the resulting `InstalledPack` in a `store::StateStore`, under the
+//! `packs` namespace; `uninstall` removes it again.
+//!
+//! A pack is plain data, so it can be written by hand and loaded with `format::load`:
+//!
+//! ```json
+//! {
+//!   "name": "sinister-dungeons",
+//!   "version": "1.2.0",
+//!   "source": "https://example.com/sinister-dungeons",
+//!   "scenes": [{"name": "crypt", "track": "Crypt", "sounds": ["file:///sfx/rain.ogg"]}],
+//!   "tags": {"Crypt": ["undead", "tomb"]},
+//!   "gains": {"Crypt": 0.6}
+//! }
+//! ```
+use crate::{
+    aliases::Aliases, playlist::PlaylistGetResponse, scenes::Scene,
+    soundboard::SoundboardGetResponse, store::StateStore, Controller, KenkuError, Volume,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The namespace of a `StateStore` the installed packs are kept in.
+pub const PACKS_NAMESPACE: &str = "packs";
+
+/// Represents a content pack as published by its creator.
+///
+/// # Fields
+///
+/// * `name` - The name of the pack, which it is installed under. May only contain ASCII letters, digits, `-` and `_`.
+/// * `version` - The version of the pack, as the creator numbers it.
+/// * `source` - Where the audio pack it goes with is published, if given.
+/// * `scenes` - The scenes of the pack. Their `playlist`, `track` and `sounds` hold titles or URLs instead of ids.
+/// * `tags` - Extra names for tracks and sounds, keyed by title or URL, installed as `aliases::Aliases`.
+/// * `gains` - The volume each track sounds right at, keyed by title or URL.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Pack {
+    pub name: String,
+    pub version: String,
+    pub source: Option<String>,
+    pub scenes: Vec<Scene>,
+    pub tags: BTreeMap<String, Vec<String>>,
+    pub gains: BTreeMap<String, Volume>,
+}
+
+/// Represents a pack resolved against the library of one Kenku FM installation.
+///
+/// # Fields
+///
+/// * `name` - The name of the pack.
+/// * `version` - The version of the pack.
+/// * `scenes` - The scenes of the pack, naming ids. A scene playing a track with a gain and no volume of its own plays it at that gain.
+/// * `aliases` - The tags of the pack, by id.
+/// * `gains` - The gains of the pack, by track id.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct InstalledPack {
+    pub name: String,
+    pub version: String,
+    pub scenes: Vec<Scene>,
+    pub aliases: Aliases,
+    pub gains: BTreeMap<String, Volume>,
+}
+
+impl Pack {
+    /// Resolves the titles and URLs of this pack against a library.
+    ///
+    /// Titles are compared exactly; a URL matches the track or sound imported from it.
+    ///
+    /// # Arguments
+    ///
+    /// * `playlists` - The playlists and tracks of the installation.
+    /// * `soundboard` - The soundboards and sounds of the installation.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the `InstalledPack`, or a `KenkuError::NotFound` naming every title or
+    /// URL missing from the library, so that a pack is never half installed.
+    pub fn resolve(
+        &self,
+        playlists: &PlaylistGetResponse,
+        soundboard: &SoundboardGetResponse,
+    ) -> Result<InstalledPack, KenkuError> {
+        let track = |name: &str| {
+            find(
+                playlists
+                    .tracks
+                    .iter()
+                    .map(|track| (&*track.id, &*track.title, &*track.url)),
+                name,
+            )
+        };
+        let sound = |name: &str| {
+            find(
+                soundboard
+                    .sounds
+                    .iter()
+                    .map(|sound| (&*sound.id, &*sound.title, &*sound.url)),
+                name,
+            )
+        };
+        let playlist = |name: &str| {
+            find(
+                playlists
+                    .playlists
+                    .iter()
+                    .map(|playlist| (&*playlist.id, &*playlist.title, "")),
+                name,
+            )
+        };
+        let mut missing = Vec::new();
+        let mut require = |found: Option<String>, name: &str| {
+            if found.is_none() {
+                missing.push(name.to_string());
+            }
+
+            found.unwrap_or_default()
+        };
+
+        let gains: BTreeMap<String, Volume> = self
+            .gains
+            .iter()
+            .map(|(name, gain)| (require(track(name), name), *gain))
+            .collect();
+
+        let mut scenes = Vec::new();
+
+        for scene in &self.scenes {
+            let mut installed = scene.clone();
+            installed.playlist = scene
+                .playlist
+                .as_deref()
+                .map(|name| require(playlist(name), name));
+            installed.track = scene
+                .track
+                .as_deref()
+                .map(|name| require(track(name), name));
+            installed.sounds = scene
+                .sounds
+                .iter()
+                .map(|name| require(sound(name), name))
+                .collect();
+
+            if installed.volume.is_none() {
+                installed.volume = installed
+                    .track
+                    .as_ref()
+                    .and_then(|id| gains.get(id).copied());
+            }
+
+            scenes.push(installed);
+        }
+
+        let mut aliases = Aliases::new();
+
+        for (name, tags) in &self.tags {
+            let id = require(track(name).or_else(|| sound(name)), name);
+
+            for tag in tags {
+                aliases.insert(id.clone(), tag.clone());
+            }
+        }
+
+        if !missing.is_empty() {
+            return Err(KenkuError::NotFound(format!(
+                "{:?} in the library, needed by the pack {:?}",
+                missing, self.name
+            )));
+        }
+
+        Ok(InstalledPack {
+            name: self.name.clone(),
+            version: self.version.clone(),
+            scenes,
+            aliases,
+            gains,
+        })
+    }
+
+    /// Resolves this pack against the library of `controller` and keeps it in `store`, replacing
+    /// any installed pack with the same name.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the `InstalledPack`, or the error raised while reading the library,
+    /// resolving the pack or saving it. Nothing is saved if the pack cannot be resolved.
+    pub async fn install(
+        &self,
+        controller: &Controller,
+        store: &StateStore,
+    ) -> Result<InstalledPack, KenkuError> {
+        let playlists = controller.get_playlist().await?;
+        let soundboard = controller.get_soundboard().await?;
+        let installed = self.resolve(&playlists, &soundboard)?;
+
+        store
+            .namespace(PACKS_NAMESPACE)?
+            .save(&installed.name, &installed)?;
+
+        Ok(installed)
+    }
+}
+
+/// Returns the id of the first of `items`, given as id, title and URL, titled or imported from `name`.
+fn find<'a>(
+    items: impl IntoIterator<Item = (&'a str, &'a str, &'a str)>,
+    name: &str,
+) -> Option<String> {
+    items
+        .into_iter()
+        .find(|(_, title, url)| *title == name || *url == name)
+        .map(|(id, _, _)| id.to_string())
+}
+
+/// Returns every pack installed in `store`, ordered by name.
+pub fn installed(store: &StateStore) -> Result<Vec<InstalledPack>, KenkuError> {
+    let packs = store.namespace(PACKS_NAMESPACE)?;
+    let mut installed = Vec::new();
+
+    for name in packs.keys()? {
+        installed.extend(packs.load(&name)?);
+    }
+
+    Ok(installed)
+}
+
+/// Removes the pack called `name` from `store`.
+///
+/// # Returns
+///
+/// This function returns `true` if the pack was installed, and `false` if it was not.
+pub fn uninstall(store: &StateStore, name: &str) -> Result<bool, KenkuError> {
+    let packs = store.namespace(PACKS_NAMESPACE)?;
+    let was_installed = packs.load::<InstalledPack>(name)?.is_some();
+    packs.remove(name)?;
+
+    Ok(was_installed)
+}
@@ -2,6 +2,7 @@ use super::*;
 use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use std::collections::HashMap;
 
 /// Represents the repeat mode for a playlist or track.
 ///
@@ -9,7 +10,7 @@ use serde_json::json;
 /// * `Track`: Represents that the current track should be repeated.
 /// * `Playlist`: Represents that the entire playlist should be repeated.
 /// * `Off`: Represents that no repeat mode is active.
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub enum Repeat {
     #[serde(rename = "track")]
     Track,
@@ -19,6 +20,17 @@ pub enum Repeat {
     Off,
 }
 
+impl Repeat {
+    /// Returns the mode after this one, in the order `Track`, `Playlist`, `Off`, then `Track` again.
+    pub fn next(&self) -> Repeat {
+        match self {
+            Repeat::Track => Repeat::Playlist,
+            Repeat::Playlist => Repeat::Off,
+            Repeat::Off => Repeat::Track,
+        }
+    }
+}
+
 /// Represents the response from a GET request to a playlist.
 ///
 /// This struct is used to model the response from a GET request to a playlist. It includes a vector of `Playlist` and a vector of `Track`.
@@ -27,7 +39,7 @@ pub enum Repeat {
 ///
 /// * `playlists` - A vector of `Playlist` representing the playlists in the response.
 /// * `tracks` - A vector of `Track` representing the tracks in the response.
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct PlaylistGetResponse {
     pub playlists: Vec<Playlist>,
     pub tracks: Vec<Track>,
@@ -42,6 +54,122 @@ impl PlaylistGetResponse {
     pub fn get_playlists(&self) -> &Vec<Playlist> {
         &self.playlists
     }
+
+    /// Returns the playlist with the id `id`, if any.
+    pub fn playlist_by_id(&self, id: &str) -> Option<&Playlist> {
+        self.playlists.iter().find(|playlist| playlist.id == id)
+    }
+
+    /// Returns the first playlist titled `title`, compared case-insensitively.
+    pub fn playlist_by_title(&self, title: &str) -> Option<&Playlist> {
+        self.playlists
+            .iter()
+            .find(|playlist| playlist.title.eq_ignore_ascii_case(title))
+    }
+
+    /// Returns the track with the id `id`, if any.
+    pub fn track_by_id(&self, id: &str) -> Option<&Track> {
+        self.tracks.iter().find(|track| track.id == id)
+    }
+
+    /// Returns the first track titled `title`, compared case-insensitively.
+    pub fn track_by_title(&self, title: &str) -> Option<&Track> {
+        self.tracks
+            .iter()
+            .find(|track| track.title.eq_ignore_ascii_case(title))
+    }
+
+    /// Returns the tracks of `playlist`, in the playlist's order.
+    ///
+    /// Track ids that are missing from this response are skipped.
+    pub fn tracks_of_playlist(&self, playlist: &Playlist) -> Vec<&Track> {
+        playlist
+            .tracks
+            .iter()
+            .flatten()
+            .filter_map(|id| self.track_by_id(id))
+            .collect()
+    }
+
+    /// Iterates over every playlist entry in the order Kenku displays them.
+    ///
+    /// Playlists come in their listed order and each playlist's tracks in the playlist's order, so a track in several playlists is yielded once per playlist. Track ids that are missing from this response are skipped.
+    ///
+    /// # Returns
+    ///
+    /// An iterator of `(playlist, track)` pairs.
+    pub fn iter_in_playlist_order(&self) -> impl Iterator<Item = (&Playlist, &Track)> + '_ {
+        self.playlists.iter().flat_map(move |playlist| {
+            self.tracks_of_playlist(playlist)
+                .into_iter()
+                .map(move |track| (playlist, track))
+        })
+    }
+
+    /// Returns the library in the order Kenku displays it, with every track listed once.
+    ///
+    /// Each track keeps the position of its first appearance in [`iter_in_playlist_order`](Self::iter_in_playlist_order) and records every playlist it belongs to, in playlist order. Tracks that belong to no playlist follow in their response order, with no memberships.
+    ///
+    /// # Returns
+    ///
+    /// A vector of `LibraryEntry`, one per distinct track.
+    pub fn library_in_playlist_order(&self) -> Vec<LibraryEntry<'_>> {
+        let mut entries: Vec<LibraryEntry> = Vec::with_capacity(self.tracks.len());
+        let mut positions: HashMap<&str, usize> = HashMap::with_capacity(self.tracks.len());
+
+        for (playlist, track) in self.iter_in_playlist_order() {
+            match positions.get(track.id.as_str()) {
+                Some(&position) => {
+                    let memberships = &mut entries[position].playlists;
+
+                    if !memberships.iter().any(|member| member.id == playlist.id) {
+                        memberships.push(playlist);
+                    }
+                }
+                None => {
+                    positions.insert(&track.id, entries.len());
+                    entries.push(LibraryEntry {
+                        track,
+                        playlists: vec![playlist],
+                    });
+                }
+            }
+        }
+
+        for track in &self.tracks {
+            if !positions.contains_key(track.id.as_str()) {
+                positions.insert(&track.id, entries.len());
+                entries.push(LibraryEntry {
+                    track,
+                    playlists: Vec::new(),
+                });
+            }
+        }
+
+        entries
+    }
+
+    /// Returns the playlists that contain `track`, in playlist order.
+    pub fn playlists_containing(&self, track: &Track) -> Vec<&Playlist> {
+        self.playlists
+            .iter()
+            .filter(|playlist| playlist.tracks.iter().flatten().any(|id| *id == track.id))
+            .collect()
+    }
+}
+
+/// A distinct track of the library, together with the playlists it belongs to.
+///
+/// Produced by [`PlaylistGetResponse::library_in_playlist_order`].
+///
+/// # Fields
+///
+/// * `track` - The track.
+/// * `playlists` - The playlists that contain the track, in playlist order. Empty for tracks that belong to no playlist.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LibraryEntry<'a> {
+    pub track: &'a Track,
+    pub playlists: Vec<&'a Playlist>,
 }
 
 /// Represents the response from a playback request to a playlist.
@@ -58,7 +186,7 @@ impl PlaylistGetResponse {
 /// * `tracks` - An optional vector of `Track` representing the current tracks in the playlist.
 /// * `playlist` - An optional `Playlist` representing the current playlist.
 #[serde_with::skip_serializing_none]
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct PlaylistPlaybackResponse {
     pub playing: bool,
     pub volume: f64,
@@ -69,6 +197,13 @@ pub struct PlaylistPlaybackResponse {
     pub playlist: Option<Playlist>,
 }
 
+impl PlaylistPlaybackResponse {
+    /// Returns the playlist volume as a `Volume`, clamped to the valid range.
+    pub fn volume_level(&self) -> Volume {
+        Volume::clamped(self.volume)
+    }
+}
+
 /// Represents a playlist.
 ///
 /// This struct is used to model a playlist with its properties.
@@ -80,7 +215,7 @@ pub struct PlaylistPlaybackResponse {
 /// * `background` - An optional string representing the background of the playlist.
 /// * `title` - The title of the playlist.
 #[serde_with::skip_serializing_none]
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct Playlist {
     pub id: String,
     pub tracks: Option<Vec<String>>,
@@ -88,6 +223,29 @@ pub struct Playlist {
     pub title: String,
 }
 
+impl Playlist {
+    /// Sends a request to the Kenku server to play this playlist from its first track.
+    ///
+    /// This function sends a PUT request for the 'PlaylistPlay' command with the playlist ID as JSON payload, so the shuffle and repeat modes apply to the whole playlist, and returns the HTTP status code of the response.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - A reference to the `Playlist` struct to play.
+    /// * `controller` - A reference to a `Controller` struct, which includes a HTTP client, the IP address and port of the server, and the current state of the server.
+    ///
+    /// # Returns
+    ///
+    /// This function returns a `Result` that contains a `StatusCode` if the request was sent successfully, or a `KenkuError` if the request failed.
+    pub async fn play(&self, controller: &Controller) -> Result<StatusCode, KenkuError> {
+        controller.play_playlist(&self.id).await
+    }
+}
+
+/// Returns `progress` as a percentage of `duration`, between 0.0 and 100.0.
+pub(crate) fn percent(progress: f64, duration: f64) -> Option<f64> {
+    (duration > 0.0).then(|| (progress / duration * 100.0).clamp(0.0, 100.0))
+}
+
 /// Represents a track.
 ///
 /// This struct is used to model a track with its properties.
@@ -100,7 +258,7 @@ pub struct Playlist {
 /// * `duration` - The total duration of the track, in milliseconds. This is an optional field.
 /// * `progress` - The current progress of the track, in milliseconds. This is an optional field.
 #[serde_with::skip_serializing_none]
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct Track {
     pub id: String,
     pub url: String,
@@ -110,6 +268,27 @@ pub struct Track {
 }
 
 impl Track {
+    /// Returns the `duration` of the track as a `Duration`, if the remote reported it.
+    pub fn duration_time(&self) -> Option<std::time::Duration> {
+        self.duration
+            .map(|millis| std::time::Duration::from_millis(millis.into()))
+    }
+
+    /// Returns the `progress` of the track as a `Duration`, if the remote reported it.
+    pub fn progress_time(&self) -> Option<std::time::Duration> {
+        self.progress
+            .map(|millis| std::time::Duration::from_millis(millis.into()))
+    }
+
+    /// Returns how far the track is, from 0.0 to 100.0.
+    ///
+    /// # Returns
+    ///
+    /// This function returns `None` if the remote did not report the duration and progress, or the duration is zero.
+    pub fn percent_complete(&self) -> Option<f64> {
+        percent(f64::from(self.progress?), f64::from(self.duration?))
+    }
+
     /// Sends a request to the Kenku server to play a specific track in the playlist.
     ///
     /// This function constructs a URL for the 'PlaylistPlay' command, sends a PUT request to that URL with the track ID as JSON payload, and returns the HTTP status code of the response.
@@ -121,32 +300,134 @@ impl Track {
     ///
     /// # Returns
     ///
-    /// This function returns a `Result` that contains a `StatusCode` if the request was sent successfully, or a `reqwest::Error` if the request failed.
-    pub async fn play(&self, controller: &Controller) -> Result<StatusCode, reqwest::Error> {
-        let command = &KenkuCommand::KenkuPut(KenkuPutCommand::PlaylistPlay);
-        let url = process_url(command, controller.address);
-        let json = json!({"id": self.id});
+    /// This function returns a `Result` that contains a `StatusCode` if the request was sent successfully, or a `KenkuError` if the request failed.
+    pub async fn play(&self, controller: &Controller) -> Result<StatusCode, KenkuError> {
+        controller.play_track_id(&self.id).await
+    }
+}
+
+/// The time between two volume changes of a crossfade.
+#[cfg(feature = "crossfade")]
+const CROSSFADE_STEP: std::time::Duration = std::time::Duration::from_millis(50);
+
+/// A handle to a running crossfade, returned by `crossfade_to`.
+///
+/// Dropping the handle lets the crossfade run to its end in the background.
+#[cfg(feature = "crossfade")]
+#[derive(Debug)]
+pub struct Crossfade {
+    handle: tokio::task::JoinHandle<Result<(), KenkuError>>,
+    cancel: tokio::sync::oneshot::Sender<()>,
+}
 
-        let response = controller
-            .client
-            .put(url)
-            .header("Content-Type", "application/json")
-            .json(&json)
-            .send()
-            .await?
-            .status();
+#[cfg(feature = "crossfade")]
+impl Crossfade {
+    /// Returns `true` once the crossfade ended, successfully or not.
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    /// Waits for the crossfade to end.
+    ///
+    /// # Returns
+    ///
+    /// This function returns `Ok(())` once the new track plays at the original volume, or the `KenkuError` that stopped the crossfade.
+    pub async fn wait(self) -> Result<(), KenkuError> {
+        Crossfade::join(self.handle).await
+    }
+
+    /// Stops the crossfade and puts the playlist volume back to the level it had before.
+    ///
+    /// The track is left as it is: if the switch already happened, the new track keeps playing.
+    ///
+    /// # Returns
+    ///
+    /// This function returns `Ok(())` once the volume is restored, or a `KenkuError` if the crossfade failed or the volume could not be restored.
+    pub async fn cancel(self) -> Result<(), KenkuError> {
+        let _ = self.cancel.send(());
+
+        Crossfade::join(self.handle).await
+    }
 
-        Ok(response)
+    /// Waits for the task, resuming its panic if it panicked.
+    async fn join(
+        handle: tokio::task::JoinHandle<Result<(), KenkuError>>,
+    ) -> Result<(), KenkuError> {
+        match handle.await {
+            Ok(result) => result,
+            Err(error) => std::panic::resume_unwind(error.into_panic()),
+        }
     }
 }
 
+/// Crossfades the playlist playback to `track`.
+///
+/// The volume fades down to silence over the first half of `duration`, `track` starts playing,
+/// and the volume fades back up to where it was over the second half. The crossfade runs as a
+/// background task; use the returned `Crossfade` to wait for it or cancel it.
+///
+/// # Arguments
+///
+/// * `controller` - A reference to a `Controller` struct, which includes the HTTP client, the IP address and port of the server, and the current state of the server.
+/// * `track` - The `Track` to switch to.
+/// * `duration` - How long the whole crossfade lasts.
+///
+/// # Returns
+///
+/// This function returns a `Crossfade` handle to the running task.
+///
+/// # Panics
+///
+/// This function panics if it is called outside of a tokio runtime.
+#[cfg(feature = "crossfade")]
+pub fn crossfade_to(
+    controller: &Controller,
+    track: &Track,
+    duration: std::time::Duration,
+) -> Crossfade {
+    let controller = controller.clone();
+    let id = track.id.clone();
+    let (cancel, cancelled) = tokio::sync::oneshot::channel();
+
+    let handle = tokio::spawn(async move {
+        let start = controller.get_playlist_playback().await?.volume_level();
+        let half = duration / 2;
+        let steps = (half.as_millis() / CROSSFADE_STEP.as_millis()).max(1) as u32;
+
+        let fade = async {
+            playback::fade_volume_to(&controller, Volume::SILENT, half, steps).await?;
+            controller.play_track_id(&id).await?;
+            playback::fade_volume_to(&controller, start, half, steps).await?;
+
+            Ok(())
+        };
+
+        tokio::select! {
+            result = fade => result,
+            Ok(()) = cancelled => playback::playback_volume(&controller, start).await.map(|_| ()),
+        }
+    });
+
+    Crossfade { handle, cancel }
+}
+
 #[allow(unused)]
 pub mod playback {
 
     use super::{
-        json, playlist, process_url, Controller, KenkuCommand, KenkuPostCommand, KenkuPutCommand,
-        StatusCode,
+        json, playlist, Controller, KenkuError, KenkuPostCommand, KenkuPutCommand,
+        PlaylistPlaybackResponse, Repeat, StatusCode, Track, Volume,
     };
+    use std::time::Duration;
+
+    /// The longest `wait_for_track_end` waits between two checks of the playback.
+    const TRACK_END_POLL: Duration = Duration::from_millis(500);
+
+    /// The shortest `wait_for_track_end` waits between two checks of the playback.
+    const TRACK_END_MIN_POLL: Duration = Duration::from_millis(50);
+
+    /// How close to its end a track stopped by the remote counts as finished rather than paused.
+    const TRACK_END_TOLERANCE: u32 = 1000;
 
     /// Sends a request to the Kenku server to play the current track in the playlist.
     ///
@@ -158,13 +439,11 @@ pub mod playback {
     ///
     /// # Returns
     ///
-    /// This function returns a `Result` that contains a `StatusCode` if the request was sent successfully, or a `reqwest::Error` if the request failed.
-    pub async fn playback_play(controller: &Controller) -> Result<StatusCode, reqwest::Error> {
-        let command = &KenkuCommand::KenkuPut(KenkuPutCommand::PlaylistPlaybackPlay);
-        let url = process_url(command, controller.address);
-        let response = controller.client.put(url).send().await?.status();
-
-        Ok(response)
+    /// This function returns a `Result` that contains a `StatusCode` if the request was sent successfully, or a `KenkuError` if the request failed.
+    pub async fn playback_play(controller: &Controller) -> Result<StatusCode, KenkuError> {
+        controller
+            .send_put(KenkuPutCommand::PlaylistPlaybackPlay, None)
+            .await
     }
 
     /// Sends a request to the Kenku server to pause the current track in the playlist.
@@ -177,13 +456,32 @@ pub mod playback {
     ///
     /// # Returns
     ///
-    /// This function returns a `Result` that contains a `StatusCode` if the request was sent successfully, or a `reqwest::Error` if the request failed.
-    pub async fn playback_pause(controller: &Controller) -> Result<StatusCode, reqwest::Error> {
-        let command = &KenkuCommand::KenkuPut(KenkuPutCommand::PlaylistPlaybackPause);
-        let url = process_url(command, controller.address);
-        let response = controller.client.put(url).send().await?.status();
+    /// This function returns a `Result` that contains a `StatusCode` if the request was sent successfully, or a `KenkuError` if the request failed.
+    pub async fn playback_pause(controller: &Controller) -> Result<StatusCode, KenkuError> {
+        controller
+            .send_put(KenkuPutCommand::PlaylistPlaybackPause, None)
+            .await
+    }
+
+    /// Pauses the playlist if it is playing, and plays it otherwise, as a play/pause button does.
+    ///
+    /// # Arguments
+    ///
+    /// * `controller` - A reference to a `Controller` struct, which includes a HTTP client, the IP address and port of the server, and the current state of the server.
+    ///
+    /// # Returns
+    ///
+    /// This function returns `true` if the playlist is now playing, or a `KenkuError` if either request failed.
+    pub async fn toggle_play(controller: &Controller) -> Result<bool, KenkuError> {
+        let playing = !controller.get_playlist_playback().await?.playing;
 
-        Ok(response)
+        if playing {
+            playback_play(controller).await?;
+        } else {
+            playback_pause(controller).await?;
+        }
+
+        Ok(playing)
     }
 
     /// Sends a request to the Kenku server to play the next track in the playlist.
@@ -196,13 +494,11 @@ pub mod playback {
     ///
     /// # Returns
     ///
-    /// This function returns a `Result` that contains a `StatusCode` if the request was sent successfully, or a `reqwest::Error` if the request failed.
-    pub async fn playback_next(controller: &Controller) -> Result<StatusCode, reqwest::Error> {
-        let command = &KenkuCommand::KenkuPost(KenkuPostCommand::PlaylistPlaybackNext);
-        let url = process_url(command, controller.address);
-        let response = controller.client.post(url).send().await?.status();
-
-        Ok(response)
+    /// This function returns a `Result` that contains a `StatusCode` if the request was sent successfully, or a `KenkuError` if the request failed.
+    pub async fn playback_next(controller: &Controller) -> Result<StatusCode, KenkuError> {
+        controller
+            .send_post(KenkuPostCommand::PlaylistPlaybackNext)
+            .await
     }
 
     /// Sends a request to the Kenku server to play the previous track in the playlist.
@@ -215,13 +511,11 @@ pub mod playback {
     ///
     /// # Returns
     ///
-    /// This function returns a `Result` that contains a `StatusCode` if the request was sent successfully, or a `reqwest::Error` if the request failed.
-    pub async fn playback_previous(controller: &Controller) -> Result<StatusCode, reqwest::Error> {
-        let command = &KenkuCommand::KenkuPost(KenkuPostCommand::PlaylistPlaybackPrevious);
-        let url = process_url(command, controller.address);
-        let response = controller.client.post(url).send().await?.status();
-
-        Ok(response)
+    /// This function returns a `Result` that contains a `StatusCode` if the request was sent successfully, or a `KenkuError` if the request failed.
+    pub async fn playback_previous(controller: &Controller) -> Result<StatusCode, KenkuError> {
+        controller
+            .send_post(KenkuPostCommand::PlaylistPlaybackPrevious)
+            .await
     }
 
     /// Sends a PUT request to the Kenku server to mute or unmute the playlist.
@@ -235,58 +529,151 @@ pub mod playback {
     ///
     /// # Returns
     ///
-    /// This function returns a `Result` that contains a `StatusCode`, if the request was sent successfully, or a `reqwest::Error`, if the request failed.
+    /// This function returns a `Result` that contains a `StatusCode`, if the request was sent successfully, or a `KenkuError`, if the request failed.
     pub async fn playback_mute(
         controller: &Controller,
         mute: bool,
-    ) -> Result<StatusCode, reqwest::Error> {
-        let command = &KenkuCommand::KenkuPut(KenkuPutCommand::PlaylistPlaybackMute);
-        let url = process_url(command, controller.address);
-        let json = json!({"mute": mute});
+    ) -> Result<StatusCode, KenkuError> {
+        controller
+            .send_put(
+                KenkuPutCommand::PlaylistPlaybackMute,
+                Some(json!({"mute": mute})),
+            )
+            .await
+    }
 
-        let response = controller
-            .client
-            .put(url)
-            .header("content-type", "application/json")
-            .json(&json)
-            .send()
-            .await?
-            .status();
+    /// Mutes the playlist if it is unmuted, and unmutes it otherwise.
+    ///
+    /// # Arguments
+    ///
+    /// * `controller` - A reference to a `Controller` struct, which includes a HTTP client, the IP address and port of the server, and the current state of the server.
+    ///
+    /// # Returns
+    ///
+    /// This function returns `true` if the playlist is now muted, or a `KenkuError` if either request failed.
+    pub async fn toggle_mute(controller: &Controller) -> Result<bool, KenkuError> {
+        let muted = !controller.get_playlist_playback().await?.muted;
+        playback_mute(controller, muted).await?;
 
-        Ok(response)
+        Ok(muted)
     }
 
     /// Changes the volume of the playlist.
     ///
-    /// This function takes a `Controller` and a floating point number representing the desired volume level.
+    /// This function takes a `Controller` and a `Volume` representing the desired volume level.
     /// It sends a PUT request to the server to change the volume of the playlist.
     ///
     /// # Arguments
     ///
     /// * `controller` - A reference to a `Controller` struct, which includes the HTTP client, the IP address and port of the server, and the current state of the server.
-    /// * `volume` - A floating point number representing the desired volume level. The value should be between 0.0 and 1.0, where 0.0 is mute and 1.0 is the maximum volume.
+    /// * `volume` - The desired `Volume`, between 0.0 (silent) and 1.0 (full volume).
     ///
     /// # Returns
     ///
-    /// This function returns a `Result` with a `StatusCode`. If the PUT request is successful, it returns `Ok(StatusCode)`. If the PUT request fails, it returns `Err(reqwest::Error)`.
+    /// This function returns a `Result` with a `StatusCode`. If the PUT request is successful, it returns `Ok(StatusCode)`. If the PUT request fails, it returns `Err(KenkuError)`.
     pub async fn playback_volume(
         controller: &Controller,
-        volume: f64,
-    ) -> Result<StatusCode, reqwest::Error> {
-        let command = &KenkuCommand::KenkuPut(KenkuPutCommand::PlaylistPlaybackVolume);
-        let url = process_url(command, controller.address);
-        let json = json!({"volume": volume});
+        volume: Volume,
+    ) -> Result<StatusCode, KenkuError> {
+        controller
+            .send_put(
+                KenkuPutCommand::PlaylistPlaybackVolume,
+                Some(json!({"volume": volume})),
+            )
+            .await
+    }
+
+    /// Raises the volume of the playlist by `step`, stopping at full volume.
+    ///
+    /// # Arguments
+    ///
+    /// * `controller` - A reference to a `Controller` struct, which includes the HTTP client, the IP address and port of the server, and the current state of the server.
+    /// * `step` - How much to raise the volume, such as `Volume::from_percent(10)`.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the `Volume` that was set, or a `KenkuError` if either request failed.
+    pub async fn volume_up(controller: &Controller, step: Volume) -> Result<Volume, KenkuError> {
+        step_volume(controller, step.value()).await
+    }
+
+    /// Lowers the volume of the playlist by `step`, stopping at silence.
+    ///
+    /// # Arguments
+    ///
+    /// * `controller` - A reference to a `Controller` struct, which includes the HTTP client, the IP address and port of the server, and the current state of the server.
+    /// * `step` - How much to lower the volume, such as `Volume::from_percent(10)`.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the `Volume` that was set, or a `KenkuError` if either request failed.
+    pub async fn volume_down(controller: &Controller, step: Volume) -> Result<Volume, KenkuError> {
+        step_volume(controller, -step.value()).await
+    }
+
+    /// Changes the volume of the playlist by `delta`, clamped to the valid range.
+    async fn step_volume(controller: &Controller, delta: f64) -> Result<Volume, KenkuError> {
+        let current = controller.get_playlist_playback().await?.volume_level();
+        let volume = Volume::clamped(current.value() + delta);
+        playback_volume(controller, volume).await?;
+
+        Ok(volume)
+    }
+
+    /// Ramps the volume of the playlist to `target` over `duration`.
+    ///
+    /// The volume is read once, then changed in `steps` evenly spaced increments, the last one
+    /// landing exactly on `target`. Use `restore_volume` with the returned level to undo the fade.
+    ///
+    /// # Arguments
+    ///
+    /// * `controller` - A reference to a `Controller` struct, which includes the HTTP client, the IP address and port of the server, and the current state of the server.
+    /// * `target` - The `Volume` to end the fade at.
+    /// * `duration` - How long the fade lasts.
+    /// * `steps` - How many volume changes to send. Zero is treated as one, which jumps straight to `target` after `duration`.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the `Volume` the playlist had before the fade, or a `KenkuError` if any request failed. A failed step stops the fade where it is.
+    pub async fn fade_volume_to(
+        controller: &Controller,
+        target: Volume,
+        duration: Duration,
+        steps: u32,
+    ) -> Result<Volume, KenkuError> {
+        let start = controller.get_playlist_playback().await?.volume_level();
+        let steps = steps.max(1);
+        let interval = duration / steps;
+
+        for step in 1..=steps {
+            tokio::time::sleep(interval).await;
+
+            let level =
+                start.value() + (target.value() - start.value()) * step as f64 / steps as f64;
+            let level = if step == steps {
+                target
+            } else {
+                Volume::clamped(level)
+            };
 
-        let response = controller
-            .client
-            .put(url)
-            .header("content-type", "application/json")
-            .json(&json)
-            .send()
-            .await?
-            .status();
+            playback_volume(controller, level).await?;
+        }
 
-        Ok(response)
+        Ok(start)
+    }
+
+    /// Ramps the volume of the playlist back to `previous`, the level returned by `fade_volume_to`.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the `Volume` the playlist had before the restore, or a `KenkuError` if any request failed.
+    pub async fn restore_volume(
+        controller: &Controller,
+        previous: Volume,
+        duration: Duration,
+        steps: u32,
+    ) -> Result<Volume, KenkuError> {
+        fade_volume_to(controller, previous, duration, steps).await
     }
 
     /// Changes the shuffle state of the playlist.
@@ -301,25 +688,17 @@ pub mod playback {
     ///
     /// # Returns
     ///
-    /// This function returns a `Result` with a `StatusCode`. If the PUT request is successful, it returns `Ok(StatusCode)`. If the PUT request fails, it returns `Err(reqwest::Error)`.
+    /// This function returns a `Result` with a `StatusCode`. If the PUT request is successful, it returns `Ok(StatusCode)`. If the PUT request fails, it returns `Err(KenkuError)`.
     pub async fn playback_shuffle(
         controller: &Controller,
         shuffle: bool,
-    ) -> Result<StatusCode, reqwest::Error> {
-        let command = &KenkuCommand::KenkuPut(KenkuPutCommand::PlaylistPlaybackShuffle);
-        let url = process_url(command, controller.address);
-        let json = json!({"shuffle": shuffle});
-
-        let response = controller
-            .client
-            .put(url)
-            .header("content-type", "application/json")
-            .json(&json)
-            .send()
-            .await?
-            .status();
-
-        Ok(response)
+    ) -> Result<StatusCode, KenkuError> {
+        controller
+            .send_put(
+                KenkuPutCommand::PlaylistPlaybackShuffle,
+                Some(json!({"shuffle": shuffle})),
+            )
+            .await
     }
 
     /// Sends a PUT request to the Kenku server to set the repeat mode of the playlist.
@@ -333,24 +712,271 @@ pub mod playback {
     ///
     /// # Returns
     ///
-    /// This function returns a `Result` that contains a `StatusCode`, if the request was sent successfully, or a `reqwest::Error`, if the request failed.
+    /// This function returns a `Result` that contains a `StatusCode`, if the request was sent successfully, or a `KenkuError`, if the request failed.
     pub async fn playback_repeat(
         controller: &Controller,
         repeat: playlist::Repeat,
-    ) -> Result<StatusCode, reqwest::Error> {
-        let command = &KenkuCommand::KenkuPut(KenkuPutCommand::PlaylistPlaybackRepeat);
-        let url = process_url(command, controller.address);
-        let json = json!({"repeat": repeat});
-
-        let response = controller
-            .client
-            .put(url)
-            .header("content-type", "application/json")
-            .json(&json)
-            .send()
-            .await?
-            .status();
-
-        Ok(response)
+    ) -> Result<StatusCode, KenkuError> {
+        controller
+            .send_put(
+                KenkuPutCommand::PlaylistPlaybackRepeat,
+                Some(json!({"repeat": repeat})),
+            )
+            .await
+    }
+
+    /// Advances the repeat mode of the playlist to the next one, as a repeat button does.
+    ///
+    /// The current mode is read, then the one given by `Repeat::next` is set.
+    ///
+    /// # Arguments
+    ///
+    /// * `controller` - A reference to a `Controller` struct, which includes a HTTP client, the IP address and port of the server, and the current state of the server.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the `Repeat` mode that was set, or a `KenkuError` if either request failed.
+    pub async fn cycle_repeat(controller: &Controller) -> Result<Repeat, KenkuError> {
+        let repeat = controller.get_playlist_playback().await?.repeat.next();
+        playback_repeat(controller, repeat.clone()).await?;
+
+        Ok(repeat)
+    }
+
+    /// Waits until the track playing now finishes or is changed.
+    ///
+    /// The playback is polled, more often as the track nears its end, until it shows another
+    /// track, no track, the same track started over, or the playlist stopped at the end of the
+    /// track. Pausing the track halfway does not end the wait.
+    ///
+    /// # Arguments
+    ///
+    /// * `controller` - A reference to a `Controller` struct, which includes a HTTP client, the IP address and port of the server, and the current state of the server.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the `Track` that ended, `None` right away if no track was playing, or a `KenkuError` if the playback could not be fetched.
+    pub async fn wait_for_track_end(controller: &Controller) -> Result<Option<Track>, KenkuError> {
+        let mut last = controller.get_playlist_playback().await?;
+        let Some(track) = last.track.clone().filter(|_| last.playing) else {
+            return Ok(None);
+        };
+
+        loop {
+            tokio::time::sleep(next_check(&last)).await;
+
+            let current = controller.get_playlist_playback().await?;
+            if moved_on(&last, &current, &track.id) {
+                return Ok(Some(track));
+            }
+
+            last = current;
+        }
+    }
+
+    /// Returns how long to wait before checking `playback` again: until the end of the track,
+    /// within the poll bounds.
+    fn next_check(playback: &PlaylistPlaybackResponse) -> Duration {
+        let remaining = playback
+            .track
+            .as_ref()
+            .filter(|_| playback.playing)
+            .and_then(|track| Some(track.duration?.saturating_sub(track.progress?)));
+
+        match remaining {
+            Some(remaining) => {
+                Duration::from_millis(remaining.into()).clamp(TRACK_END_MIN_POLL, TRACK_END_POLL)
+            }
+            None => TRACK_END_POLL,
+        }
+    }
+
+    /// Returns `true` when the track with the id `id`, shown by `previous`, is over in `current`.
+    pub(crate) fn moved_on(
+        previous: &PlaylistPlaybackResponse,
+        current: &PlaylistPlaybackResponse,
+        id: &str,
+    ) -> bool {
+        let Some(now) = current.track.as_ref().filter(|now| now.id == id) else {
+            return true;
+        };
+        let before = previous.track.as_ref().and_then(|track| track.progress);
+
+        // A progress that went back means a restart; a missing one tells nothing either way.
+        if matches!((now.progress, before), (Some(now), Some(before)) if now < before) {
+            return true;
+        }
+
+        !current.playing
+            && matches!(
+                (now.duration, now.progress),
+                (Some(duration), Some(progress)) if duration.saturating_sub(progress) <= TRACK_END_TOLERANCE
+            )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{playback, PlaylistGetResponse, PlaylistPlaybackResponse, Repeat, Track};
+    use crate::testing;
+    use serde_json::json;
+    use std::time::Duration;
+
+    fn response(track: &str, playing: bool, progress: u32) -> PlaylistPlaybackResponse {
+        let track = Track {
+            duration: Some(60_000),
+            progress: Some(progress),
+            ..testing::track(track)
+        };
+
+        PlaylistPlaybackResponse {
+            playing,
+            ..testing::playback(Some(track))
+        }
+    }
+
+    #[test]
+    fn repeat_modes_cycle() {
+        assert_eq!(Repeat::Track.next(), Repeat::Playlist);
+        assert_eq!(Repeat::Playlist.next(), Repeat::Off);
+        assert_eq!(Repeat::Off.next(), Repeat::Track);
+    }
+
+    #[test]
+    fn track_times_are_durations() {
+        let track = response("t1", true, 15_000).track.unwrap();
+
+        assert_eq!(track.duration_time(), Some(Duration::from_secs(60)));
+        assert_eq!(track.progress_time(), Some(Duration::from_secs(15)));
+        assert_eq!(track.percent_complete(), Some(25.0));
+
+        let unknown = Track {
+            duration: Some(0),
+            ..track
+        };
+        assert_eq!(unknown.percent_complete(), None);
+    }
+
+    #[test]
+    fn tracks_end_when_changed_restarted_or_stopped_at_the_end() {
+        let before = response("t1", true, 30_000);
+
+        assert!(!playback::moved_on(
+            &before,
+            &response("t1", true, 31_000),
+            "t1"
+        ));
+        assert!(!playback::moved_on(
+            &before,
+            &response("t1", false, 31_000),
+            "t1"
+        ));
+        assert!(playback::moved_on(&before, &response("t2", true, 0), "t1"));
+        assert!(playback::moved_on(
+            &before,
+            &response("t1", true, 100),
+            "t1"
+        ));
+        assert!(playback::moved_on(
+            &before,
+            &response("t1", false, 60_000),
+            "t1"
+        ));
+
+        let unknown = PlaylistPlaybackResponse {
+            track: Some(Track {
+                progress: None,
+                ..testing::track("t1")
+            }),
+            ..before.clone()
+        };
+        assert!(!playback::moved_on(&before, &unknown, "t1"));
+        assert!(!playback::moved_on(
+            &unknown,
+            &response("t1", true, 100),
+            "t1"
+        ));
+    }
+
+    #[test]
+    fn lookups_join_playlists_and_tracks() {
+        let response: PlaylistGetResponse = serde_json::from_value(json!({
+            "playlists": [{"id": "p1", "title": "Town", "tracks": ["t2", "gone", "t1"]}],
+            "tracks": [
+                {"id": "t1", "url": "", "title": "Market"},
+                {"id": "t2", "url": "", "title": "Tavern"}
+            ]
+        }))
+        .unwrap();
+
+        let playlist = response.playlist_by_id("p1").unwrap();
+        let tracks: Vec<&str> = response
+            .tracks_of_playlist(playlist)
+            .iter()
+            .map(|track| track.title.as_str())
+            .collect();
+
+        assert_eq!(tracks, ["Tavern", "Market"]);
+        assert_eq!(response.track_by_title("tavern").unwrap().id, "t2");
+        assert!(response.playlist_by_title("Dungeon").is_none());
+    }
+
+    #[test]
+    fn library_is_listed_once_in_playlist_order() {
+        let response: PlaylistGetResponse = serde_json::from_value(json!({
+            "playlists": [
+                {"id": "p1", "title": "Town", "tracks": ["t2", "t1", "t2"]},
+                {"id": "p2", "title": "Road", "tracks": ["t3", "t1"]}
+            ],
+            "tracks": [
+                {"id": "t1", "url": "", "title": "Market"},
+                {"id": "t2", "url": "", "title": "Tavern"},
+                {"id": "t3", "url": "", "title": "Wagon"},
+                {"id": "t4", "url": "", "title": "Loose"}
+            ]
+        }))
+        .unwrap();
+
+        let entries: Vec<(&str, &str)> = response
+            .iter_in_playlist_order()
+            .map(|(playlist, track)| (playlist.id.as_str(), track.id.as_str()))
+            .collect();
+        assert_eq!(
+            entries,
+            [
+                ("p1", "t2"),
+                ("p1", "t1"),
+                ("p1", "t2"),
+                ("p2", "t3"),
+                ("p2", "t1")
+            ]
+        );
+
+        let library: Vec<(&str, Vec<&str>)> = response
+            .library_in_playlist_order()
+            .iter()
+            .map(|entry| {
+                (
+                    entry.track.id.as_str(),
+                    entry
+                        .playlists
+                        .iter()
+                        .map(|playlist| playlist.id.as_str())
+                        .collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            library,
+            [
+                ("t2", vec!["p1"]),
+                ("t1", vec!["p1", "p2"]),
+                ("t3", vec!["p2"]),
+                ("t4", vec![]),
+            ]
+        );
+
+        let market = response.track_by_id("t1").unwrap();
+        assert_eq!(response.playlists_containing(market).len(), 2);
     }
 }
@@ -0,0 +1,30 @@
+//! Re-exports the types most applications need.
+//!
+//! ```
+//! use kenku_control::prelude::*;
+//! ```
+#[cfg(feature = "search")]
+pub use crate::aliases::Aliases;
+#[cfg(feature = "capability")]
+pub use crate::capability::Feature;
+#[cfg(feature = "events")]
+pub use crate::events::KenkuEvent;
+pub use crate::playlist::{
+    playback, Playlist, PlaylistGetResponse, PlaylistPlaybackResponse, Repeat, Track,
+};
+#[cfg(feature = "search")]
+pub use crate::search::{
+    find_sound, find_sound_with_aliases, find_track, find_track_with_aliases, SearchMatch,
+};
+#[cfg(feature = "snapshot")]
+pub use crate::snapshot::{KenkuSnapshot, PlaybackSnapshot};
+pub use crate::soundboard::{
+    SoundboardGetResponse, SoundboardPlaybackResponse, Soundboards, Sounds,
+};
+#[cfg(not(target_arch = "wasm32"))]
+pub use crate::KenkuApi;
+#[cfg(not(target_arch = "wasm32"))]
+pub use crate::ReconnectPolicy;
+pub use crate::{
+    Controller, ControllerBuilder, KenkuAddress, KenkuError, KenkuState, RetryPolicy, Volume,
+};
@@ -0,0 +1,177 @@
+//! Smooth progress values between polls, for progress bars.
+//!
+//! The Kenku Remote reports how far the track and each sound are when it is asked, so a progress
+//! bar fed straight from polls jumps once per poll. `ProgressTracker` keeps the last playback it
+//! was given with the time it was received, and moves the progress forward by the time elapsed
+//! since, as long as the playback was playing. A bar can then be redrawn every frame while the
+//! remote is only polled every second or so.
+use crate::{
+    playlist::PlaylistPlaybackResponse, snapshot::PlaybackSnapshot,
+    soundboard::SoundboardPlaybackResponse,
+};
+use std::time::{Duration, Instant};
+
+/// Interpolates the progress of the track and sounds between polls.
+///
+/// # Fields
+///
+/// * `playlist` - The last playlist playback given, with when it was received.
+/// * `soundboard` - The last soundboard playback given, with when it was received.
+#[derive(Debug, Clone, Default)]
+pub struct ProgressTracker {
+    playlist: Option<(PlaylistPlaybackResponse, Instant)>,
+    soundboard: Option<(SoundboardPlaybackResponse, Instant)>,
+}
+
+impl ProgressTracker {
+    /// Creates a tracker that has not been given any playback yet.
+    pub fn new() -> ProgressTracker {
+        ProgressTracker::default()
+    }
+
+    /// Takes `playback` as the playlist playback of right now.
+    pub fn update_playlist(&mut self, playback: &PlaylistPlaybackResponse) {
+        self.playlist = Some((playback.clone(), Instant::now()));
+    }
+
+    /// Takes `playback` as the soundboard playback of right now.
+    pub fn update_soundboard(&mut self, playback: &SoundboardPlaybackResponse) {
+        self.soundboard = Some((playback.clone(), Instant::now()));
+    }
+
+    /// Takes both playbacks of `snapshot` as those of right now.
+    pub fn update(&mut self, snapshot: &PlaybackSnapshot) {
+        self.update_playlist(&snapshot.playlist);
+        self.update_soundboard(&snapshot.soundboard);
+    }
+
+    /// Returns how far the current track is now.
+    ///
+    /// While the playlist plays, the progress last reported moves forward with the time elapsed
+    /// since, up to the duration of the track. While it is paused, it stays where it was.
+    ///
+    /// # Returns
+    ///
+    /// This function returns `None` if no playlist playback was given, no track is loaded, or the
+    /// remote did not report its progress.
+    pub fn current_progress(&self) -> Option<Duration> {
+        self.track_progress_at(Instant::now())
+    }
+
+    /// Returns how far the sound with the id `id` is now.
+    ///
+    /// The progress last reported moves forward with the time elapsed since. A looping sound
+    /// starts over once it reaches its duration; other sounds stop at it.
+    ///
+    /// # Returns
+    ///
+    /// This function returns `None` if no soundboard playback was given, the sound was not
+    /// playing, or the remote did not report its progress.
+    pub fn sound_progress(&self, id: &str) -> Option<Duration> {
+        self.sound_progress_at(id, Instant::now())
+    }
+
+    fn track_progress_at(&self, now: Instant) -> Option<Duration> {
+        let (playback, received) = self.playlist.as_ref()?;
+        let track = playback.track.as_ref()?;
+        let progress = track.progress_time()?;
+
+        if !playback.playing {
+            return Some(progress);
+        }
+
+        let progress = progress + now.saturating_duration_since(*received);
+
+        Some(match track.duration_time() {
+            Some(duration) => progress.min(duration),
+            None => progress,
+        })
+    }
+
+    fn sound_progress_at(&self, id: &str, now: Instant) -> Option<Duration> {
+        let (playback, received) = self.soundboard.as_ref()?;
+        let sound = playback.sounds.iter().find(|sound| sound.id == id)?;
+        let progress = sound.progress_time()? + now.saturating_duration_since(*received);
+
+        Some(match sound.duration_time() {
+            Some(duration) if !duration.is_zero() && sound._loop => {
+                Duration::from_micros((progress.as_micros() % duration.as_micros()) as u64)
+            }
+            Some(duration) => progress.min(duration),
+            None => progress,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ProgressTracker;
+    use crate::{
+        playlist::{PlaylistPlaybackResponse, Track},
+        soundboard::{SoundboardPlaybackResponse, Sounds},
+        testing,
+    };
+    use std::time::{Duration, Instant};
+
+    fn tracker(playing: bool) -> ProgressTracker {
+        let track = Track {
+            title: "Tavern".to_string(),
+            duration: Some(10_000),
+            progress: Some(4_000),
+            ..testing::track("t1")
+        };
+        let sound = |id: &str, title: &str, looping: bool| Sounds {
+            title: title.to_string(),
+            _loop: looping,
+            duration: Some(3_000),
+            progress: Some(2_500.0),
+            ..testing::sound(id)
+        };
+
+        let mut tracker = ProgressTracker::new();
+        tracker.update_playlist(&PlaylistPlaybackResponse {
+            playing,
+            ..testing::playback(Some(track))
+        });
+        tracker.update_soundboard(&SoundboardPlaybackResponse {
+            sounds: vec![sound("s1", "Rain", true), sound("s2", "Thunder", false)],
+        });
+        tracker
+    }
+
+    #[test]
+    fn track_progress_moves_while_playing() {
+        let playing = tracker(true);
+        let later = playing.playlist.as_ref().unwrap().1 + Duration::from_secs(2);
+
+        assert_eq!(
+            playing.track_progress_at(later),
+            Some(Duration::from_secs(6))
+        );
+        assert_eq!(
+            playing.track_progress_at(later + Duration::from_secs(60)),
+            Some(Duration::from_secs(10))
+        );
+        assert_eq!(
+            tracker(false).track_progress_at(Instant::now() + Duration::from_secs(2)),
+            Some(Duration::from_secs(4))
+        );
+        assert_eq!(ProgressTracker::new().current_progress(), None);
+    }
+
+    #[test]
+    fn sound_progress_loops_or_stops_at_the_end() {
+        let tracker = tracker(true);
+        let later = tracker.soundboard.as_ref().unwrap().1 + Duration::from_secs(1);
+
+        assert_eq!(
+            tracker.sound_progress_at("s1", later),
+            Some(Duration::from_millis(500))
+        );
+        assert_eq!(
+            tracker.sound_progress_at("s2", later),
+            Some(Duration::from_secs(3))
+        );
+        assert_eq!(tracker.sound_progress_at("s3", later), None);
+    }
+}
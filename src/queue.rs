@@ -0,0 +1,138 @@
+//! Sending commands one after the other.
+//!
+//! A burst of hotkey presses sent straight to the Kenku Remote can arrive out of order or trip
+//! over each other. A `CommandQueue` sends its commands sequentially, in the order they were
+//! pushed, leaving a configurable gap between two of them.
+use crate::{Controller, KenkuCommandWithPayload, KenkuError};
+use reqwest::StatusCode;
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::{
+    sync::{mpsc, oneshot},
+    time::Instant,
+};
+
+type Sent = oneshot::Sender<Result<StatusCode, KenkuError>>;
+
+/// Sends commands through a `Controller` one at a time, in the order they were pushed.
+///
+/// The commands are sent by a background task. Dropping the queue lets that task send the
+/// commands already pushed and then stop.
+#[derive(Debug)]
+pub struct CommandQueue {
+    sender: mpsc::UnboundedSender<(KenkuCommandWithPayload, Sent)>,
+    pending: Arc<AtomicUsize>,
+}
+
+/// A handle to the result of a queued command.
+#[derive(Debug)]
+pub struct QueuedCommand {
+    receiver: oneshot::Receiver<Result<StatusCode, KenkuError>>,
+}
+
+impl CommandQueue {
+    /// Creates a `CommandQueue` sending its commands through `controller`.
+    ///
+    /// # Arguments
+    ///
+    /// * `controller` - The `Controller` the commands are sent with.
+    /// * `spacing` - The minimum time between the end of a command and the start of the next one.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if it is called outside of a tokio runtime.
+    pub fn new(controller: &Controller, spacing: Duration) -> CommandQueue {
+        let controller = controller.clone();
+        let pending = Arc::new(AtomicUsize::new(0));
+        let (sender, mut receiver) = mpsc::unbounded_channel::<(KenkuCommandWithPayload, Sent)>();
+        let worker_pending = pending.clone();
+
+        tokio::spawn(async move {
+            let mut ready_at = Instant::now();
+
+            while let Some((command, sent)) = receiver.recv().await {
+                tokio::time::sleep_until(ready_at).await;
+
+                let result = controller.execute(&command).await;
+                worker_pending.fetch_sub(1, Ordering::SeqCst);
+                let _ = sent.send(result);
+
+                ready_at = Instant::now() + spacing;
+            }
+        });
+
+        CommandQueue { sender, pending }
+    }
+
+    /// Adds `command` to the end of the queue.
+    ///
+    /// # Returns
+    ///
+    /// This function returns a `QueuedCommand` to wait for the result of the command with.
+    pub fn push(&self, command: impl Into<KenkuCommandWithPayload>) -> QueuedCommand {
+        let (sent, receiver) = oneshot::channel();
+
+        self.pending.fetch_add(1, Ordering::SeqCst);
+
+        if self.sender.send((command.into(), sent)).is_err() {
+            self.pending.fetch_sub(1, Ordering::SeqCst);
+        }
+
+        QueuedCommand { receiver }
+    }
+
+    /// Adds every command of `commands` to the end of the queue, in order.
+    pub fn push_all<C: Into<KenkuCommandWithPayload>>(
+        &self,
+        commands: impl IntoIterator<Item = C>,
+    ) -> Vec<QueuedCommand> {
+        commands
+            .into_iter()
+            .map(|command| self.push(command))
+            .collect()
+    }
+
+    /// Returns how many commands are waiting or being sent.
+    pub fn len(&self) -> usize {
+        self.pending.load(Ordering::SeqCst)
+    }
+
+    /// Returns `true` when no command is waiting or being sent.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl QueuedCommand {
+    /// Waits until the command was sent.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the `StatusCode` of the response or the `KenkuError` of the command. A `KenkuError::QueueClosed` is returned if the queue stopped before sending it, which only happens when the runtime shuts down.
+    pub async fn result(self) -> Result<StatusCode, KenkuError> {
+        self.receiver.await.unwrap_or(Err(KenkuError::QueueClosed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::QueuedCommand;
+    use crate::KenkuError;
+    use tokio::sync::oneshot;
+
+    #[tokio::test]
+    async fn commands_dropped_by_the_queue_report_it_closed() {
+        let (sender, receiver) = oneshot::channel();
+        drop(sender);
+
+        assert!(matches!(
+            QueuedCommand { receiver }.result().await,
+            Err(KenkuError::QueueClosed)
+        ));
+    }
+}
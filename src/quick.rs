@@ -0,0 +1,128 @@
+//! One-line helpers for small scripts.
+//!
+//! Every function in this module builds its own `Controller` from an address string such as
+//! `"127.0.0.1:3333"` or `"kenku-host.local:3333"`, looks up what it needs and issues a single command. For anything
+//! long-running, build a `Controller` once and use the rest of the API instead.
+use super::*;
+use reqwest::StatusCode;
+use std::str::FromStr;
+
+/// Builds a `Controller` from a `host:port` string.
+fn connect(address: &str) -> Result<Controller, KenkuError> {
+    Ok(Controller::from_address(KenkuAddress::from_str(address)?))
+}
+
+/// Returns the item whose title best matches `title`, as ranked by `search::rank`.
+fn find_by_title<'a, T>(items: &'a [T], title: &str, get_title: fn(&T) -> &str) -> Option<&'a T> {
+    search::rank(items, title, get_title)
+        .into_iter()
+        .next()
+        .map(|found| found.item)
+}
+
+/// Plays the track matching `title`.
+///
+/// # Arguments
+///
+/// * `address` - The `host:port` of the Kenku Remote, e.g. `"127.0.0.1:3333"`.
+/// * `title` - The title, or part of the title, of the track to play.
+///
+/// # Returns
+///
+/// This function returns a `Result` that contains the `StatusCode` of the play request, or a `KenkuError` if the address is invalid, no track matched or the request failed.
+pub async fn play(address: &str, title: &str) -> Result<StatusCode, KenkuError> {
+    let controller = connect(address)?;
+    let playlists = controller.get_playlist().await?;
+    let track = find_by_title(&playlists.tracks, title, |track| &track.title)
+        .ok_or_else(|| KenkuError::NotFound(format!("\"{}\"", title)))?;
+
+    track.play(&controller).await
+}
+
+/// Plays the soundboard sound matching `title`.
+///
+/// # Arguments
+///
+/// * `address` - The `host:port` of the Kenku Remote, e.g. `"127.0.0.1:3333"`.
+/// * `title` - The title, or part of the title, of the sound to play.
+///
+/// # Returns
+///
+/// This function returns a `Result` that contains the `StatusCode` of the play request, or a `KenkuError` if the address is invalid, no sound matched or the request failed.
+pub async fn play_sound(address: &str, title: &str) -> Result<StatusCode, KenkuError> {
+    let controller = connect(address)?;
+    let soundboards = controller.get_soundboard().await?;
+    let sound = find_by_title(&soundboards.sounds, title, |sound| &sound.title)
+        .ok_or_else(|| KenkuError::NotFound(format!("\"{}\"", title)))?;
+
+    sound.play(&controller).await
+}
+
+/// Stops the soundboard sound matching `title`.
+///
+/// # Arguments
+///
+/// * `address` - The `host:port` of the Kenku Remote, e.g. `"127.0.0.1:3333"`.
+/// * `title` - The title, or part of the title, of the sound to stop.
+///
+/// # Returns
+///
+/// This function returns a `Result` that contains the `StatusCode` of the stop request, or a `KenkuError` if the address is invalid, no sound matched or the request failed.
+pub async fn stop_sound(address: &str, title: &str) -> Result<StatusCode, KenkuError> {
+    let controller = connect(address)?;
+    let soundboards = controller.get_soundboard().await?;
+    let sound = find_by_title(&soundboards.sounds, title, |sound| &sound.title)
+        .ok_or_else(|| KenkuError::NotFound(format!("\"{}\"", title)))?;
+
+    sound.stop(&controller).await
+}
+
+/// Resumes the playlist playback.
+///
+/// # Arguments
+///
+/// * `address` - The `host:port` of the Kenku Remote, e.g. `"127.0.0.1:3333"`.
+pub async fn resume(address: &str) -> Result<StatusCode, KenkuError> {
+    let controller = connect(address)?;
+
+    playlist::playback::playback_play(&controller).await
+}
+
+/// Pauses the playlist playback.
+///
+/// # Arguments
+///
+/// * `address` - The `host:port` of the Kenku Remote, e.g. `"127.0.0.1:3333"`.
+pub async fn pause(address: &str) -> Result<StatusCode, KenkuError> {
+    let controller = connect(address)?;
+
+    playlist::playback::playback_pause(&controller).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{connect, find_by_title, KenkuError};
+
+    #[test]
+    fn exact_title_wins_over_partial_match() {
+        let titles = ["Tavern Brawl", "tavern"];
+        let found = find_by_title(&titles, "Tavern", |title| *title);
+
+        assert_eq!(found, Some(&"tavern"));
+    }
+
+    #[test]
+    fn partial_title_matches_case_insensitively() {
+        let titles = ["Forest Night", "Busy Tavern"];
+        let found = find_by_title(&titles, "TAVERN", |title| *title);
+
+        assert_eq!(found, Some(&"Busy Tavern"));
+    }
+
+    #[test]
+    fn invalid_address_is_rejected() {
+        let result = connect("not an address");
+
+        assert!(matches!(result, Err(KenkuError::InvalidInput(_))));
+    }
+}
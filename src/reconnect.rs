@@ -0,0 +1,122 @@
+//! Waiting for an offline Kenku Remote to come back.
+use crate::KenkuAddress;
+use std::time::{Duration, Instant};
+
+/// Represents how a `Controller` waits for the Kenku Remote after a connection error.
+///
+/// When a request fails because the remote cannot be reached, the controller probes it by
+/// opening a TCP connection, sleeping between probes with an exponential backoff, and resends
+/// the request once the remote is online again. Probes never block the runtime, and each one
+/// gives up after `max_backoff` or the part of `max_wait` still left, whichever is shorter.
+///
+/// Waiting needs a tokio runtime with its timer, so this module is not compiled on `wasm32`.
+///
+/// # Fields
+///
+/// * `initial_backoff` - The delay before the first probe.
+/// * `max_backoff` - The upper bound of the delay, which doubles after every failed probe.
+/// * `max_wait` - How long to wait in total before giving up and returning the connection error. `None` waits forever.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReconnectPolicy {
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub max_wait: Option<Duration>,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        ReconnectPolicy {
+            initial_backoff: Duration::from_millis(250),
+            max_backoff: Duration::from_secs(10),
+            max_wait: Some(Duration::from_secs(60)),
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Waits until the Kenku Remote at `address` accepts connections again.
+    ///
+    /// # Returns
+    ///
+    /// This function returns `true` once the remote is online, or `false` when `max_wait` elapsed first.
+    pub async fn wait_until_online(&self, address: &KenkuAddress) -> bool {
+        let started = Instant::now();
+        let mut backoff = self.initial_backoff;
+
+        loop {
+            if let Some(max_wait) = self.max_wait {
+                let elapsed = started.elapsed();
+
+                if elapsed >= max_wait {
+                    return false;
+                }
+
+                backoff = backoff.min(max_wait - elapsed);
+            }
+
+            tokio::time::sleep(backoff).await;
+
+            let timeout = match self.max_wait {
+                Some(max_wait) => self
+                    .max_backoff
+                    .min(max_wait.saturating_sub(started.elapsed())),
+                None => self.max_backoff,
+            };
+
+            if probe(address, timeout).await {
+                return true;
+            }
+
+            backoff = backoff.saturating_mul(2).min(self.max_backoff);
+        }
+    }
+}
+
+/// Returns `true` if the remote at `address` accepts a TCP connection within `timeout`.
+///
+/// Names are resolved and connected to without blocking the runtime.
+async fn probe(address: &KenkuAddress, timeout: Duration) -> bool {
+    let connect = tokio::net::TcpStream::connect((address.host.as_str(), address.port));
+
+    matches!(tokio::time::timeout(timeout, connect).await, Ok(Ok(_)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ReconnectPolicy;
+    use crate::KenkuAddress;
+    use std::{net::TcpListener, time::Duration};
+
+    fn fast_policy(max_wait: Duration) -> ReconnectPolicy {
+        ReconnectPolicy {
+            initial_backoff: Duration::from_millis(5),
+            max_backoff: Duration::from_millis(20),
+            max_wait: Some(max_wait),
+        }
+    }
+
+    #[tokio::test]
+    async fn returns_once_the_remote_is_listening() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = KenkuAddress::from(listener.local_addr().unwrap());
+
+        assert!(
+            fast_policy(Duration::from_secs(1))
+                .wait_until_online(&address)
+                .await
+        );
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_wait() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = KenkuAddress::from(listener.local_addr().unwrap());
+        drop(listener);
+
+        assert!(
+            !fast_policy(Duration::from_millis(50))
+                .wait_until_online(&address)
+                .await
+        );
+    }
+}
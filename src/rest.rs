@@ -0,0 +1,692 @@
+//! An HTTP facade over the controller, for exposing Kenku FM beyond localhost.
+//!
+//! Kenku Remote only listens on the machine running Kenku FM and has no authentication. `RestApi`
+//! serves the same data through axum, behind an optional `auth::Authenticator`, and adds the
+//! features of this crate on top:
+//!
+//! | Route                            | Effect                                                   |
+//! | -------------------------------- | -------------------------------------------------------- |
+//! | `GET /playlist`                  | The playlists and tracks                                 |
+//! | `GET /playlist/playback`         | The playlist playback                                    |
+//! | `GET /soundboard`                | The soundboards and sounds                               |
+//! | `GET /soundboard/playback`       | The sounds playing                                       |
+//! | `POST /commands`                 | Sends a JSON `KenkuCommandWithPayload`, or an array of them (admins) |
+//! | `GET /snapshot`                  | The current `PlaybackSnapshot`                           |
+//! | `PUT /snapshot`                  | Restores a `PlaybackSnapshot` (admins)                   |
+//! | `GET /scenes`                    | The names of the scenes                                  |
+//! | `POST /scenes/{name}`            | Activates a scene (admins)                               |
+//! | `GET /search/tracks?q=QUERY`     | The tracks matching `QUERY`, best match first            |
+//! | `GET /search/sounds?q=QUERY`     | The sounds matching `QUERY`, best match first            |
+//! | `GET /rooms`                     | The snapshot of every federated room, or its `{"error": "..."}` |
+//! | `POST /rooms/{room}/commands`    | Forwards commands to the gateway of a room (admins)      |
+//! | `POST /rooms/{room}/scenes/{name}` | Activates a scene of the gateway of a room (admins)    |
+//! | `GET /admin/clients`             | The `clients::ClientStats` of every client (moderators)  |
+//! | `PUT /admin/bans/{name}`         | Bans a client for `{"seconds": 600}` (moderators)        |
+//! | `DELETE /admin/bans/{name}`      | Lifts the ban of a client (moderators)                   |
+//! | `GET /admin/automation`          | Whether automation is paused, `{"paused": true}` (moderators) |
+//! | `PUT /admin/automation`          | Pauses the automated actions of the controller (moderators) |
+//! | `DELETE /admin/automation`       | Resumes the automated actions of the controller (moderators) |
+//! | `DELETE /admin/tokens/{name}`    | Revokes the credentials of a client (moderators)         |
+//! | `GET /metrics`                   | The counts of every client, for Prometheus (moderators)  |
+//! | `GET /admin/log-filter`          | The tracing filter in effect, `{"directives": "..."}` (moderators) |
+//! | `PUT /admin/log-filter`          | Changes the tracing filter to `{"directives": "..."}` (moderators) |
+//!
+//! Routes that send commands answer with `{"sent": 2, "failures": [...]}`, where each failure
+//! holds the command and its error. Errors answer with `{"error": "..."}` and a status that
+//! follows the `KenkuError`: 400 for invalid input, 404 for unknown names, 502 when the Kenku
+//! Remote failed and 504 when it timed out. With an authenticator set, requests it turns away
+//! are answered with 401, and the `auth::Identity` of the others is added to their extensions.
+//! The routes marked for admins answer 403 to identities without the `admin` flag.
+//!
+//! Every command is counted by a `clients::ClientTracker`, which answers 429 to the commands
+//! beyond its limit and 403 to every request of a banned client. The routes marked for
+//! moderators answer 403 to the clients the tracker does not list as moderators.
+//!
+//! `DELETE /admin/tokens/{name}` asks the authenticator to revoke the credentials of a client, as
+//! `auth::BearerTokens` does, and answers 404 when it has none to revoke. Clients already
+//! connected elsewhere, such as over a WebSocket, stay connected until they are banned.
+//!
+//! The `/admin/log-filter` routes are only served with the `log-filter` feature, and answer 404
+//! until `RestApi::log_filter` is set. The `/rooms` routes are only served with the `gateway`
+//! feature, and list no rooms until `RestApi::federation` is set.
+#[cfg(feature = "gateway")]
+use crate::gateway::{client::GatewayReport, federation::Federation};
+#[cfg(feature = "log-filter")]
+use crate::log_filter::LogFilter;
+use crate::{
+    auth::{Authenticator, BearerTokens, Identity},
+    batch::BatchReport,
+    clients::{ClientStats, ClientTracker, Refusal},
+    scenes::Scene,
+    search::{find_sound, find_track},
+    snapshot::PlaybackSnapshot,
+    Controller, KenkuCommandWithPayload, KenkuError,
+};
+use axum::{
+    extract::{rejection::JsonRejection, ConnectInfo, Extension, Path, Query, Request, State},
+    http::{header, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{delete, get, post, put},
+    Json, Router,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{collections::BTreeMap, fmt, net::SocketAddr, sync::Arc};
+use tokio::{
+    net::{TcpListener, ToSocketAddrs},
+    task::JoinHandle,
+};
+
+/// Builds the HTTP facade of a controller.
+///
+/// # Fields
+///
+/// * `controller` - The controller the requests are forwarded to.
+/// * `auth` - Who may send requests, or `None` to let everyone in.
+/// * `scenes` - The scenes served under `/scenes`, by name.
+/// * `clients` - The tracker counting, limiting and banning the clients.
+/// * `log_filter` - The tracing filter served under `/admin/log-filter`, if any.
+/// * `federation` - The gateways of the rooms served under `/rooms`.
+#[derive(Clone)]
+pub struct RestApi {
+    controller: Controller,
+    auth: Option<Arc<dyn Authenticator>>,
+    scenes: BTreeMap<String, Scene>,
+    clients: ClientTracker,
+    #[cfg(feature = "log-filter")]
+    log_filter: Option<LogFilter>,
+    #[cfg(feature = "gateway")]
+    federation: Federation,
+}
+
+/// A running `RestApi`. It stops when dropped.
+#[derive(Debug)]
+pub struct RestServer {
+    address: SocketAddr,
+    handle: JoinHandle<()>,
+}
+
+/// The answer to a failed request.
+enum ApiError {
+    Kenku(KenkuError),
+    Refused(Refusal),
+}
+
+/// The body of `PUT /admin/bans/{name}`.
+#[derive(Deserialize)]
+struct Ban {
+    seconds: u64,
+}
+
+/// The body of `PUT /admin/log-filter`.
+#[cfg(feature = "log-filter")]
+#[derive(Deserialize)]
+struct Directives {
+    directives: String,
+}
+
+/// The query string of the search routes.
+#[derive(Deserialize)]
+struct SearchQuery {
+    q: String,
+}
+
+/// The body of `POST /commands`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Commands {
+    One(KenkuCommandWithPayload),
+    Many(Vec<KenkuCommandWithPayload>),
+}
+
+type Shared = Arc<RestApi>;
+
+impl RestApi {
+    /// Creates a facade of `controller`, without authentication, scenes, limits or moderators.
+    pub fn new(controller: &Controller) -> RestApi {
+        RestApi {
+            controller: controller.clone(),
+            auth: None,
+            scenes: BTreeMap::new(),
+            clients: ClientTracker::new(),
+            #[cfg(feature = "log-filter")]
+            log_filter: None,
+            #[cfg(feature = "gateway")]
+            federation: Federation::new(),
+        }
+    }
+
+    /// Requires every request to carry `Authorization: Bearer <token>`.
+    ///
+    /// This is a shorthand for `auth(BearerTokens::new().admin(token, "token"))`.
+    pub fn token(self, token: impl Into<String>) -> RestApi {
+        self.auth(BearerTokens::new().admin(token, "token"))
+    }
+
+    /// Only lets in the requests `auth` accepts, replacing any token or authenticator set before.
+    pub fn auth(mut self, auth: impl Authenticator + 'static) -> RestApi {
+        self.auth = Some(Arc::new(auth));
+        self
+    }
+
+    /// Counts, limits and bans the clients with `clients`, which may be shared with a
+    /// `websocket::WebSocketServer`.
+    pub fn clients(mut self, clients: ClientTracker) -> RestApi {
+        self.clients = clients;
+        self
+    }
+
+    /// Lets moderators read and change the directives of `filter` under `/admin/log-filter`.
+    #[cfg(feature = "log-filter")]
+    pub fn log_filter(mut self, filter: LogFilter) -> RestApi {
+        self.log_filter = Some(filter);
+        self
+    }
+
+    /// Proxies the rooms of `federation` under `/rooms`, replacing any federation set before.
+    #[cfg(feature = "gateway")]
+    pub fn federation(mut self, federation: Federation) -> RestApi {
+        self.federation = federation;
+        self
+    }
+
+    /// Serves `scene` under `/scenes/<name>`, replacing any scene with the same name.
+    pub fn scene(mut self, scene: Scene) -> RestApi {
+        self.scenes.insert(scene.name.clone(), scene);
+        self
+    }
+
+    /// Serves every scene of `scenes`, keyed by the name they are stored under, such as the
+    /// `scenes` of an `EffectiveConfig`.
+    pub fn scenes(mut self, scenes: impl IntoIterator<Item = (String, Scene)>) -> RestApi {
+        self.scenes.extend(scenes);
+        self
+    }
+
+    /// Returns the routes, to be served with `axum::serve` or nested in a larger application.
+    ///
+    /// Unlike `start`, this does not check where the routes are served: without a token or an
+    /// authenticator, every request may send commands. Serve it with
+    /// `into_make_service_with_connect_info::<SocketAddr>` to tell the authenticator where each
+    /// request came from.
+    pub fn router(self) -> Router {
+        let state: Shared = Arc::new(self);
+        let moderated = Router::new()
+            .route("/admin/clients", get(list_clients))
+            .route("/admin/bans/{name}", put(ban).delete(unban))
+            .route(
+                "/admin/automation",
+                get(automation)
+                    .put(pause_automation)
+                    .delete(resume_automation),
+            )
+            .route("/admin/tokens/{name}", delete(revoke_token))
+            .route("/metrics", get(metrics));
+
+        #[cfg(feature = "log-filter")]
+        let moderated =
+            moderated.route("/admin/log-filter", get(log_filter).put(change_log_filter));
+
+        let router = Router::new();
+
+        #[cfg(feature = "gateway")]
+        let router = router
+            .route("/rooms", get(rooms))
+            .route(
+                "/rooms/{room}/commands",
+                post(room_commands).route_layer(middleware::from_fn(require_admin)),
+            )
+            .route(
+                "/rooms/{room}/scenes/{name}",
+                post(activate_room_scene).route_layer(middleware::from_fn(require_admin)),
+            );
+
+        router
+            .route("/playlist", get(playlist))
+            .route("/playlist/playback", get(playlist_playback))
+            .route("/soundboard", get(soundboard))
+            .route("/soundboard/playback", get(soundboard_playback))
+            .route(
+                "/commands",
+                post(commands).route_layer(middleware::from_fn(require_admin)),
+            )
+            .route(
+                "/snapshot",
+                get(snapshot).merge(put(restore).route_layer(middleware::from_fn(require_admin))),
+            )
+            .route("/scenes", get(scenes))
+            .route(
+                "/scenes/{name}",
+                post(activate).route_layer(middleware::from_fn(require_admin)),
+            )
+            .route("/search/tracks", get(search_tracks))
+            .route("/search/sounds", get(search_sounds))
+            .merge(moderated.route_layer(middleware::from_fn_with_state(
+                state.clone(),
+                require_moderator,
+            )))
+            .layer(middleware::from_fn_with_state(state.clone(), authorize))
+            .with_state(state)
+    }
+
+    /// Serves the routes on `bind`, such as `"0.0.0.0:8080"`.
+    ///
+    /// Without a token or an authenticator, anyone reaching the server could control the table, so
+    /// only loopback addresses such as `"127.0.0.1:8080"` may be bound then.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the running `RestServer`, a `KenkuError::InvalidInput` if `bind` is not
+    /// a loopback address and no authentication is set, or a `KenkuError::Io` if `bind` could not
+    /// be bound.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if it is called outside of a tokio runtime.
+    pub async fn start(self, bind: impl ToSocketAddrs) -> Result<RestServer, KenkuError> {
+        let listener = TcpListener::bind(bind).await?;
+        let address = listener.local_addr()?;
+
+        if self.auth.is_none() && !address.ip().is_loopback() {
+            return Err(KenkuError::InvalidInput(format!(
+                "refusing to serve {} without a token or an authenticator",
+                address
+            )));
+        }
+
+        let router = self.router();
+
+        let handle = tokio::spawn(async move {
+            let service = router.into_make_service_with_connect_info::<SocketAddr>();
+            let _ = axum::serve(listener, service).await;
+        });
+
+        Ok(RestServer { address, handle })
+    }
+}
+
+impl fmt::Debug for RestApi {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut api = f.debug_struct("RestApi");
+
+        api.field("controller", &self.controller)
+            .field("auth", &self.auth.is_some())
+            .field("scenes", &self.scenes)
+            .field("clients", &self.clients);
+
+        #[cfg(feature = "log-filter")]
+        api.field("log_filter", &self.log_filter);
+
+        api.finish()
+    }
+}
+
+impl RestServer {
+    /// Returns the address the server listens on.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.address
+    }
+}
+
+impl Drop for RestServer {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+impl From<KenkuError> for ApiError {
+    fn from(error: KenkuError) -> ApiError {
+        ApiError::Kenku(error)
+    }
+}
+
+impl From<JsonRejection> for ApiError {
+    fn from(rejection: JsonRejection) -> ApiError {
+        ApiError::Kenku(KenkuError::InvalidInput(rejection.body_text()))
+    }
+}
+
+impl From<Refusal> for ApiError {
+    fn from(refusal: Refusal) -> ApiError {
+        ApiError::Refused(refusal)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let error = match self {
+            ApiError::Kenku(error) => error,
+            ApiError::Refused(refusal) => {
+                let status = match refusal {
+                    Refusal::Banned(_) => StatusCode::FORBIDDEN,
+                    Refusal::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+                };
+
+                return (status, Json(json!({"error": refusal.to_string()}))).into_response();
+            }
+        };
+        let status = match &error {
+            KenkuError::InvalidInput(_) => StatusCode::BAD_REQUEST,
+            KenkuError::NotFound(_) => StatusCode::NOT_FOUND,
+            KenkuError::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
+            KenkuError::Connection(_)
+            | KenkuError::Deserialization(_)
+            | KenkuError::Status { .. }
+            | KenkuError::Request(_)
+            | KenkuError::Unsupported(_) => StatusCode::BAD_GATEWAY,
+            KenkuError::QueueClosed => StatusCode::SERVICE_UNAVAILABLE,
+            KenkuError::Io(_) | KenkuError::Serialization(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+
+        (status, Json(json!({"error": error.to_string()}))).into_response()
+    }
+}
+
+/// Rejects the requests the authenticator turns away, and tags the others with their `Identity`.
+///
+/// Without an authenticator, every request is made by an anonymous admin, which is why `start`
+/// only binds loopback addresses then.
+async fn authorize(State(api): State<Shared>, mut request: Request, next: Next) -> Response {
+    let identity = match &api.auth {
+        Some(auth) => {
+            let peer = request
+                .extensions()
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(address)| address.ip());
+
+            auth.authenticate(request.headers(), peer).await
+        }
+        None => Some(Identity {
+            name: "anonymous".to_string(),
+            admin: true,
+        }),
+    };
+
+    match identity {
+        Some(identity) => {
+            if let Err(refusal) = api.clients.check(&identity) {
+                return ApiError::from(refusal).into_response();
+            }
+
+            request.extensions_mut().insert(identity);
+            next.run(request).await
+        }
+        None => (
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, "Bearer")],
+            Json(json!({"error": "missing or rejected credentials"})),
+        )
+            .into_response(),
+    }
+}
+
+/// Answers 403 to the requests of identities without the `admin` flag.
+async fn require_admin(request: Request, next: Next) -> Response {
+    let admin = request
+        .extensions()
+        .get::<Identity>()
+        .is_some_and(|identity| identity.admin);
+
+    if admin {
+        next.run(request).await
+    } else {
+        (
+            StatusCode::FORBIDDEN,
+            Json(json!({"error": "only admins may send commands"})),
+        )
+            .into_response()
+    }
+}
+
+/// Answers 403 to the requests of clients the tracker does not list as moderators.
+async fn require_moderator(State(api): State<Shared>, request: Request, next: Next) -> Response {
+    let moderator = request
+        .extensions()
+        .get::<Identity>()
+        .is_some_and(|identity| api.clients.is_moderator(identity));
+
+    if moderator {
+        next.run(request).await
+    } else {
+        (
+            StatusCode::FORBIDDEN,
+            Json(json!({"error": "only moderators may use this route"})),
+        )
+            .into_response()
+    }
+}
+
+/// Describes a batch as `{"sent": n, "failures": [{"command": ..., "error": ...}]}`.
+fn report(report: &BatchReport) -> Json<Value> {
+    let failures: Vec<Value> = report
+        .failures()
+        .into_iter()
+        .map(|(command, error)| json!({"command": command, "error": error.to_string()}))
+        .collect();
+
+    Json(json!({"sent": report.results.len(), "failures": failures}))
+}
+
+async fn playlist(State(api): State<Shared>) -> Result<Json<Value>, ApiError> {
+    Ok(Json(json!(api.controller.get_playlist().await?)))
+}
+
+async fn playlist_playback(State(api): State<Shared>) -> Result<Json<Value>, ApiError> {
+    Ok(Json(json!(api.controller.get_playlist_playback().await?)))
+}
+
+async fn soundboard(State(api): State<Shared>) -> Result<Json<Value>, ApiError> {
+    Ok(Json(json!(api.controller.get_soundboard().await?)))
+}
+
+async fn soundboard_playback(State(api): State<Shared>) -> Result<Json<Value>, ApiError> {
+    Ok(Json(json!(api.controller.get_soundboard_playback().await?)))
+}
+
+async fn commands(
+    State(api): State<Shared>,
+    Extension(identity): Extension<Identity>,
+    body: Result<Json<Commands>, JsonRejection>,
+) -> Result<Json<Value>, ApiError> {
+    let commands = match body?.0 {
+        Commands::One(command) => vec![command],
+        Commands::Many(commands) => commands,
+    };
+    api.clients.record(&identity, commands.len())?;
+
+    Ok(report(&api.controller.execute_batch(commands).await))
+}
+
+async fn snapshot(State(api): State<Shared>) -> Result<Json<PlaybackSnapshot>, ApiError> {
+    Ok(Json(api.controller.snapshot().await?))
+}
+
+async fn restore(
+    State(api): State<Shared>,
+    Extension(identity): Extension<Identity>,
+    snapshot: Result<Json<PlaybackSnapshot>, JsonRejection>,
+) -> Result<Json<Value>, ApiError> {
+    let snapshot = snapshot?.0;
+    api.clients.record(&identity, 1)?;
+
+    Ok(report(&api.controller.restore(&snapshot).await?))
+}
+
+async fn scenes(State(api): State<Shared>) -> Json<Vec<String>> {
+    Json(api.scenes.keys().cloned().collect())
+}
+
+async fn activate(
+    State(api): State<Shared>,
+    Extension(identity): Extension<Identity>,
+    Path(name): Path<String>,
+) -> Result<Json<Value>, ApiError> {
+    let scene = api
+        .scenes
+        .get(&name)
+        .ok_or_else(|| KenkuError::NotFound(format!("scene {:?}", name)))?;
+    api.clients.record(&identity, 1)?;
+
+    Ok(report(&scene.activate(&api.controller).await?))
+}
+
+#[cfg(feature = "gateway")]
+async fn rooms(State(api): State<Shared>) -> Json<Value> {
+    let rooms: serde_json::Map<String, Value> = api
+        .federation
+        .snapshots()
+        .await
+        .into_iter()
+        .map(|(room, snapshot)| {
+            let view = match snapshot {
+                Ok(snapshot) => json!({"snapshot": snapshot}),
+                Err(error) => json!({"error": error.to_string()}),
+            };
+
+            (room, view)
+        })
+        .collect();
+
+    Json(Value::Object(rooms))
+}
+
+#[cfg(feature = "gateway")]
+async fn room_commands(
+    State(api): State<Shared>,
+    Extension(identity): Extension<Identity>,
+    Path(room): Path<String>,
+    body: Result<Json<Commands>, JsonRejection>,
+) -> Result<Json<GatewayReport>, ApiError> {
+    let commands = match body?.0 {
+        Commands::One(command) => vec![command],
+        Commands::Many(commands) => commands,
+    };
+    api.federation.gateway(&room)?;
+    api.clients.record(&identity, commands.len())?;
+
+    Ok(Json(api.federation.execute_batch(&room, &commands).await?))
+}
+
+#[cfg(feature = "gateway")]
+async fn activate_room_scene(
+    State(api): State<Shared>,
+    Extension(identity): Extension<Identity>,
+    Path((room, name)): Path<(String, String)>,
+) -> Result<Json<GatewayReport>, ApiError> {
+    api.federation.gateway(&room)?;
+    api.clients.record(&identity, 1)?;
+
+    Ok(Json(api.federation.activate_scene(&room, &name).await?))
+}
+
+async fn search_tracks(
+    State(api): State<Shared>,
+    Query(query): Query<SearchQuery>,
+) -> Result<Json<Value>, ApiError> {
+    let library = api.controller.get_playlist().await?;
+    let found: Vec<Value> = find_track(&library, &query.q)
+        .into_iter()
+        .map(|found| json!({"track": found.item, "score": found.score}))
+        .collect();
+
+    Ok(Json(json!(found)))
+}
+
+async fn search_sounds(
+    State(api): State<Shared>,
+    Query(query): Query<SearchQuery>,
+) -> Result<Json<Value>, ApiError> {
+    let soundboard = api.controller.get_soundboard().await?;
+    let found: Vec<Value> = find_sound(&soundboard, &query.q)
+        .into_iter()
+        .map(|found| json!({"sound": found.item, "score": found.score}))
+        .collect();
+
+    Ok(Json(json!(found)))
+}
+
+async fn list_clients(State(api): State<Shared>) -> Json<Vec<ClientStats>> {
+    Json(api.clients.clients())
+}
+
+async fn ban(
+    State(api): State<Shared>,
+    Path(name): Path<String>,
+    body: Result<Json<Ban>, JsonRejection>,
+) -> Result<StatusCode, ApiError> {
+    api.clients
+        .ban(&name, std::time::Duration::from_secs(body?.0.seconds));
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn unban(
+    State(api): State<Shared>,
+    Path(name): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    if !api.clients.unban(&name) {
+        return Err(KenkuError::NotFound(format!("ban of {:?}", name)).into());
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn automation(State(api): State<Shared>) -> Json<Value> {
+    Json(json!({"paused": api.controller.automation_paused()}))
+}
+
+async fn pause_automation(State(api): State<Shared>) -> StatusCode {
+    api.controller.pause_automation();
+    StatusCode::NO_CONTENT
+}
+
+async fn resume_automation(State(api): State<Shared>) -> StatusCode {
+    api.controller.resume_automation();
+    StatusCode::NO_CONTENT
+}
+
+async fn revoke_token(
+    State(api): State<Shared>,
+    Path(name): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    if !api.auth.as_ref().is_some_and(|auth| auth.revoke(&name)) {
+        return Err(KenkuError::NotFound(format!("tokens of {:?}", name)).into());
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn metrics(State(api): State<Shared>) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        api.clients.metrics(),
+    )
+}
+
+#[cfg(feature = "log-filter")]
+async fn log_filter(State(api): State<Shared>) -> Result<Json<Value>, ApiError> {
+    let directives = api
+        .log_filter
+        .as_ref()
+        .and_then(LogFilter::directives)
+        .ok_or_else(|| KenkuError::NotFound("log filter".to_string()))?;
+
+    Ok(Json(json!({"directives": directives})))
+}
+
+#[cfg(feature = "log-filter")]
+async fn change_log_filter(
+    State(api): State<Shared>,
+    body: Result<Json<Directives>, JsonRejection>,
+) -> Result<Json<Value>, ApiError> {
+    let directives = body?.0.directives;
+    let filter = api
+        .log_filter
+        .as_ref()
+        .ok_or_else(|| KenkuError::NotFound("log filter".to_string()))?;
+
+    filter.set(&directives)?;
+
+    Ok(Json(json!({"directives": directives})))
+}
@@ -0,0 +1,183 @@
+//! Retrying requests that failed for a transient reason.
+use crate::KenkuError;
+use reqwest::Method;
+use std::time::Duration;
+
+/// Represents which failed requests a `Controller` sends again, and how long it waits in between.
+///
+/// A flaky network at the game table shows up as connection errors and timeouts, which are
+/// retried by default once `max_retries` is raised. A Kenku Remote answering with a 5xx status
+/// can be retried too, with `retry_on_server_error`.
+///
+/// A request that timed out or got a 5xx answer may still have been carried out. Sending a GET
+/// or PUT again is harmless, but sending a POST such as "next track" again could skip two tracks.
+/// POST requests are therefore only retried after a connection error, when they never reached
+/// the remote, unless `retry_non_idempotent` is set.
+///
+/// On `wasm32`, which has no tokio timer, the backoff is ignored and retries are sent at once.
+///
+/// # Fields
+///
+/// * `max_retries` - How many times a request is sent again after the first attempt. 0 sends every request once.
+/// * `initial_backoff` - The delay before the first retry.
+/// * `max_backoff` - The upper bound of the delay, which doubles after every retry.
+/// * `retry_on_connect` - Whether connection errors are retried.
+/// * `retry_on_timeout` - Whether timeouts are retried.
+/// * `retry_on_server_error` - Whether 5xx answers are retried.
+/// * `retry_non_idempotent` - Whether POST requests are retried after timeouts and 5xx answers too.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub retry_on_connect: bool,
+    pub retry_on_timeout: bool,
+    pub retry_on_server_error: bool,
+    pub retry_non_idempotent: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 0,
+            initial_backoff: Duration::ZERO,
+            max_backoff: Duration::from_secs(2),
+            retry_on_connect: true,
+            retry_on_timeout: true,
+            retry_on_server_error: false,
+            retry_non_idempotent: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Creates a `RetryPolicy` retrying connection errors and timeouts up to `max_retries` times, without waiting.
+    pub fn new(max_retries: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_retries,
+            ..RetryPolicy::default()
+        }
+    }
+
+    /// Sets the delay before the first retry and its upper bound.
+    pub fn backoff(mut self, initial: Duration, max: Duration) -> RetryPolicy {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+
+    /// Sets whether 5xx answers are retried.
+    pub fn retry_on_server_error(mut self, enabled: bool) -> RetryPolicy {
+        self.retry_on_server_error = enabled;
+        self
+    }
+
+    /// Sets whether POST requests are retried after timeouts and 5xx answers, which may send
+    /// them twice.
+    pub fn retry_non_idempotent(mut self, enabled: bool) -> RetryPolicy {
+        self.retry_non_idempotent = enabled;
+        self
+    }
+
+    /// Returns `true` when a `method` request that failed with `error` is one this policy
+    /// retries. The number of attempts is not checked.
+    pub fn should_retry(&self, method: &Method, error: &KenkuError) -> bool {
+        let maybe_delivered = !matches!(error, KenkuError::Connection(_));
+
+        if maybe_delivered && !method.is_idempotent() && !self.retry_non_idempotent {
+            return false;
+        }
+
+        match error {
+            KenkuError::Connection(_) => self.retry_on_connect,
+            KenkuError::Timeout(_) => self.retry_on_timeout,
+            KenkuError::Status { status, .. } => {
+                self.retry_on_server_error && status.is_server_error()
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns how long to wait before retry number `retry`, starting at 1.
+    pub fn delay(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RetryPolicy;
+    use crate::KenkuError;
+    use reqwest::{Method, StatusCode};
+    use std::time::Duration;
+
+    #[test]
+    fn delay_doubles_up_to_the_bound() {
+        let policy =
+            RetryPolicy::new(5).backoff(Duration::from_millis(100), Duration::from_millis(300));
+
+        assert_eq!(policy.delay(1), Duration::from_millis(100));
+        assert_eq!(policy.delay(2), Duration::from_millis(200));
+        assert_eq!(policy.delay(3), Duration::from_millis(300));
+    }
+
+    #[test]
+    fn server_errors_are_only_retried_when_enabled() {
+        let error = KenkuError::Status {
+            status: StatusCode::BAD_GATEWAY,
+            body: String::new(),
+        };
+        let rejected = KenkuError::Status {
+            status: StatusCode::BAD_REQUEST,
+            body: String::new(),
+        };
+
+        assert!(!RetryPolicy::new(1).should_retry(&Method::GET, &error));
+        assert!(RetryPolicy::new(1)
+            .retry_on_server_error(true)
+            .should_retry(&Method::GET, &error));
+        assert!(!RetryPolicy::new(1)
+            .retry_on_server_error(true)
+            .should_retry(&Method::GET, &rejected));
+    }
+
+    #[tokio::test]
+    async fn posts_are_only_retried_when_they_never_left() {
+        let timeout = KenkuError::from(timed_out().await);
+        let refused = KenkuError::from(refused().await);
+        let policy = RetryPolicy::new(1);
+
+        assert!(policy.should_retry(&Method::PUT, &timeout));
+        assert!(!policy.should_retry(&Method::POST, &timeout));
+        assert!(policy.should_retry(&Method::POST, &refused));
+        assert!(policy
+            .retry_non_idempotent(true)
+            .should_retry(&Method::POST, &timeout));
+    }
+
+    /// Returns the error of a request to a server that never answers.
+    async fn timed_out() -> reqwest::Error {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        reqwest::Client::new()
+            .get(url)
+            .timeout(Duration::from_millis(10))
+            .send()
+            .await
+            .unwrap_err()
+    }
+
+    /// Returns the error of a request to a port nobody listens on.
+    async fn refused() -> reqwest::Error {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+
+        reqwest::Client::new().get(url).send().await.unwrap_err()
+    }
+}
@@ -0,0 +1,271 @@
+//! Scenes: named bundles of music, playback settings and soundboard sounds.
+//!
+//! Game masters think in scenes such as "tavern" or "ambush" rather than in single commands. A
+//! `Scene` describes what should be heard, and `Scene::activate` sends whatever commands bring the
+//! Kenku Remote there. Scenes are plain data, so a campaign's scenes can be saved with
+//! `format::save` and loaded back.
+use crate::{
+    batch::BatchReport, playlist::Repeat, snapshot::PlaybackSnapshot, Controller,
+    KenkuCommandWithPayload, KenkuError, KenkuPlaybackCommand, Volume,
+};
+use serde::{Deserialize, Serialize};
+
+/// Represents a scene.
+///
+/// Settings left to `None` keep their current value when the scene is activated.
+///
+/// # Fields
+///
+/// * `name` - The name of the scene, such as `"tavern"`.
+/// * `playlist` - The id of the playlist to play from its first track, if any.
+/// * `track` - The id of the track to play, if any. Takes precedence over `playlist`. A track that is already playing is not restarted.
+/// * `volume` - The playlist volume.
+/// * `muted` - Whether the playlist playback is muted.
+/// * `shuffle` - Whether the playlist plays in shuffle mode.
+/// * `repeat` - The repeat mode of the playlist playback.
+/// * `sounds` - The ids of the soundboard sounds to play. Sounds already playing are left alone.
+/// * `exclusive` - Whether sounds playing outside of `sounds` are stopped.
+#[serde_with::skip_serializing_none]
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Scene {
+    pub name: String,
+    pub playlist: Option<String>,
+    pub track: Option<String>,
+    pub volume: Option<Volume>,
+    pub muted: Option<bool>,
+    pub shuffle: Option<bool>,
+    pub repeat: Option<Repeat>,
+    pub sounds: Vec<String>,
+    pub exclusive: bool,
+}
+
+impl Scene {
+    /// Creates a scene named `name` that changes nothing.
+    pub fn new(name: impl Into<String>) -> Scene {
+        Scene {
+            name: name.into(),
+            ..Scene::default()
+        }
+    }
+
+    /// Plays the playlist with the id `id` from its first track.
+    pub fn playlist(mut self, id: impl Into<String>) -> Scene {
+        self.playlist = Some(id.into());
+        self
+    }
+
+    /// Plays the track with the id `id`.
+    pub fn track(mut self, id: impl Into<String>) -> Scene {
+        self.track = Some(id.into());
+        self
+    }
+
+    /// Sets the playlist volume.
+    pub fn volume(mut self, volume: Volume) -> Scene {
+        self.volume = Some(volume);
+        self
+    }
+
+    /// Mutes or unmutes the playlist playback.
+    pub fn muted(mut self, muted: bool) -> Scene {
+        self.muted = Some(muted);
+        self
+    }
+
+    /// Turns shuffle mode on or off.
+    pub fn shuffle(mut self, shuffle: bool) -> Scene {
+        self.shuffle = Some(shuffle);
+        self
+    }
+
+    /// Sets the repeat mode.
+    pub fn repeat(mut self, repeat: Repeat) -> Scene {
+        self.repeat = Some(repeat);
+        self
+    }
+
+    /// Adds the soundboard sound with the id `id`.
+    pub fn sound(mut self, id: impl Into<String>) -> Scene {
+        self.sounds.push(id.into());
+        self
+    }
+
+    /// Stops the sounds that are not part of this scene when it is activated.
+    pub fn exclusive(mut self, exclusive: bool) -> Scene {
+        self.exclusive = exclusive;
+        self
+    }
+
+    /// Lists the commands that bring the playback from `current` to this scene.
+    ///
+    /// Playback settings come first, so the music starts with them applied, followed by the track
+    /// or playlist, the sounds to stop and the sounds to play.
+    ///
+    /// # Returns
+    ///
+    /// The commands to send, in order.
+    pub fn commands(&self, current: &PlaybackSnapshot) -> Vec<KenkuCommandWithPayload> {
+        let now = &current.playlist;
+        let mut commands = Vec::new();
+
+        if let Some(volume) = self.volume.filter(|volume| *volume != now.volume_level()) {
+            commands.push(KenkuPlaybackCommand::PlaylistPlaybackVolume(volume).into());
+        }
+
+        if let Some(muted) = self.muted.filter(|muted| *muted != now.muted) {
+            commands.push(KenkuPlaybackCommand::PlaylistPlaybackMute(muted).into());
+        }
+
+        if let Some(shuffle) = self.shuffle.filter(|shuffle| *shuffle != now.shuffle) {
+            commands.push(KenkuPlaybackCommand::PlaylistPlaybackShuffle(shuffle).into());
+        }
+
+        if let Some(repeat) = self.repeat.as_ref().filter(|repeat| **repeat != now.repeat) {
+            commands.push(KenkuPlaybackCommand::PlaylistPlaybackRepeat(repeat.clone()).into());
+        }
+
+        let playing_track = now
+            .track
+            .as_ref()
+            .filter(|_| now.playing)
+            .map(|track| &track.id);
+
+        match (&self.track, &self.playlist) {
+            (Some(track), _) if playing_track == Some(track) => {}
+            (Some(track), _) => commands.push(KenkuCommandWithPayload::PlayTrack(track.clone())),
+            (None, Some(playlist)) => {
+                commands.push(KenkuCommandWithPayload::PlayPlaylist(playlist.clone()))
+            }
+            (None, None) => {}
+        }
+
+        if self.exclusive {
+            for sound in &current.soundboard.sounds {
+                if !self.sounds.contains(&sound.id) {
+                    commands.push(KenkuCommandWithPayload::StopSound(sound.id.clone()));
+                }
+            }
+        }
+
+        for sound in &self.sounds {
+            if !current.soundboard.is_playing(sound) {
+                commands.push(KenkuCommandWithPayload::PlaySound(sound.clone()));
+            }
+        }
+
+        commands
+    }
+
+    /// Checks that every playlist, track and sound of this scene exists on the Kenku Remote.
+    ///
+    /// # Returns
+    ///
+    /// This function returns `Ok(())` when they all exist, a `KenkuError::NotFound` naming the first missing one, or the `KenkuError` raised while fetching the library.
+    pub async fn validate(&self, controller: &Controller) -> Result<(), KenkuError> {
+        if self.playlist.is_some() || self.track.is_some() {
+            let library = controller.get_playlist().await?;
+
+            if let Some(id) = self
+                .playlist
+                .as_ref()
+                .filter(|id| library.playlist_by_id(id).is_none())
+            {
+                return Err(KenkuError::NotFound(format!("playlist {:?}", id)));
+            }
+
+            if let Some(id) = self
+                .track
+                .as_ref()
+                .filter(|id| library.track_by_id(id).is_none())
+            {
+                return Err(KenkuError::NotFound(format!("track {:?}", id)));
+            }
+        }
+
+        if !self.sounds.is_empty() {
+            let soundboard = controller.get_soundboard().await?;
+
+            if let Some(id) = self
+                .sounds
+                .iter()
+                .find(|id| soundboard.sound_by_id(id).is_none())
+            {
+                return Err(KenkuError::NotFound(format!("sound {:?}", id)));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Activates this scene on the Kenku Remote, all or nothing.
+    ///
+    /// The scene is validated before anything is sent, so a scene pointing at a deleted sound
+    /// changes nothing. When a command fails midway, the playback is restored to how it was
+    /// before the activation, as far as the remote lets it.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the `BatchReport` of the commands sent, or the first `KenkuError`, after rolling back.
+    pub async fn activate(&self, controller: &Controller) -> Result<BatchReport, KenkuError> {
+        self.validate(controller).await?;
+
+        let before = controller.snapshot().await?;
+        let mut results = Vec::new();
+
+        for command in self.commands(&before) {
+            match controller.execute(&command).await {
+                Ok(status) => results.push((command, Ok(status))),
+                Err(error) => {
+                    let _ = controller.restore(&before).await;
+                    return Err(error);
+                }
+            }
+        }
+
+        Ok(BatchReport { results })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Scene;
+    use crate::{
+        playlist::Repeat,
+        testing::{playback, snapshot},
+        KenkuCommandWithPayload, KenkuPlaybackCommand, Volume,
+    };
+    use serde_json::json;
+
+    #[test]
+    fn scene_sends_only_what_changes() {
+        let tavern = Scene::new("tavern")
+            .playlist("p1")
+            .volume(Volume::from_percent(70))
+            .shuffle(false)
+            .repeat(Repeat::Playlist)
+            .sound("fire")
+            .sound("crowd")
+            .exclusive(true);
+
+        assert_eq!(
+            tavern.commands(&snapshot(playback(None), &["crowd", "rain"])),
+            vec![
+                KenkuPlaybackCommand::PlaylistPlaybackVolume(Volume::from_percent(70)).into(),
+                KenkuPlaybackCommand::PlaylistPlaybackRepeat(Repeat::Playlist).into(),
+                KenkuCommandWithPayload::PlayPlaylist("p1".to_string()),
+                KenkuCommandWithPayload::StopSound("rain".to_string()),
+                KenkuCommandWithPayload::PlaySound("fire".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn scenes_load_with_defaults() {
+        let scene: Scene =
+            serde_json::from_value(json!({"name": "ambush", "sounds": ["drums"]})).unwrap();
+
+        assert_eq!(scene, Scene::new("ambush").sound("drums"));
+        assert!(!scene.exclusive);
+    }
+}
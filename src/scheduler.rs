@@ -0,0 +1,364 @@
+//! Running commands at a given time or after a delay.
+//!
+//! ```no_run
+//! # async fn rain() {
+//! use kenku_control::{Controller, KenkuCommandWithPayload};
+//! use std::time::Duration;
+//!
+//! let controller = Controller::new("127.0.0.1", 3333);
+//! let scheduler = controller.schedule();
+//!
+//! let rain = scheduler.after(
+//!     Duration::from_secs(10 * 60),
+//!     KenkuCommandWithPayload::PlaySound("rain-sound-id".to_string()),
+//! );
+//!
+//! println!("{} job(s) pending", scheduler.pending().len());
+//! rain.cancel();
+//! # }
+//! ```
+use crate::{Controller, KenkuCommandWithPayload, KenkuError};
+use reqwest::StatusCode;
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, SystemTime},
+};
+use tokio::task::JoinHandle;
+
+/// The longest delay a job waits, about thirty years, which is as far as tokio timers reach.
+const MAX_DELAY: Duration = Duration::from_secs(30 * 365 * 24 * 60 * 60);
+
+/// Represents a job waiting to run.
+///
+/// # Fields
+///
+/// * `id` - The id of the job, unique within its `Scheduler`.
+/// * `due` - When the job runs.
+/// * `command` - The command the job sends.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingJob {
+    pub id: u64,
+    pub due: SystemTime,
+    pub command: KenkuCommandWithPayload,
+}
+
+/// Represents how a scheduled job ended.
+///
+/// # Variants
+///
+/// * `Executed` - The command was sent, with the result of the request.
+/// * `Skipped` - The job was due while automation was paused with `Controller::pause_automation`, so the command was not sent.
+/// * `Cancelled` - The job was cancelled before it was due.
+#[derive(Debug)]
+pub enum JobOutcome {
+    Executed(Result<StatusCode, KenkuError>),
+    Skipped,
+    Cancelled,
+}
+
+type Jobs = Arc<Mutex<BTreeMap<u64, (PendingJob, tokio::task::AbortHandle)>>>;
+
+/// Runs commands on a `Controller` at a given time or after a delay.
+///
+/// Each job runs as its own task. Jobs are scheduled automation: a job that is due while
+/// automation is paused is skipped. Dropping the scheduler does not cancel its jobs.
+#[derive(Debug, Clone)]
+pub struct Scheduler {
+    controller: Controller,
+    jobs: Jobs,
+    next_id: Arc<AtomicU64>,
+}
+
+/// A handle to a scheduled job.
+///
+/// Dropping the handle does not cancel the job.
+#[derive(Debug)]
+pub struct JobHandle {
+    id: u64,
+    jobs: Jobs,
+    handle: JoinHandle<JobOutcome>,
+}
+
+impl Controller {
+    /// Creates a `Scheduler` sending its commands through this controller.
+    pub fn schedule(&self) -> Scheduler {
+        Scheduler {
+            controller: self.clone(),
+            jobs: Arc::new(Mutex::new(BTreeMap::new())),
+            next_id: Arc::new(AtomicU64::new(1)),
+        }
+    }
+}
+
+impl Scheduler {
+    /// Schedules `command` to run once `delay` has passed.
+    ///
+    /// Delays longer than thirty years, such as `Duration::MAX`, are shortened to thirty years,
+    /// which leaves the job pending until it is cancelled in practice.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if it is called outside of a tokio runtime.
+    pub fn after(&self, delay: Duration, command: KenkuCommandWithPayload) -> JobHandle {
+        let delay = delay.min(MAX_DELAY);
+
+        self.spawn(SystemTime::now() + delay, delay, command)
+    }
+
+    /// Schedules `command` to run at `time`. A time in the past runs the command right away.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if it is called outside of a tokio runtime.
+    pub fn at(&self, time: SystemTime, command: KenkuCommandWithPayload) -> JobHandle {
+        let delay = time
+            .duration_since(SystemTime::now())
+            .unwrap_or(Duration::ZERO);
+
+        self.spawn(time, delay, command)
+    }
+
+    /// Schedules `command` to run every time `rule` applies, until the job is cancelled.
+    ///
+    /// The next run is worked out from the current time after every run, so the job follows the
+    /// wall clock of its zone across daylight saving time changes. The results of the runs are
+    /// not kept, and `JobHandle::wait` only returns once the job is cancelled. While pending, the
+    /// job is listed with the time of its next run.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if it is called outside of a tokio runtime.
+    #[cfg(feature = "timezone")]
+    pub fn every(
+        &self,
+        rule: crate::wall_clock::WallClock,
+        command: KenkuCommandWithPayload,
+    ) -> JobHandle {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let controller = self.controller.clone();
+        let jobs = self.jobs.clone();
+        let job = PendingJob {
+            id,
+            due: SystemTime::from(rule.next_after(chrono::Utc::now())),
+            command: command.clone(),
+        };
+
+        let mut registry = self.jobs.lock().unwrap();
+
+        let handle = tokio::spawn(async move {
+            loop {
+                let due = rule.next_after(chrono::Utc::now());
+
+                match jobs.lock().unwrap().get_mut(&id) {
+                    Some((job, _)) => job.due = SystemTime::from(due),
+                    None => return JobOutcome::Cancelled,
+                }
+
+                let delay = (due - chrono::Utc::now())
+                    .to_std()
+                    .unwrap_or(Duration::ZERO);
+                tokio::time::sleep(delay).await;
+
+                if !jobs.lock().unwrap().contains_key(&id) {
+                    return JobOutcome::Cancelled;
+                }
+
+                if !controller.automation_paused() {
+                    let _ = controller.execute(&command).await;
+                }
+            }
+        });
+
+        registry.insert(id, (job, handle.abort_handle()));
+        drop(registry);
+
+        JobHandle {
+            id,
+            jobs: self.jobs.clone(),
+            handle,
+        }
+    }
+
+    /// Returns the jobs that have not run yet, ordered by id.
+    pub fn pending(&self) -> Vec<PendingJob> {
+        self.jobs
+            .lock()
+            .unwrap()
+            .values()
+            .map(|(job, _)| job.clone())
+            .collect()
+    }
+
+    /// Cancels the job with the id `id`.
+    ///
+    /// # Returns
+    ///
+    /// This function returns `true` if the job was still pending.
+    pub fn cancel(&self, id: u64) -> bool {
+        cancel(&self.jobs, id)
+    }
+
+    /// Cancels every pending job.
+    pub fn cancel_all(&self) {
+        let jobs = std::mem::take(&mut *self.jobs.lock().unwrap());
+
+        for (_, abort) in jobs.into_values() {
+            abort.abort();
+        }
+    }
+
+    fn spawn(
+        &self,
+        due: SystemTime,
+        delay: Duration,
+        command: KenkuCommandWithPayload,
+    ) -> JobHandle {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let controller = self.controller.clone();
+        let jobs = self.jobs.clone();
+        let job = PendingJob {
+            id,
+            due,
+            command: command.clone(),
+        };
+
+        // Holding the lock until the job is registered keeps a job with no delay from
+        // removing itself before it was added.
+        let mut registry = self.jobs.lock().unwrap();
+
+        let handle = tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+
+            if jobs.lock().unwrap().remove(&id).is_none() {
+                return JobOutcome::Cancelled;
+            }
+
+            if controller.automation_paused() {
+                return JobOutcome::Skipped;
+            }
+
+            JobOutcome::Executed(controller.execute(&command).await)
+        });
+
+        registry.insert(id, (job, handle.abort_handle()));
+        drop(registry);
+
+        JobHandle {
+            id,
+            jobs: self.jobs.clone(),
+            handle,
+        }
+    }
+}
+
+impl JobHandle {
+    /// Returns the id of the job.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Returns `true` while the job has not run yet.
+    pub fn is_pending(&self) -> bool {
+        self.jobs.lock().unwrap().contains_key(&self.id)
+    }
+
+    /// Cancels the job.
+    ///
+    /// # Returns
+    ///
+    /// This function returns `true` if the job was still pending.
+    pub fn cancel(&self) -> bool {
+        cancel(&self.jobs, self.id)
+    }
+
+    /// Waits for the job to end.
+    pub async fn wait(self) -> JobOutcome {
+        match self.handle.await {
+            Ok(outcome) => outcome,
+            Err(error) if error.is_cancelled() => JobOutcome::Cancelled,
+            Err(error) => std::panic::resume_unwind(error.into_panic()),
+        }
+    }
+}
+
+fn cancel(jobs: &Jobs, id: u64) -> bool {
+    match jobs.lock().unwrap().remove(&id) {
+        Some((_, abort)) => {
+            abort.abort();
+            true
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::JobOutcome;
+    use crate::{Controller, KenkuCommandWithPayload};
+    use std::time::Duration;
+
+    fn command() -> KenkuCommandWithPayload {
+        KenkuCommandWithPayload::PlaySound("s1".to_string())
+    }
+
+    #[tokio::test]
+    async fn pending_jobs_can_be_inspected_and_cancelled() {
+        let scheduler = Controller::new("127.0.0.1", 3333).schedule();
+        let first = scheduler.after(Duration::from_secs(60), command());
+        let second = scheduler.after(Duration::from_secs(120), command());
+
+        assert_eq!(scheduler.pending().len(), 2);
+        assert!(first.cancel());
+        assert!(!first.cancel());
+        assert_eq!(scheduler.pending()[0].id, second.id());
+        assert!(matches!(first.wait().await, JobOutcome::Cancelled));
+
+        scheduler.cancel_all();
+        assert!(!second.is_pending());
+        assert!(matches!(second.wait().await, JobOutcome::Cancelled));
+    }
+
+    #[tokio::test]
+    async fn endless_delays_leave_the_job_pending() {
+        let scheduler = Controller::new("127.0.0.1", 3333).schedule();
+        let job = scheduler.after(Duration::MAX, command());
+
+        assert!(job.is_pending());
+        assert!(scheduler.pending()[0].due > std::time::SystemTime::now());
+        assert!(job.cancel());
+    }
+
+    #[cfg(feature = "timezone")]
+    #[tokio::test]
+    async fn recurring_jobs_list_their_next_run() {
+        use crate::wall_clock::{ClockZone, WallClock};
+
+        let scheduler = Controller::new("127.0.0.1", 3333).schedule();
+        let rule = WallClock::daily(chrono::NaiveTime::MIN, ClockZone::Utc);
+        let job = scheduler.every(rule.clone(), command());
+
+        let pending = scheduler.pending();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(
+            pending[0].due,
+            std::time::SystemTime::from(rule.next_after(chrono::Utc::now()))
+        );
+
+        assert!(job.cancel());
+        assert!(matches!(job.wait().await, JobOutcome::Cancelled));
+    }
+
+    #[tokio::test]
+    async fn due_jobs_are_skipped_while_automation_is_paused() {
+        let controller = Controller::new("127.0.0.1", 3333);
+        controller.pause_automation();
+
+        let job = controller.schedule().after(Duration::ZERO, command());
+
+        assert!(matches!(job.wait().await, JobOutcome::Skipped));
+    }
+}
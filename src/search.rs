@@ -0,0 +1,245 @@
+//! Ranked, case-insensitive and typo-tolerant lookup of tracks and sounds by title.
+//!
+//! Useful to resolve names typed or spoken by a user, such as "tavern" or "thunder storm", to
+//! the ids the Kenku Remote needs. The `*_with_aliases` variants also match the names users gave
+//! to tracks and sounds in an `Aliases` map, such as their titles in the players' language.
+use crate::{
+    aliases::Aliases,
+    playlist::{PlaylistGetResponse, Track},
+    soundboard::{SoundboardGetResponse, Sounds},
+};
+
+/// The lowest score a title needs to be returned as a match.
+const MIN_SCORE: f64 = 0.3;
+
+/// Represents an item whose title matched a query.
+///
+/// # Fields
+///
+/// * `item` - The matching track or sound.
+/// * `score` - How well the title matched, from 0.0 to 1.0. An exact match scores 1.0.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SearchMatch<'a, T> {
+    pub item: &'a T,
+    pub score: f64,
+}
+
+/// Finds the tracks whose title matches `query`, best match first.
+pub fn find_track<'a>(
+    response: &'a PlaylistGetResponse,
+    query: &str,
+) -> Vec<SearchMatch<'a, Track>> {
+    rank(&response.tracks, query, |track| &track.title)
+}
+
+/// Finds the soundboard sounds whose title matches `query`, best match first.
+pub fn find_sound<'a>(
+    response: &'a SoundboardGetResponse,
+    query: &str,
+) -> Vec<SearchMatch<'a, Sounds>> {
+    rank(&response.sounds, query, |sound| &sound.title)
+}
+
+/// Finds the tracks whose title or one of whose aliases matches `query`, best match first.
+pub fn find_track_with_aliases<'a>(
+    response: &'a PlaylistGetResponse,
+    query: &str,
+    aliases: &Aliases,
+) -> Vec<SearchMatch<'a, Track>> {
+    rank_names(&response.tracks, query, |track| {
+        aliases.names_of(&track.id, &track.title)
+    })
+}
+
+/// Finds the soundboard sounds whose title or one of whose aliases matches `query`, best match first.
+pub fn find_sound_with_aliases<'a>(
+    response: &'a SoundboardGetResponse,
+    query: &str,
+    aliases: &Aliases,
+) -> Vec<SearchMatch<'a, Sounds>> {
+    rank_names(&response.sounds, query, |sound| {
+        aliases.names_of(&sound.id, &sound.title)
+    })
+}
+
+/// Scores the title of every item against `query` and returns the matches, best first.
+///
+/// Titles and queries are compared case-insensitively and without punctuation. From best to
+/// worst, a title can be equal to the query, start with it, contain it, have words starting with
+/// every word of the query, or be close to it despite typos. Equal scores keep the order of `items`.
+///
+/// # Arguments
+///
+/// * `items` - The items to search.
+/// * `query` - What to look for.
+/// * `get_title` - Returns the title of an item.
+pub fn rank<'a, T>(
+    items: &'a [T],
+    query: &str,
+    get_title: impl Fn(&T) -> &str,
+) -> Vec<SearchMatch<'a, T>> {
+    rank_names(items, query, |item| vec![get_title(item)])
+}
+
+/// Scores every name of every item against `query` and returns the matches, best first.
+///
+/// Works like `rank`, with an item scoring as well as its best matching name.
+///
+/// # Arguments
+///
+/// * `items` - The items to search.
+/// * `query` - What to look for.
+/// * `get_names` - Returns the names of an item, such as its title and its aliases.
+pub fn rank_names<'a: 'n, 'n, T>(
+    items: &'a [T],
+    query: &str,
+    get_names: impl Fn(&'a T) -> Vec<&'n str>,
+) -> Vec<SearchMatch<'a, T>> {
+    let query = normalize(query);
+
+    if query.is_empty() {
+        return Vec::new();
+    }
+
+    let mut matches: Vec<SearchMatch<'a, T>> = items
+        .iter()
+        .map(|item| SearchMatch {
+            item,
+            score: get_names(item)
+                .into_iter()
+                .map(|name| score(&query, &normalize(name)))
+                .fold(0.0, f64::max),
+        })
+        .filter(|found| found.score >= MIN_SCORE)
+        .collect();
+
+    matches.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+    matches
+}
+
+/// Scores how well the normalized `title` matches the normalized `query`.
+fn score(query: &str, title: &str) -> f64 {
+    if title == query {
+        return 1.0;
+    }
+
+    if title.starts_with(query) {
+        return 0.9;
+    }
+
+    if title.contains(query) {
+        return 0.8;
+    }
+
+    let words: Vec<&str> = title.split(' ').collect();
+
+    if query
+        .split(' ')
+        .all(|part| words.iter().any(|word| word.starts_with(part)))
+    {
+        return 0.7;
+    }
+
+    // Typos: compare with the whole title and with each of its words, keeping the closest.
+    let closest = words
+        .iter()
+        .map(|word| similarity(query, word))
+        .fold(similarity(query, title), f64::max);
+
+    closest * 0.6
+}
+
+/// Returns the similarity of `a` and `b`, from 0.0 to 1.0, based on their edit distance.
+fn similarity(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let longest = a.len().max(b.len());
+
+    if longest == 0 {
+        return 1.0;
+    }
+
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, a_char) in a.iter().enumerate() {
+        let mut current = vec![i + 1];
+
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+
+        previous = current;
+    }
+
+    1.0 - previous[b.len()] as f64 / longest as f64
+}
+
+/// Lowercases `text` and keeps only its words, separated by single spaces.
+pub(crate) fn normalize(text: &str) -> String {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_track_with_aliases, rank};
+    use crate::{aliases::Aliases, playlist::PlaylistGetResponse};
+    use serde_json::json;
+
+    fn titles<'a>(items: &'a [&'a str], query: &str) -> Vec<&'a str> {
+        rank(items, query, |title| title)
+            .into_iter()
+            .map(|found| *found.item)
+            .collect()
+    }
+
+    #[test]
+    fn matches_are_ranked() {
+        let items = ["Busy Tavern", "Tavern", "Tavern Brawl", "Forest Night"];
+
+        assert_eq!(
+            titles(&items, "tavern"),
+            ["Tavern", "Tavern Brawl", "Busy Tavern"]
+        );
+    }
+
+    #[test]
+    fn word_prefixes_and_typos_match() {
+        let items = ["Thunder Storm", "Forest Night", "Dragon's Lair"];
+
+        assert_eq!(titles(&items, "thu sto"), ["Thunder Storm"]);
+        assert_eq!(titles(&items, "dragons lair"), ["Dragon's Lair"]);
+        assert_eq!(titles(&items, "forrest"), ["Forest Night"]);
+        assert!(titles(&items, "").is_empty());
+    }
+
+    #[test]
+    fn aliases_match_like_titles() {
+        let response: PlaylistGetResponse = serde_json::from_value(json!({
+            "playlists": [],
+            "tracks": [
+                {"id": "t1", "url": "", "title": "Tavern"},
+                {"id": "t2", "url": "", "title": "Thunder Storm"}
+            ]
+        }))
+        .unwrap();
+        let aliases = Aliases::new().alias("t2", "Tempestade");
+
+        let found = find_track_with_aliases(&response, "tempestade", &aliases);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].item.id, "t2");
+        assert_eq!(found[0].score, 1.0);
+
+        assert_eq!(
+            find_track_with_aliases(&response, "tavern", &aliases)[0]
+                .item
+                .id,
+            "t1"
+        );
+    }
+}
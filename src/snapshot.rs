@@ -0,0 +1,239 @@
+//! Fetching everything the Kenku Remote knows in one call, and putting the playback back the way it was.
+use crate::{
+    batch::BatchReport,
+    playlist::{PlaylistGetResponse, PlaylistPlaybackResponse},
+    soundboard::{SoundboardGetResponse, SoundboardPlaybackResponse},
+    Controller, KenkuCommandWithPayload, KenkuError, KenkuPlaybackCommand,
+};
+use serde::{Deserialize, Serialize};
+
+/// Represents the full state of the Kenku Remote at one point in time.
+///
+/// # Fields
+///
+/// * `soundboard` - The soundboards and their sounds.
+/// * `soundboard_playback` - The sounds currently playing.
+/// * `playlist` - The playlists and their tracks.
+/// * `playlist_playback` - The current playlist playback.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct KenkuSnapshot {
+    pub soundboard: SoundboardGetResponse,
+    pub soundboard_playback: SoundboardPlaybackResponse,
+    pub playlist: PlaylistGetResponse,
+    pub playlist_playback: PlaylistPlaybackResponse,
+}
+
+/// Represents the playlist and soundboard playback at one point in time.
+///
+/// # Fields
+///
+/// * `playlist` - The playlist playback: volume, mute, shuffle, repeat and the current track.
+/// * `soundboard` - The sounds that were playing.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct PlaybackSnapshot {
+    pub playlist: PlaylistPlaybackResponse,
+    pub soundboard: SoundboardPlaybackResponse,
+}
+
+impl PlaybackSnapshot {
+    /// Lists the commands that bring the playback from `current` back to this snapshot.
+    ///
+    /// Settings that already match are skipped. The volume, mute, shuffle and repeat modes come
+    /// first, so a resumed track starts at the saved volume. A different current track is played
+    /// from its start, since Kenku Remote cannot seek; the same track resumes where it is.
+    /// Sounds playing now but not in the snapshot are stopped, and the saved ones are played
+    /// again from their start.
+    ///
+    /// # Returns
+    ///
+    /// The commands to send, in order.
+    pub fn restore_commands(&self, current: &PlaybackSnapshot) -> Vec<KenkuCommandWithPayload> {
+        let (saved, now) = (&self.playlist, &current.playlist);
+        let mut commands = Vec::new();
+
+        if saved.volume != now.volume {
+            commands
+                .push(KenkuPlaybackCommand::PlaylistPlaybackVolume(saved.volume_level()).into());
+        }
+
+        if saved.muted != now.muted {
+            commands.push(KenkuPlaybackCommand::PlaylistPlaybackMute(saved.muted).into());
+        }
+
+        if saved.shuffle != now.shuffle {
+            commands.push(KenkuPlaybackCommand::PlaylistPlaybackShuffle(saved.shuffle).into());
+        }
+
+        if saved.repeat != now.repeat {
+            commands
+                .push(KenkuPlaybackCommand::PlaylistPlaybackRepeat(saved.repeat.clone()).into());
+        }
+
+        let track_id = |playback: &PlaylistPlaybackResponse| {
+            playback.track.as_ref().map(|track| track.id.clone())
+        };
+
+        let playing = match track_id(saved) {
+            Some(id) if track_id(now).as_ref() != Some(&id) => {
+                commands.push(KenkuCommandWithPayload::PlayTrack(id));
+                true
+            }
+            _ => now.playing,
+        };
+
+        match (playing, saved.playing) {
+            (true, false) => commands.push(KenkuPlaybackCommand::PlaylistPlaybackPause.into()),
+            (false, true) if saved.track.is_some() => {
+                commands.push(KenkuPlaybackCommand::PlaylistPlaybackPlay.into())
+            }
+            _ => {}
+        }
+
+        for sound in &current.soundboard.sounds {
+            if !self.soundboard.is_playing(&sound.id) {
+                commands.push(KenkuCommandWithPayload::StopSound(sound.id.clone()));
+            }
+        }
+
+        for sound in &self.soundboard.sounds {
+            if !current.soundboard.is_playing(&sound.id) {
+                commands.push(KenkuCommandWithPayload::PlaySound(sound.id.clone()));
+            }
+        }
+
+        commands
+    }
+}
+
+impl Controller {
+    /// Fetches the playlist and soundboard playback together.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is either a `PlaybackSnapshot` or a `KenkuError`.
+    pub async fn snapshot(&self) -> Result<PlaybackSnapshot, KenkuError> {
+        Ok(PlaybackSnapshot {
+            playlist: self.get_playlist_playback().await?,
+            soundboard: self.get_soundboard_playback().await?,
+        })
+    }
+
+    /// Puts the playback back the way it was when `snapshot` was taken.
+    ///
+    /// The current playback is fetched first and only the commands that differ are sent, as
+    /// listed by `PlaybackSnapshot::restore_commands`. A failed command does not stop the others.
+    ///
+    /// ```no_run
+    /// # async fn break_time() -> Result<(), kenku_control::KenkuError> {
+    /// use kenku_control::{playlist::playback, Controller};
+    ///
+    /// let controller = Controller::new("127.0.0.1", 3333);
+    /// let before = controller.snapshot().await?;
+    ///
+    /// playback::playback_pause(&controller).await?;
+    /// // ... the break ...
+    /// controller.restore(&before).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is either a `BatchReport` of the commands sent, or the `KenkuError` raised while fetching the current playback.
+    pub async fn restore(&self, snapshot: &PlaybackSnapshot) -> Result<BatchReport, KenkuError> {
+        let current = self.snapshot().await?;
+
+        Ok(self
+            .execute_batch(snapshot.restore_commands(&current))
+            .await)
+    }
+
+    /// Fetches the soundboards, the soundboard playback, the playlists and the playlist playback at the same time.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is either a `KenkuSnapshot` or the first `KenkuError` among the four requests.
+    pub async fn get_all(&self) -> Result<KenkuSnapshot, KenkuError> {
+        let (soundboard, soundboard_playback, playlist, playlist_playback) = tokio::join!(
+            self.get_soundboard(),
+            self.get_soundboard_playback(),
+            self.get_playlist(),
+            self.get_playlist_playback(),
+        );
+
+        Ok(KenkuSnapshot {
+            soundboard: soundboard?,
+            soundboard_playback: soundboard_playback?,
+            playlist: playlist?,
+            playlist_playback: playlist_playback?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PlaybackSnapshot;
+    use crate::{
+        playlist::{PlaylistPlaybackResponse, Repeat},
+        testing::{self, MockRemote},
+        KenkuCommandWithPayload, KenkuPlaybackCommand, Volume,
+    };
+
+    #[tokio::test]
+    async fn get_all_fetches_every_endpoint() {
+        let remote = MockRemote::start().await;
+        remote.set_playing_sounds(["s1"]);
+
+        let snapshot = remote.controller().get_all().await.unwrap();
+
+        assert_eq!(snapshot.soundboard.soundboards.len(), 1);
+        assert_eq!(snapshot.soundboard_playback.sounds.len(), 1);
+        assert_eq!(snapshot.playlist.playlists.len(), 2);
+        assert!(!snapshot.playlist_playback.playing);
+    }
+
+    fn playback(
+        track: Option<&str>,
+        playing: bool,
+        volume: f64,
+        sounds: &[&str],
+    ) -> PlaybackSnapshot {
+        let playlist = PlaylistPlaybackResponse {
+            playing,
+            volume,
+            ..testing::playback(track.map(testing::track))
+        };
+
+        testing::snapshot(playlist, sounds)
+    }
+
+    #[test]
+    fn restore_sends_only_what_changed() {
+        let saved = playback(Some("t1"), true, 0.5, &["rain", "wind"]);
+
+        assert!(saved.restore_commands(&saved).is_empty());
+
+        let paused = playback(Some("t1"), false, 0.5, &["wind", "thunder"]);
+        assert_eq!(
+            saved.restore_commands(&paused),
+            vec![
+                KenkuPlaybackCommand::PlaylistPlaybackPlay.into(),
+                KenkuCommandWithPayload::StopSound("thunder".to_string()),
+                KenkuCommandWithPayload::PlaySound("rain".to_string()),
+            ]
+        );
+
+        let mut moved_on = playback(Some("t2"), true, 1.0, &["rain", "wind"]);
+        moved_on.playlist.repeat = Repeat::Track;
+        let paused_saved = playback(Some("t1"), false, 0.5, &["rain", "wind"]);
+        assert_eq!(
+            paused_saved.restore_commands(&moved_on),
+            vec![
+                KenkuPlaybackCommand::PlaylistPlaybackVolume(Volume::from_percent(50)).into(),
+                KenkuPlaybackCommand::PlaylistPlaybackRepeat(Repeat::Off).into(),
+                KenkuCommandWithPayload::PlayTrack("t1".to_string()),
+                KenkuPlaybackCommand::PlaylistPlaybackPause.into(),
+            ]
+        );
+    }
+}
@@ -0,0 +1,449 @@
+//! Soak runs, checking that a controller holds up over a whole session.
+//!
+//! A bridge driving a 10-hour charity marathon has to run far longer than any test. A `Soak`
+//! watches the playback through `Controller::events` while sending rounds of commands at a steady
+//! pace, and samples the memory and open file descriptors of the process as it goes.
+//! `SoakReport::anomalies` then flags the growth, failures and slow-downs that would take a long
+//! session down. The commands change nothing that is heard: each round reads the playlist
+//! playback and sends its volume and mute state back as they are, so a soak can run against the
+//! Kenku Remote of a live show as well as against `testing::MockRemote`.
+//!
+//! The resident memory and the open files are read from `/proc`, so they are only sampled on
+//! Linux. Elsewhere the report still covers failures and round times.
+use crate::{Controller, KenkuError, KenkuPlaybackCommand};
+use futures_util::StreamExt;
+use std::{fmt, pin::pin, time::Duration};
+use tokio::time::{interval, sleep_until, Instant, MissedTickBehavior};
+
+/// How much the resident memory may grow, in bytes, before it is flagged.
+const MEMORY_GROWTH: u64 = 16 * 1024 * 1024;
+
+/// How many file descriptors, sockets included, may be opened before it is flagged.
+const FILE_GROWTH: usize = 16;
+
+/// How much slower than at the start a round may get before it is flagged.
+const SLOW_DOWN: Duration = Duration::from_millis(100);
+
+/// Configures a soak run.
+///
+/// # Fields
+///
+/// * `duration` - How long the run lasts.
+/// * `command_interval` - The time between two rounds of commands.
+/// * `poll_interval` - The time between two polls of the event stream.
+/// * `sample_interval` - The time between two samples of the process.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Soak {
+    pub duration: Duration,
+    pub command_interval: Duration,
+    pub poll_interval: Duration,
+    pub sample_interval: Duration,
+}
+
+/// Represents the state of the run at one point in time.
+///
+/// # Fields
+///
+/// * `elapsed` - The time since the run started.
+/// * `resident_bytes` - The resident memory of the process, if known.
+/// * `open_files` - The file descriptors open in the process, sockets included, if known.
+/// * `rounds` - How many rounds of commands were sent so far.
+/// * `failures` - How many of those rounds failed.
+/// * `events` - How many events the event stream yielded so far.
+/// * `round_time` - The mean time of the rounds sent since the previous sample, if any were.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SoakSample {
+    pub elapsed: Duration,
+    pub resident_bytes: Option<u64>,
+    pub open_files: Option<usize>,
+    pub rounds: u64,
+    pub failures: u64,
+    pub events: u64,
+    pub round_time: Option<Duration>,
+}
+
+/// Represents something in a soak run that would not last a long session.
+///
+/// # Variants
+///
+/// * `MemoryGrowth` - The resident memory grew by half and by more than 16 MiB.
+/// * `FileGrowth` - More than 16 file descriptors were left open, usually leaked connections.
+/// * `Failures` - More than 1% of the rounds failed.
+/// * `SlowDown` - Rounds got three times slower, and slower by more than 100 ms.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Anomaly {
+    MemoryGrowth { from: u64, to: u64 },
+    FileGrowth { from: usize, to: usize },
+    Failures { failures: u64, rounds: u64 },
+    SlowDown { from: Duration, to: Duration },
+}
+
+/// Holds the samples taken during a soak run.
+///
+/// # Fields
+///
+/// * `samples` - The samples, oldest first. The last one is taken when the run ends.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SoakReport {
+    pub samples: Vec<SoakSample>,
+}
+
+impl Default for Soak {
+    fn default() -> Soak {
+        Soak {
+            duration: Duration::from_secs(60 * 60),
+            command_interval: Duration::from_secs(5),
+            poll_interval: Duration::from_millis(500),
+            sample_interval: Duration::from_secs(60),
+        }
+    }
+}
+
+impl Soak {
+    /// Creates a one-hour run, sending a round every 5 seconds and sampling every minute.
+    pub fn new() -> Soak {
+        Soak::default()
+    }
+
+    /// Sets how long the run lasts.
+    pub fn duration(mut self, duration: Duration) -> Soak {
+        self.duration = duration;
+        self
+    }
+
+    /// Sets the time between two rounds of commands, at least a millisecond.
+    pub fn command_interval(mut self, command_interval: Duration) -> Soak {
+        self.command_interval = command_interval.max(Duration::from_millis(1));
+        self
+    }
+
+    /// Sets the time between two polls of the event stream, at least a millisecond.
+    pub fn poll_interval(mut self, poll_interval: Duration) -> Soak {
+        self.poll_interval = poll_interval.max(Duration::from_millis(1));
+        self
+    }
+
+    /// Sets the time between two samples of the process, at least a millisecond.
+    pub fn sample_interval(mut self, sample_interval: Duration) -> Soak {
+        self.sample_interval = sample_interval.max(Duration::from_millis(1));
+        self
+    }
+
+    /// Runs the soak against `controller` until `duration` has elapsed.
+    ///
+    /// A `duration` too long for the clock to reach, such as `Duration::MAX`, runs until the
+    /// returned future is dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `controller` - The controller to wear down.
+    /// * `on_sample` - Called with every sample as soon as it is taken, to show progress.
+    ///
+    /// # Returns
+    ///
+    /// The `SoakReport` holding every sample. Failed rounds are counted, never returned.
+    pub async fn run(
+        &self,
+        controller: &Controller,
+        mut on_sample: impl FnMut(&SoakSample),
+    ) -> SoakReport {
+        let started = Instant::now();
+        let deadline = started.checked_add(self.duration);
+        let mut finished = pin!(async move {
+            match deadline {
+                Some(deadline) => sleep_until(deadline).await,
+                None => std::future::pending().await,
+            }
+        });
+        let mut events = pin!(controller.events(self.poll_interval));
+        let mut rounds = interval(self.command_interval);
+        let mut samples = interval(self.sample_interval);
+        rounds.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        samples.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        let mut report = SoakReport::default();
+        let mut counts = Counts::default();
+
+        loop {
+            tokio::select! {
+                _ = &mut finished => break,
+                Some(_) = events.next() => counts.events += 1,
+                _ = rounds.tick() => {
+                    let sent = Instant::now();
+                    let succeeded = round(controller).await.is_ok_and(|success| success);
+
+                    counts.rounds += 1;
+                    counts.failures += u64::from(!succeeded);
+                    counts.window.push(sent.elapsed());
+                }
+                _ = samples.tick() => {
+                    let sample = counts.sample(started.elapsed());
+                    on_sample(&sample);
+                    report.samples.push(sample);
+                }
+            }
+        }
+
+        let sample = counts.sample(started.elapsed());
+        on_sample(&sample);
+        report.samples.push(sample);
+        report
+    }
+}
+
+impl SoakReport {
+    /// Returns what in the run would not last a long session.
+    ///
+    /// The last sample is compared with the first one taken once the run was under way: the
+    /// second sample when there are more than two, so the connections and buffers set up by the
+    /// first rounds are not mistaken for growth.
+    pub fn anomalies(&self) -> Vec<Anomaly> {
+        let (Some(first), Some(last)) = (
+            self.samples.get(usize::from(self.samples.len() > 2)),
+            self.samples.last(),
+        ) else {
+            return Vec::new();
+        };
+        let mut anomalies = Vec::new();
+
+        if let (Some(from), Some(to)) = (first.resident_bytes, last.resident_bytes) {
+            if to > from + (from / 2).max(MEMORY_GROWTH) {
+                anomalies.push(Anomaly::MemoryGrowth { from, to });
+            }
+        }
+
+        if let (Some(from), Some(to)) = (first.open_files, last.open_files) {
+            if to > from + FILE_GROWTH {
+                anomalies.push(Anomaly::FileGrowth { from, to });
+            }
+        }
+
+        if last.failures * 100 > last.rounds {
+            anomalies.push(Anomaly::Failures {
+                failures: last.failures,
+                rounds: last.rounds,
+            });
+        }
+
+        let mut round_times = self.samples.iter().filter_map(|sample| sample.round_time);
+
+        if let (Some(from), Some(to)) = (round_times.clone().next(), round_times.next_back()) {
+            if to > from * 3 && to > from + SLOW_DOWN {
+                anomalies.push(Anomaly::SlowDown { from, to });
+            }
+        }
+
+        anomalies
+    }
+}
+
+/// Formats the sample on one line, such as `1:00:00 48.2 MiB, 23 files, 720 rounds (0 failed), 3 events, 4 ms per round`.
+impl fmt::Display for SoakSample {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let seconds = self.elapsed.as_secs();
+        write!(
+            f,
+            "{}:{:02}:{:02}",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        )?;
+
+        if let Some(bytes) = self.resident_bytes {
+            write!(f, " {:.1} MiB,", mebibytes(bytes))?;
+        }
+
+        if let Some(files) = self.open_files {
+            write!(f, " {} files,", files)?;
+        }
+
+        write!(
+            f,
+            " {} rounds ({} failed), {} events",
+            self.rounds, self.failures, self.events
+        )?;
+
+        match self.round_time {
+            Some(time) => write!(f, ", {} ms per round", time.as_millis()),
+            None => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for Anomaly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Anomaly::MemoryGrowth { from, to } => write!(
+                f,
+                "resident memory grew from {:.1} MiB to {:.1} MiB",
+                mebibytes(*from),
+                mebibytes(*to)
+            ),
+            Anomaly::FileGrowth { from, to } => {
+                write!(f, "open files grew from {} to {}", from, to)
+            }
+            Anomaly::Failures { failures, rounds } => {
+                write!(f, "{} of {} rounds failed", failures, rounds)
+            }
+            Anomaly::SlowDown { from, to } => write!(
+                f,
+                "rounds slowed down from {} ms to {} ms",
+                from.as_millis(),
+                to.as_millis()
+            ),
+        }
+    }
+}
+
+/// The counters of a run, and the round times since the previous sample.
+#[derive(Default)]
+struct Counts {
+    rounds: u64,
+    failures: u64,
+    events: u64,
+    window: Vec<Duration>,
+}
+
+impl Counts {
+    /// Samples the process, and starts a new window of round times.
+    fn sample(&mut self, elapsed: Duration) -> SoakSample {
+        let window = std::mem::take(&mut self.window);
+
+        SoakSample {
+            elapsed,
+            resident_bytes: resident_bytes(),
+            open_files: open_files(),
+            rounds: self.rounds,
+            failures: self.failures,
+            events: self.events,
+            round_time: (!window.is_empty())
+                .then(|| window.iter().sum::<Duration>() / window.len() as u32),
+        }
+    }
+}
+
+/// Sends one round of commands: the playlist volume and mute state, as the remote reports them.
+///
+/// # Returns
+///
+/// This function returns whether every command got a success status, or the first `KenkuError`.
+async fn round(controller: &Controller) -> Result<bool, KenkuError> {
+    let playback = controller.get_playlist_playback().await?;
+    let volume = controller
+        .execute(&KenkuPlaybackCommand::PlaylistPlaybackVolume(playback.volume_level()).into())
+        .await?;
+    let mute = controller
+        .execute(&KenkuPlaybackCommand::PlaylistPlaybackMute(playback.muted).into())
+        .await?;
+
+    Ok(volume.is_success() && mute.is_success())
+}
+
+/// Reads the resident memory of the process from `/proc/self/status`.
+fn resident_bytes() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let kibibytes = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))?
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+
+    Some(kibibytes * 1024)
+}
+
+/// Counts the file descriptors open in the process through `/proc/self/fd`.
+fn open_files() -> Option<usize> {
+    Some(std::fs::read_dir("/proc/self/fd").ok()?.count())
+}
+
+fn mebibytes(bytes: u64) -> f64 {
+    bytes as f64 / (1024.0 * 1024.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Anomaly, Soak, SoakReport, SoakSample};
+    use crate::testing::MockRemote;
+    use std::time::Duration;
+
+    fn sample(
+        minutes: u64,
+        megabytes: u64,
+        files: usize,
+        rounds: u64,
+        failures: u64,
+    ) -> SoakSample {
+        SoakSample {
+            elapsed: Duration::from_secs(minutes * 60),
+            resident_bytes: Some(megabytes * 1024 * 1024),
+            open_files: Some(files),
+            rounds,
+            failures,
+            events: 0,
+            round_time: Some(Duration::from_millis(4)),
+        }
+    }
+
+    #[test]
+    fn a_steady_run_has_no_anomalies() {
+        let report = SoakReport {
+            samples: vec![
+                sample(0, 8, 10, 0, 0),
+                sample(1, 20, 14, 12, 0),
+                sample(600, 22, 15, 7200, 3),
+            ],
+        };
+
+        assert_eq!(report.anomalies(), []);
+        assert_eq!(
+            report.samples[2].to_string(),
+            "10:00:00 22.0 MiB, 15 files, 7200 rounds (3 failed), 0 events, 4 ms per round"
+        );
+    }
+
+    #[test]
+    fn leaks_failures_and_slow_downs_are_flagged() {
+        let mut last = sample(600, 400, 300, 7200, 200);
+        last.round_time = Some(Duration::from_millis(900));
+        let report = SoakReport {
+            samples: vec![sample(0, 8, 10, 0, 0), sample(1, 20, 14, 12, 0), last],
+        };
+
+        assert_eq!(
+            report.anomalies(),
+            [
+                Anomaly::MemoryGrowth {
+                    from: 20 * 1024 * 1024,
+                    to: 400 * 1024 * 1024
+                },
+                Anomaly::FileGrowth { from: 14, to: 300 },
+                Anomaly::Failures {
+                    failures: 200,
+                    rounds: 7200
+                },
+                Anomaly::SlowDown {
+                    from: Duration::from_millis(4),
+                    to: Duration::from_millis(900)
+                },
+            ]
+        );
+        assert_eq!(SoakReport::default().anomalies(), []);
+    }
+
+    #[tokio::test]
+    async fn zero_intervals_are_taken_as_a_millisecond() {
+        let remote = MockRemote::start().await;
+        let report = Soak::new()
+            .duration(Duration::from_millis(50))
+            .command_interval(Duration::ZERO)
+            .poll_interval(Duration::ZERO)
+            .sample_interval(Duration::ZERO)
+            .run(&remote.controller(), |_| {})
+            .await;
+
+        assert!(!report.samples.is_empty());
+    }
+}
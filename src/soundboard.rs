@@ -1,10 +1,15 @@
 /// all the content of Soundboard of Kenku FM
 use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
-use serde_json::json;
 
 use super::*;
 
+/// The longest `Sounds::wait_until_finished` waits between two checks of the playback.
+const FINISH_POLL: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// The shortest `Sounds::wait_until_finished` waits between two checks of the playback.
+const FINISH_MIN_POLL: std::time::Duration = std::time::Duration::from_millis(50);
+
 /// Represents the response from a GET request to a soundboard.
 ///
 /// This struct is used to model the response from a GET request to a soundboard. It includes a vector of `Soundboards` and a vector of `Sounds`.
@@ -13,22 +18,95 @@ use super::*;
 ///
 /// * `soundboards` - A vector of `Soundboards` representing the soundboards in the response.
 /// * `sounds` - A vector of `Sounds` representing the sounds in the response.
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct SoundboardGetResponse {
     pub soundboards: Vec<Soundboards>,
     pub sounds: Vec<Sounds>,
 }
 
+impl SoundboardGetResponse {
+    /// Returns the soundboard with the id `id`, if any.
+    pub fn soundboard_by_id(&self, id: &str) -> Option<&Soundboards> {
+        self.soundboards
+            .iter()
+            .find(|soundboard| soundboard.id == id)
+    }
+
+    /// Returns the first soundboard titled `title`, compared case-insensitively.
+    pub fn soundboard_by_title(&self, title: &str) -> Option<&Soundboards> {
+        self.soundboards
+            .iter()
+            .find(|soundboard| soundboard.title.eq_ignore_ascii_case(title))
+    }
+
+    /// Returns the sound with the id `id`, if any.
+    pub fn sound_by_id(&self, id: &str) -> Option<&Sounds> {
+        self.sounds.iter().find(|sound| sound.id == id)
+    }
+
+    /// Returns the first sound titled `title`, compared case-insensitively.
+    pub fn sound_by_title(&self, title: &str) -> Option<&Sounds> {
+        self.sounds
+            .iter()
+            .find(|sound| sound.title.eq_ignore_ascii_case(title))
+    }
+
+    /// Returns the sounds of `soundboard`, in the soundboard's order.
+    ///
+    /// Sound ids that are missing from this response are skipped.
+    pub fn sounds_of_soundboard(&self, soundboard: &Soundboards) -> Vec<&Sounds> {
+        soundboard
+            .sounds
+            .iter()
+            .filter_map(|id| self.sound_by_id(id))
+            .collect()
+    }
+}
+
 /// Represents the response from a playback request to a soundboard.
 ///
 /// This struct is used to model the response from a playback request to a soundboard. It includes a vector of `Sounds`.
 ///
 /// # Fields
 ///
-/// * `sounds` - A vector of `Sounds` representing the sounds in the response.
-#[derive(Debug, Deserialize, Serialize, Clone)]
+/// * `sounds` - A vector of `Sounds` representing the sounds currently playing.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct SoundboardPlaybackResponse {
-    sounds: Vec<Sounds>,
+    pub sounds: Vec<Sounds>,
+}
+
+impl SoundboardPlaybackResponse {
+    /// Returns the sounds currently playing.
+    pub fn playing_sounds(&self) -> &[Sounds] {
+        &self.sounds
+    }
+
+    /// Returns `true` if the sound with the id `id` is currently playing.
+    pub fn is_playing(&self, id: &str) -> bool {
+        self.sounds.iter().any(|sound| sound.id == id)
+    }
+
+    /// Returns how long the sound with the id `id` still plays before it ends or loops.
+    ///
+    /// # Returns
+    ///
+    /// This function returns `None` if the sound is not playing or the remote did not report its duration and progress.
+    pub fn remaining_time(&self, id: &str) -> Option<std::time::Duration> {
+        self.sounds
+            .iter()
+            .find(|sound| sound.id == id)?
+            .remaining_time()
+    }
+
+    /// Returns each sound currently playing with how long it still plays before it ends or loops.
+    ///
+    /// Sounds whose duration and progress the remote did not report are left out.
+    pub fn remaining(&self) -> Vec<(&Sounds, std::time::Duration)> {
+        self.sounds
+            .iter()
+            .filter_map(|sound| Some((sound, sound.remaining_time()?)))
+            .collect()
+    }
 }
 
 /// Represents a soundboard.
@@ -42,7 +120,7 @@ pub struct SoundboardPlaybackResponse {
 /// * `background` - A string representing the background of the soundboard.
 /// * `title` - The title of the soundboard.
 #[serde_with::skip_serializing_none]
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct Soundboards {
     pub id: String,
     pub sounds: Vec<String>,
@@ -66,7 +144,7 @@ pub struct Soundboards {
 /// * `duration` - The total duration of the sound, in milliseconds. This is an optional field and only is need in playback response.
 /// * `progress` - The current progress of the sound, starts in 0 and go to duration. This is an optional field and only is need in playback response.
 #[serde_with::skip_serializing_none]
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct Sounds {
     pub id: String,
     pub url: String,
@@ -83,6 +161,38 @@ pub struct Sounds {
 }
 
 impl Sounds {
+    /// Returns the volume of this sound as a `Volume`, clamped to the valid range.
+    pub fn volume_level(&self) -> Volume {
+        Volume::clamped(self.volume)
+    }
+
+    /// Returns the `duration` of the sound as a `Duration`, if the remote reported it.
+    pub fn duration_time(&self) -> Option<std::time::Duration> {
+        self.duration
+            .map(|millis| std::time::Duration::from_millis(millis.into()))
+    }
+
+    /// Returns the `progress` of the sound as a `Duration`, if the remote reported it. A negative
+    /// progress counts as zero, and one too large for a `Duration` as unreported.
+    pub fn progress_time(&self) -> Option<std::time::Duration> {
+        std::time::Duration::try_from_secs_f64(self.progress?.max(0.0) / 1000.0).ok()
+    }
+
+    /// Returns how long the sound still plays before it ends or loops, if the remote reported its
+    /// duration and progress.
+    pub fn remaining_time(&self) -> Option<std::time::Duration> {
+        Some(self.duration_time()?.saturating_sub(self.progress_time()?))
+    }
+
+    /// Returns how far the sound is, from 0.0 to 100.0.
+    ///
+    /// # Returns
+    ///
+    /// This function returns `None` if the remote did not report the duration and progress, or the duration is zero.
+    pub fn percent_complete(&self) -> Option<f64> {
+        playlist::percent(self.progress?, f64::from(self.duration?))
+    }
+
     /// Sends a request to the Kenku server to play a specific sound in the soundboard.
     ///
     /// This function constructs a URL for the 'SoundboardPlay' command, sends a PUT request to that URL with the track ID as JSON payload, and returns the HTTP status code of the response.
@@ -94,23 +204,9 @@ impl Sounds {
     ///
     /// # Returns
     ///
-    /// This function returns a `Result` that contains a `StatusCode` if the request was sent successfully, or a `reqwest::Error` if the request failed.
-    pub async fn play(&self, controller: &Controller) -> Result<StatusCode, reqwest::Error> {
-        let command = &KenkuCommand::KenkuPut(KenkuPutCommand::SoundboardPlay);
-
-        let url = process_url(command, controller.address);
-        let json = json!({"id": self.id});
-
-        let response = controller
-            .client
-            .put(url)
-            .header("Content-Type", "application/json")
-            .json(&json)
-            .send()
-            .await?
-            .status();
-
-        Ok(response)
+    /// This function returns a `Result` that contains a `StatusCode` if the request was sent successfully, or a `KenkuError` if the request failed.
+    pub async fn play(&self, controller: &Controller) -> Result<StatusCode, KenkuError> {
+        controller.play_sound_id(&self.id).await
     }
 
     /// Sends a request to the Kenku server to stop a specific sound in the soundboard.
@@ -124,22 +220,149 @@ impl Sounds {
     ///
     /// # Returns
     ///
-    /// This function returns a `Result` that contains a `StatusCode` if the request was sent successfully, or a `reqwest::Error` if the request failed.
-    pub async fn stop(&self, controller: &Controller) -> Result<StatusCode, reqwest::Error> {
-        let command = &KenkuCommand::KenkuPut(KenkuPutCommand::SoundboardStop);
+    /// This function returns a `Result` that contains a `StatusCode` if the request was sent successfully, or a `KenkuError` if the request failed.
+    pub async fn stop(&self, controller: &Controller) -> Result<StatusCode, KenkuError> {
+        controller.stop_sound_id(&self.id).await
+    }
+
+    /// Waits until this sound is no longer playing on the soundboard.
+    ///
+    /// The soundboard playback is polled, more often as the sound nears its end, so one-shot
+    /// effects can be chained without guessing their length. A looping sound only finishes once
+    /// it is stopped.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - A reference to the `Sound` struct, which represents a sound in the soundboard.
+    /// * `controller` - A reference to a `Controller` struct, which includes a HTTP client, the IP address and port of the server, and the current state of the server.
+    ///
+    /// # Returns
+    ///
+    /// This function returns `Ok(())` right away if the sound is not playing, or a `KenkuError` if the playback could not be fetched.
+    pub async fn wait_until_finished(&self, controller: &Controller) -> Result<(), KenkuError> {
+        loop {
+            let playback = controller.get_soundboard_playback().await?;
+
+            if !playback.is_playing(&self.id) {
+                return Ok(());
+            }
+
+            let wait = playback
+                .remaining_time(&self.id)
+                .map_or(FINISH_POLL, |remaining| {
+                    remaining.clamp(FINISH_MIN_POLL, FINISH_POLL)
+                });
+
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+pub mod playback {
+    use super::{Controller, KenkuError, Sounds, StatusCode};
+    use futures_util::future::join_all;
+
+    /// Stops every sound currently playing on the soundboard.
+    ///
+    /// This function fetches the soundboard playback and sends a stop command for each playing sound, all at the same time.
+    ///
+    /// # Arguments
+    ///
+    /// * `controller` - A reference to a `Controller` struct, which includes a HTTP client, the IP address and port of the server, and the current state of the server.
+    ///
+    /// # Returns
+    ///
+    /// This function returns every sound that was playing with the result of its stop command, or a `KenkuError` if the playback could not be fetched.
+    pub async fn stop_all(
+        controller: &Controller,
+    ) -> Result<Vec<(Sounds, Result<StatusCode, KenkuError>)>, KenkuError> {
+        let playback = controller.get_soundboard_playback().await?;
+        let results = join_all(playback.sounds.iter().map(|sound| sound.stop(controller))).await;
+
+        Ok(playback.sounds.into_iter().zip(results).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{playback, SoundboardGetResponse, SoundboardPlaybackResponse};
+    use crate::testing::MockRemote;
+    use serde_json::json;
+    use std::time::Duration;
+
+    #[test]
+    fn lookups_join_soundboards_and_sounds() {
+        let response: SoundboardGetResponse = serde_json::from_value(json!({
+            "soundboards": [{"id": "b1", "title": "Weather", "background": "", "sounds": ["s2", "gone", "s1"]}],
+            "sounds": [
+                {"id": "s1", "url": "", "title": "Rain", "loop": true, "volume": 1.0, "fadeIn": 0, "fadeOut": 0},
+                {"id": "s2", "url": "", "title": "Thunder", "loop": false, "volume": 1.0, "fadeIn": 0, "fadeOut": 0}
+            ]
+        }))
+        .unwrap();
+
+        let soundboard = response.soundboard_by_title("weather").unwrap();
+        let sounds: Vec<&str> = response
+            .sounds_of_soundboard(soundboard)
+            .iter()
+            .map(|sound| sound.title.as_str())
+            .collect();
+
+        assert_eq!(sounds, ["Thunder", "Rain"]);
+        assert_eq!(response.sound_by_title("RAIN").unwrap().id, "s1");
+        assert!(response.soundboard_by_id("missing").is_none());
+    }
+
+    #[tokio::test]
+    async fn stop_all_stops_every_playing_sound() {
+        let remote = MockRemote::start().await;
+        remote.set_playing_sounds(["s1", "s2"]);
+
+        let results = playback::stop_all(&remote.controller()).await.unwrap();
+
+        let stopped: Vec<&str> = results.iter().map(|(sound, _)| sound.id.as_str()).collect();
+        assert_eq!(stopped, ["s1", "s2"]);
+        assert!(results.iter().all(|(_, result)| result.is_ok()));
+        assert!(remote.playing_sounds().is_empty());
+    }
+
+    #[test]
+    fn helpers_read_the_playing_sounds() {
+        let playback: SoundboardPlaybackResponse = serde_json::from_value(json!({"sounds": [{
+            "id": "rain", "url": "", "title": "Rain", "loop": true, "volume": 1.0,
+            "fadeIn": 0, "fadeOut": 0, "duration": 5000, "progress": 1500.0
+        }]}))
+        .unwrap();
 
-        let url = process_url(command, controller.address);
-        let json = json!({"id": self.id});
+        assert_eq!(playback.playing_sounds().len(), 1);
+        assert!(playback.is_playing("rain"));
+        assert!(!playback.is_playing("thunder"));
+        assert_eq!(
+            playback.remaining_time("rain"),
+            Some(Duration::from_millis(3500))
+        );
+        assert_eq!(playback.remaining_time("thunder"), None);
+        assert_eq!(
+            playback
+                .remaining()
+                .iter()
+                .map(|(sound, remaining)| (sound.id.as_str(), *remaining))
+                .collect::<Vec<_>>(),
+            [("rain", Duration::from_millis(3500))]
+        );
 
-        let response = controller
-            .client
-            .put(url)
-            .header("Content-Type", "application/json")
-            .json(&json)
-            .send()
-            .await?
-            .status();
+        let rain = &playback.sounds[0];
+        assert_eq!(rain.duration_time(), Some(Duration::from_secs(5)));
+        assert_eq!(rain.progress_time(), Some(Duration::from_millis(1500)));
+        assert_eq!(rain.percent_complete(), Some(30.0));
+        assert_eq!(rain.remaining_time(), Some(Duration::from_millis(3500)));
 
-        Ok(response)
+        let mut huge = rain.clone();
+        huge.progress = Some(1e300);
+        assert_eq!(huge.progress_time(), None);
+        assert_eq!(
+            SoundboardPlaybackResponse { sounds: vec![huge] }.remaining_time("rain"),
+            None
+        );
     }
 }
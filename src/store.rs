@@ -0,0 +1,266 @@
+//! Keeping the persisted state of each feature apart.
+//!
+//! Features that remember things between sessions, such as grid layouts, statistics, cooldowns or
+//! bookmarks, each get their own `Namespace` under a common `StateStore` directory. A namespace is
+//! a subdirectory holding one file per key, so one feature can be listed or reset without touching
+//! the state of the others.
+use crate::{format::Format, KenkuError};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Represents the directory holding the persisted state of every feature.
+///
+/// # Fields
+///
+/// * `root` - The directory the namespaces are kept in. It is created on the first save.
+/// * `format` - The format new values are saved in. Values already saved in another enabled format are still read.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StateStore {
+    pub root: PathBuf,
+    pub format: Format,
+}
+
+impl StateStore {
+    /// Creates a store in `root` saving values as JSON.
+    pub fn new(root: impl Into<PathBuf>) -> StateStore {
+        StateStore {
+            root: root.into(),
+            format: Format::Json,
+        }
+    }
+
+    /// Returns this store saving new values in `format`.
+    pub fn with_format(mut self, format: Format) -> StateStore {
+        self.format = format;
+        self
+    }
+
+    /// Returns the namespace `name`.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the `Namespace`, or a `KenkuError::InvalidInput` if `name` is not a valid namespace name.
+    pub fn namespace(&self, name: &str) -> Result<Namespace, KenkuError> {
+        check_name("namespace", name)?;
+
+        Ok(Namespace {
+            name: name.to_string(),
+            dir: self.root.join(name),
+            format: self.format,
+        })
+    }
+
+    /// Returns the names of the namespaces holding state, in alphabetical order.
+    pub fn namespaces(&self) -> Result<Vec<String>, KenkuError> {
+        let mut names = Vec::new();
+
+        for entry in read_dir(&self.root)? {
+            let entry = entry?;
+
+            if entry.file_type()?.is_dir() {
+                if let Some(name) = entry.file_name().to_str() {
+                    names.push(name.to_string());
+                }
+            }
+        }
+
+        names.sort();
+
+        Ok(names)
+    }
+
+    /// Deletes the state of the namespace `name`, leaving the other namespaces untouched.
+    pub fn clear(&self, name: &str) -> Result<(), KenkuError> {
+        self.namespace(name)?.clear()
+    }
+
+    /// Deletes the state of every namespace.
+    pub fn clear_all(&self) -> Result<(), KenkuError> {
+        for name in self.namespaces()? {
+            self.clear(&name)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Represents the persisted state of one feature.
+///
+/// Each key is stored in its own file, named after the key with the extension of its format.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Namespace {
+    name: String,
+    dir: PathBuf,
+    format: Format,
+}
+
+impl Namespace {
+    /// Returns the name of this namespace.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the directory this namespace is kept in.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Saves `value` under `key`, replacing the previous value in any format.
+    ///
+    /// # Returns
+    ///
+    /// This function returns `Ok(())` once the file is written, a `KenkuError::InvalidInput` if `key` is not a valid key, or the error raised while encoding or writing.
+    pub fn save<T: Serialize + ?Sized>(&self, key: &str, value: &T) -> Result<(), KenkuError> {
+        check_name("key", key)?;
+
+        let bytes = self.format.encode(value)?;
+        self.remove(key)?;
+        fs::create_dir_all(&self.dir)?;
+        fs::write(self.path(key, self.format), bytes)?;
+
+        Ok(())
+    }
+
+    /// Loads the value saved under `key`.
+    ///
+    /// # Returns
+    ///
+    /// This function returns `Ok(None)` when nothing is saved under `key`, the decoded value, or the error raised while reading or decoding.
+    pub fn load<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, KenkuError> {
+        check_name("key", key)?;
+
+        for format in Format::available() {
+            match fs::read(self.path(key, *format)) {
+                Ok(bytes) => return format.decode(&bytes).map(Some),
+                Err(error) if error.kind() == io::ErrorKind::NotFound => continue,
+                Err(error) => return Err(error.into()),
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Returns the keys holding a value, in alphabetical order.
+    pub fn keys(&self) -> Result<Vec<String>, KenkuError> {
+        let mut keys = Vec::new();
+
+        for entry in read_dir(&self.dir)? {
+            let path = entry?.path();
+
+            if Format::from_path(&path).is_some() {
+                if let Some(key) = path.file_stem().and_then(|stem| stem.to_str()) {
+                    keys.push(key.to_string());
+                }
+            }
+        }
+
+        keys.sort();
+        keys.dedup();
+
+        Ok(keys)
+    }
+
+    /// Deletes the value saved under `key`, if any.
+    pub fn remove(&self, key: &str) -> Result<(), KenkuError> {
+        check_name("key", key)?;
+
+        for format in Format::available() {
+            match fs::remove_file(self.path(key, *format)) {
+                Err(error) if error.kind() != io::ErrorKind::NotFound => return Err(error.into()),
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Deletes every value of this namespace.
+    pub fn clear(&self) -> Result<(), KenkuError> {
+        match fs::remove_dir_all(&self.dir) {
+            Err(error) if error.kind() != io::ErrorKind::NotFound => Err(error.into()),
+            _ => Ok(()),
+        }
+    }
+
+    fn path(&self, key: &str, format: Format) -> PathBuf {
+        self.dir.join(format!("{}.{}", key, format.extension()))
+    }
+}
+
+/// Lists `dir`, treating a missing directory as empty.
+fn read_dir(dir: &Path) -> Result<Vec<io::Result<fs::DirEntry>>, KenkuError> {
+    match fs::read_dir(dir) {
+        Ok(entries) => Ok(entries.collect()),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(error) => Err(error.into()),
+    }
+}
+
+/// Accepts names made of ASCII letters, digits, `-` and `_`, so they stay inside the store.
+fn check_name(kind: &str, name: &str) -> Result<(), KenkuError> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+    if valid {
+        Ok(())
+    } else {
+        Err(KenkuError::InvalidInput(format!(
+            "{} {:?} may only contain ASCII letters, digits, '-' and '_'",
+            kind, name
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StateStore;
+    use crate::KenkuError;
+
+    #[test]
+    fn namespaces_are_cleared_separately() {
+        let root = std::env::temp_dir().join(format!("kenku_control_store_{}", std::process::id()));
+        let store = StateStore::new(&root);
+        let layouts = store.namespace("layouts").unwrap();
+        let stats = store.namespace("stats").unwrap();
+
+        layouts.save("stream-deck", &vec![1, 2, 3]).unwrap();
+        stats.save("plays", &42).unwrap();
+        stats.save("skips", &3).unwrap();
+
+        assert_eq!(store.namespaces().unwrap(), ["layouts", "stats"]);
+        assert_eq!(stats.keys().unwrap(), ["plays", "skips"]);
+        assert_eq!(stats.load::<u32>("plays").unwrap(), Some(42));
+
+        store.clear("stats").unwrap();
+
+        assert_eq!(stats.load::<u32>("plays").unwrap(), None);
+        assert_eq!(
+            layouts.load::<Vec<u32>>("stream-deck").unwrap(),
+            Some(vec![1, 2, 3])
+        );
+
+        store.clear_all().unwrap();
+        assert!(store.namespaces().unwrap().is_empty());
+
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn names_cannot_leave_the_store() {
+        let store = StateStore::new("unused");
+
+        assert!(matches!(
+            store.namespace("../etc"),
+            Err(KenkuError::InvalidInput(_))
+        ));
+        assert!(matches!(
+            store.namespace("stats").unwrap().load::<u32>(""),
+            Err(KenkuError::InvalidInput(_))
+        ));
+    }
+}
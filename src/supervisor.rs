@@ -0,0 +1,402 @@
+//! Supervision of long-running background tasks.
+//!
+//! A `Supervisor` owns every task it spawns. When a task returns an error or panics it is
+//! restarted after an exponential backoff, and its health can be read at any time with
+//! `Supervisor::health`, e.g. to serve it from a health endpoint. A panic never takes a task
+//! down silently: it is turned into a `TaskFailure` carrying the panic message, published on
+//! `Supervisor::failures`, and handled like any other error. Only unwinding panics can be caught:
+//! a binary built with `panic = "abort"` exits on the first one.
+use crate::KenkuError;
+use std::{
+    any::Any,
+    collections::HashMap,
+    future::Future,
+    panic::AssertUnwindSafe,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{
+    sync::{broadcast, watch},
+    task::JoinHandle,
+};
+
+/// How many failures a slow `Supervisor::failures` receiver can fall behind before it lags.
+const FAILURE_CAPACITY: usize = 64;
+
+/// Represents how a supervised task is restarted after a failure.
+///
+/// # Fields
+///
+/// * `initial_backoff` - The delay before the first restart.
+/// * `max_backoff` - The upper bound of the delay, which doubles after every consecutive failure.
+/// * `max_restarts` - The number of restarts after which the task is given up on. `None` restarts forever.
+#[derive(Debug, Clone)]
+pub struct RestartPolicy {
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub max_restarts: Option<u32>,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        RestartPolicy {
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            max_restarts: None,
+        }
+    }
+}
+
+/// Represents the current status of a supervised task.
+///
+/// # Variants
+///
+/// * `Running` - The task is running.
+/// * `Restarting` - The task failed and is waiting for its backoff before restarting.
+/// * `Finished` - The task returned successfully and will not be restarted.
+/// * `GaveUp` - The task failed more often than `RestartPolicy::max_restarts` allows.
+/// * `Stopped` - The supervisor was shut down.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TaskStatus {
+    Running,
+    Restarting,
+    Finished,
+    GaveUp,
+    Stopped,
+}
+
+/// Represents the health of a supervised task.
+///
+/// # Fields
+///
+/// * `status` - The current `TaskStatus` of the task.
+/// * `restarts` - How many times the task has been restarted.
+/// * `last_error` - The error of the most recent failure, if any.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaskHealth {
+    pub status: TaskStatus,
+    pub restarts: u32,
+    pub last_error: Option<String>,
+}
+
+/// Represents one failure of a supervised task.
+///
+/// # Fields
+///
+/// * `task` - The name of the task.
+/// * `panicked` - Whether the task panicked rather than returning an error.
+/// * `message` - The error, or the panic message.
+/// * `restarts` - How many times the task had been restarted before this failure.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaskFailure {
+    pub task: String,
+    pub panicked: bool,
+    pub message: String,
+    pub restarts: u32,
+}
+
+type HealthMap = Arc<Mutex<HashMap<String, TaskHealth>>>;
+
+/// Owns a set of background tasks and restarts them when they fail.
+///
+/// Dropping the supervisor stops its tasks as well; `Supervisor::shutdown` also waits for them to finish.
+#[derive(Debug)]
+pub struct Supervisor {
+    policy: RestartPolicy,
+    health: HealthMap,
+    shutdown: watch::Sender<bool>,
+    failures: broadcast::Sender<TaskFailure>,
+    handles: Vec<JoinHandle<()>>,
+}
+
+impl Supervisor {
+    /// Creates a new `Supervisor` that restarts its tasks according to `policy`.
+    pub fn new(policy: RestartPolicy) -> Supervisor {
+        let (shutdown, _) = watch::channel(false);
+        let (failures, _) = broadcast::channel(FAILURE_CAPACITY);
+
+        Supervisor {
+            policy,
+            health: Arc::new(Mutex::new(HashMap::new())),
+            shutdown,
+            failures,
+            handles: Vec::new(),
+        }
+    }
+
+    /// Spawns a supervised task.
+    ///
+    /// The `factory` is called once per run, so every restart gets a fresh future. A panic in the
+    /// factory or in the future counts as a failure, like an error.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name the task is reported under in `Supervisor::health`.
+    /// * `factory` - A closure producing the future to run.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if it is called outside of a tokio runtime.
+    pub fn spawn<F, Fut>(&mut self, name: impl Into<String>, factory: F)
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), KenkuError>> + Send + 'static,
+    {
+        let name = name.into();
+        let policy = self.policy.clone();
+        let health = self.health.clone();
+        let mut shutdown = self.shutdown.subscribe();
+        let failures = self.failures.clone();
+
+        set_health(&health, &name, TaskStatus::Running, None, false);
+
+        let handle = tokio::spawn(async move {
+            let mut backoff = policy.initial_backoff;
+            let mut restarts = 0;
+
+            loop {
+                let failure = match std::panic::catch_unwind(AssertUnwindSafe(&factory)) {
+                    Ok(future) => {
+                        let mut run = tokio::spawn(future);
+
+                        tokio::select! {
+                            result = &mut run => match result {
+                                Ok(Ok(())) => {
+                                    set_health(&health, &name, TaskStatus::Finished, None, false);
+                                    return;
+                                }
+                                Ok(Err(error)) => (false, error.to_string()),
+                                Err(error) => match error.try_into_panic() {
+                                    Ok(payload) => (true, panic_message(payload)),
+                                    Err(error) => (false, error.to_string()),
+                                },
+                            },
+                            _ = shutdown.changed() => {
+                                run.abort();
+                                set_health(&health, &name, TaskStatus::Stopped, None, false);
+                                return;
+                            }
+                        }
+                    }
+                    Err(payload) => (true, panic_message(payload)),
+                };
+
+                let (panicked, message) = failure;
+                let error = if panicked {
+                    format!("panicked: {}", message)
+                } else {
+                    message.clone()
+                };
+
+                #[cfg(feature = "tracing")]
+                tracing::error!(
+                    task = %name,
+                    panicked,
+                    restarts,
+                    message = %message,
+                    "supervised task failed"
+                );
+
+                let _ = failures.send(TaskFailure {
+                    task: name.clone(),
+                    panicked,
+                    message,
+                    restarts,
+                });
+
+                if policy.max_restarts.is_some_and(|max| restarts >= max) {
+                    set_health(&health, &name, TaskStatus::GaveUp, Some(error), false);
+                    return;
+                }
+
+                set_health(&health, &name, TaskStatus::Restarting, Some(error), false);
+
+                tokio::select! {
+                    _ = tokio::time::sleep(backoff) => {}
+                    _ = shutdown.changed() => {
+                        set_health(&health, &name, TaskStatus::Stopped, None, false);
+                        return;
+                    }
+                }
+
+                restarts += 1;
+                backoff = backoff.saturating_mul(2).min(policy.max_backoff);
+                set_health(&health, &name, TaskStatus::Running, None, true);
+            }
+        });
+
+        self.handles.push(handle);
+    }
+
+    /// Subscribes to the failures of every task, as they happen.
+    pub fn failures(&self) -> broadcast::Receiver<TaskFailure> {
+        self.failures.subscribe()
+    }
+
+    /// Returns the health of every task, keyed by name.
+    pub fn health(&self) -> HashMap<String, TaskHealth> {
+        self.health.lock().unwrap().clone()
+    }
+
+    /// Returns `true` when no task is restarting or has been given up on.
+    pub fn is_healthy(&self) -> bool {
+        self.health
+            .lock()
+            .unwrap()
+            .values()
+            .all(|task| !matches!(task.status, TaskStatus::Restarting | TaskStatus::GaveUp))
+    }
+
+    /// Stops every task and waits for them to finish.
+    pub async fn shutdown(self) {
+        let _ = self.shutdown.send(true);
+
+        for handle in self.handles {
+            let _ = handle.await;
+        }
+    }
+}
+
+/// Extracts the message of a panic payload, which is a `&str` or a `String` for `panic!` with a message.
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => match payload.downcast::<&str>() {
+            Ok(message) => message.to_string(),
+            Err(_) => "unknown panic payload".to_string(),
+        },
+    }
+}
+
+/// Updates the health entry of `name`, keeping the previous error when `error` is `None`.
+fn set_health(
+    health: &HealthMap,
+    name: &str,
+    status: TaskStatus,
+    error: Option<String>,
+    restarted: bool,
+) {
+    let mut health = health.lock().unwrap();
+    let task = health.entry(name.to_string()).or_insert(TaskHealth {
+        status: TaskStatus::Running,
+        restarts: 0,
+        last_error: None,
+    });
+
+    task.status = status;
+
+    if error.is_some() {
+        task.last_error = error;
+    }
+
+    if restarted {
+        task.restarts += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RestartPolicy, Supervisor, TaskStatus};
+    use crate::KenkuError;
+    use std::{
+        sync::{
+            atomic::{AtomicU32, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    fn fast_policy(max_restarts: Option<u32>) -> RestartPolicy {
+        RestartPolicy {
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(4),
+            max_restarts,
+        }
+    }
+
+    #[tokio::test]
+    async fn failing_task_is_restarted_until_it_succeeds() {
+        let mut supervisor = Supervisor::new(fast_policy(None));
+        let runs = Arc::new(AtomicU32::new(0));
+        let counter = runs.clone();
+
+        supervisor.spawn("flaky", move || {
+            let counter = counter.clone();
+            async move {
+                if counter.fetch_add(1, Ordering::SeqCst) < 2 {
+                    return Err(KenkuError::InvalidInput("boom".to_string()));
+                }
+                Ok(())
+            }
+        });
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let health = supervisor.health()["flaky"].clone();
+
+        assert_eq!(health.status, TaskStatus::Finished);
+        assert_eq!(health.restarts, 2);
+        assert_eq!(health.last_error.as_deref(), Some("invalid input: boom"));
+    }
+
+    #[tokio::test]
+    async fn task_is_given_up_after_max_restarts() {
+        let mut supervisor = Supervisor::new(fast_policy(Some(1)));
+
+        supervisor.spawn("broken", || async {
+            Err(KenkuError::InvalidInput("always".to_string()))
+        });
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        assert_eq!(supervisor.health()["broken"].status, TaskStatus::GaveUp);
+        assert!(!supervisor.is_healthy());
+    }
+
+    #[tokio::test]
+    async fn panics_become_failures_and_restart_the_task() {
+        let mut supervisor = Supervisor::new(fast_policy(None));
+        let mut failures = supervisor.failures();
+        let runs = Arc::new(AtomicU32::new(0));
+        let counter = runs.clone();
+
+        supervisor.spawn("panicky", move || {
+            if counter.fetch_add(1, Ordering::SeqCst) == 0 {
+                panic!("factory exploded");
+            }
+            async { panic!("task exploded") }
+        });
+
+        let first = failures.recv().await.unwrap();
+        let second = failures.recv().await.unwrap();
+
+        assert_eq!(first.task, "panicky");
+        assert!(first.panicked);
+        assert_eq!(first.message, "factory exploded");
+        assert_eq!(
+            (second.message.as_str(), second.restarts),
+            ("task exploded", 1)
+        );
+        assert!(supervisor.health()["panicky"]
+            .last_error
+            .as_deref()
+            .is_some_and(|error| error.starts_with("panicked: ")));
+    }
+
+    #[tokio::test]
+    async fn shutdown_stops_running_tasks() {
+        let mut supervisor = Supervisor::new(fast_policy(None));
+
+        supervisor.spawn("forever", || async {
+            std::future::pending::<()>().await;
+            Ok(())
+        });
+
+        let health = supervisor.health.clone();
+        supervisor.shutdown().await;
+
+        assert_eq!(
+            health.lock().unwrap()["forever"].status,
+            TaskStatus::Stopped
+        );
+    }
+}
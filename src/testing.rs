@@ -0,0 +1,215 @@
+//! Helpers for testing code built on this crate.
+//!
+//! Golden files pin down how a value serializes: the first run writes the serialized value next
+//! to the tests, and later runs fail with a line diff as soon as the serialization changes. Once a
+//! change is intended, rerun the tests with `KENKU_UPDATE_GOLDEN=1` to accept it.
+//!
+//! ```no_run
+//! use kenku_control::testing::assert_golden;
+//! use serde_json::json;
+//!
+//! assert_golden("volume_payload", &json!({"volume": 0.5}));
+//! ```
+//!
+//! `MockRemote` serves the Kenku Remote API in-process, so tests of code driving Kenku FM run
+//! without Kenku FM, on CI too. It answers commands like Kenku FM does, can be filled with any
+//! library and playback, records the requests it receives and can be told to fail with `Fault`.
+//!
+//! ```no_run
+//! # async fn example() {
+//! use kenku_control::testing::MockRemote;
+//!
+//! let remote = MockRemote::start().await;
+//! let controller = remote.controller();
+//!
+//! controller.play_sound_id("s1").await.unwrap();
+//! assert_eq!(remote.playing_sounds(), ["s1"]);
+//! # }
+//! ```
+//!
+//! Unit tests that only need values, not a remote, build them with `track`, `sound`, `playlist`,
+//! `soundboard`, `playback` and `snapshot`.
+mod fixtures;
+mod mock;
+
+pub use fixtures::{playback, playlist, snapshot, sound, soundboard, track};
+pub use mock::{Fault, MockRemote};
+use serde::Serialize;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// The environment variable that makes golden assertions overwrite their files.
+pub const UPDATE_GOLDEN_ENV: &str = "KENKU_UPDATE_GOLDEN";
+
+/// Compares `value`, as pretty-printed JSON, with the golden file `tests/golden/<name>.json`.
+///
+/// The directory is resolved from `CARGO_MANIFEST_DIR`, so this works from any crate's tests.
+///
+/// # Panics
+///
+/// Panics with a line diff when the file differs, or when the value cannot be serialized or the file cannot be written.
+pub fn assert_golden<T: Serialize + ?Sized>(name: &str, value: &T) {
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_else(|_| ".".to_string());
+
+    Golden::new(Path::new(&manifest_dir).join("tests").join("golden")).assert(name, value);
+}
+
+/// A directory of golden files.
+#[derive(Debug, Clone)]
+pub struct Golden {
+    directory: PathBuf,
+    update: bool,
+}
+
+impl Golden {
+    /// Uses the golden files in `directory`. Files are overwritten when `KENKU_UPDATE_GOLDEN` is set.
+    pub fn new(directory: impl Into<PathBuf>) -> Golden {
+        Golden {
+            directory: directory.into(),
+            update: std::env::var_os(UPDATE_GOLDEN_ENV).is_some(),
+        }
+    }
+
+    /// Overwrites the golden files instead of comparing with them when `update` is `true`.
+    pub fn update(mut self, update: bool) -> Golden {
+        self.update = update;
+        self
+    }
+
+    /// Compares `value` with the golden file `<name>.json`, writing the file when it is missing.
+    ///
+    /// # Panics
+    ///
+    /// Panics with a line diff when the file differs, or when the value cannot be serialized or the file cannot be written.
+    pub fn assert<T: Serialize + ?Sized>(&self, name: &str, value: &T) {
+        if let Err(message) = self.check(name, value) {
+            panic!("{}", message);
+        }
+    }
+
+    /// Compares `value` with the golden file `<name>.json` like `Golden::assert`, returning the diff instead of panicking.
+    pub fn check<T: Serialize + ?Sized>(&self, name: &str, value: &T) -> Result<(), String> {
+        let actual = serde_json::to_string_pretty(value)
+            .map_err(|error| format!("cannot serialize golden value {}: {}", name, error))?
+            + "\n";
+        let path = self.directory.join(format!("{}.json", name));
+
+        let expected = match fs::read_to_string(&path) {
+            Ok(expected) if !self.update => expected,
+            _ => {
+                fs::create_dir_all(&self.directory)
+                    .and_then(|_| fs::write(&path, &actual))
+                    .map_err(|error| format!("cannot write {}: {}", path.display(), error))?;
+
+                return Ok(());
+            }
+        };
+
+        if expected.replace("\r\n", "\n") == actual {
+            return Ok(());
+        }
+
+        Err(format!(
+            "golden file {} differs (- expected, + actual); rerun with {}=1 to accept:\n{}",
+            path.display(),
+            UPDATE_GOLDEN_ENV,
+            diff(&expected, &actual)
+        ))
+    }
+}
+
+/// Returns a line diff of `expected` and `actual`, marking removed lines with `-` and added lines with `+`.
+pub fn diff(expected: &str, actual: &str) -> String {
+    let old: Vec<&str> = expected.lines().collect();
+    let new: Vec<&str> = actual.lines().collect();
+
+    // Longest common subsequence table, filled from the end.
+    let mut common = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i] == new[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut output = String::new();
+
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            output.push_str(&format!("  {}\n", old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || common[i + 1][j] >= common[i][j + 1]) {
+            output.push_str(&format!("- {}\n", old[i]));
+            i += 1;
+        } else {
+            output.push_str(&format!("+ {}\n", new[j]));
+            j += 1;
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff, Golden, MockRemote};
+    use crate::playlist::{PlaylistGetResponse, PlaylistPlaybackResponse, Repeat};
+    use serde_json::json;
+
+    #[test]
+    fn diff_marks_changed_lines() {
+        assert_eq!(diff("a\nb\nc\n", "a\nx\nc\n"), "  a\n- b\n+ x\n  c\n");
+    }
+
+    #[test]
+    fn golden_file_is_written_then_compared() {
+        let directory =
+            std::env::temp_dir().join(format!("kenku_control_golden_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        let golden = Golden::new(&directory).update(false);
+
+        assert!(golden.check("volume", &json!({"volume": 0.5})).is_ok());
+        assert!(golden.check("volume", &json!({"volume": 0.5})).is_ok());
+
+        let error = golden.check("volume", &json!({"volume": 0.7})).unwrap_err();
+        assert!(error.contains("-   \"volume\": 0.5"));
+        assert!(error.contains("+   \"volume\": 0.7"));
+        let _ = std::fs::remove_dir_all(directory);
+    }
+
+    #[tokio::test]
+    async fn mock_serves_the_library_and_playback_it_is_given() {
+        let remote = MockRemote::empty().await;
+        let controller = remote.controller();
+
+        assert!(controller.get_playlist().await.unwrap().tracks.is_empty());
+
+        let library: PlaylistGetResponse = serde_json::from_value(json!({
+            "playlists": [{"id": "p9", "title": "Forest", "tracks": ["t9"]}],
+            "tracks": [{"id": "t9", "url": "file:///forest.mp3", "title": "Birds"}],
+        }))
+        .unwrap();
+        remote.set_playlists(&library);
+
+        let playback: PlaylistPlaybackResponse =
+            serde_json::from_value(json!({"playing": true, "volume": 0.3, "muted": false,
+                "shuffle": true, "repeat": "track", "track": library.tracks[0]}))
+            .unwrap();
+        remote.set_playback(&playback);
+
+        assert_eq!(controller.get_playlist().await.unwrap(), library);
+
+        let served = controller.get_playlist_playback().await.unwrap();
+        assert!(served.playing && served.shuffle);
+        assert_eq!(served.volume, 0.3);
+        assert_eq!(served.repeat, Repeat::Track);
+        assert_eq!(served.track.unwrap().title, "Birds");
+    }
+}
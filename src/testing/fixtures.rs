@@ -0,0 +1,80 @@
+//! Kenku Remote values for unit tests, without writing their JSON by hand.
+//!
+//! Every builder fills the fields a test rarely cares about with neutral values: titles are the
+//! ids, urls are empty, volumes are full and nothing plays. Change the rest with struct update
+//! syntax, such as `Track { progress: Some(4_000), ..track("t1") }`.
+use crate::{
+    playlist::{Playlist, PlaylistPlaybackResponse, Repeat, Track},
+    snapshot::PlaybackSnapshot,
+    soundboard::{SoundboardPlaybackResponse, Soundboards, Sounds},
+};
+
+/// Returns the track `id`, titled `id`, without a duration or progress.
+pub fn track(id: &str) -> Track {
+    Track {
+        id: id.to_string(),
+        url: String::new(),
+        title: id.to_string(),
+        duration: None,
+        progress: None,
+    }
+}
+
+/// Returns the sound `id`, titled `id`, at full volume, without a loop, fades, duration or progress.
+pub fn sound(id: &str) -> Sounds {
+    Sounds {
+        id: id.to_string(),
+        url: String::new(),
+        title: id.to_string(),
+        _loop: false,
+        volume: 1.0,
+        fade_in: 0,
+        fade_out: 0,
+        duration: None,
+        progress: None,
+    }
+}
+
+/// Returns the playlist `id`, titled `id`, holding the tracks `tracks`.
+pub fn playlist(id: &str, tracks: &[&str]) -> Playlist {
+    Playlist {
+        id: id.to_string(),
+        tracks: Some(tracks.iter().map(|id| id.to_string()).collect()),
+        background: None,
+        title: id.to_string(),
+    }
+}
+
+/// Returns the soundboard `id`, titled `id`, holding the sounds `sounds`.
+pub fn soundboard(id: &str, sounds: &[&str]) -> Soundboards {
+    Soundboards {
+        id: id.to_string(),
+        sounds: sounds.iter().map(|id| id.to_string()).collect(),
+        background: String::new(),
+        title: id.to_string(),
+    }
+}
+
+/// Returns a stopped playlist playback of `track`, at full volume, unmuted, without shuffle or
+/// repeat.
+pub fn playback(track: Option<Track>) -> PlaylistPlaybackResponse {
+    PlaylistPlaybackResponse {
+        playing: false,
+        volume: 1.0,
+        muted: false,
+        shuffle: false,
+        repeat: Repeat::Off,
+        track,
+        playlist: None,
+    }
+}
+
+/// Returns a snapshot of `playlist` with the sounds `sounds` playing, built with `sound`.
+pub fn snapshot(playlist: PlaylistPlaybackResponse, sounds: &[&str]) -> PlaybackSnapshot {
+    PlaybackSnapshot {
+        playlist,
+        soundboard: SoundboardPlaybackResponse {
+            sounds: sounds.iter().map(|id| sound(id)).collect(),
+        },
+    }
+}
@@ -0,0 +1,542 @@
+//! A small, stateful imitation of the Kenku Remote HTTP API.
+use crate::{
+    playlist::{PlaylistGetResponse, PlaylistPlaybackResponse},
+    soundboard::SoundboardGetResponse,
+    Controller, KenkuAddress,
+};
+use serde_json::{json, Value};
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    task::{JoinHandle, JoinSet},
+};
+
+/// A misbehavior the mock applies to the next request it receives.
+#[derive(Debug, Clone)]
+pub enum Fault {
+    /// Answers with this status and a plain text body.
+    Status(u16),
+    /// Waits this long before answering normally.
+    Delay(Duration),
+    /// Answers `200 OK` with a body that is not valid JSON.
+    MalformedBody,
+    /// Closes the connection without answering.
+    Disconnect,
+}
+
+/// The state of the imitated Kenku FM.
+#[derive(Debug)]
+struct State {
+    playlists: Vec<Value>,
+    tracks: Vec<Value>,
+    soundboards: Vec<Value>,
+    sounds: Vec<Value>,
+    playing: bool,
+    volume: f64,
+    muted: bool,
+    shuffle: bool,
+    repeat: String,
+    current_playlist: Option<String>,
+    current_track: Option<String>,
+    playing_sounds: Vec<String>,
+    faults: VecDeque<Fault>,
+    requests: Vec<String>,
+    connections: usize,
+}
+
+impl State {
+    /// The sample library: two playlists, three tracks, one soundboard and two sounds.
+    fn sample() -> State {
+        State {
+            playlists: vec![
+                json!({"id": "p1", "title": "Town", "background": "town.png", "tracks": ["t1", "t2"]}),
+                json!({"id": "p2", "title": "Dungeon", "tracks": ["t3"]}),
+            ],
+            tracks: vec![
+                json!({"id": "t1", "url": "file:///music/tavern.mp3", "title": "Tavern"}),
+                json!({"id": "t2", "url": "file:///music/market.mp3", "title": "Market"}),
+                json!({"id": "t3", "url": "file:///music/crypt.mp3", "title": "Crypt"}),
+            ],
+            soundboards: vec![
+                json!({"id": "b1", "title": "Weather", "background": "", "sounds": ["s1", "s2"]}),
+            ],
+            sounds: vec![
+                json!({"id": "s1", "url": "file:///sfx/rain.ogg", "title": "Rain", "loop": true, "volume": 1.0, "fadeIn": 0, "fadeOut": 0}),
+                json!({"id": "s2", "url": "file:///sfx/thunder.ogg", "title": "Thunder", "loop": false, "volume": 0.8, "fadeIn": 100, "fadeOut": 200}),
+            ],
+            playing: false,
+            volume: 1.0,
+            muted: false,
+            shuffle: false,
+            repeat: "off".to_string(),
+            current_playlist: None,
+            current_track: None,
+            playing_sounds: Vec::new(),
+            faults: VecDeque::new(),
+            requests: Vec::new(),
+            connections: 0,
+        }
+    }
+
+    /// A Kenku FM with nothing in its library.
+    fn empty() -> State {
+        State {
+            playlists: Vec::new(),
+            tracks: Vec::new(),
+            soundboards: Vec::new(),
+            sounds: Vec::new(),
+            ..State::sample()
+        }
+    }
+
+    fn find<'a>(items: &'a [Value], id: &str) -> Option<&'a Value> {
+        items.iter().find(|item| item["id"] == id)
+    }
+
+    fn playlist_playback(&self) -> Value {
+        let mut playback = json!({
+            "playing": self.playing,
+            "volume": self.volume,
+            "muted": self.muted,
+            "shuffle": self.shuffle,
+            "repeat": self.repeat,
+        });
+
+        if let Some(track) = self
+            .current_track
+            .as_deref()
+            .and_then(|id| State::find(&self.tracks, id))
+        {
+            let mut track = track.clone();
+            track["duration"] = json!(180_000);
+            track["progress"] = json!(0);
+            playback["track"] = track;
+        }
+
+        if let Some(playlist) = self
+            .current_playlist
+            .as_deref()
+            .and_then(|id| State::find(&self.playlists, id))
+        {
+            playback["playlist"] = json!({"id": playlist["id"], "title": playlist["title"]});
+        }
+
+        playback
+    }
+
+    fn soundboard_playback(&self) -> Value {
+        let sounds: Vec<Value> = self
+            .playing_sounds
+            .iter()
+            .filter_map(|id| State::find(&self.sounds, id))
+            .map(|sound| {
+                let mut sound = sound.clone();
+                sound["duration"] = json!(5000);
+                sound["progress"] = json!(0.0);
+                sound
+            })
+            .collect();
+
+        json!({ "sounds": sounds })
+    }
+
+    /// Moves `offset` tracks through the current playlist, wrapping around.
+    fn skip(&mut self, offset: isize) {
+        let Some(playlist) = self
+            .current_playlist
+            .as_deref()
+            .and_then(|id| State::find(&self.playlists, id))
+        else {
+            return;
+        };
+        let tracks: Vec<String> = playlist["tracks"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|id| id.as_str().map(str::to_string))
+            .collect();
+
+        if tracks.is_empty() {
+            return;
+        }
+
+        let position = self
+            .current_track
+            .as_ref()
+            .and_then(|current| tracks.iter().position(|id| id == current))
+            .unwrap_or(0) as isize;
+        let next = (position + offset).rem_euclid(tracks.len() as isize) as usize;
+
+        self.current_track = Some(tracks[next].clone());
+    }
+
+    /// Answers one request with a status and a JSON body.
+    fn handle(&mut self, method: &str, path: &str, body: &Value) -> (u16, Value) {
+        let id = body["id"].as_str().unwrap_or_default().to_string();
+
+        match (method, path) {
+            ("GET", "/v1/playlist") => (
+                200,
+                json!({"playlists": self.playlists, "tracks": self.tracks}),
+            ),
+            ("GET", "/v1/playlist/playback") => (200, self.playlist_playback()),
+            ("GET", "/v1/soundboard") => (
+                200,
+                json!({"soundboards": self.soundboards, "sounds": self.sounds}),
+            ),
+            ("GET", "/v1/soundboard/playback") => (200, self.soundboard_playback()),
+            ("PUT", "/v1/playlist/play") => {
+                if let Some(playlist) = State::find(&self.playlists, &id) {
+                    self.current_track = playlist["tracks"][0].as_str().map(str::to_string);
+                    self.current_playlist = Some(id);
+                } else if State::find(&self.tracks, &id).is_some() {
+                    self.current_playlist = self
+                        .playlists
+                        .iter()
+                        .find(|playlist| {
+                            playlist["tracks"]
+                                .as_array()
+                                .is_some_and(|tracks| tracks.iter().any(|track| track == &id))
+                        })
+                        .and_then(|playlist| playlist["id"].as_str().map(str::to_string));
+                    self.current_track = Some(id);
+                } else {
+                    return (404, json!({"error": "not found"}));
+                }
+
+                self.playing = true;
+                (200, json!({}))
+            }
+            ("PUT", "/v1/playlist/playback/play") => {
+                self.playing = true;
+                (200, json!({}))
+            }
+            ("PUT", "/v1/playlist/playback/pause") => {
+                self.playing = false;
+                (200, json!({}))
+            }
+            ("POST", "/v1/playlist/playback/next") => {
+                self.skip(1);
+                (200, json!({}))
+            }
+            ("POST", "/v1/playlist/playback/previous") => {
+                self.skip(-1);
+                (200, json!({}))
+            }
+            ("PUT", "/v1/playlist/playback/mute") => match body["mute"].as_bool() {
+                Some(mute) => {
+                    self.muted = mute;
+                    (200, json!({}))
+                }
+                None => (400, json!({"error": "invalid mute"})),
+            },
+            ("PUT", "/v1/playlist/playback/volume") => match body["volume"].as_f64() {
+                Some(volume) if (0.0..=1.0).contains(&volume) => {
+                    self.volume = volume;
+                    (200, json!({}))
+                }
+                _ => (400, json!({"error": "invalid volume"})),
+            },
+            ("PUT", "/v1/playlist/playback/shuffle") => match body["shuffle"].as_bool() {
+                Some(shuffle) => {
+                    self.shuffle = shuffle;
+                    (200, json!({}))
+                }
+                None => (400, json!({"error": "invalid shuffle"})),
+            },
+            ("PUT", "/v1/playlist/playback/repeat") => match body["repeat"].as_str() {
+                Some(repeat @ ("track" | "playlist" | "off")) => {
+                    self.repeat = repeat.to_string();
+                    (200, json!({}))
+                }
+                _ => (400, json!({"error": "invalid repeat"})),
+            },
+            ("PUT", "/v1/soundboard/play") => {
+                if State::find(&self.sounds, &id).is_none() {
+                    return (404, json!({"error": "not found"}));
+                }
+
+                if !self.playing_sounds.contains(&id) {
+                    self.playing_sounds.push(id);
+                }
+
+                (200, json!({}))
+            }
+            ("PUT", "/v1/soundboard/stop") => {
+                if State::find(&self.sounds, &id).is_none() {
+                    return (404, json!({"error": "not found"}));
+                }
+
+                self.playing_sounds.retain(|playing| playing != &id);
+                (200, json!({}))
+            }
+            _ => (
+                404,
+                json!({
+                    "message": format!("Route {}:{} not found", method, path),
+                    "error": "Not Found",
+                    "statusCode": 404
+                }),
+            ),
+        }
+    }
+}
+
+/// A mock Kenku Remote listening on a random local port.
+///
+/// It serves the v1 API from an in-memory library and playback: commands change the playback the
+/// way Kenku FM would, so a test can send commands and read back their effect. Like Kenku FM, it
+/// keeps connections open for more requests unless the client asks to close them. The server
+/// stops, and closes every connection, when the `MockRemote` is dropped.
+#[derive(Debug)]
+pub struct MockRemote {
+    address: KenkuAddress,
+    state: Arc<Mutex<State>>,
+    handle: JoinHandle<()>,
+}
+
+impl MockRemote {
+    /// Starts a mock remote with a sample library.
+    ///
+    /// The library has the playlists `p1` "Town" (tracks `t1` "Tavern" and `t2` "Market") and
+    /// `p2` "Dungeon" (track `t3` "Crypt"), and the soundboard `b1` "Weather" with the sounds
+    /// `s1` "Rain" and `s2` "Thunder". Nothing is playing.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if no local port can be bound, or if it is called outside of a tokio runtime.
+    pub async fn start() -> MockRemote {
+        MockRemote::serve(State::sample()).await
+    }
+
+    /// Starts a mock remote with an empty library, to be filled with `set_playlists` and `set_soundboard`.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if no local port can be bound, or if it is called outside of a tokio runtime.
+    pub async fn empty() -> MockRemote {
+        MockRemote::serve(State::empty()).await
+    }
+
+    async fn serve(state: State) -> MockRemote {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = KenkuAddress::from(listener.local_addr().unwrap());
+        let state = Arc::new(Mutex::new(state));
+        let server_state = state.clone();
+
+        let handle = tokio::spawn(async move {
+            // Dropping the set, when the server is aborted, closes the open connections.
+            let mut connections = JoinSet::new();
+
+            while let Ok((stream, _)) = listener.accept().await {
+                server_state.lock().unwrap().connections += 1;
+                connections.spawn(serve(stream, server_state.clone()));
+
+                while connections.try_join_next().is_some() {}
+            }
+        });
+
+        MockRemote {
+            address,
+            state,
+            handle,
+        }
+    }
+
+    /// Returns the address the mock listens on.
+    pub fn address(&self) -> KenkuAddress {
+        self.address.clone()
+    }
+
+    /// Returns a new controller for the mock, with the default timeout.
+    pub fn controller(&self) -> Controller {
+        Controller::from_address(self.address())
+    }
+
+    /// Replaces the playlists and tracks. The playback is left as it is.
+    pub fn set_playlists(&self, library: &PlaylistGetResponse) {
+        let mut state = self.state.lock().unwrap();
+        state.playlists = to_values(&library.playlists);
+        state.tracks = to_values(&library.tracks);
+    }
+
+    /// Replaces the soundboards and sounds. Sounds playing that are no longer listed stop.
+    pub fn set_soundboard(&self, soundboard: &SoundboardGetResponse) {
+        let mut state = self.state.lock().unwrap();
+        state.soundboards = to_values(&soundboard.soundboards);
+        state.sounds = to_values(&soundboard.sounds);
+
+        let State {
+            sounds,
+            playing_sounds,
+            ..
+        } = &mut *state;
+        playing_sounds.retain(|id| State::find(sounds, id).is_some());
+    }
+
+    /// Replaces the playlist playback.
+    ///
+    /// The track and playlist are looked up by id in the library, so they must be part of it to
+    /// show up in the playback; their duration and progress are not kept.
+    pub fn set_playback(&self, playback: &PlaylistPlaybackResponse) {
+        let mut state = self.state.lock().unwrap();
+        state.playing = playback.playing;
+        state.volume = playback.volume;
+        state.muted = playback.muted;
+        state.shuffle = playback.shuffle;
+        state.repeat = json!(playback.repeat).as_str().unwrap_or("off").to_string();
+        state.current_track = playback.track.as_ref().map(|track| track.id.clone());
+        state.current_playlist = playback
+            .playlist
+            .as_ref()
+            .map(|playlist| playlist.id.clone());
+    }
+
+    /// Makes the sounds with the ids `ids` the ones playing, in order.
+    pub fn set_playing_sounds<I, S>(&self, ids: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.state.lock().unwrap().playing_sounds = ids.into_iter().map(Into::into).collect();
+    }
+
+    /// Makes the next request misbehave as described by `fault`. Faults queue up in order.
+    pub fn inject(&self, fault: Fault) {
+        self.state.lock().unwrap().faults.push_back(fault);
+    }
+
+    /// Returns every request received so far, as `METHOD /path`.
+    pub fn requests(&self) -> Vec<String> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    /// Returns how many connections were accepted so far.
+    pub fn connections(&self) -> usize {
+        self.state.lock().unwrap().connections
+    }
+
+    /// Returns the ids of the sounds currently playing.
+    pub fn playing_sounds(&self) -> Vec<String> {
+        self.state.lock().unwrap().playing_sounds.clone()
+    }
+}
+
+impl Drop for MockRemote {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+/// Answers the HTTP requests read from `stream`, until the client closes the connection or asks
+/// to close it.
+async fn serve(stream: TcpStream, state: Arc<Mutex<State>>) {
+    let mut reader = BufReader::new(stream);
+
+    while let Some(close) = serve_one(&mut reader, &state).await {
+        if close {
+            break;
+        }
+    }
+
+    let _ = reader.into_inner().shutdown().await;
+}
+
+/// Reads one HTTP request from `reader` and answers it.
+///
+/// # Returns
+///
+/// This function returns whether the connection must be closed, or `None` if it was closed or
+/// dropped.
+async fn serve_one(reader: &mut BufReader<TcpStream>, state: &Mutex<State>) -> Option<bool> {
+    let mut request_line = String::new();
+
+    if reader.read_line(&mut request_line).await.unwrap_or(0) == 0 {
+        return None;
+    }
+
+    let mut content_length = 0;
+    let mut close = false;
+
+    loop {
+        let mut header = String::new();
+
+        if reader.read_line(&mut header).await.unwrap_or(0) == 0 || header.trim().is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            } else if name.eq_ignore_ascii_case("connection") {
+                close = value.trim().eq_ignore_ascii_case("close");
+            }
+        }
+    }
+
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).await.ok()?;
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let path = parts.next().unwrap_or_default().to_string();
+    let body: Value = serde_json::from_slice(&body).unwrap_or(Value::Null);
+
+    let fault = {
+        let mut state = state.lock().unwrap();
+        state.requests.push(format!("{} {}", method, path));
+        state.faults.pop_front()
+    };
+
+    let (status, body) = match fault {
+        Some(Fault::Disconnect) => return None,
+        Some(Fault::Status(status)) => (status, "injected failure".to_string()),
+        Some(Fault::MalformedBody) => (200, "{\"playing\": tru".to_string()),
+        Some(Fault::Delay(delay)) => {
+            tokio::time::sleep(delay).await;
+            answer(state, &method, &path, &body)
+        }
+        None => answer(state, &method, &path, &body),
+    };
+
+    let response = format!(
+        "HTTP/1.1 {} {}\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: {}\r\n\r\n{}",
+        status,
+        reason(status),
+        body.len(),
+        if close { "close" } else { "keep-alive" },
+        body
+    );
+    reader.get_mut().write_all(response.as_bytes()).await.ok()?;
+
+    Some(close)
+}
+
+fn to_values<T: serde::Serialize>(items: &[T]) -> Vec<Value> {
+    items
+        .iter()
+        .map(|item| serde_json::to_value(item).unwrap_or(Value::Null))
+        .collect()
+}
+
+fn answer(state: &Mutex<State>, method: &str, path: &str, body: &Value) -> (u16, String) {
+    let (status, body) = state.lock().unwrap().handle(method, path, body);
+
+    (status, body.to_string())
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        500 => "Internal Server Error",
+        503 => "Service Unavailable",
+        _ => "Unknown",
+    }
+}
@@ -0,0 +1,244 @@
+//! Moving from one scene to the next with fades.
+//!
+//! A `TransitionEngine` remembers the scene it last activated. `TransitionEngine::transition_to`
+//! fades the music out, starts the sounds of the next scene while the old ones still play, stops
+//! the old sounds, activates the next scene and fades the music back in. The transition runs as a
+//! background task, and starting another transition interrupts it, so a game master can change
+//! their mind halfway through.
+use crate::{playlist::playback, scenes::Scene, Controller, KenkuError, Volume};
+use std::{
+    collections::BTreeSet,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::task::{AbortHandle, JoinHandle};
+
+/// The time between two volume changes of a fade.
+const FADE_STEP: Duration = Duration::from_millis(50);
+
+/// Represents how one scene hands over to the next.
+///
+/// # Fields
+///
+/// * `fade_out` - How long the music of the current scene takes to fade to silence.
+/// * `fade_in` - How long the music of the next scene takes to fade in.
+/// * `overlap` - How long before the end of the fade-out the sounds of the next scene start, playing over the old ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Transition {
+    pub fade_out: Duration,
+    pub fade_in: Duration,
+    pub overlap: Duration,
+}
+
+impl Transition {
+    /// Switches scenes at once, without fades.
+    pub const CUT: Transition = Transition {
+        fade_out: Duration::ZERO,
+        fade_in: Duration::ZERO,
+        overlap: Duration::ZERO,
+    };
+
+    /// Fades out and in over `fade` each, with no sound overlap.
+    pub fn fade(fade: Duration) -> Transition {
+        Transition {
+            fade_out: fade,
+            fade_in: fade,
+            overlap: Duration::ZERO,
+        }
+    }
+
+    /// Starts the sounds of the next scene `overlap` before the fade-out ends.
+    pub fn overlap(mut self, overlap: Duration) -> Transition {
+        self.overlap = overlap;
+        self
+    }
+}
+
+/// Represents how a transition ended.
+///
+/// # Variants
+///
+/// * `Completed` - The next scene is active and the music is back at its volume.
+/// * `Interrupted` - Another transition started before this one ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransitionOutcome {
+    Completed,
+    Interrupted,
+}
+
+/// A handle to a running transition, returned by `TransitionEngine::transition_to`.
+///
+/// Dropping the handle lets the transition run to its end in the background.
+#[derive(Debug)]
+pub struct TransitionHandle {
+    handle: JoinHandle<Result<(), KenkuError>>,
+}
+
+impl TransitionHandle {
+    /// Returns `true` once the transition ended, whichever way.
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    /// Waits for the transition to end.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the `TransitionOutcome`, or the `KenkuError` that stopped the transition.
+    pub async fn wait(self) -> Result<TransitionOutcome, KenkuError> {
+        match self.handle.await {
+            Ok(result) => result.map(|()| TransitionOutcome::Completed),
+            Err(error) if error.is_cancelled() => Ok(TransitionOutcome::Interrupted),
+            Err(error) => std::panic::resume_unwind(error.into_panic()),
+        }
+    }
+}
+
+/// What the engine remembers between transitions.
+#[derive(Debug, Default)]
+struct EngineState {
+    current: Option<Scene>,
+    volume: Option<Volume>,
+    sounds: BTreeSet<String>,
+    running: Option<AbortHandle>,
+}
+
+/// Runs scene transitions on a controller, one at a time.
+///
+/// Clones share the same state, so a transition started from one clone interrupts a transition
+/// started from another.
+#[derive(Debug, Clone)]
+pub struct TransitionEngine {
+    controller: Controller,
+    state: Arc<Mutex<EngineState>>,
+}
+
+impl TransitionEngine {
+    /// Creates an engine with no active scene.
+    pub fn new(controller: &Controller) -> TransitionEngine {
+        TransitionEngine {
+            controller: controller.clone(),
+            state: Arc::default(),
+        }
+    }
+
+    /// Returns the scene of the last completed transition, if any.
+    pub fn current(&self) -> Option<Scene> {
+        self.state.lock().unwrap().current.clone()
+    }
+
+    /// Returns `true` while a transition is running.
+    pub fn is_running(&self) -> bool {
+        self.state
+            .lock()
+            .unwrap()
+            .running
+            .as_ref()
+            .is_some_and(|running| !running.is_finished())
+    }
+
+    /// Starts the transition to `next`, interrupting the running one.
+    ///
+    /// The music fades to silence over `transition.fade_out`, and the sounds of `next` start
+    /// `transition.overlap` before the fade-out ends. The sounds started by earlier scenes and
+    /// not part of `next` are then stopped, `next` is activated while silent, and the music fades
+    /// in over `transition.fade_in` to the volume of `next`, or to the volume of the previous
+    /// scene when `next` does not set one. An interrupted transition leaves its sounds to the
+    /// transition that interrupted it.
+    ///
+    /// # Returns
+    ///
+    /// This function returns a `TransitionHandle` to the running task.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if it is called outside of a tokio runtime.
+    pub fn transition_to(&self, next: Scene, transition: Transition) -> TransitionHandle {
+        let mut state = self.state.lock().unwrap();
+
+        if let Some(running) = state.running.take() {
+            running.abort();
+        }
+
+        let previous_sounds = state.sounds.clone();
+        state.sounds.extend(next.sounds.iter().cloned());
+
+        let volume = next.volume.or(state.volume);
+        let controller = self.controller.clone();
+        let shared = self.state.clone();
+
+        let handle = tokio::spawn(async move {
+            let volume = match volume {
+                Some(volume) => volume,
+                None => controller.get_playlist_playback().await?.volume_level(),
+            };
+            shared.lock().unwrap().volume = Some(volume);
+
+            let fade_out = playback::fade_volume_to(
+                &controller,
+                Volume::SILENT,
+                transition.fade_out,
+                steps(transition.fade_out),
+            );
+            let early_sounds = async {
+                tokio::time::sleep(transition.fade_out.saturating_sub(transition.overlap)).await;
+
+                let playing = controller.get_soundboard_playback().await?;
+
+                for sound in next.sounds.iter().filter(|id| !playing.is_playing(id)) {
+                    controller.play_sound_id(sound).await?;
+                }
+
+                Ok(())
+            };
+            tokio::try_join!(fade_out, early_sounds)?;
+
+            for sound in previous_sounds.difference(&next.sounds.iter().cloned().collect()) {
+                controller.stop_sound_id(sound).await?;
+            }
+
+            next.clone()
+                .volume(Volume::SILENT)
+                .activate(&controller)
+                .await?;
+            playback::fade_volume_to(
+                &controller,
+                volume,
+                transition.fade_in,
+                steps(transition.fade_in),
+            )
+            .await?;
+
+            let mut state = shared.lock().unwrap();
+            state.sounds = next.sounds.iter().cloned().collect();
+            state.current = Some(next);
+
+            Ok(())
+        });
+
+        state.running = Some(handle.abort_handle());
+
+        TransitionHandle { handle }
+    }
+}
+
+/// Returns how many volume changes a fade of `duration` sends.
+fn steps(duration: Duration) -> u32 {
+    (duration.as_millis() / FADE_STEP.as_millis()).max(1) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{steps, Transition};
+    use std::time::Duration;
+
+    #[test]
+    fn fades_step_every_fifty_milliseconds() {
+        let transition =
+            Transition::fade(Duration::from_secs(1)).overlap(Duration::from_millis(300));
+
+        assert_eq!(steps(transition.fade_out), 20);
+        assert_eq!(steps(Transition::CUT.fade_in), 1);
+        assert_eq!(transition.overlap, Duration::from_millis(300));
+    }
+}
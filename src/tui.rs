@@ -0,0 +1,380 @@
+//! A terminal dashboard for running the table from a terminal.
+//!
+//! `run` takes over the terminal and shows the current track with its progress, the playlist
+//! volume and the soundboard sounds playing, refreshed every poll interval from
+//! `Controller::snapshot`. The keyboard controls the playback:
+//!
+//! | Key             | Action                     |
+//! | --------------- | -------------------------- |
+//! | `space`         | Play or pause              |
+//! | `n`, `→`        | Next track                 |
+//! | `p`, `←`        | Previous track             |
+//! | `+`, `↑`        | Volume up by 5%            |
+//! | `-`, `↓`        | Volume down by 5%          |
+//! | `m`             | Mute or unmute             |
+//! | `s`             | Stop every soundboard sound |
+//! | `q`, `esc`      | Quit                       |
+//!
+//! `render` and `action_for` are public so the dashboard can be embedded in a larger ratatui
+//! application that owns the terminal itself.
+use crate::{
+    display::clock, snapshot::PlaybackSnapshot, soundboard::playback::stop_all, Controller,
+    KenkuError, KenkuPlaybackCommand, Volume,
+};
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    layout::{Constraint, Layout},
+    style::{Color, Style, Stylize},
+    text::Line,
+    widgets::{Block, Gauge, List, ListItem, Paragraph},
+    Frame,
+};
+use std::time::{Duration, Instant};
+
+/// How long the dashboard waits for a key before checking whether a refresh is due.
+const KEY_POLL: Duration = Duration::from_millis(50);
+
+/// How much the volume keys change the volume, in percent.
+const VOLUME_STEP: u8 = 5;
+
+/// Represents what a key asks the dashboard to do.
+///
+/// # Variants
+///
+/// * `TogglePlay` - Plays or pauses the playlist playback.
+/// * `Next` - Skips to the next track.
+/// * `Previous` - Goes back to the previous track.
+/// * `VolumeUp` - Raises the playlist volume by 5%.
+/// * `VolumeDown` - Lowers the playlist volume by 5%.
+/// * `ToggleMute` - Mutes or unmutes the playlist playback.
+/// * `StopSounds` - Stops every soundboard sound.
+/// * `Quit` - Leaves the dashboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    TogglePlay,
+    Next,
+    Previous,
+    VolumeUp,
+    VolumeDown,
+    ToggleMute,
+    StopSounds,
+    Quit,
+}
+
+/// Returns the action bound to `key`, if any.
+pub fn action_for(key: KeyEvent) -> Option<Action> {
+    if key.kind == KeyEventKind::Release {
+        return None;
+    }
+
+    let action = match key.code {
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::Quit,
+        KeyCode::Char(' ') => Action::TogglePlay,
+        KeyCode::Char('n') | KeyCode::Right => Action::Next,
+        KeyCode::Char('p') | KeyCode::Left => Action::Previous,
+        KeyCode::Char('+') | KeyCode::Char('=') | KeyCode::Up => Action::VolumeUp,
+        KeyCode::Char('-') | KeyCode::Down => Action::VolumeDown,
+        KeyCode::Char('m') => Action::ToggleMute,
+        KeyCode::Char('s') => Action::StopSounds,
+        KeyCode::Char('q') | KeyCode::Esc => Action::Quit,
+        _ => return None,
+    };
+
+    Some(action)
+}
+
+/// Represents what the dashboard shows.
+///
+/// # Fields
+///
+/// * `playback` - The last playback fetched, if any.
+/// * `error` - The error of the last failed refresh or action, cleared by the next successful refresh.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DashboardState {
+    pub playback: Option<PlaybackSnapshot>,
+    pub error: Option<String>,
+}
+
+impl DashboardState {
+    /// Fetches the playback from `controller`, keeping the previous one when it fails.
+    pub async fn refresh(&mut self, controller: &Controller) {
+        match controller.snapshot().await {
+            Ok(playback) => {
+                self.playback = Some(playback);
+                self.error = None;
+            }
+            Err(error) => self.error = Some(error.to_string()),
+        }
+    }
+
+    /// Carries out `action` on `controller`, based on the last playback fetched.
+    ///
+    /// # Returns
+    ///
+    /// This function returns `Ok(())` once the commands were sent, or the first `KenkuError`.
+    pub async fn apply(&self, controller: &Controller, action: Action) -> Result<(), KenkuError> {
+        let playlist = self.playback.as_ref().map(|playback| &playback.playlist);
+        let volume = playlist.map_or(Volume::FULL, |playlist| playlist.volume_level());
+
+        let command = match action {
+            Action::TogglePlay if playlist.is_some_and(|playlist| playlist.playing) => {
+                KenkuPlaybackCommand::PlaylistPlaybackPause
+            }
+            Action::TogglePlay => KenkuPlaybackCommand::PlaylistPlaybackPlay,
+            Action::Next => KenkuPlaybackCommand::PlaylistPlaybackNext,
+            Action::Previous => KenkuPlaybackCommand::PlaylistPlaybackPrevious,
+            Action::VolumeUp => KenkuPlaybackCommand::PlaylistPlaybackVolume(Volume::from_percent(
+                volume.percent().saturating_add(VOLUME_STEP).min(100),
+            )),
+            Action::VolumeDown => KenkuPlaybackCommand::PlaylistPlaybackVolume(
+                Volume::from_percent(volume.percent().saturating_sub(VOLUME_STEP)),
+            ),
+            Action::ToggleMute => KenkuPlaybackCommand::PlaylistPlaybackMute(
+                !playlist.is_some_and(|playlist| playlist.muted),
+            ),
+            Action::StopSounds => {
+                for (_, result) in stop_all(controller).await? {
+                    result?;
+                }
+
+                return Ok(());
+            }
+            Action::Quit => return Ok(()),
+        };
+
+        controller.execute(&command.into()).await.map(|_| ())
+    }
+}
+
+/// Draws `state` over the whole frame.
+pub fn render(frame: &mut Frame, state: &DashboardState) {
+    let [title, track, progress, volume, sounds, help] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Length(2),
+        Constraint::Length(1),
+        Constraint::Length(1),
+        Constraint::Min(3),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+
+    let status = match &state.error {
+        Some(error) => Line::from(format!("Kenku FM · {}", error)).fg(Color::Red),
+        None if state.playback.is_none() => Line::from("Kenku FM · connecting…"),
+        None => Line::from("Kenku FM"),
+    };
+    frame.render_widget(status.bold(), title);
+
+    let Some(playback) = &state.playback else {
+        return;
+    };
+    let playlist = &playback.playlist;
+
+    let symbol = if playlist.playing { "▶" } else { "⏸" };
+    let mut lines = vec![Line::from(format!(
+        "{} {}",
+        symbol,
+        playlist
+            .track
+            .as_ref()
+            .map_or("No track", |track| &track.title)
+    ))];
+    if let Some(current) = &playlist.playlist {
+        lines.push(Line::from(format!("  {}", current.title)).dim());
+    }
+    frame.render_widget(Paragraph::new(lines), track);
+
+    let (ratio, label) = match playlist.track.as_ref() {
+        Some(track) => match (track.progress, track.duration) {
+            (Some(progress), Some(duration)) if duration > 0 => (
+                (f64::from(progress) / f64::from(duration)).clamp(0.0, 1.0),
+                format!("{} / {}", clock(progress), clock(duration)),
+            ),
+            (Some(progress), _) => (0.0, clock(progress)),
+            _ => (0.0, String::new()),
+        },
+        None => (0.0, String::new()),
+    };
+    frame.render_widget(
+        Gauge::default()
+            .ratio(ratio)
+            .label(label)
+            .gauge_style(Style::default().fg(Color::Cyan)),
+        progress,
+    );
+
+    let level = playlist.volume_level();
+    let label = if playlist.muted {
+        format!("Volume {}% (muted)", level.percent())
+    } else {
+        format!("Volume {}%", level.percent())
+    };
+    frame.render_widget(
+        Gauge::default()
+            .ratio(level.value())
+            .label(label)
+            .gauge_style(Style::default().fg(if playlist.muted {
+                Color::DarkGray
+            } else {
+                Color::Green
+            })),
+        volume,
+    );
+
+    let items: Vec<ListItem> = playback
+        .soundboard
+        .sounds
+        .iter()
+        .map(|sound| {
+            let remaining = playback
+                .soundboard
+                .remaining_time(&sound.id)
+                .map(|remaining| format!(" ({} left)", clock(remaining.as_millis() as u32)))
+                .unwrap_or_default();
+
+            ListItem::new(format!("♪ {}{}", sound.title, remaining))
+        })
+        .collect();
+    frame.render_widget(
+        List::new(items).block(Block::bordered().title("Sounds")),
+        sounds,
+    );
+
+    frame.render_widget(
+        Line::from(
+            "space play/pause · n/p next/previous · +/- volume · m mute · s stop sounds · q quit",
+        )
+        .dim(),
+        help,
+    );
+}
+
+/// Runs the dashboard in the terminal until the user quits.
+///
+/// The terminal is switched to the alternate screen and raw mode, and restored on the way out,
+/// including when drawing fails.
+///
+/// # Returns
+///
+/// This function returns `Ok(())` once the user quit, or the `std::io::Error` raised while drawing or reading keys.
+pub async fn run(controller: &Controller, poll_interval: Duration) -> std::io::Result<()> {
+    let mut terminal = ratatui::try_init()?;
+    let result = run_in(&mut terminal, controller, poll_interval).await;
+
+    ratatui::try_restore()?;
+
+    result
+}
+
+async fn run_in(
+    terminal: &mut ratatui::DefaultTerminal,
+    controller: &Controller,
+    poll_interval: Duration,
+) -> std::io::Result<()> {
+    let mut state = DashboardState::default();
+    let mut refreshed: Option<Instant> = None;
+
+    loop {
+        if refreshed.is_none_or(|at| at.elapsed() >= poll_interval) {
+            state.refresh(controller).await;
+            refreshed = Some(Instant::now());
+        }
+
+        terminal.draw(|frame| render(frame, &state))?;
+
+        if !event::poll(Duration::ZERO)? {
+            tokio::time::sleep(KEY_POLL).await;
+            continue;
+        }
+
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+
+        match action_for(key) {
+            Some(Action::Quit) => return Ok(()),
+            Some(action) => {
+                if let Err(error) = state.apply(controller, action).await {
+                    state.error = Some(error.to_string());
+                }
+
+                refreshed = None;
+            }
+            None => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{action_for, render, Action, DashboardState};
+    use crate::{
+        playlist::{Playlist, PlaylistPlaybackResponse, Track},
+        testing,
+    };
+    use ratatui::{
+        backend::TestBackend,
+        crossterm::event::{KeyCode, KeyEvent, KeyModifiers},
+        Terminal,
+    };
+
+    #[test]
+    fn keys_map_to_actions() {
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+
+        assert_eq!(
+            action_for(key(KeyCode::Char(' '))),
+            Some(Action::TogglePlay)
+        );
+        assert_eq!(action_for(key(KeyCode::Up)), Some(Action::VolumeUp));
+        assert_eq!(action_for(key(KeyCode::Esc)), Some(Action::Quit));
+        assert_eq!(
+            action_for(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL)),
+            Some(Action::Quit)
+        );
+        assert_eq!(action_for(key(KeyCode::Char('x'))), None);
+    }
+
+    #[test]
+    fn dashboard_shows_the_playback() {
+        let track = Track {
+            title: "Tavern".to_string(),
+            duration: Some(120_000),
+            progress: Some(30_000),
+            ..testing::track("t1")
+        };
+        let playlist = PlaylistPlaybackResponse {
+            playing: true,
+            volume: 0.7,
+            muted: true,
+            playlist: Some(Playlist {
+                title: "Town".to_string(),
+                ..testing::playlist("p1", &["t1"])
+            }),
+            ..testing::playback(Some(track))
+        };
+        let mut playback = testing::snapshot(playlist, &["s1"]);
+        playback.soundboard.sounds[0].title = "Rain".to_string();
+        let state = DashboardState {
+            playback: Some(playback),
+            error: None,
+        };
+
+        let mut terminal = Terminal::new(TestBackend::new(60, 12)).unwrap();
+        terminal.draw(|frame| render(frame, &state)).unwrap();
+
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+
+        assert!(screen.contains("▶ Tavern"));
+        assert!(screen.contains("Town"));
+        assert!(screen.contains("0:30 / 2:00"));
+        assert!(screen.contains("Volume 70% (muted)"));
+        assert!(screen.contains("♪ Rain"));
+    }
+}
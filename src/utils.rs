@@ -1,21 +1,31 @@
 use super::*;
-use std::net::TcpStream;
+
+/// How long `check_kenku_server_state` waits for the server to accept a connection.
+#[cfg(not(target_arch = "wasm32"))]
+const STATE_CHECK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(1);
 
 /// Checks the state of the Kenku server.
 ///
-/// This function takes an IP address and a port, makes a GET request to the server, and returns the server's state.
+/// This function takes the address of the server, opens a TCP connection to it, and returns the server's state.
 ///
 /// # Arguments
 ///
-/// * `ip` - A string slice that holds the IP address of the server.
-/// * `port` - A 16 unsigned byte that holds the port number of the server.
-/// * `delay_in_milisseconds` - A 16 unsigned byte that holds the time in milisseconds for the connection timeout.
+/// * `address` - The address of the server, such as a `KenkuAddress`, a `SocketAddrV4` or a `SocketAddr`. Host names are resolved without blocking the runtime.
 ///
 /// # Returns
 ///
-/// This function returns a `KenkuState` that represents the state of the server. If the GET request is successful, it returns `KenkuState::Online`. If the GET request fails, it returns `KenkuState::Offline`.
-pub async fn check_kenku_server_state(address: SocketAddrV4) -> KenkuState {
-    let is_server_online = TcpStream::connect(address).is_ok();
+/// This function returns a `KenkuState` that represents the state of the server. If the server accepts the connection within one second, it returns `KenkuState::Online`. Otherwise, it returns `KenkuState::Offline`.
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn check_kenku_server_state(address: impl Into<KenkuAddress>) -> KenkuState {
+    let address = address.into();
+    let connect = tokio::net::TcpStream::connect((address.host.as_str(), address.port));
+    let is_server_online = matches!(
+        tokio::time::timeout(STATE_CHECK_TIMEOUT, connect).await,
+        Ok(Ok(_))
+    );
+
+    #[cfg(feature = "tracing")]
+    tracing::debug!(online = is_server_online, "checked kenku server state");
 
     if !is_server_online {
         return KenkuState::Offline;
@@ -24,6 +34,123 @@ pub async fn check_kenku_server_state(address: SocketAddrV4) -> KenkuState {
     KenkuState::Online
 }
 
+/// Checks the state of the Kenku server.
+///
+/// Browsers cannot open raw TCP connections, so on `wasm32` this function sends a GET request
+/// for the playlist playback instead. Any HTTP answer counts as online.
+///
+/// # Arguments
+///
+/// * `address` - The address of the server, such as a `KenkuAddress` or a `SocketAddr`.
+///
+/// # Returns
+///
+/// This function returns `KenkuState::Online` if the server answered, or `KenkuState::Offline` otherwise.
+#[cfg(target_arch = "wasm32")]
+pub async fn check_kenku_server_state(address: impl Into<KenkuAddress>) -> KenkuState {
+    let url = process_url(
+        &KenkuCommand::KenkuGet(KenkuGetCommand::PlaylistPlayback),
+        &address.into(),
+    );
+
+    match Client::new().get(url).send().await {
+        Ok(_) => KenkuState::Online,
+        Err(_) => KenkuState::Offline,
+    }
+}
+
+/// Returns `true` when `error` means the server could not be reached.
+///
+/// Browsers do not tell connection failures apart from other failed requests, so on `wasm32`
+/// every error raised while sending the request counts.
+pub(crate) fn is_connect_error(error: &reqwest::Error) -> bool {
+    #[cfg(not(target_arch = "wasm32"))]
+    return error.is_connect();
+
+    #[cfg(target_arch = "wasm32")]
+    return error.is_request();
+}
+
+/// Checks that a response from the Kenku Remote has a 2xx status.
+///
+/// # Arguments
+///
+/// * `response` - The `reqwest::Response` returned by the Kenku Remote.
+///
+/// # Returns
+///
+/// This function returns the response unchanged when its status is a success, a `KenkuError::Unsupported` when the route does not exist, or a `KenkuError::Status` holding the status and the response body otherwise.
+pub(crate) async fn ensure_success(
+    response: reqwest::Response,
+) -> Result<reqwest::Response, KenkuError> {
+    let status = response.status();
+
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let body = response.text().await.unwrap_or_default();
+
+    Err(status_error(status, body))
+}
+
+/// How long a webhook receiver has to answer.
+#[cfg(any(feature = "outro", feature = "webhooks"))]
+const WEBHOOK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// The client webhooks are posted with, built on first use.
+#[cfg(any(feature = "outro", feature = "webhooks"))]
+static WEBHOOK_CLIENT: std::sync::OnceLock<Client> = std::sync::OnceLock::new();
+
+/// Posts `body` as JSON to the webhook at `url`.
+///
+/// Webhooks go to third-party services, so they are not sent through the controller's client:
+/// the default headers set with `ControllerBuilder::header`, which may carry credentials for the
+/// Kenku Remote, stay with the Kenku Remote, and the receiver gets `WEBHOOK_TIMEOUT` to answer
+/// rather than the controller timeout.
+///
+/// # Returns
+///
+/// This function returns `Ok(())` for a 2xx answer, or the `KenkuError` of the request or status otherwise.
+#[cfg(any(feature = "outro", feature = "webhooks"))]
+pub(crate) async fn post_webhook(url: &str, body: &serde_json::Value) -> Result<(), KenkuError> {
+    let client = match WEBHOOK_CLIENT.get() {
+        Some(client) => client,
+        None => {
+            let client = Client::builder().timeout(WEBHOOK_TIMEOUT).build()?;
+            WEBHOOK_CLIENT.get_or_init(|| client)
+        }
+    };
+
+    ensure_success(client.post(url).json(body).send().await?).await?;
+    Ok(())
+}
+
+/// Builds the error for a response with a non-2xx `status` and `body`.
+///
+/// Kenku Remote answers requests to routes it does not serve with a 404 whose message reads
+/// `Route PUT:/v1/... not found`. Those become `KenkuError::Unsupported`, while a 404 for an
+/// unknown id stays a `KenkuError::Status`.
+pub(crate) fn status_error(status: StatusCode, body: String) -> KenkuError {
+    if status == StatusCode::NOT_FOUND {
+        let message = serde_json::from_str::<serde_json::Value>(&body)
+            .ok()
+            .and_then(|value| value["message"].as_str().map(str::to_string));
+        let route = message.as_deref().and_then(|message| {
+            message
+                .strip_prefix("Route ")?
+                .strip_suffix(" not found")
+                .map(str::to_string)
+        });
+
+        if let Some(route) = route {
+            return KenkuError::Unsupported(route);
+        }
+    }
+
+    KenkuError::Status { status, body }
+}
+
 /// Create a base url pathern to Kenku Remote
 ///
 /// This function takes an IP address and a port, and return a String containing the link for the Kenku Remote server
@@ -115,25 +242,29 @@ pub fn process_post_command(command: &KenkuPostCommand, base_url: &str) -> Strin
 
 /// Constructs a URL for a given command, IP address, and port.
 ///
-/// This function takes a `KenkuGetCommand`, an IP address, and a port, and returns a URL that can be used to make a GET request to the soundboard or playlist API.
+/// This function takes a `KenkuCommand` and the address of the server, and returns a URL that can be used to make a request to the soundboard or playlist API.
 ///
 /// # Arguments
 ///
-/// * `command` - A reference to a `KenkuGetCommand` enum, which specifies the type of request to make.
-/// * `ip` - A string slice that holds the IP address of the server.
-/// * `port` - A string slice that holds the port number of the server.
+/// * `command` - A reference to a `KenkuCommand` enum, which specifies the type of request to make.
+/// * `address` - A reference to the `KenkuAddress` of the server.
 ///
 /// # Returns
 ///
 /// This function returns a `String` that represents the constructed URL.
-pub fn process_url(command: &KenkuCommand, address: SocketAddrV4) -> String {
-    let base_url = format_base_url(address.ip().to_string(), address.port());
+pub fn process_url(command: &KenkuCommand, address: &KenkuAddress) -> String {
+    let base_url = format_base_url(address.url_host(), address.port);
 
-    match command {
+    let url = match command {
         KenkuCommand::KenkuGet(get_command) => process_get_command(get_command, base_url.as_str()),
         KenkuCommand::KenkuPut(put_command) => process_put_command(put_command, base_url.as_str()),
         KenkuCommand::KenkuPost(post_command) => {
             process_post_command(post_command, base_url.as_str())
         }
-    }
+    };
+
+    #[cfg(feature = "tracing")]
+    tracing::trace!(url = %url, "built kenku url");
+
+    url
 }
@@ -0,0 +1,121 @@
+//! A volume level the Kenku Remote accepts.
+use crate::KenkuError;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Represents a volume level between 0.0 (silent) and 1.0 (full volume).
+///
+/// Kenku Remote misbehaves on levels outside that range, so a `Volume` can only be built from a
+/// valid level, either by checking it with `Volume::try_new` or by clamping it with `Volume::clamped`.
+/// It serializes as a plain number.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Deserialize, Serialize)]
+#[serde(try_from = "f64", into = "f64")]
+pub struct Volume(f64);
+
+impl Volume {
+    /// No sound at all.
+    pub const SILENT: Volume = Volume(0.0);
+    /// The full volume.
+    pub const FULL: Volume = Volume(1.0);
+
+    /// Creates a `Volume` from a level between 0.0 and 1.0.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the `Volume`, or a `KenkuError::InvalidInput` if `level` is outside 0.0 to 1.0 or not a number.
+    pub fn try_new(level: f64) -> Result<Volume, KenkuError> {
+        if (0.0..=1.0).contains(&level) {
+            Ok(Volume(level))
+        } else {
+            Err(KenkuError::InvalidInput(format!(
+                "volume must be between 0.0 and 1.0, got {}",
+                level
+            )))
+        }
+    }
+
+    /// Creates a `Volume` from `level`, clamped to 0.0 to 1.0. Not-a-number becomes silent.
+    pub fn clamped(level: f64) -> Volume {
+        if level.is_nan() {
+            return Volume::SILENT;
+        }
+
+        Volume(level.clamp(0.0, 1.0))
+    }
+
+    /// Creates a `Volume` from a percentage, clamped to 100.
+    pub fn from_percent(percent: u8) -> Volume {
+        Volume(f64::from(percent.min(100)) / 100.0)
+    }
+
+    /// Returns the level, between 0.0 and 1.0.
+    pub fn value(self) -> f64 {
+        self.0
+    }
+
+    /// Returns the level as a rounded percentage, between 0 and 100.
+    pub fn percent(self) -> u8 {
+        (self.0 * 100.0).round() as u8
+    }
+}
+
+impl Default for Volume {
+    fn default() -> Self {
+        Volume::FULL
+    }
+}
+
+impl TryFrom<f64> for Volume {
+    type Error = KenkuError;
+
+    fn try_from(level: f64) -> Result<Self, Self::Error> {
+        Volume::try_new(level)
+    }
+}
+
+impl From<Volume> for f64 {
+    fn from(volume: Volume) -> Self {
+        volume.0
+    }
+}
+
+impl fmt::Display for Volume {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}%", self.percent())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Volume;
+    use crate::KenkuError;
+
+    #[test]
+    fn out_of_range_levels_are_rejected() {
+        assert_eq!(Volume::try_new(0.5).unwrap().value(), 0.5);
+        assert!(matches!(
+            Volume::try_new(3.7),
+            Err(KenkuError::InvalidInput(_))
+        ));
+        assert!(Volume::try_new(-1.0).is_err());
+        assert!(Volume::try_new(f64::NAN).is_err());
+    }
+
+    #[test]
+    fn clamping_and_percentages() {
+        assert_eq!(Volume::clamped(3.7), Volume::FULL);
+        assert_eq!(Volume::clamped(-1.0), Volume::SILENT);
+        assert_eq!(Volume::from_percent(150), Volume::FULL);
+        assert_eq!(Volume::from_percent(35).percent(), 35);
+        assert_eq!(Volume::clamped(0.456).to_string(), "46%");
+    }
+
+    #[test]
+    fn serializes_as_a_number() {
+        assert_eq!(
+            serde_json::to_string(&Volume::from_percent(50)).unwrap(),
+            "0.5"
+        );
+        assert!(serde_json::from_str::<Volume>("1.5").is_err());
+    }
+}
@@ -0,0 +1,178 @@
+//! Wall-clock rules such as "every Friday at 19:30, Berlin time".
+//!
+//! Rules are evaluated in their time zone, so a weekly game keeps its local start time across
+//! daylight saving time changes. On the night clocks move, a local time that does not exist runs
+//! once the clocks jumped, shifted by the length of the gap, and a local time that happens twice
+//! runs only the first time.
+use chrono::{DateTime, Datelike, Days, NaiveDateTime, NaiveTime, Offset, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+
+/// Represents the time zone a `WallClock` rule is read in.
+///
+/// # Variants
+///
+/// * `Utc` - Coordinated Universal Time, which never changes its offset.
+/// * `Zone` - A time zone of the IANA database, such as `Europe/Berlin`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClockZone {
+    Utc,
+    Zone(Tz),
+}
+
+impl ClockZone {
+    /// Parses a zone name such as `Europe/Berlin`. `UTC`, in any case, gives `ClockZone::Utc`.
+    pub fn from_name(name: &str) -> Option<ClockZone> {
+        if name.eq_ignore_ascii_case("utc") {
+            return Some(ClockZone::Utc);
+        }
+
+        name.parse::<Tz>().ok().map(ClockZone::Zone)
+    }
+
+    /// Converts the local date and time `local` of this zone into UTC.
+    ///
+    /// A local time skipped by a daylight saving time change is shifted forward by the length of
+    /// the gap. A local time that happens twice resolves to its first occurrence.
+    pub fn to_utc(self, local: NaiveDateTime) -> DateTime<Utc> {
+        match self {
+            ClockZone::Utc => Utc.from_utc_datetime(&local),
+            ClockZone::Zone(zone) => {
+                if let Some(time) = zone.from_local_datetime(&local).earliest() {
+                    return time.with_timezone(&Utc);
+                }
+
+                // The local time fell in a gap: read it with the offset from before the gap,
+                // which lands just as far after the jump as it was after the gap started.
+                let before = local - chrono::Duration::days(1);
+                let offset = zone.offset_from_local_datetime(&before).earliest();
+
+                match offset {
+                    Some(offset) => Utc.from_utc_datetime(
+                        &(local
+                            - chrono::Duration::seconds(i64::from(offset.fix().local_minus_utc()))),
+                    ),
+                    None => Utc.from_utc_datetime(&local),
+                }
+            }
+        }
+    }
+
+    /// Returns the local date and time of this zone at `time`.
+    pub fn to_local(self, time: DateTime<Utc>) -> NaiveDateTime {
+        match self {
+            ClockZone::Utc => time.naive_utc(),
+            ClockZone::Zone(zone) => time.with_timezone(&zone).naive_local(),
+        }
+    }
+}
+
+/// Represents a recurring wall-clock time.
+///
+/// # Fields
+///
+/// * `time` - The local time of day.
+/// * `weekdays` - The days the rule applies on. Empty means every day.
+/// * `zone` - The time zone `time` is read in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WallClock {
+    pub time: NaiveTime,
+    pub weekdays: Vec<Weekday>,
+    pub zone: ClockZone,
+}
+
+impl WallClock {
+    /// Creates a rule running every day at `time`.
+    pub fn daily(time: NaiveTime, zone: ClockZone) -> WallClock {
+        WallClock {
+            time,
+            weekdays: Vec::new(),
+            zone,
+        }
+    }
+
+    /// Creates a rule running every `weekday` at `time`.
+    pub fn weekly(weekday: Weekday, time: NaiveTime, zone: ClockZone) -> WallClock {
+        WallClock {
+            time,
+            weekdays: vec![weekday],
+            zone,
+        }
+    }
+
+    /// Returns the first time strictly after `after` at which the rule applies.
+    pub fn next_after(&self, after: DateTime<Utc>) -> DateTime<Utc> {
+        let mut date = self.zone.to_local(after).date();
+
+        loop {
+            if self.weekdays.is_empty() || self.weekdays.contains(&date.weekday()) {
+                let candidate = self.zone.to_utc(date.and_time(self.time));
+
+                if candidate > after {
+                    return candidate;
+                }
+            }
+
+            date = date + Days::new(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ClockZone, WallClock};
+    use chrono::{DateTime, NaiveTime, Utc, Weekday};
+
+    fn utc(text: &str) -> DateTime<Utc> {
+        text.parse().unwrap()
+    }
+
+    fn new_york() -> ClockZone {
+        ClockZone::from_name("America/New_York").unwrap()
+    }
+
+    fn at(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn weekly_rule_keeps_local_time_across_dst() {
+        let rule = WallClock::weekly(Weekday::Sun, at(19, 0), new_york());
+
+        let before = rule.next_after(utc("2026-03-01T12:00:00Z"));
+        let after = rule.next_after(before);
+
+        assert_eq!(before, utc("2026-03-02T00:00:00Z"));
+        assert_eq!(after, utc("2026-03-08T23:00:00Z"));
+    }
+
+    #[test]
+    fn skipped_local_time_runs_after_the_jump() {
+        let rule = WallClock::weekly(Weekday::Sun, at(2, 30), new_york());
+
+        assert_eq!(
+            rule.next_after(utc("2026-03-08T00:00:00Z")),
+            utc("2026-03-08T07:30:00Z")
+        );
+    }
+
+    #[test]
+    fn repeated_local_time_runs_once() {
+        let rule = WallClock::daily(at(1, 30), new_york());
+
+        let first = rule.next_after(utc("2026-11-01T00:00:00Z"));
+        let next = rule.next_after(first);
+
+        assert_eq!(first, utc("2026-11-01T05:30:00Z"));
+        assert_eq!(next, utc("2026-11-02T06:30:00Z"));
+    }
+
+    #[test]
+    fn utc_mode_ignores_dst() {
+        let rule = WallClock::daily(at(19, 0), ClockZone::from_name("UTC").unwrap());
+
+        assert_eq!(
+            rule.next_after(utc("2026-03-08T19:00:00Z")),
+            utc("2026-03-09T19:00:00Z")
+        );
+    }
+}
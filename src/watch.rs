@@ -0,0 +1,142 @@
+//! The latest playback of the Kenku Remote, shared through `tokio::sync::watch` channels.
+//!
+//! Unlike `Controller::events`, a watch channel only keeps the newest value, so any number of
+//! tasks can clone the receiver and read the current playback without polling the remote themselves.
+use crate::{
+    playlist::PlaylistPlaybackResponse, soundboard::SoundboardPlaybackResponse, Controller,
+    KenkuError,
+};
+use std::{future::Future, time::Duration};
+use tokio::{
+    sync::watch,
+    time::{interval, MissedTickBehavior},
+};
+
+impl Controller {
+    /// Spawns a task that fetches the playlist playback every `poll_interval` and publishes it.
+    ///
+    /// The receiver holds `None` until the first successful fetch. Receivers are only notified
+    /// when the playback changed, and failed fetches keep the last known value. The task stops
+    /// once every receiver was dropped.
+    ///
+    /// ```no_run
+    /// # async fn watch() {
+    /// use kenku_control::Controller;
+    /// use std::time::Duration;
+    ///
+    /// let controller = Controller::new("127.0.0.1", 3333);
+    /// let mut playback = controller.watch_playlist_playback(Duration::from_millis(500));
+    ///
+    /// while playback.changed().await.is_ok() {
+    ///     if let Some(playback) = &*playback.borrow() {
+    ///         println!("volume is now {}", playback.volume);
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// # Arguments
+    ///
+    /// * `poll_interval` - The time between two fetches, at least a millisecond.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if it is called outside of a tokio runtime.
+    pub fn watch_playlist_playback(
+        &self,
+        poll_interval: Duration,
+    ) -> watch::Receiver<Option<PlaylistPlaybackResponse>> {
+        spawn_poller(self.clone(), poll_interval, |controller| async move {
+            controller.get_playlist_playback().await
+        })
+    }
+
+    /// Spawns a task that fetches the soundboard playback every `poll_interval` and publishes it.
+    ///
+    /// Behaves like `Controller::watch_playlist_playback`.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if it is called outside of a tokio runtime.
+    pub fn watch_soundboard_playback(
+        &self,
+        poll_interval: Duration,
+    ) -> watch::Receiver<Option<SoundboardPlaybackResponse>> {
+        spawn_poller(self.clone(), poll_interval, |controller| async move {
+            controller.get_soundboard_playback().await
+        })
+    }
+}
+
+/// Spawns the task feeding a watch channel with the values returned by `fetch`.
+fn spawn_poller<T, F, Fut>(
+    controller: Controller,
+    poll_interval: Duration,
+    fetch: F,
+) -> watch::Receiver<Option<T>>
+where
+    T: PartialEq + Send + Sync + 'static,
+    F: Fn(Controller) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<T, KenkuError>> + Send,
+{
+    let (sender, receiver) = watch::channel(None);
+
+    tokio::spawn(async move {
+        let mut ticks = interval(poll_interval.max(Duration::from_millis(1)));
+        ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        while !sender.is_closed() {
+            ticks.tick().await;
+
+            if let Ok(value) = fetch(controller.clone()).await {
+                sender.send_if_modified(|current| {
+                    if current.as_ref() == Some(&value) {
+                        return false;
+                    }
+
+                    *current = Some(value);
+                    true
+                });
+            }
+        }
+    });
+
+    receiver
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::MockRemote;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn receiver_gets_the_latest_playback() {
+        let remote = MockRemote::start().await;
+        let controller = remote.controller();
+        let mut receiver = controller.watch_soundboard_playback(Duration::from_millis(10));
+
+        tokio::time::timeout(Duration::from_secs(2), receiver.changed())
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert!(receiver.borrow().is_some());
+        assert!(
+            tokio::time::timeout(Duration::from_millis(100), receiver.changed())
+                .await
+                .is_err(),
+            "unchanged playback must not notify receivers"
+        );
+    }
+
+    #[tokio::test]
+    async fn zero_poll_intervals_still_publish() {
+        let remote = MockRemote::start().await;
+        let mut receiver = remote.controller().watch_playlist_playback(Duration::ZERO);
+
+        tokio::time::timeout(Duration::from_secs(2), receiver.changed())
+            .await
+            .unwrap()
+            .unwrap();
+    }
+}
@@ -0,0 +1,169 @@
+//! Webhooks called when the playback changes, for logging bots and overlay services.
+//!
+//! `Webhooks` lists URLs and the events each one wants, by the `type` they serialize with, such
+//! as `track_changed`, `sound_started` or `disconnected`. `Controller::webhooks` polls the Kenku
+//! Remote like `Controller::events` and sends every event as a JSON POST body, such as
+//! `{"type": "sound_started", "sound": {...}}`, to each URL that wants it. The hooks are plain
+//! data, so they can be kept with `format::save` next to the rest of a configuration.
+//!
+//! Webhooks are posted through an HTTP client of their own, with a 10 second timeout. The
+//! headers set with `ControllerBuilder::header` are only sent to the Kenku Remote.
+use crate::{events::KenkuEvent, utils::post_webhook, Controller, KenkuError};
+use futures_util::{future::join_all, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::{sync::Arc, time::Duration};
+
+/// Represents a URL called on events.
+///
+/// # Fields
+///
+/// * `url` - The URL the events are posted to.
+/// * `events` - The types of the events to post, such as `track_changed`. Empty to post every event.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Webhook {
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<String>,
+}
+
+/// Holds every webhook.
+///
+/// # Fields
+///
+/// * `hooks` - The webhooks, called in this order for each event.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct Webhooks {
+    pub hooks: Vec<Webhook>,
+}
+
+/// Represents the outcome of posting an event to a webhook.
+///
+/// # Fields
+///
+/// * `url` - The URL of the webhook.
+/// * `event` - The type of the event, such as `track_changed`.
+/// * `result` - Whether the webhook answered with a 2xx status.
+#[derive(Debug)]
+pub struct WebhookDelivery {
+    pub url: String,
+    pub event: String,
+    pub result: Result<(), KenkuError>,
+}
+
+impl Webhooks {
+    /// Creates an empty set of webhooks.
+    pub fn new() -> Webhooks {
+        Webhooks::default()
+    }
+
+    /// Posts every event to `url`.
+    pub fn all(mut self, url: impl Into<String>) -> Webhooks {
+        self.hooks.push(Webhook {
+            url: url.into(),
+            events: Vec::new(),
+        });
+        self
+    }
+
+    /// Posts the events whose type is one of `events`, such as `["sound_started"]`, to `url`.
+    pub fn on<I, S>(mut self, url: impl Into<String>, events: I) -> Webhooks
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.hooks.push(Webhook {
+            url: url.into(),
+            events: events.into_iter().map(Into::into).collect(),
+        });
+        self
+    }
+
+    /// Returns the URLs that want events of type `event`, in order.
+    pub fn urls_for(&self, event: &str) -> Vec<&str> {
+        self.hooks
+            .iter()
+            .filter(|hook| hook.events.is_empty() || hook.events.iter().any(|kind| kind == event))
+            .map(|hook| hook.url.as_str())
+            .collect()
+    }
+}
+
+impl Controller {
+    /// Posts `event` to every webhook of `hooks` that wants it, all at the same time.
+    ///
+    /// # Returns
+    ///
+    /// A `WebhookDelivery` for each webhook called, in the order of `hooks`.
+    pub async fn deliver(&self, hooks: &Webhooks, event: &KenkuEvent) -> Vec<WebhookDelivery> {
+        let body = match serde_json::to_value(event) {
+            Ok(body) => body,
+            Err(_) => return Vec::new(),
+        };
+        let kind = body["type"].as_str().unwrap_or_default().to_string();
+
+        join_all(hooks.urls_for(&kind).into_iter().map(|url| {
+            let body = &body;
+            let kind = kind.clone();
+
+            async move {
+                let result = post_webhook(url, body).await;
+
+                WebhookDelivery {
+                    url: url.to_string(),
+                    event: kind,
+                    result,
+                }
+            }
+        }))
+        .await
+    }
+
+    /// Polls the Kenku Remote every `poll_interval`, like `Controller::events`, and posts every
+    /// event to the webhooks of `hooks` that want it.
+    ///
+    /// A failed webhook does not stop the others, nor the next events. The stream never ends;
+    /// drop it to stop.
+    ///
+    /// # Returns
+    ///
+    /// A stream yielding a `WebhookDelivery` for each webhook called.
+    pub fn webhooks(
+        &self,
+        hooks: Webhooks,
+        poll_interval: Duration,
+    ) -> impl Stream<Item = WebhookDelivery> {
+        let controller = self.clone();
+        let hooks = Arc::new(hooks);
+
+        self.events(poll_interval)
+            .then(move |event| {
+                let controller = controller.clone();
+                let hooks = hooks.clone();
+
+                async move { controller.deliver(&hooks, &event).await }
+            })
+            .flat_map(futures_util::stream::iter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Webhooks;
+
+    #[test]
+    fn hooks_get_the_events_they_want() {
+        let hooks = Webhooks::new().all("http://127.0.0.1/log").on(
+            "http://127.0.0.1/overlay",
+            ["track_changed", "sound_started"],
+        );
+
+        assert_eq!(
+            hooks.urls_for("track_changed"),
+            ["http://127.0.0.1/log", "http://127.0.0.1/overlay"]
+        );
+        assert_eq!(hooks.urls_for("disconnected"), ["http://127.0.0.1/log"]);
+
+        let saved = serde_json::to_string(&hooks).unwrap();
+        assert_eq!(serde_json::from_str::<Webhooks>(&saved).unwrap(), hooks);
+    }
+}
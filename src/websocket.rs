@@ -0,0 +1,726 @@
+//! A WebSocket server for Stream Deck plugins, browser overlays and other external clients.
+//!
+//! `WebSocketServer` polls the Kenku Remote once, through `Controller::events`, and pushes every
+//! change to all connected clients, so they do not need to poll Kenku themselves. Every message is
+//! a JSON text frame with a `type` field:
+//!
+//! * On connection, the server sends `{"type": "snapshot", "playback": ...}` with the current
+//!   `PlaybackSnapshot`, so clients can draw their first state.
+//! * Every `KenkuEvent` is then sent as it is serialized, such as
+//!   `{"type": "volume_changed", "volume": 0.5}`.
+//! * Clients send commands as a serialized `KenkuCommandWithPayload`, such as
+//!   `{"PlaySound": "rain"}` or `{"Playback": "PlaylistPlaybackPause"}`. Each command is answered
+//!   with `{"type": "result", "ok": true}`, or with `{"type": "result", "ok": false, "error": ...}`
+//!   when it could not be read or sent.
+//! * Clients that only need some events, such as an overlay widget showing the sounds of one
+//!   board, send `{"subscribe": {"types": ["track_changed", "sound_started"], "board": "Weather"}}`.
+//!   Only events whose `type` is listed are sent to them from then on, and the sound events of
+//!   other soundboards are left out. `board` is the id or the title of a soundboard, and both
+//!   fields are optional: `{"subscribe": {}}` sends every event again. The subscription is
+//!   answered like a command, with an error if the board does not exist.
+//!
+//! Clients that fall behind the events skip the ones they missed rather than slowing the others.
+//!
+//! `WebSocketServer::start_with_journal` also appends every event to a `journal::Journal`, and
+//! adds the `id` of its entry to the event, such as `{"type": "playback_paused", "id": 41}`. A
+//! client that lost its connection sends `{"since": 41}` with the last id it saw, and is answered
+//! with `{"type": "replay", "events": [...]}`, holding the journaled events after it that its
+//! subscription lets through. Servers without a journal answer `since` with an error result.
+//!
+//! `WebSocketServer::start_with_auth` only keeps the clients an `auth::Authenticator` accepts,
+//! judged by the headers of their handshake. Browsers cannot set headers on a WebSocket, so a
+//! percent-encoded `?access_token=TOKEN` query counts as an `Authorization: Bearer TOKEN` header.
+//! Clients turned away get a close frame with the policy violation code, 1008, before the
+//! snapshot. Clients whose `auth::Identity` lacks the `admin` flag may subscribe, but their
+//! commands are answered with an error. Without an authenticator, every client may send
+//! commands. Clients that do not finish the handshake within ten seconds are dropped.
+//!
+//! `WebSocketServer::start_with_clients` also counts every command with a
+//! `clients::ClientTracker`: commands beyond its limit are answered with an error, and banned
+//! clients get the same close frame as the clients turned away.
+use crate::{
+    auth::{Authenticator, Identity},
+    clients::ClientTracker,
+    journal::Journal,
+    Controller, KenkuCommandWithPayload, KenkuError,
+};
+use futures_util::{SinkExt, StreamExt};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{
+    collections::HashSet,
+    net::SocketAddr,
+    pin::pin,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    sync::broadcast::{self, error::RecvError},
+    task::{JoinHandle, JoinSet},
+};
+use tokio_tungstenite::tungstenite::{
+    handshake::server::{Request, Response},
+    http::{header::AUTHORIZATION, HeaderMap, HeaderValue},
+    protocol::{frame::coding::CloseCode, CloseFrame},
+    Message,
+};
+
+/// How many serialized events are kept for clients that are behind.
+const BACKLOG: usize = 64;
+
+/// How long a client may take to complete the WebSocket handshake.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The journal of a server, shared by the poller and the connections.
+type SharedJournal = Arc<Mutex<Journal<Value>>>;
+
+/// An event as pushed to the clients.
+///
+/// # Fields
+///
+/// * `kind` - The `type` of the event, such as `sound_started`.
+/// * `sound` - The id of the sound, for sound events.
+/// * `text` - The event, serialized.
+#[derive(Debug)]
+struct Pushed {
+    kind: String,
+    sound: Option<String>,
+    text: String,
+}
+
+/// A subscription message, `{"subscribe": {...}}`.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Subscribe {
+    subscribe: Subscription,
+}
+
+/// A replay message, `{"since": N}`.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Since {
+    since: u64,
+}
+
+/// Represents which events a client asked for.
+///
+/// # Fields
+///
+/// * `types` - The `type` of the events to send, or `None` for every type.
+/// * `board` - The id or title of the soundboard whose sound events are sent, or `None` for every board.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Subscription {
+    types: Option<HashSet<String>>,
+    board: Option<String>,
+}
+
+/// The events a client gets, with the board of a subscription resolved to its sounds.
+#[derive(Debug, Default)]
+struct Filter {
+    types: Option<HashSet<String>>,
+    sounds: Option<HashSet<String>>,
+}
+
+/// A running WebSocket server. It stops, and closes every connection, when dropped.
+#[derive(Debug)]
+pub struct WebSocketServer {
+    address: SocketAddr,
+    handle: JoinHandle<()>,
+}
+
+impl WebSocketServer {
+    /// Starts a server on `bind`, such as `"127.0.0.1:9090"`, in front of `controller`.
+    ///
+    /// Every client is an admin, so the server only listens on a loopback address; use
+    /// `start_with_auth` to serve other hosts.
+    ///
+    /// # Arguments
+    ///
+    /// * `bind` - The address to listen on.
+    /// * `controller` - The controller whose events are pushed and which runs the commands.
+    /// * `poll_interval` - How often the Kenku Remote is polled for events.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the running `WebSocketServer`, a `KenkuError::InvalidInput` if `bind`
+    /// is not a loopback address, or a `KenkuError::Io` if `bind` could not be bound.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if it is called outside of a tokio runtime.
+    pub async fn start(
+        bind: impl ToSocketAddrs,
+        controller: &Controller,
+        poll_interval: Duration,
+    ) -> Result<WebSocketServer, KenkuError> {
+        WebSocketServer::listen(
+            bind,
+            controller,
+            poll_interval,
+            None,
+            ClientTracker::new(),
+            None,
+        )
+        .await
+    }
+
+    /// Starts a server like `start`, which only keeps the clients `auth` accepts.
+    ///
+    /// # Arguments
+    ///
+    /// * `bind` - The address to listen on.
+    /// * `controller` - The controller whose events are pushed and which runs the commands.
+    /// * `poll_interval` - How often the Kenku Remote is polled for events.
+    /// * `auth` - Who may connect.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the running `WebSocketServer`, or a `KenkuError::Io` if `bind` could not be bound.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if it is called outside of a tokio runtime.
+    pub async fn start_with_auth(
+        bind: impl ToSocketAddrs,
+        controller: &Controller,
+        poll_interval: Duration,
+        auth: impl Authenticator + 'static,
+    ) -> Result<WebSocketServer, KenkuError> {
+        let auth: Arc<dyn Authenticator> = Arc::new(auth);
+
+        WebSocketServer::listen(
+            bind,
+            controller,
+            poll_interval,
+            Some(auth),
+            ClientTracker::new(),
+            None,
+        )
+        .await
+    }
+
+    /// Starts a server like `start_with_auth`, which counts, limits and bans the clients with `clients`.
+    ///
+    /// # Arguments
+    ///
+    /// * `bind` - The address to listen on.
+    /// * `controller` - The controller whose events are pushed and which runs the commands.
+    /// * `poll_interval` - How often the Kenku Remote is polled for events.
+    /// * `auth` - Who may connect.
+    /// * `clients` - The tracker of the clients, which may be shared with a `rest::RestApi`.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the running `WebSocketServer`, or a `KenkuError::Io` if `bind` could not be bound.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if it is called outside of a tokio runtime.
+    pub async fn start_with_clients(
+        bind: impl ToSocketAddrs,
+        controller: &Controller,
+        poll_interval: Duration,
+        auth: impl Authenticator + 'static,
+        clients: &ClientTracker,
+    ) -> Result<WebSocketServer, KenkuError> {
+        let auth: Arc<dyn Authenticator> = Arc::new(auth);
+
+        WebSocketServer::listen(
+            bind,
+            controller,
+            poll_interval,
+            Some(auth),
+            clients.clone(),
+            None,
+        )
+        .await
+    }
+
+    /// Starts a server like `start_with_clients`, which appends every event to `journal` and
+    /// replays it to the clients asking with `{"since": N}`.
+    ///
+    /// # Arguments
+    ///
+    /// * `bind` - The address to listen on.
+    /// * `controller` - The controller whose events are pushed and which runs the commands.
+    /// * `poll_interval` - How often the Kenku Remote is polled for events.
+    /// * `auth` - Who may connect.
+    /// * `clients` - The tracker of the clients, which may be shared with a `rest::RestApi`.
+    /// * `journal` - The journal the events are appended to, reopened from an earlier run or new.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the running `WebSocketServer`, or a `KenkuError::Io` if `bind` could not be bound.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if it is called outside of a tokio runtime.
+    pub async fn start_with_journal(
+        bind: impl ToSocketAddrs,
+        controller: &Controller,
+        poll_interval: Duration,
+        auth: impl Authenticator + 'static,
+        clients: &ClientTracker,
+        journal: Journal<Value>,
+    ) -> Result<WebSocketServer, KenkuError> {
+        let auth: Arc<dyn Authenticator> = Arc::new(auth);
+
+        WebSocketServer::listen(
+            bind,
+            controller,
+            poll_interval,
+            Some(auth),
+            clients.clone(),
+            Some(Arc::new(Mutex::new(journal))),
+        )
+        .await
+    }
+
+    async fn listen(
+        bind: impl ToSocketAddrs,
+        controller: &Controller,
+        poll_interval: Duration,
+        auth: Option<Arc<dyn Authenticator>>,
+        clients: ClientTracker,
+        journal: Option<SharedJournal>,
+    ) -> Result<WebSocketServer, KenkuError> {
+        let listener = TcpListener::bind(bind).await?;
+        let address = listener.local_addr()?;
+
+        if auth.is_none() && !address.ip().is_loopback() {
+            return Err(KenkuError::InvalidInput(format!(
+                "refusing to serve {} without an authenticator",
+                address
+            )));
+        }
+
+        let controller = controller.clone();
+
+        let handle = tokio::spawn(async move {
+            let (sender, _) = broadcast::channel::<Arc<Pushed>>(BACKLOG);
+            // Dropping the set, when the server is aborted, aborts the poller and the connections.
+            let mut tasks = JoinSet::new();
+
+            let poller = controller.clone();
+            let events = sender.clone();
+            let journaled = journal.clone();
+            tasks.spawn(async move {
+                let mut stream = pin!(poller.events(poll_interval));
+
+                while let Some(event) = stream.next().await {
+                    if let Ok(mut value) = serde_json::to_value(&event) {
+                        // An event the journal cannot store is still pushed, without an id.
+                        let id = journaled
+                            .as_ref()
+                            .and_then(|journal| journal.lock().unwrap().append(value.clone()).ok());
+
+                        if let Some(id) = id {
+                            with_id(&mut value, id);
+                        }
+
+                        let _ = events.send(Arc::new(Pushed::new(&value)));
+                    }
+                }
+            });
+
+            while let Ok((stream, peer)) = listener.accept().await {
+                tasks.spawn(serve(
+                    stream,
+                    peer,
+                    controller.clone(),
+                    auth.clone(),
+                    clients.clone(),
+                    journal.clone(),
+                    sender.subscribe(),
+                ));
+
+                while tasks.try_join_next().is_some() {}
+            }
+        });
+
+        Ok(WebSocketServer { address, handle })
+    }
+
+    /// Returns the address the server listens on.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.address
+    }
+}
+
+impl Drop for WebSocketServer {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+/// Runs one connection: the check of `auth`, the snapshot, then events and command results
+/// until the client leaves.
+// The error of the handshake callback is the rejection response tungstenite defines.
+#[allow(clippy::result_large_err)]
+async fn serve(
+    stream: TcpStream,
+    peer: SocketAddr,
+    controller: Controller,
+    auth: Option<Arc<dyn Authenticator>>,
+    clients: ClientTracker,
+    journal: Option<SharedJournal>,
+    mut events: broadcast::Receiver<Arc<Pushed>>,
+) {
+    let mut headers = HeaderMap::new();
+    let handshake = tokio_tungstenite::accept_hdr_async(stream, |request: &Request, response| {
+        headers = handshake_headers(request);
+        Ok::<Response, _>(response)
+    });
+    let Ok(Ok(mut socket)) = tokio::time::timeout(HANDSHAKE_TIMEOUT, handshake).await else {
+        return;
+    };
+
+    let identity = match auth {
+        Some(auth) => auth.authenticate(&headers, Some(peer.ip())).await,
+        None => Some(Identity {
+            name: "anonymous".to_string(),
+            admin: true,
+        }),
+    };
+    let refusal = match &identity {
+        Some(identity) => clients
+            .check(identity)
+            .err()
+            .map(|refusal| refusal.to_string()),
+        None => Some("missing or rejected credentials".to_string()),
+    };
+
+    let (Some(identity), None) = (identity, refusal.clone()) else {
+        let _ = socket
+            .close(Some(CloseFrame {
+                code: CloseCode::Policy,
+                reason: refusal.unwrap_or_default().into(),
+            }))
+            .await;
+        return;
+    };
+
+    let (mut outgoing, mut incoming) = socket.split();
+
+    let hello = match controller.snapshot().await {
+        Ok(playback) => json!({"type": "snapshot", "playback": playback}),
+        Err(error) => json!({"type": "disconnected", "error": error.to_string()}),
+    };
+
+    if outgoing
+        .send(Message::text(hello.to_string()))
+        .await
+        .is_err()
+    {
+        return;
+    }
+
+    let mut filter = Filter::default();
+
+    loop {
+        let reply = tokio::select! {
+            event = events.recv() => match event {
+                Ok(event) if filter.matches(&event) => event.text.clone(),
+                Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            },
+            message = incoming.next() => match message {
+                Some(Ok(Message::Text(text))) => {
+                    let journal = journal.as_ref();
+                    respond(&controller, &text, &identity, &clients, &mut filter, journal).await
+                }
+                Some(Ok(Message::Close(_)) | Err(_)) | None => break,
+                Some(Ok(_)) => continue,
+            },
+        };
+
+        if outgoing.send(Message::text(reply)).await.is_err() {
+            break;
+        }
+    }
+}
+
+/// Returns the headers of a handshake, with an `access_token` query turned into a bearer token.
+///
+/// The query is percent-decoded, as `URLSearchParams` and `encodeURIComponent` encode it.
+fn handshake_headers(request: &Request) -> HeaderMap {
+    let mut headers = request.headers().clone();
+    let query = request.uri().query().unwrap_or_default();
+    let token = form_urlencoded::parse(query.as_bytes())
+        .find(|(key, _)| key == "access_token")
+        .map(|(_, token)| token);
+
+    if let Some(value) =
+        token.and_then(|token| HeaderValue::try_from(format!("Bearer {}", token)).ok())
+    {
+        headers.entry(AUTHORIZATION).or_insert(value);
+    }
+
+    headers
+}
+
+/// Runs the command, takes the subscription or replays the journal in `text`, and returns the
+/// answer.
+///
+/// Commands are only run when `identity` is an admin and `clients` lets them through.
+async fn respond(
+    controller: &Controller,
+    text: &str,
+    identity: &Identity,
+    clients: &ClientTracker,
+    filter: &mut Filter,
+    journal: Option<&SharedJournal>,
+) -> String {
+    let result = if let Ok(Since { since }) = serde_json::from_str(text) {
+        match journal {
+            Some(journal) => return replay(journal, since, filter),
+            None => Err("this server keeps no journal".to_string()),
+        }
+    } else if let Ok(Subscribe { subscribe }) = serde_json::from_str(text) {
+        Filter::resolve(controller, subscribe)
+            .await
+            .map(|resolved| *filter = resolved)
+            .map_err(|error| error.to_string())
+    } else if !identity.admin {
+        Err("only admins may send commands".to_string())
+    } else if let Err(refusal) = clients.record(identity, 1) {
+        Err(refusal.to_string())
+    } else {
+        match serde_json::from_str::<KenkuCommandWithPayload>(text) {
+            Ok(command) => controller.execute(&command).await.map(|_| ()),
+            Err(error) => Err(KenkuError::InvalidInput(format!(
+                "{:?} is not a command: {}",
+                text, error
+            ))),
+        }
+        .map_err(|error| error.to_string())
+    };
+
+    match result {
+        Ok(()) => json!({"type": "result", "ok": true}),
+        Err(error) => json!({"type": "result", "ok": false, "error": error}),
+    }
+    .to_string()
+}
+
+/// Returns the `replay` message holding the events of `journal` after `since` that pass `filter`.
+fn replay(journal: &SharedJournal, since: u64, filter: &Filter) -> String {
+    let entries = journal.lock().unwrap().since(since);
+    let events: Vec<Value> = entries
+        .into_iter()
+        .map(|entry| {
+            let mut event = entry.event;
+            with_id(&mut event, entry.id);
+            event
+        })
+        .filter(|event| filter.matches(&Pushed::new(event)))
+        .collect();
+
+    json!({"type": "replay", "events": events}).to_string()
+}
+
+/// Adds the journal `id` to the serialized `event`.
+fn with_id(event: &mut Value, id: u64) {
+    if let Some(fields) = event.as_object_mut() {
+        fields.insert("id".to_string(), json!(id));
+    }
+}
+
+impl Pushed {
+    /// Wraps a serialized event, reading the fields the filters match on.
+    fn new(event: &Value) -> Pushed {
+        Pushed {
+            kind: event["type"].as_str().unwrap_or_default().to_string(),
+            sound: event["sound"]["id"].as_str().map(str::to_string),
+            text: event.to_string(),
+        }
+    }
+}
+
+impl Filter {
+    /// Looks the board of `subscription` up, so sound events can be matched by sound id.
+    ///
+    /// # Returns
+    ///
+    /// This function returns the `Filter`, a `KenkuError::NotFound` if no soundboard has the id or
+    /// title of the board, or the `KenkuError` of fetching the soundboards.
+    async fn resolve(
+        controller: &Controller,
+        subscription: Subscription,
+    ) -> Result<Filter, KenkuError> {
+        let sounds = match subscription.board {
+            Some(board) => {
+                let soundboard = controller.get_soundboard().await?;
+                let found = soundboard
+                    .soundboards
+                    .into_iter()
+                    .find(|found| found.id == board || found.title == board)
+                    .ok_or_else(|| KenkuError::NotFound(format!("soundboard {:?}", board)))?;
+
+                Some(found.sounds.into_iter().collect())
+            }
+            None => None,
+        };
+
+        Ok(Filter {
+            types: subscription.types,
+            sounds,
+        })
+    }
+
+    /// Returns whether `event` is sent to the client.
+    fn matches(&self, event: &Pushed) -> bool {
+        let type_matches = self
+            .types
+            .as_ref()
+            .is_none_or(|types| types.contains(&event.kind));
+        let board_matches = match (&self.sounds, &event.sound) {
+            (Some(sounds), Some(sound)) => sounds.contains(sound),
+            _ => true,
+        };
+
+        type_matches && board_matches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{handshake_headers, respond, Filter, Pushed};
+    use crate::{auth::Identity, clients::ClientTracker, Controller};
+    use serde_json::{json, Value};
+    use tokio_tungstenite::tungstenite::{handshake::server::Request, http::header::AUTHORIZATION};
+
+    fn player(admin: bool) -> Identity {
+        Identity {
+            name: "ana".to_string(),
+            admin,
+        }
+    }
+
+    #[tokio::test]
+    async fn unreadable_commands_are_answered_with_an_error() {
+        let controller = Controller::new("127.0.0.1", 1);
+        let reply: Value = serde_json::from_str(
+            &respond(
+                &controller,
+                r#"{"Jump": "p1"}"#,
+                &player(true),
+                &ClientTracker::new(),
+                &mut Filter::default(),
+                None,
+            )
+            .await,
+        )
+        .unwrap();
+
+        assert_eq!(reply["type"], json!("result"));
+        assert_eq!(reply["ok"], json!(false));
+        assert!(reply["error"]
+            .as_str()
+            .unwrap()
+            .contains("is not a command"));
+    }
+
+    #[tokio::test]
+    async fn clients_without_the_admin_flag_may_only_subscribe() {
+        let controller = Controller::new("127.0.0.1", 1);
+        let clients = ClientTracker::new();
+        let mut filter = Filter::default();
+
+        let subscribed: Value = serde_json::from_str(
+            &respond(
+                &controller,
+                r#"{"subscribe": {}}"#,
+                &player(false),
+                &clients,
+                &mut filter,
+                None,
+            )
+            .await,
+        )
+        .unwrap();
+        assert_eq!(subscribed["ok"], json!(true));
+
+        let refused: Value = serde_json::from_str(
+            &respond(
+                &controller,
+                r#"{"PlaySound": "s1"}"#,
+                &player(false),
+                &clients,
+                &mut filter,
+                None,
+            )
+            .await,
+        )
+        .unwrap();
+        assert_eq!(refused["error"], json!("only admins may send commands"));
+    }
+
+    #[tokio::test]
+    async fn commands_beyond_the_limit_are_answered_with_an_error() {
+        let controller = Controller::new("127.0.0.1", 1);
+        let clients = ClientTracker::new().limit(0);
+
+        let refused: Value = serde_json::from_str(
+            &respond(
+                &controller,
+                r#"{"PlaySound": "s1"}"#,
+                &player(true),
+                &clients,
+                &mut Filter::default(),
+                None,
+            )
+            .await,
+        )
+        .unwrap();
+
+        assert_eq!(
+            refused["error"],
+            json!("too many commands, try again in a minute")
+        );
+        assert_eq!(clients.clients()[0].refused, 1);
+    }
+
+    #[test]
+    fn filters_keep_the_listed_types_and_the_sounds_of_their_board() {
+        let pushed = |kind: &str, sound: Option<&str>| Pushed {
+            kind: kind.to_string(),
+            sound: sound.map(str::to_string),
+            text: String::new(),
+        };
+        let filter = Filter {
+            types: Some(["track_changed", "sound_started"].map(String::from).into()),
+            sounds: Some(["s1"].map(String::from).into()),
+        };
+
+        assert!(filter.matches(&pushed("track_changed", None)));
+        assert!(filter.matches(&pushed("sound_started", Some("s1"))));
+        assert!(!filter.matches(&pushed("sound_started", Some("s9"))));
+        assert!(!filter.matches(&pushed("volume_changed", None)));
+        assert!(Filter::default().matches(&pushed("volume_changed", None)));
+    }
+
+    #[test]
+    fn access_tokens_in_the_query_become_bearer_tokens() {
+        let request = |uri: &str| Request::builder().uri(uri).body(()).unwrap();
+
+        assert_eq!(
+            handshake_headers(&request("/?overlay=1&access_token=abc"))[AUTHORIZATION],
+            "Bearer abc"
+        );
+        assert_eq!(
+            handshake_headers(&request("/?access_token=a%2Bb%2Fc%3D"))[AUTHORIZATION],
+            "Bearer a+b/c="
+        );
+        assert!(handshake_headers(&request("/"))
+            .get(AUTHORIZATION)
+            .is_none());
+
+        let mut both = request("/?access_token=abc");
+        both.headers_mut()
+            .insert(AUTHORIZATION, "Bearer xyz".parse().unwrap());
+        assert_eq!(handshake_headers(&both)[AUTHORIZATION], "Bearer xyz");
+    }
+}
@@ -0,0 +1,38 @@
+//! Announcement clips played over ducked music.
+#![cfg(feature = "announce")]
+
+mod common;
+
+use kenku_control::{announce::Announcer, playlist::playback, KenkuError, Volume};
+use std::time::Duration;
+
+#[tokio::test]
+async fn announcements_duck_and_restore_the_music() {
+    let remote = common::remote().await;
+    let controller = remote.controller();
+    let soundboard = controller
+        .get_soundboard()
+        .await
+        .expect("failed to get kenku soundboards");
+    let clip = soundboard.sounds[0].id.clone();
+
+    playback::playback_volume(&controller, Volume::from_percent(80))
+        .await
+        .unwrap();
+
+    let announcer = Announcer::new()
+        .clip("initiative", clip.clone())
+        .fade(Duration::from_millis(100))
+        .max_length(Duration::from_millis(300));
+
+    announcer.announce(&controller, "initiative").await.unwrap();
+
+    let after = controller.snapshot().await.unwrap();
+    assert_eq!(after.playlist.volume_level(), Volume::from_percent(80));
+    assert!(!after.soundboard.is_playing(&clip));
+
+    assert!(matches!(
+        announcer.announce(&controller, "break").await,
+        Err(KenkuError::NotFound(_))
+    ));
+}
@@ -1,27 +1,19 @@
-use kenku_control::{utils::check_kenku_server_state, *};
-use std::{
-    net::{Ipv4Addr, SocketAddrV4},
-    str::FromStr,
-};
-
-const DEFAULT_IP: &str = "127.0.0.1";
-const DEFAULT_PORT: u16 = 3333;
+mod common;
 
-fn get_default_address() -> SocketAddrV4 {
-    SocketAddrV4::new(Ipv4Addr::from_str("127.0.0.1").unwrap(), 3333)
-}
+use kenku_control::{utils::check_kenku_server_state, *};
 
 #[tokio::test]
 async fn kenku_remote_is_online() {
-    let default_address = get_default_address();
-    let server_state = check_kenku_server_state(default_address).await;
+    let remote = common::remote().await;
+    let server_state = check_kenku_server_state(remote.address.clone()).await;
 
     assert_eq!(server_state, KenkuState::Online);
 }
 
 #[tokio::test]
 async fn get_playlists() {
-    let controller = Controller::new(DEFAULT_IP.to_string(), DEFAULT_PORT);
+    let remote = common::remote().await;
+    let controller = remote.controller();
     let playlist = controller.get_playlist().await;
 
     assert!(playlist.is_ok());
@@ -29,7 +21,8 @@ async fn get_playlists() {
 
 #[tokio::test]
 async fn get_soundboards() {
-    let controller = Controller::new(DEFAULT_IP.to_string(), DEFAULT_PORT);
+    let remote = common::remote().await;
+    let controller = remote.controller();
     let soundboard = controller.get_soundboard().await;
 
     assert!(soundboard.is_ok());
@@ -37,7 +30,8 @@ async fn get_soundboards() {
 
 #[tokio::test]
 async fn get_playlist_playback() {
-    let controller = Controller::new(DEFAULT_IP.to_string(), DEFAULT_PORT);
+    let remote = common::remote().await;
+    let controller = remote.controller();
     let playlist_playback = controller.get_playlist_playback().await;
 
     assert!(playlist_playback.is_ok());
@@ -45,7 +39,8 @@ async fn get_playlist_playback() {
 
 #[tokio::test]
 async fn get_soundboard_playback() {
-    let controller = Controller::new(DEFAULT_IP.to_string(), DEFAULT_PORT);
+    let remote = common::remote().await;
+    let controller = remote.controller();
     let soundboard_playback = controller.get_soundboard_playback().await;
 
     assert!(soundboard_playback.is_ok());
@@ -1,12 +1,12 @@
-use kenku_control::*;
-use rand::Rng;
+mod common;
 
-const DEFAULT_IP_ADDRESS: &str = "127.0.0.1";
-const DEFAULT_PORT: u16 = 3333;
+use kenku_control::{KenkuCommandWithPayload, KenkuPlaybackCommand, Volume};
+use rand::Rng;
 
 #[tokio::test]
 async fn play_a_random_track() {
-    let controller = Controller::new(DEFAULT_IP_ADDRESS.to_string(), DEFAULT_PORT);
+    let remote = common::remote().await;
+    let controller = remote.controller();
     let playlists = controller
         .get_playlist()
         .await
@@ -24,7 +24,8 @@ async fn play_a_random_track() {
 
 #[tokio::test]
 async fn play_a_random_sond() {
-    let controller = Controller::new(DEFAULT_IP_ADDRESS.to_string(), DEFAULT_PORT);
+    let remote = common::remote().await;
+    let controller = remote.controller();
     let soundboards = controller
         .get_soundboard()
         .await
@@ -39,3 +40,161 @@ async fn play_a_random_sond() {
         assert!(status_code.is_success())
     }
 }
+
+#[tokio::test]
+async fn play_and_stop_a_sound_by_id() {
+    let remote = common::remote().await;
+    let controller = remote.controller();
+    let soundboards = controller
+        .get_soundboard()
+        .await
+        .expect("failed to get kenku soundboards");
+    let id = soundboards.sounds[0].id.clone();
+
+    assert!(controller.play_sound_id(&id).await.unwrap().is_success());
+    assert!(controller
+        .get_soundboard_playback()
+        .await
+        .unwrap()
+        .is_playing(&id));
+    assert!(controller.stop_sound_id(&id).await.unwrap().is_success());
+}
+
+#[tokio::test]
+async fn wait_for_a_sound_to_finish() {
+    let remote = common::remote().await;
+    let controller = remote.controller();
+    let soundboards = controller
+        .get_soundboard()
+        .await
+        .expect("failed to get kenku soundboards");
+    let sound = soundboards.sounds[0].clone();
+
+    assert!(sound.play(&controller).await.unwrap().is_success());
+
+    let stopper = controller.clone();
+    let id = sound.id.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+        stopper.stop_sound_id(&id).await
+    });
+
+    sound
+        .wait_until_finished(&controller)
+        .await
+        .expect("failed to wait for the sound");
+
+    assert!(!controller
+        .get_soundboard_playback()
+        .await
+        .unwrap()
+        .is_playing(&sound.id));
+}
+
+#[tokio::test]
+async fn play_a_track_by_id() {
+    let remote = common::remote().await;
+    let controller = remote.controller();
+    let playlists = controller
+        .get_playlist()
+        .await
+        .expect("failed to get kenku playlists");
+    let id = playlists.tracks[0].id.clone();
+
+    assert!(controller.play_track_id(&id).await.unwrap().is_success());
+}
+
+#[tokio::test]
+async fn execute_stored_commands() {
+    let remote = common::remote().await;
+    let controller = remote.controller();
+    let soundboard = controller
+        .get_soundboard()
+        .await
+        .expect("failed to get kenku soundboards");
+    let id = soundboard.sounds[0].id.clone();
+    let commands = [
+        KenkuCommandWithPayload::PlaySound(id.clone()),
+        KenkuCommandWithPayload::Playback(KenkuPlaybackCommand::PlaylistPlaybackShuffle(false)),
+        KenkuCommandWithPayload::StopSound(id),
+    ];
+
+    for command in &commands {
+        assert!(controller.execute(command).await.unwrap().is_success());
+    }
+}
+
+#[tokio::test]
+async fn execute_a_batch_of_commands() {
+    let remote = common::remote().await;
+    let controller = remote.controller();
+    let playlists = controller
+        .get_playlist()
+        .await
+        .expect("failed to get kenku playlists");
+    let soundboard = controller
+        .get_soundboard()
+        .await
+        .expect("failed to get kenku soundboards");
+
+    let report = controller
+        .execute_batch(vec![
+            KenkuPlaybackCommand::PlaylistPlaybackVolume(Volume::from_percent(60)).into(),
+            KenkuPlaybackCommand::PlaylistPlaybackShuffle(false).into(),
+            KenkuCommandWithPayload::PlayPlaylist(playlists.playlists[0].id.clone()),
+            KenkuCommandWithPayload::PlaySound(soundboard.sounds[0].id.clone()),
+            KenkuCommandWithPayload::PlaySound("missing".to_string()),
+        ])
+        .await;
+
+    assert!(!report.is_success());
+    assert_eq!(report.succeeded(), 4);
+    assert_eq!(
+        report.failures()[0].0,
+        &KenkuCommandWithPayload::PlaySound("missing".to_string())
+    );
+}
+
+#[tokio::test]
+async fn restore_a_playback_snapshot() {
+    let remote = common::remote().await;
+    let controller = remote.controller();
+    let playlists = controller
+        .get_playlist()
+        .await
+        .expect("failed to get kenku playlists");
+    let soundboard = controller
+        .get_soundboard()
+        .await
+        .expect("failed to get kenku soundboards");
+    let sound = soundboard.sounds[0].id.clone();
+
+    controller
+        .execute_batch(vec![
+            KenkuCommandWithPayload::PlayTrack(playlists.tracks[0].id.clone()),
+            KenkuPlaybackCommand::PlaylistPlaybackVolume(Volume::from_percent(40)).into(),
+            KenkuCommandWithPayload::PlaySound(sound.clone()),
+        ])
+        .await;
+    let before = controller.snapshot().await.unwrap();
+
+    controller
+        .execute_batch(vec![
+            KenkuPlaybackCommand::PlaylistPlaybackPause.into(),
+            KenkuPlaybackCommand::PlaylistPlaybackVolume(Volume::FULL).into(),
+            KenkuCommandWithPayload::StopSound(sound.clone()),
+        ])
+        .await;
+
+    let report = controller.restore(&before).await.unwrap();
+    let after = controller.snapshot().await.unwrap();
+
+    assert!(report.is_success());
+    assert!(after.playlist.playing);
+    assert_eq!(after.playlist.volume_level(), Volume::from_percent(40));
+    assert_eq!(
+        after.playlist.track.map(|track| track.id),
+        before.playlist.track.map(|track| track.id)
+    );
+    assert!(after.soundboard.is_playing(&sound));
+}
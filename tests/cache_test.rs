@@ -0,0 +1,44 @@
+//! Listing caching, checked against the mock remote only since it counts the requests.
+#![cfg(all(feature = "cache", not(feature = "live-tests")))]
+
+mod common;
+
+use std::time::Duration;
+
+#[tokio::test]
+async fn cached_listings_skip_the_remote() {
+    let remote = common::remote().await;
+    let controller =
+        kenku_control::Controller::builder(remote.address.host.clone(), remote.address.port)
+            .cache_ttl(Duration::from_secs(60))
+            .build()
+            .unwrap();
+
+    let first = controller.get_playlist().await.unwrap();
+    let second = controller.get_playlist().await.unwrap();
+    assert_eq!(first, second);
+    assert_eq!(remote.mock().requests().len(), 1);
+
+    controller.get_playlist_playback().await.unwrap();
+    controller.get_playlist_playback().await.unwrap();
+    assert_eq!(remote.mock().requests().len(), 3);
+
+    controller.invalidate();
+    controller.get_playlist().await.unwrap();
+    assert_eq!(remote.mock().requests().len(), 4);
+
+    controller.refresh().await.unwrap();
+    controller.get_soundboard().await.unwrap();
+    controller.clone().get_playlist().await.unwrap();
+    assert_eq!(remote.mock().requests().len(), 6);
+}
+
+#[tokio::test]
+async fn listings_are_fetched_every_time_by_default() {
+    let remote = common::remote().await;
+    let controller = remote.controller();
+
+    controller.get_soundboard().await.unwrap();
+    controller.get_soundboard().await.unwrap();
+    assert_eq!(remote.mock().requests().len(), 2);
+}
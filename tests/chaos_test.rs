@@ -0,0 +1,74 @@
+//! Recovery under injected faults, with the chaos proxy in front of the mock remote.
+#![cfg(all(feature = "chaos", not(feature = "live-tests")))]
+
+mod common;
+
+use kenku_control::{
+    chaos::{ChaosConfig, ChaosProxy},
+    Controller, KenkuError,
+};
+
+async fn proxy(config: ChaosConfig) -> (common::TestRemote, ChaosProxy) {
+    let remote = common::remote().await;
+    let proxy = ChaosProxy::start(remote.address.clone(), config)
+        .await
+        .unwrap();
+
+    (remote, proxy)
+}
+
+#[tokio::test]
+async fn clean_proxy_is_transparent() {
+    let (_remote, proxy) = proxy(ChaosConfig::default()).await;
+    let controller = Controller::from_address(proxy.address());
+
+    let snapshot = controller.get_all().await.unwrap();
+
+    assert_eq!(snapshot.playlist.tracks.len(), 3);
+    assert_eq!(proxy.stats().requests, 4);
+}
+
+#[tokio::test]
+async fn error_burst_is_reported_then_clears() {
+    let (_remote, proxy) = proxy(ChaosConfig {
+        error_rate: 1.0,
+        error_burst: 2,
+        seed: Some(1),
+        ..ChaosConfig::default()
+    })
+    .await;
+    let controller = Controller::from_address(proxy.address());
+
+    let error = controller.get_playlist().await.unwrap_err();
+    assert!(matches!(error, KenkuError::Status { status, .. } if status == 503));
+
+    proxy.set_config(ChaosConfig::default());
+    assert!(controller.get_playlist().await.is_ok());
+}
+
+#[tokio::test]
+async fn malformed_json_is_a_deserialization_error() {
+    let (_remote, proxy) = proxy(ChaosConfig {
+        malformed_rate: 1.0,
+        ..ChaosConfig::default()
+    })
+    .await;
+    let controller = Controller::from_address(proxy.address());
+
+    let error = controller.get_soundboard().await.unwrap_err();
+
+    assert!(matches!(error, KenkuError::Deserialization(_)));
+}
+
+#[tokio::test]
+async fn dropped_connections_are_errors() {
+    let (_remote, proxy) = proxy(ChaosConfig {
+        drop_rate: 1.0,
+        ..ChaosConfig::default()
+    })
+    .await;
+    let controller = Controller::from_address(proxy.address());
+
+    assert!(controller.get_playlist_playback().await.is_err());
+    assert_eq!(proxy.stats().dropped, 1);
+}
@@ -0,0 +1,141 @@
+//! The `kenku-ctl` binary, run against the mock remote.
+#![cfg(all(feature = "cli", not(feature = "live-tests")))]
+
+mod common;
+
+use kenku_control::Volume;
+use std::process::Output;
+
+async fn kenku_ctl(remote: &common::TestRemote, config: &str, args: &[&str]) -> Output {
+    let dir = std::env::temp_dir().join(format!("kenku_ctl_{}", remote.address.port));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("kenku.toml"), config).unwrap();
+
+    tokio::process::Command::new(env!("CARGO_BIN_EXE_kenku-ctl"))
+        .current_dir(&dir)
+        .env_remove("KENKU_CONFIG")
+        .args(["--host", &remote.address.host])
+        .args(["--port", &remote.address.port.to_string()])
+        .args(args)
+        .output()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn commands_reach_the_remote() {
+    let remote = common::remote().await;
+    let controller = remote.controller();
+
+    let output = kenku_ctl(&remote, "", &["volume", "40"]).await;
+    assert!(output.status.success());
+    assert_eq!(
+        controller
+            .get_playlist_playback()
+            .await
+            .unwrap()
+            .volume_level(),
+        Volume::from_percent(40)
+    );
+
+    let output = kenku_ctl(&remote, "", &["sound", "thunder"]).await;
+    assert!(output.status.success());
+    assert_eq!(remote.mock().playing_sounds(), ["s2"]);
+
+    let output = kenku_ctl(&remote, "", &["playlists"]).await;
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "p1\tTown\np2\tDungeon\n"
+    );
+}
+
+#[tokio::test]
+async fn scenes_come_from_the_configuration_file() {
+    let remote = common::remote().await;
+    let config = "[scenes.storm]\nname = \"storm\"\nsounds = [\"s1\", \"s2\"]\n";
+
+    let output = kenku_ctl(&remote, config, &["scene", "storm"]).await;
+    assert!(output.status.success());
+    assert_eq!(remote.mock().playing_sounds(), ["s1", "s2"]);
+
+    let output = kenku_ctl(&remote, config, &["scene", "calm"]).await;
+    assert_eq!(output.status.code(), Some(1));
+
+    let output = kenku_ctl(&remote, config, &["volume", "loud"]).await;
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[cfg(feature = "rest")]
+#[tokio::test]
+async fn admin_commands_manage_the_gateway() {
+    use kenku_control::{auth::BearerTokens, clients::ClientTracker, rest::RestApi};
+
+    let remote = common::remote().await;
+    let controller = remote.controller();
+    let server = RestApi::new(&controller)
+        .auth(BearerTokens::new().admin("gm-token", "gm"))
+        .clients(ClientTracker::new().moderator("gm"))
+        .start("127.0.0.1:0")
+        .await
+        .unwrap();
+    let gateway = format!("http://{}", server.local_addr());
+    let admin = |args: &[&str], token: &str| {
+        tokio::process::Command::new(env!("CARGO_BIN_EXE_kenku-ctl"))
+            .env_remove("KENKU_GATEWAY")
+            .env("KENKU_GATEWAY_TOKEN", token)
+            .args(["--gateway", &gateway, "admin"])
+            .args(args)
+            .output()
+    };
+
+    let output = admin(&["pause-automation"], "gm-token").await.unwrap();
+    assert!(output.status.success());
+    assert!(controller.automation_paused());
+
+    let output = admin(&["automation"], "gm-token").await.unwrap();
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "automation paused\n"
+    );
+
+    let output = admin(&["clients"], "gm-token").await.unwrap();
+    assert!(output.status.success());
+
+    let output = admin(&["insights"], "gm-token").await.unwrap();
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "0 clients, 0 commands sent, 0 refused (0%), 0 banned\n"
+    );
+
+    let output = admin(&["resume-automation"], "wrong-token").await.unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(controller.automation_paused());
+
+    let output = admin(&["revoke-token"], "gm-token").await.unwrap();
+    assert_eq!(output.status.code(), Some(2));
+
+    let output = admin(&["revoke-token", "gm"], "gm-token").await.unwrap();
+    assert!(output.status.success());
+    let output = admin(&["automation"], "gm-token").await.unwrap();
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[cfg(feature = "soak")]
+#[tokio::test]
+async fn soak_runs_against_the_remote() {
+    let remote = common::remote().await;
+
+    let output = kenku_ctl(&remote, "", &["soak", "0.02"]).await;
+    let printed = String::from_utf8(output.stdout).unwrap();
+    assert!(output.status.success(), "{}", printed);
+    assert!(printed.contains("1 rounds (0 failed)"), "{}", printed);
+    assert!(remote
+        .mock()
+        .requests()
+        .contains(&"PUT /v1/playlist/playback/volume".to_string()));
+
+    for minutes in ["forever", "1e300"] {
+        let output = kenku_ctl(&remote, "", &["soak", minutes]).await;
+        assert_eq!(output.status.code(), Some(2));
+    }
+}
@@ -0,0 +1,55 @@
+//! Sequential sending through a `CommandQueue`, checked against the requests the mock remote saw.
+#![cfg(all(feature = "queue", not(feature = "live-tests")))]
+
+mod common;
+
+use kenku_control::{
+    playlist::Repeat, queue::CommandQueue, KenkuCommandWithPayload, KenkuError,
+    KenkuPlaybackCommand,
+};
+use std::time::{Duration, Instant};
+
+#[tokio::test]
+async fn commands_are_sent_in_order_with_spacing() {
+    let remote = common::remote().await;
+    let controller = remote.controller();
+    let queue = CommandQueue::new(&controller, Duration::from_millis(50));
+    let started = Instant::now();
+
+    let results = queue.push_all([
+        KenkuCommandWithPayload::PlaySound("s1".to_string()),
+        KenkuPlaybackCommand::PlaylistPlaybackPause.into(),
+        KenkuPlaybackCommand::PlaylistPlaybackRepeat(Repeat::Track).into(),
+    ]);
+
+    for result in results {
+        assert!(result.result().await.unwrap().is_success());
+    }
+
+    assert!(started.elapsed() >= Duration::from_millis(100));
+    assert!(queue.is_empty());
+    assert_eq!(
+        remote.mock().requests(),
+        [
+            "PUT /v1/soundboard/play",
+            "PUT /v1/playlist/playback/pause",
+            "PUT /v1/playlist/playback/repeat"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn each_command_gets_its_own_result() {
+    let remote = common::remote().await;
+    let controller = remote.controller();
+    let queue = CommandQueue::new(&controller, Duration::ZERO);
+
+    let missing = queue.push(KenkuCommandWithPayload::PlaySound("missing".to_string()));
+    let found = queue.push(KenkuCommandWithPayload::PlaySound("s2".to_string()));
+
+    assert!(matches!(
+        missing.result().await,
+        Err(KenkuError::Status { status, .. }) if status == 404
+    ));
+    assert!(found.result().await.unwrap().is_success());
+}
@@ -0,0 +1,53 @@
+//! Shared setup for the integration tests.
+//!
+//! By default every test talks to an in-process mock of Kenku Remote, so the suite runs anywhere.
+//! With `--features live-tests`, the tests that make sense against real data run against the
+//! Kenku Remote listening on `127.0.0.1:3333` instead.
+#![allow(dead_code)]
+
+#[allow(unused_imports)]
+pub use kenku_control::testing::{Fault, MockRemote};
+
+use kenku_control::{Controller, KenkuAddress};
+
+/// The Kenku Remote a test talks to.
+pub struct TestRemote {
+    pub address: KenkuAddress,
+    pub mock: Option<MockRemote>,
+}
+
+impl TestRemote {
+    /// Returns a new controller for this remote, with the default 100 ms timeout.
+    pub fn controller(&self) -> Controller {
+        Controller::from_address(self.address.clone())
+    }
+
+    /// Returns the mock behind this remote.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the tests run against a live remote; tests needing the mock are compiled out then.
+    pub fn mock(&self) -> &MockRemote {
+        self.mock.as_ref().expect("this test needs the mock remote")
+    }
+}
+
+/// Returns the remote for the current test mode.
+#[cfg(not(feature = "live-tests"))]
+pub async fn remote() -> TestRemote {
+    let mock = MockRemote::start().await;
+
+    TestRemote {
+        address: mock.address(),
+        mock: Some(mock),
+    }
+}
+
+/// Returns the remote for the current test mode.
+#[cfg(feature = "live-tests")]
+pub async fn remote() -> TestRemote {
+    TestRemote {
+        address: KenkuAddress::new("127.0.0.1", 3333),
+        mock: None,
+    }
+}
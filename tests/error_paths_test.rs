@@ -0,0 +1,193 @@
+//! Failure handling, checked against the mock remote only since a live one cannot be told to fail.
+#![cfg(not(feature = "live-tests"))]
+
+mod common;
+
+use common::Fault;
+#[cfg(feature = "capability")]
+use kenku_control::capability::Feature;
+use kenku_control::{playlist::playback, KenkuError, KenkuState, RetryPolicy, Volume};
+use std::time::Duration;
+
+#[tokio::test]
+async fn server_error_keeps_the_status_and_body() {
+    let remote = common::remote().await;
+    let controller = remote.controller();
+    remote.mock().inject(Fault::Status(500));
+
+    let error = controller.get_playlist().await.unwrap_err();
+
+    match error {
+        KenkuError::Status { status, body } => {
+            assert_eq!(status, 500);
+            assert_eq!(body, "injected failure");
+        }
+        other => panic!("expected a status error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn unknown_sound_is_not_found() {
+    let remote = common::remote().await;
+    let controller = remote.controller();
+    let mut sounds = controller.get_soundboard().await.unwrap().sounds;
+    sounds[0].id = "missing".to_string();
+
+    let error = sounds[0].play(&controller).await.unwrap_err();
+
+    assert!(matches!(error, KenkuError::Status { status, .. } if status == 404));
+}
+
+#[cfg(feature = "capability")]
+#[tokio::test]
+async fn missing_routes_are_unsupported() {
+    let remote = common::remote().await;
+    let controller = remote.controller();
+
+    assert!(controller.supports(Feature::Playlist).await.unwrap());
+    assert!(controller
+        .supports(Feature::SoundboardPlayback)
+        .await
+        .unwrap());
+    assert!(!controller.supports(Feature::Seek).await.unwrap());
+}
+
+#[tokio::test]
+async fn remote_rejection_is_a_status_error() {
+    let remote = common::remote().await;
+    let controller = remote.controller();
+    remote.mock().inject(Fault::Status(400));
+
+    let error = playback::playback_volume(&controller, Volume::FULL)
+        .await
+        .unwrap_err();
+
+    assert!(matches!(error, KenkuError::Status { status, .. } if status == 400));
+}
+
+#[tokio::test]
+async fn slow_answer_times_out() {
+    let remote = common::remote().await;
+    let controller = remote.controller();
+    remote
+        .mock()
+        .inject(Fault::Delay(Duration::from_millis(500)));
+
+    let error = controller.get_playlist_playback().await.unwrap_err();
+
+    assert!(matches!(error, KenkuError::Timeout(_)));
+    assert_eq!(controller.state(), KenkuState::Offline);
+}
+
+#[tokio::test]
+async fn retries_recover_from_a_timeout() {
+    let remote = common::remote().await;
+    let controller =
+        kenku_control::Controller::builder(remote.address.host.clone(), remote.address.port)
+            .retries(1)
+            .build()
+            .unwrap();
+    remote
+        .mock()
+        .inject(Fault::Delay(Duration::from_millis(500)));
+
+    assert!(controller.get_playlist_playback().await.is_ok());
+    assert_eq!(remote.mock().requests().len(), 2);
+}
+
+#[tokio::test]
+async fn timed_out_posts_are_not_sent_twice() {
+    let remote = common::remote().await;
+    let controller =
+        kenku_control::Controller::builder(remote.address.host.clone(), remote.address.port)
+            .retries(1)
+            .build()
+            .unwrap();
+    remote
+        .mock()
+        .inject(Fault::Delay(Duration::from_millis(500)));
+
+    assert!(matches!(
+        playback::playback_next(&controller).await,
+        Err(KenkuError::Timeout(_))
+    ));
+    assert_eq!(
+        remote.mock().requests(),
+        ["POST /v1/playlist/playback/next"]
+    );
+}
+
+#[tokio::test]
+async fn retry_policy_recovers_from_server_errors() {
+    let remote = common::remote().await;
+    let controller =
+        kenku_control::Controller::builder(remote.address.host.clone(), remote.address.port)
+            .retry_policy(
+                RetryPolicy::new(2)
+                    .backoff(Duration::from_millis(10), Duration::from_millis(50))
+                    .retry_on_server_error(true),
+            )
+            .build()
+            .unwrap();
+    remote.mock().inject(Fault::Status(503));
+    remote.mock().inject(Fault::Status(502));
+
+    assert!(controller.get_playlist_playback().await.is_ok());
+    assert_eq!(remote.mock().requests().len(), 3);
+
+    remote.mock().inject(Fault::Status(400));
+    assert!(controller.get_playlist_playback().await.is_err());
+    assert_eq!(remote.mock().requests().len(), 4);
+}
+
+#[tokio::test]
+async fn malformed_payload_is_a_deserialization_error() {
+    let remote = common::remote().await;
+    let controller = remote.controller();
+    remote.mock().inject(Fault::MalformedBody);
+
+    let error = controller.get_playlist_playback().await.unwrap_err();
+
+    assert!(matches!(error, KenkuError::Deserialization(_)));
+}
+
+#[tokio::test]
+async fn dropped_connection_is_an_error() {
+    let remote = common::remote().await;
+    let controller = remote.controller();
+    remote.mock().inject(Fault::Disconnect);
+
+    assert!(controller.get_soundboard().await.is_err());
+    assert!(controller.get_soundboard().await.is_ok());
+}
+
+#[tokio::test]
+async fn offline_remote_is_a_connection_error() {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    drop(listener);
+
+    let error = kenku_control::Controller::from_address(address.into())
+        .get_playlist()
+        .await
+        .unwrap_err();
+
+    assert!(matches!(error, KenkuError::Connection(_)));
+}
+
+#[tokio::test]
+async fn commands_change_the_remote_state() {
+    let remote = common::remote().await;
+    let controller = remote.controller();
+    let tracks = controller.get_playlist().await.unwrap().tracks;
+
+    tracks[1].play(&controller).await.unwrap();
+    playback::playback_next(&controller).await.unwrap();
+    playback::playback_mute(&controller, true).await.unwrap();
+
+    let state = controller.get_playlist_playback().await.unwrap();
+    assert!(state.playing);
+    assert!(state.muted);
+    assert_eq!(state.track.unwrap().id, tracks[0].id);
+    assert_eq!(state.playlist.unwrap().title, "Town");
+}
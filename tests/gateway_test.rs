@@ -0,0 +1,222 @@
+//! The gateway client, driving a REST gateway in front of the mock remote.
+#![cfg(all(feature = "gateway", feature = "rest", not(feature = "live-tests")))]
+
+mod common;
+
+use kenku_control::{
+    auth::BearerTokens, clients::ClientTracker, gateway::client::GatewayClient, rest::RestApi,
+    scenes::Scene, KenkuCommandWithPayload, KenkuError,
+};
+use reqwest::StatusCode;
+use std::time::Duration;
+
+#[tokio::test]
+async fn the_client_drives_the_gateway_like_a_controller() {
+    let remote = common::remote().await;
+    let controller = remote.controller();
+    let server = RestApi::new(&controller)
+        .token("hunter2")
+        .scene(Scene::new("boss fight").sound("s2"))
+        .start("127.0.0.1:0")
+        .await
+        .unwrap();
+    let gateway = GatewayClient::new(format!("http://{}", server.local_addr())).token("hunter2");
+
+    assert_eq!(
+        gateway.get_playlist().await.unwrap(),
+        controller.get_playlist().await.unwrap()
+    );
+    assert_eq!(gateway.scenes().await.unwrap(), ["boss fight"]);
+
+    gateway.play_sound_id("s1").await.unwrap();
+    assert!(matches!(
+        gateway.play_sound_id("nope").await,
+        Err(KenkuError::Status {
+            status: StatusCode::BAD_GATEWAY,
+            ..
+        })
+    ));
+
+    let report = gateway
+        .execute_batch(&[
+            KenkuCommandWithPayload::StopSound("s1".to_string()),
+            KenkuCommandWithPayload::PlaySound("nope".to_string()),
+        ])
+        .await
+        .unwrap();
+    assert_eq!((report.sent, report.succeeded()), (2, 1));
+    assert_eq!(
+        report.failures[0].command,
+        KenkuCommandWithPayload::PlaySound("nope".to_string())
+    );
+
+    let snapshot = gateway.snapshot().await.unwrap();
+    assert!(gateway
+        .activate_scene("boss fight")
+        .await
+        .unwrap()
+        .is_success());
+    assert_eq!(remote.mock().playing_sounds(), ["s2"]);
+    assert!(gateway.restore(&snapshot).await.unwrap().is_success());
+    assert_eq!(gateway.get_soundboard_playback().await.unwrap().sounds, []);
+
+    match gateway.activate_scene("calm").await {
+        Err(KenkuError::Status { status, body }) => {
+            assert_eq!(status, StatusCode::NOT_FOUND);
+            assert_eq!(body, "scene \"calm\" was not found");
+        }
+        other => panic!("expected a 404, got {:?}", other),
+    }
+
+    let stranger = GatewayClient::new(format!("http://{}", server.local_addr()));
+    assert!(matches!(
+        stranger.snapshot().await,
+        Err(KenkuError::Status {
+            status: StatusCode::UNAUTHORIZED,
+            ..
+        })
+    ));
+}
+
+#[tokio::test]
+async fn moderators_manage_the_gateway() {
+    let remote = common::remote().await;
+    let controller = remote.controller();
+    let server = RestApi::new(&controller)
+        .auth(
+            BearerTokens::new()
+                .admin("ana-token", "ana")
+                .admin("gm-token", "gm"),
+        )
+        .clients(ClientTracker::new().moderator("gm"))
+        .start("127.0.0.1:0")
+        .await
+        .unwrap();
+    let base = format!("http://{}", server.local_addr());
+    let ana = GatewayClient::new(&base).token("ana-token");
+    let gm = GatewayClient::new(&base).token("gm-token");
+
+    ana.play_sound_id("s1").await.unwrap();
+    assert!(matches!(
+        ana.pause_automation().await,
+        Err(KenkuError::Status {
+            status: StatusCode::FORBIDDEN,
+            ..
+        })
+    ));
+
+    gm.pause_automation().await.unwrap();
+    assert!(gm.automation_paused().await.unwrap());
+    assert!(controller.automation_paused());
+    gm.resume_automation().await.unwrap();
+    assert!(!gm.automation_paused().await.unwrap());
+
+    gm.ban("ana", Duration::from_secs(600)).await.unwrap();
+    let clients = gm.clients().await.unwrap();
+    assert_eq!((clients[0].name.as_str(), clients[0].sent), ("ana", 1));
+    assert!(clients[0].banned_secs.is_some());
+    gm.unban("ana").await.unwrap();
+    assert!(gm.unban("ana").await.is_err());
+
+    gm.revoke_token("ana").await.unwrap();
+    assert!(matches!(
+        ana.snapshot().await,
+        Err(KenkuError::Status {
+            status: StatusCode::UNAUTHORIZED,
+            ..
+        })
+    ));
+    assert!(matches!(
+        gm.revoke_token("ana").await,
+        Err(KenkuError::Status {
+            status: StatusCode::NOT_FOUND,
+            ..
+        })
+    ));
+}
+
+#[tokio::test]
+async fn one_gateway_proxies_the_rooms_of_a_federation() {
+    use kenku_control::gateway::federation::Federation;
+
+    let hall = common::remote().await;
+    let side = common::remote().await;
+    let hall_server = RestApi::new(&hall.controller())
+        .token("hall-token")
+        .start("127.0.0.1:0")
+        .await
+        .unwrap();
+    let side_server = RestApi::new(&side.controller())
+        .token("side-token")
+        .scene(Scene::new("storm").sound("s2"))
+        .start("127.0.0.1:0")
+        .await
+        .unwrap();
+    let federation = Federation::new()
+        .room(
+            "hall",
+            GatewayClient::new(format!("http://{}", hall_server.local_addr())).token("hall-token"),
+        )
+        .room(
+            "side",
+            GatewayClient::new(format!("http://{}", side_server.local_addr())).token("side-token"),
+        )
+        .room("attic", GatewayClient::new("http://127.0.0.1:9"));
+    let seat_remote = common::remote().await;
+    let seat = RestApi::new(&seat_remote.controller())
+        .token("seat-token")
+        .federation(federation)
+        .start("127.0.0.1:0")
+        .await
+        .unwrap();
+    let url = |path: &str| format!("http://{}{}", seat.local_addr(), path);
+    let client = reqwest::Client::new();
+
+    let sent: serde_json::Value = client
+        .post(url("/rooms/side/commands"))
+        .bearer_auth("seat-token")
+        .json(&KenkuCommandWithPayload::PlaySound("s1".to_string()))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(sent, serde_json::json!({"sent": 1, "failures": []}));
+    assert_eq!(side.mock().playing_sounds(), ["s1"]);
+    assert!(hall.mock().playing_sounds().is_empty());
+
+    let activated = client
+        .post(url("/rooms/side/scenes/storm"))
+        .bearer_auth("seat-token")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(activated.status(), StatusCode::OK);
+    assert_eq!(side.mock().playing_sounds(), ["s1", "s2"]);
+
+    let missing = client
+        .post(url("/rooms/cellar/commands"))
+        .bearer_auth("seat-token")
+        .json(&KenkuCommandWithPayload::PlaySound("s1".to_string()))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+
+    let rooms: serde_json::Value = client
+        .get(url("/rooms"))
+        .bearer_auth("seat-token")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(
+        rooms["side"]["snapshot"]["soundboard"]["sounds"][1]["id"],
+        "s2"
+    );
+    assert!(rooms["hall"]["snapshot"].is_object());
+    assert!(rooms["attic"]["error"].is_string());
+}
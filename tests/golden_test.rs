@@ -0,0 +1,32 @@
+//! Pins down how the response models serialize, using the mock remote's fixed library.
+#![cfg(not(feature = "live-tests"))]
+
+mod common;
+
+use kenku_control::testing::assert_golden;
+
+#[tokio::test]
+async fn library_serialization_is_stable() {
+    let remote = common::remote().await;
+    let controller = remote.controller();
+
+    assert_golden("playlist", &controller.get_playlist().await.unwrap());
+    assert_golden("soundboard", &controller.get_soundboard().await.unwrap());
+}
+
+#[tokio::test]
+async fn playback_serialization_is_stable() {
+    let remote = common::remote().await;
+    let controller = remote.controller();
+    controller.play_track_id("t1").await.unwrap();
+    controller.play_sound_id("s1").await.unwrap();
+
+    assert_golden(
+        "playlist_playback",
+        &controller.get_playlist_playback().await.unwrap(),
+    );
+    assert_golden(
+        "soundboard_playback",
+        &controller.get_soundboard_playback().await.unwrap(),
+    );
+}
@@ -0,0 +1,72 @@
+//! The gRPC service, called through the generated client against the mock remote.
+#![cfg(all(feature = "grpc", not(feature = "live-tests")))]
+
+mod common;
+
+use kenku_control::{
+    grpc::{
+        proto::{
+            kenku_control_client::KenkuControlClient, playback_request, soundboard_request,
+            LibraryRequest, PlaybackRequest, SoundboardRequest,
+        },
+        KenkuControlService,
+    },
+    KenkuError,
+};
+use tonic::Code;
+
+#[tokio::test]
+async fn calls_reach_the_remote() {
+    let remote = common::remote().await;
+    let server = KenkuControlService::new(&remote.controller())
+        .start("127.0.0.1:0")
+        .await
+        .unwrap();
+    let mut client = KenkuControlClient::connect(format!("http://{}", server.local_addr()))
+        .await
+        .unwrap();
+
+    let library = client
+        .library(LibraryRequest {})
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(library.playlists.len(), 2);
+    assert_eq!(library.sounds[0].title, "Rain");
+
+    let playback = client
+        .playback(PlaybackRequest {
+            action: Some(playback_request::Action::Volume(0.4)),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(playback.volume, 0.4);
+
+    let playing = client
+        .soundboard(SoundboardRequest {
+            action: Some(soundboard_request::Action::Play("s2".to_string())),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(playing.sounds[0].id, "s2");
+
+    let rejected = client
+        .playback(PlaybackRequest {
+            action: Some(playback_request::Action::Volume(3.0)),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(rejected.code(), Code::InvalidArgument);
+}
+
+#[tokio::test]
+async fn the_service_only_listens_on_loopback() {
+    let remote = common::remote().await;
+    let service = KenkuControlService::new(&remote.controller());
+
+    let open = service.clone().start("0.0.0.0:0").await;
+    assert!(matches!(open, Err(KenkuError::InvalidInput(_))));
+    assert!(service.start("127.0.0.1:0").await.is_ok());
+}
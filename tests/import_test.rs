@@ -0,0 +1,45 @@
+//! Library descriptions diffed against the mock remote's fixed library.
+#![cfg(all(feature = "import", not(feature = "live-tests")))]
+
+mod common;
+
+use kenku_control::import::{EntryKind, LibraryChange, LibraryDescription};
+use serde_json::json;
+
+#[tokio::test]
+async fn descriptions_are_diffed_against_the_remote() {
+    let remote = common::remote().await;
+    let controller = remote.controller();
+
+    let description: LibraryDescription = serde_json::from_value(json!({
+        "playlists": [{"title": "Town", "tracks": [{"title": "Tavern"}, {"title": "Market"}]}],
+        "soundboards": [{"title": "Weather", "sounds": [{"id": "s1", "title": "Rainfall"}]}]
+    }))
+    .unwrap();
+
+    let diff = controller.diff_library(&description).await.unwrap();
+
+    assert_eq!(
+        diff.changes,
+        vec![
+            LibraryChange::Extra {
+                kind: EntryKind::Playlist,
+                parent: None,
+                id: "p2".to_string(),
+                title: "Dungeon".to_string(),
+            },
+            LibraryChange::Retitled {
+                kind: EntryKind::Sound,
+                id: "s1".to_string(),
+                expected: "Rainfall".to_string(),
+                actual: "Rain".to_string(),
+            },
+            LibraryChange::Extra {
+                kind: EntryKind::Sound,
+                parent: Some("Weather".to_string()),
+                id: "s2".to_string(),
+                title: "Thunder".to_string(),
+            },
+        ]
+    );
+}
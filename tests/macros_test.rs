@@ -0,0 +1,63 @@
+//! Recording commands with a `Recorder` and replaying the macro against the mock remote.
+#![cfg(all(feature = "macros", not(feature = "live-tests")))]
+
+mod common;
+
+use kenku_control::{macros::Recorder, KenkuCommandWithPayload, KenkuError, KenkuPlaybackCommand};
+use std::time::{Duration, Instant};
+
+#[tokio::test]
+async fn recorded_commands_replay_with_their_spacing() {
+    let remote = common::remote().await;
+    let controller = remote.controller();
+    let recorder = Recorder::new(&controller);
+
+    recorder
+        .execute(&KenkuCommandWithPayload::PlayPlaylist("p2".to_string()))
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    recorder
+        .execute(&KenkuCommandWithPayload::PlaySound("s2".to_string()))
+        .await
+        .unwrap();
+    let intro = recorder.finish();
+
+    assert_eq!(intro.steps.len(), 2);
+    assert!(intro.duration() >= Duration::from_millis(200));
+
+    let started = Instant::now();
+    let report = controller.replay(&intro, 2.0).await.unwrap();
+
+    assert!(report.is_success());
+    assert!(started.elapsed() >= Duration::from_millis(100));
+    assert!(started.elapsed() < intro.duration());
+    assert_eq!(
+        remote.mock().requests()[2..],
+        ["PUT /v1/playlist/play", "PUT /v1/soundboard/play"]
+    );
+}
+
+#[tokio::test]
+async fn failed_commands_are_recorded_and_do_not_stop_the_replay() {
+    let remote = common::remote().await;
+    let controller = remote.controller();
+    let recorder = Recorder::new(&controller);
+
+    assert!(recorder
+        .execute(&KenkuCommandWithPayload::PlaySound("missing".to_string()))
+        .await
+        .is_err());
+    recorder
+        .execute(&KenkuPlaybackCommand::PlaylistPlaybackPause.into())
+        .await
+        .unwrap();
+
+    let report = controller.replay(&recorder.recorded(), 1.0).await.unwrap();
+
+    assert_eq!(report.succeeded(), 1);
+    assert!(matches!(
+        report.failures()[..],
+        [(_, KenkuError::Status { status, .. })] if *status == 404
+    ));
+}
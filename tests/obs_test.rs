@@ -0,0 +1,81 @@
+//! The OBS bridge, driven by a fake obs-websocket server against the mock remote.
+#![cfg(all(feature = "obs", not(feature = "live-tests")))]
+
+mod common;
+
+use futures_util::{SinkExt, StreamExt};
+use kenku_control::{obs::ObsBridge, scenes::Scene};
+use serde_json::{json, Value};
+use tokio::net::TcpListener;
+use tokio_tungstenite::tungstenite::Message;
+
+#[tokio::test]
+async fn obs_scene_changes_activate_kenku_scenes() {
+    let remote = common::remote().await;
+    let controller = remote.controller();
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+
+    let obs = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+        let send = |value: Value| Message::text(value.to_string());
+
+        socket
+            .send(send(
+                json!({"op": 0, "d": {"obsWebSocketVersion": "5.5.0", "rpcVersion": 1}}),
+            ))
+            .await
+            .unwrap();
+        let identify = next(&mut socket).await;
+        assert_eq!(identify["op"], json!(1));
+
+        socket
+            .send(send(json!({"op": 2, "d": {"negotiatedRpcVersion": 1}})))
+            .await
+            .unwrap();
+        let request = next(&mut socket).await;
+        assert_eq!(request["d"]["requestType"], json!("GetCurrentProgramScene"));
+
+        socket
+            .send(send(json!({"op": 7, "d": {
+                "requestType": "GetCurrentProgramScene",
+                "requestId": request["d"]["requestId"],
+                "requestStatus": {"result": true, "code": 100},
+                "responseData": {"currentProgramSceneName": "Starting soon"},
+            }})))
+            .await
+            .unwrap();
+        socket
+            .send(send(json!({"op": 5, "d": {
+                "eventType": "CurrentProgramSceneChanged",
+                "eventIntent": 4,
+                "eventData": {"sceneName": "Storm"},
+            }})))
+            .await
+            .unwrap();
+        socket.close(None).await.unwrap();
+    });
+
+    ObsBridge::new(url)
+        .on_scene("Starting soon", Scene::new("waiting").sound("s1"))
+        .on_scene("Storm", Scene::new("storm").sound("s2"))
+        .run(&controller)
+        .await
+        .unwrap();
+    obs.await.unwrap();
+
+    assert_eq!(remote.mock().playing_sounds(), ["s1", "s2"]);
+}
+
+/// Returns the next text message, as JSON.
+async fn next<S>(socket: &mut S) -> Value
+where
+    S: StreamExt<Item = Result<Message, tokio_tungstenite::tungstenite::Error>> + Unpin,
+{
+    loop {
+        if let Message::Text(text) = socket.next().await.unwrap().unwrap() {
+            return serde_json::from_str(&text).unwrap();
+        }
+    }
+}
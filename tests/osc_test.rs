@@ -0,0 +1,57 @@
+//! The OSC server, driven over UDP against the mock remote.
+#![cfg(all(feature = "osc", not(feature = "live-tests")))]
+
+mod common;
+
+use kenku_control::{osc::OscServer, Volume};
+use rosc::{OscBundle, OscMessage, OscPacket, OscTime, OscType};
+use std::time::Duration;
+use tokio::net::UdpSocket;
+
+#[tokio::test]
+async fn osc_messages_drive_the_remote() {
+    let remote = common::remote().await;
+    let controller = remote.controller();
+    let server = OscServer::start("127.0.0.1:0", &controller).await.unwrap();
+
+    let message = |addr: &str, args: Vec<OscType>| {
+        OscPacket::Message(OscMessage {
+            addr: addr.to_string(),
+            args,
+        })
+    };
+    let bundle = OscPacket::Bundle(OscBundle {
+        timetag: OscTime::from((0, 1)),
+        content: vec![
+            message("/kenku/playlist/volume", vec![OscType::Float(0.25)]),
+            message("/kenku/soundboard/play/s1", vec![OscType::Float(1.0)]),
+            message("/kenku/soundboard/play/s2", vec![OscType::Float(0.0)]),
+        ],
+    });
+
+    let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    socket
+        .send_to(
+            &rosc::encoder::encode(&bundle).unwrap(),
+            server.local_addr(),
+        )
+        .await
+        .unwrap();
+
+    for _ in 0..50 {
+        if !remote.mock().playing_sounds().is_empty() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+
+    assert_eq!(remote.mock().playing_sounds(), ["s1"]);
+    assert_eq!(
+        controller
+            .get_playlist_playback()
+            .await
+            .unwrap()
+            .volume_level(),
+        Volume::from_percent(25)
+    );
+}
@@ -0,0 +1,75 @@
+//! Content packs installed against the mock remote's library.
+#![cfg(all(feature = "packs", not(feature = "live-tests")))]
+
+mod common;
+
+use kenku_control::{
+    packs::{self, Pack},
+    scenes::Scene,
+    search::find_track_with_aliases,
+    store::StateStore,
+    KenkuError, Volume,
+};
+
+fn pack() -> Pack {
+    Pack {
+        name: "sinister-dungeons".to_string(),
+        version: "1.2.0".to_string(),
+        scenes: vec![
+            Scene::new("crypt")
+                .track("Crypt")
+                .sound("file:///sfx/rain.ogg"),
+            Scene::new("town").playlist("Town"),
+        ],
+        tags: [("Crypt".to_string(), vec!["tomb".to_string()])].into(),
+        gains: [("Crypt".to_string(), Volume::from_percent(60))].into(),
+        ..Pack::default()
+    }
+}
+
+#[tokio::test]
+async fn packs_are_resolved_by_title_and_installed() {
+    let remote = common::remote().await;
+    let controller = remote.controller();
+    let root = std::env::temp_dir().join(format!("kenku_control_packs_{}", remote.address.port));
+    let store = StateStore::new(&root);
+
+    let installed = pack().install(&controller, &store).await.unwrap();
+    let crypt = &installed.scenes[0];
+    assert_eq!(crypt.track.as_deref(), Some("t3"));
+    assert_eq!(crypt.sounds, ["s1"]);
+    assert_eq!(crypt.volume, Some(Volume::from_percent(60)));
+    assert_eq!(installed.scenes[1].playlist.as_deref(), Some("p1"));
+
+    let library = controller.get_playlist().await.unwrap();
+    let found = find_track_with_aliases(&library, "tomb", &installed.aliases);
+    assert_eq!(found[0].item.id, "t3");
+
+    assert!(crypt.activate(&controller).await.unwrap().is_success());
+    assert_eq!(remote.mock().playing_sounds(), ["s1"]);
+
+    assert_eq!(packs::installed(&store).unwrap(), [installed]);
+    assert!(packs::uninstall(&store, "sinister-dungeons").unwrap());
+    assert!(!packs::uninstall(&store, "sinister-dungeons").unwrap());
+    assert_eq!(packs::installed(&store).unwrap(), []);
+
+    std::fs::remove_dir_all(root).ok();
+}
+
+#[tokio::test]
+async fn packs_missing_from_the_library_are_not_installed() {
+    let remote = common::remote().await;
+    let root = std::env::temp_dir().join(format!("kenku_control_packs_{}", remote.address.port));
+    let store = StateStore::new(&root);
+    let mut pack = pack();
+    pack.scenes
+        .push(Scene::new("forest").track("Birds").sound("Wolves"));
+
+    match pack.install(&remote.controller(), &store).await {
+        Err(KenkuError::NotFound(missing)) => {
+            assert!(missing.contains(r#"["Birds", "Wolves"]"#), "{}", missing)
+        }
+        other => panic!("expected the missing titles, got {:?}", other),
+    }
+    assert_eq!(packs::installed(&store).unwrap(), []);
+}
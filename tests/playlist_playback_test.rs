@@ -1,12 +1,13 @@
+mod common;
+
 use kenku_control::{playlist::playback, *};
 use rand::prelude::*;
-
-const DEFAULT_IP_ADDRESS: &str = "127.0.0.1";
-const DEFAULT_PORT: u16 = 3333;
+use std::time::Duration;
 
 #[tokio::test]
 async fn pause_playlist_playback() {
-    let controller = Controller::new(DEFAULT_IP_ADDRESS.to_string(), DEFAULT_PORT);
+    let remote = common::remote().await;
+    let controller = remote.controller();
     let command = playback::playback_pause(&controller)
         .await
         .expect("failed to pause the playback.");
@@ -16,7 +17,8 @@ async fn pause_playlist_playback() {
 
 #[tokio::test]
 async fn play_playlist_playback() {
-    let controller = Controller::new(DEFAULT_IP_ADDRESS.to_string(), DEFAULT_PORT);
+    let remote = common::remote().await;
+    let controller = remote.controller();
     let command = playback::playback_play(&controller)
         .await
         .expect("failed to play the playback.");
@@ -24,9 +26,33 @@ async fn play_playlist_playback() {
     assert!(command.is_success());
 }
 
+#[tokio::test]
+async fn toggle_playlist_playback() {
+    let remote = common::remote().await;
+    let controller = remote.controller();
+    let was_playing = controller
+        .get_playlist_playback()
+        .await
+        .expect("failed to get the playback.")
+        .playing;
+
+    let playing = playback::toggle_play(&controller)
+        .await
+        .expect("failed to toggle the playback.");
+    let is_playing = controller
+        .get_playlist_playback()
+        .await
+        .expect("failed to get the playback.")
+        .playing;
+
+    assert_eq!(playing, !was_playing);
+    assert_eq!(is_playing, playing);
+}
+
 #[tokio::test]
 async fn next_playlist_playback() {
-    let controller = Controller::new(DEFAULT_IP_ADDRESS.to_string(), DEFAULT_PORT);
+    let remote = common::remote().await;
+    let controller = remote.controller();
     let command = playback::playback_next(&controller)
         .await
         .expect("failed to go to next track on playback.");
@@ -36,7 +62,8 @@ async fn next_playlist_playback() {
 
 #[tokio::test]
 async fn previous_playlist_playback() {
-    let controller = Controller::new(DEFAULT_IP_ADDRESS.to_string(), DEFAULT_PORT);
+    let remote = common::remote().await;
+    let controller = remote.controller();
     let command = playback::playback_previous(&controller)
         .await
         .expect("failed to go to previous track on playback.");
@@ -46,7 +73,8 @@ async fn previous_playlist_playback() {
 
 #[tokio::test]
 async fn mute_playlist_playback() {
-    let controller = Controller::new(DEFAULT_IP_ADDRESS.to_string(), DEFAULT_PORT);
+    let remote = common::remote().await;
+    let controller = remote.controller();
     let is_muted = controller
         .get_playlist_playback()
         .await
@@ -61,27 +89,97 @@ async fn mute_playlist_playback() {
 
 #[tokio::test]
 async fn repeat_playlist_playback() {
-    let controller = Controller::new(DEFAULT_IP_ADDRESS.to_string(), DEFAULT_PORT);
+    let remote = common::remote().await;
+    let controller = remote.controller();
     let repeat_state = controller
         .get_playlist_playback()
         .await
         .expect("failed to get repeat state.")
         .repeat;
-    let repeat = match repeat_state {
-        playlist::Repeat::Track => playlist::Repeat::Playlist,
-        playlist::Repeat::Playlist => playlist::Repeat::Off,
-        playlist::Repeat::Off => playlist::Repeat::Track,
-    };
-    let command = playback::playback_repeat(&controller, repeat)
+    let command = playback::playback_repeat(&controller, repeat_state.next())
         .await
         .expect("failed to change repeat state.");
 
     assert!(command.is_success());
 }
 
+#[tokio::test]
+async fn cycle_playlist_repeat() {
+    let remote = common::remote().await;
+    let controller = remote.controller();
+    let before = controller
+        .get_playlist_playback()
+        .await
+        .expect("failed to get repeat state.")
+        .repeat;
+
+    let repeat = playback::cycle_repeat(&controller)
+        .await
+        .expect("failed to cycle repeat state.");
+    let after = controller
+        .get_playlist_playback()
+        .await
+        .expect("failed to get repeat state.")
+        .repeat;
+
+    assert_eq!(repeat, before.next());
+    assert_eq!(after, repeat);
+}
+
+#[tokio::test]
+async fn toggle_playlist_mute() {
+    let remote = common::remote().await;
+    let controller = remote.controller();
+    let was_muted = controller
+        .get_playlist_playback()
+        .await
+        .expect("failed to get mute state.")
+        .muted;
+
+    let muted = playback::toggle_mute(&controller)
+        .await
+        .expect("failed to toggle mute state.");
+    let is_muted = controller
+        .get_playlist_playback()
+        .await
+        .expect("failed to get mute state.")
+        .muted;
+
+    assert_eq!(muted, !was_muted);
+    assert_eq!(is_muted, muted);
+}
+
+#[tokio::test]
+async fn step_playlist_volume() {
+    let remote = common::remote().await;
+    let controller = remote.controller();
+    playback::playback_volume(&controller, Volume::from_percent(95))
+        .await
+        .expect("failed to change the volume.");
+
+    let raised = playback::volume_up(&controller, Volume::from_percent(10))
+        .await
+        .expect("failed to raise the volume.");
+    let lowered = playback::volume_down(&controller, Volume::from_percent(30))
+        .await
+        .expect("failed to lower the volume.");
+
+    assert_eq!(raised, Volume::FULL);
+    assert_eq!(lowered, Volume::from_percent(70));
+    assert_eq!(
+        controller
+            .get_playlist_playback()
+            .await
+            .expect("failed to get the volume.")
+            .volume_level(),
+        lowered
+    );
+}
+
 #[tokio::test]
 async fn shuffle_playlist_playback() {
-    let controller = Controller::new(DEFAULT_IP_ADDRESS.to_string(), DEFAULT_PORT);
+    let remote = common::remote().await;
+    let controller = remote.controller();
     let is_shuffled = controller
         .get_playlist_playback()
         .await
@@ -96,12 +194,151 @@ async fn shuffle_playlist_playback() {
 
 #[tokio::test]
 async fn volume_playlist_playback() {
-    let controller = Controller::new(DEFAULT_IP_ADDRESS.to_string(), DEFAULT_PORT);
+    let remote = common::remote().await;
+    let controller = remote.controller();
     let mut rng = rand::thread_rng();
-    let volume: f64 = rng.gen_range(0..=10) as f64 / 10.0;
+    let volume = Volume::from_percent(rng.gen_range(0..=10) * 10);
     let command = playback::playback_volume(&controller, volume)
         .await
         .expect("failed to change playback volume.");
 
     assert!(command.is_success());
 }
+
+#[tokio::test]
+async fn fade_and_restore_playlist_volume() {
+    let remote = common::remote().await;
+    let controller = remote.controller();
+    let target = Volume::from_percent(20);
+
+    let previous = playback::fade_volume_to(&controller, target, Duration::from_millis(50), 5)
+        .await
+        .expect("failed to fade the playback volume.");
+    let faded = controller
+        .get_playlist_playback()
+        .await
+        .unwrap()
+        .volume_level();
+
+    playback::restore_volume(&controller, previous, Duration::from_millis(50), 5)
+        .await
+        .expect("failed to restore the playback volume.");
+    let restored = controller
+        .get_playlist_playback()
+        .await
+        .unwrap()
+        .volume_level();
+
+    assert_eq!(faded, target);
+    assert_eq!(restored, previous);
+}
+
+#[cfg(feature = "crossfade")]
+#[tokio::test]
+async fn crossfade_to_another_track() {
+    let remote = common::remote().await;
+    let controller = remote.controller();
+    let track = controller.get_playlist().await.unwrap().tracks.remove(2);
+    let volume = controller
+        .get_playlist_playback()
+        .await
+        .unwrap()
+        .volume_level();
+
+    playlist::crossfade_to(&controller, &track, Duration::from_millis(200))
+        .wait()
+        .await
+        .expect("failed to crossfade.");
+    let playback = controller.get_playlist_playback().await.unwrap();
+
+    assert_eq!(playback.volume_level(), volume);
+    assert_eq!(playback.track.map(|current| current.id), Some(track.id));
+}
+
+#[cfg(feature = "crossfade")]
+#[tokio::test]
+async fn cancelled_crossfade_restores_the_volume() {
+    let remote = common::remote().await;
+    let controller = remote.controller();
+    let track = controller.get_playlist().await.unwrap().tracks.remove(2);
+    let volume = controller
+        .get_playlist_playback()
+        .await
+        .unwrap()
+        .volume_level();
+
+    let crossfade = playlist::crossfade_to(&controller, &track, Duration::from_secs(10));
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    crossfade
+        .cancel()
+        .await
+        .expect("failed to cancel the crossfade.");
+
+    assert_eq!(
+        controller
+            .get_playlist_playback()
+            .await
+            .unwrap()
+            .volume_level(),
+        volume
+    );
+}
+
+#[tokio::test]
+async fn play_a_whole_playlist() {
+    let remote = common::remote().await;
+    let controller = remote.controller();
+    let playlists = controller
+        .get_playlist()
+        .await
+        .expect("failed to get kenku playlists.");
+    let playlist = &playlists.playlists[0];
+
+    let command = playlist
+        .play(&controller)
+        .await
+        .expect("failed to play the playlist.");
+    let playback = controller
+        .get_playlist_playback()
+        .await
+        .expect("failed to get the playback.");
+
+    assert!(command.is_success());
+    assert_eq!(
+        playback.playlist.map(|playing| playing.id),
+        Some(playlist.id.clone())
+    );
+}
+
+#[tokio::test]
+async fn wait_for_the_track_to_change() {
+    let remote = common::remote().await;
+    let controller = remote.controller();
+    let playlists = controller
+        .get_playlist()
+        .await
+        .expect("failed to get kenku playlists.");
+
+    playlists.playlists[0]
+        .play(&controller)
+        .await
+        .expect("failed to play the playlist.");
+    let playing = controller
+        .get_playlist_playback()
+        .await
+        .expect("failed to get the playback.")
+        .track
+        .expect("no track is playing.");
+
+    let skipper = controller.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        playback::playback_next(&skipper).await
+    });
+
+    let ended = playback::wait_for_track_end(&controller)
+        .await
+        .expect("failed to wait for the track.");
+
+    assert_eq!(ended.map(|track| track.id), Some(playing.id));
+}
@@ -0,0 +1,473 @@
+//! The REST facade, driven over HTTP against the mock remote.
+#![cfg(all(feature = "rest", not(feature = "live-tests")))]
+
+mod common;
+
+use kenku_control::{
+    auth::{BearerTokens, DiscordGuild, ProxyHeader},
+    clients::ClientTracker,
+    rest::RestApi,
+    scenes::Scene,
+    KenkuError,
+};
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
+
+/// Stands in for the Discord API: `member-token` belongs to the member `42`, other tokens to
+/// users outside the server.
+///
+/// # Returns
+///
+/// This function returns the base URL of the API and how many requests it answered.
+async fn discord() -> (String, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/api", listener.local_addr().unwrap());
+    let asked = Arc::new(AtomicUsize::new(0));
+    let counter = asked.clone();
+
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let mut request = Vec::new();
+            let mut buffer = [0; 4096];
+
+            while !String::from_utf8_lossy(&request).contains("\r\n\r\n") {
+                let read = stream.read(&mut buffer).await.unwrap();
+                request.extend_from_slice(&buffer[..read]);
+            }
+
+            counter.fetch_add(1, Ordering::SeqCst);
+            let head = String::from_utf8_lossy(&request).to_lowercase();
+            let answer = if head.starts_with("get /api/users/@me/guilds/777/member ")
+                && head.contains("authorization: bearer member-token")
+            {
+                let body = r#"{"user": {"id": "42"}, "roles": []}"#;
+                format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                )
+            } else {
+                "HTTP/1.1 404 Not Found\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+                    .to_string()
+            };
+
+            let _ = stream.write_all(answer.as_bytes()).await;
+        }
+    });
+
+    (url, asked)
+}
+
+#[tokio::test]
+async fn requests_need_the_token_and_reach_the_remote() {
+    let remote = common::remote().await;
+    let controller = remote.controller();
+    let server = RestApi::new(&controller)
+        .token("hunter2")
+        .scene(Scene::new("storm").sound("s2"))
+        .start("127.0.0.1:0")
+        .await
+        .unwrap();
+    let url = |path: &str| format!("http://{}{}", server.local_addr(), path);
+    let client = reqwest::Client::new();
+
+    let denied = client.get(url("/snapshot")).send().await.unwrap();
+    assert_eq!(denied.status(), StatusCode::UNAUTHORIZED);
+
+    let found: Value = client
+        .get(url("/search/sounds?q=rain"))
+        .bearer_auth("hunter2")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(found[0]["sound"]["id"], json!("s1"));
+
+    let sent: Value = client
+        .post(url("/commands"))
+        .bearer_auth("hunter2")
+        .json(&json!([{"PlaySound": "s1"}, {"PlaySound": "nope"}]))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(sent["sent"], json!(2));
+    assert_eq!(sent["failures"][0]["command"], json!({"PlaySound": "nope"}));
+
+    let activated = client
+        .post(url("/scenes/storm"))
+        .bearer_auth("hunter2")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(activated.status(), StatusCode::OK);
+    assert_eq!(remote.mock().playing_sounds(), ["s1", "s2"]);
+
+    let missing = client
+        .post(url("/scenes/calm"))
+        .bearer_auth("hunter2")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn unreadable_bodies_are_answered_with_a_json_error() {
+    let remote = common::remote().await;
+    let server = RestApi::new(&remote.controller())
+        .start("127.0.0.1:0")
+        .await
+        .unwrap();
+    let url = |path: &str| format!("http://{}{}", server.local_addr(), path);
+    let client = reqwest::Client::new();
+
+    for request in [
+        client.post(url("/commands")).json(&json!({"Dance": "s1"})),
+        client
+            .put(url("/snapshot"))
+            .body("{")
+            .header("content-type", "application/json"),
+    ] {
+        let response = request.send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let body: Value = response.json().await.unwrap();
+        assert!(body["error"].as_str().unwrap().starts_with("invalid input"));
+    }
+}
+
+#[tokio::test]
+async fn open_servers_only_listen_on_loopback() {
+    let remote = common::remote().await;
+    let controller = remote.controller();
+
+    let open = RestApi::new(&controller).start("0.0.0.0:0").await;
+    assert!(matches!(open, Err(KenkuError::InvalidInput(_))));
+
+    assert!(RestApi::new(&controller).start("127.0.0.1:0").await.is_ok());
+    assert!(RestApi::new(&controller)
+        .token("hunter2")
+        .start("0.0.0.0:0")
+        .await
+        .is_ok());
+}
+
+#[tokio::test]
+async fn proxy_headers_name_the_users_let_in() {
+    let remote = common::remote().await;
+    let server = RestApi::new(&remote.controller())
+        .auth(ProxyHeader::new("X-Forwarded-User").users(["ana"]))
+        .start("127.0.0.1:0")
+        .await
+        .unwrap();
+    let url = format!("http://{}/playlist", server.local_addr());
+    let client = reqwest::Client::new();
+
+    let user = |name: &'static str| client.get(&url).header("x-forwarded-user", name).send();
+
+    assert_eq!(user("ana").await.unwrap().status(), StatusCode::OK);
+    assert_eq!(
+        user("eve").await.unwrap().status(),
+        StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(
+        client.get(&url).send().await.unwrap().status(),
+        StatusCode::UNAUTHORIZED
+    );
+}
+
+#[tokio::test]
+async fn only_admins_change_the_playback() {
+    let remote = common::remote().await;
+    let server = RestApi::new(&remote.controller())
+        .auth(
+            ProxyHeader::new("X-Forwarded-User")
+                .users(["ana"])
+                .admins(["bea"]),
+        )
+        .scene(Scene::new("storm").sound("s2"))
+        .start("127.0.0.1:0")
+        .await
+        .unwrap();
+    let url = |path: &str| format!("http://{}{}", server.local_addr(), path);
+    let client = reqwest::Client::new();
+
+    let snapshot: Value = client
+        .get(url("/snapshot"))
+        .header("x-forwarded-user", "ana")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+
+    for user in ["ana", "bea"] {
+        let expected = match user {
+            "bea" => StatusCode::OK,
+            _ => StatusCode::FORBIDDEN,
+        };
+        let sent = client
+            .post(url("/commands"))
+            .header("x-forwarded-user", user)
+            .json(&json!({"PlaySound": "s1"}))
+            .send()
+            .await
+            .unwrap();
+        let restored = client
+            .put(url("/snapshot"))
+            .header("x-forwarded-user", user)
+            .json(&snapshot)
+            .send()
+            .await
+            .unwrap();
+        let activated = client
+            .post(url("/scenes/storm"))
+            .header("x-forwarded-user", user)
+            .send()
+            .await
+            .unwrap();
+
+        assert_eq!(sent.status(), expected);
+        assert_eq!(restored.status(), expected);
+        assert_eq!(activated.status(), expected);
+    }
+}
+
+#[tokio::test]
+async fn clients_are_limited_banned_and_counted_for_moderators() {
+    let remote = common::remote().await;
+    let server = RestApi::new(&remote.controller())
+        .auth(ProxyHeader::new("X-Forwarded-User").admins(["ana", "gm"]))
+        .clients(ClientTracker::new().limit(2).moderator("gm"))
+        .start("127.0.0.1:0")
+        .await
+        .unwrap();
+    let url = |path: &str| format!("http://{}{}", server.local_addr(), path);
+    let client = reqwest::Client::new();
+    let send = |user: &'static str, body: Value| {
+        client
+            .post(url("/commands"))
+            .header("x-forwarded-user", user)
+            .json(&body)
+            .send()
+    };
+
+    let sent = send("ana", json!([{"PlaySound": "s1"}, {"PlaySound": "s2"}]))
+        .await
+        .unwrap();
+    let limited = send("ana", json!({"PlaySound": "s1"})).await.unwrap();
+    assert_eq!(sent.status(), StatusCode::OK);
+    assert_eq!(limited.status(), StatusCode::TOO_MANY_REQUESTS);
+
+    let forbidden = client
+        .get(url("/admin/clients"))
+        .header("x-forwarded-user", "ana")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(forbidden.status(), StatusCode::FORBIDDEN);
+
+    let banned = client
+        .put(url("/admin/bans/ana"))
+        .header("x-forwarded-user", "gm")
+        .json(&json!({"seconds": 600}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(banned.status(), StatusCode::NO_CONTENT);
+
+    let refused = client
+        .get(url("/snapshot"))
+        .header("x-forwarded-user", "ana")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(refused.status(), StatusCode::FORBIDDEN);
+
+    let clients: Value = client
+        .get(url("/admin/clients"))
+        .header("x-forwarded-user", "gm")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(clients[0]["name"], json!("ana"));
+    assert_eq!(clients[0]["sent"], json!(2));
+    assert_eq!(clients[0]["refused"], json!(1));
+    assert!(clients[0]["banned_secs"].as_u64().unwrap() > 590);
+
+    let metrics = client
+        .get(url("/metrics"))
+        .header("x-forwarded-user", "gm")
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert!(metrics.contains("kenku_gateway_client_banned{client=\"ana\"} 1\n"));
+
+    for expected in [StatusCode::NO_CONTENT, StatusCode::NOT_FOUND] {
+        let unbanned = client
+            .delete(url("/admin/bans/ana"))
+            .header("x-forwarded-user", "gm")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(unbanned.status(), expected);
+    }
+}
+
+#[tokio::test]
+async fn moderators_pause_automation_and_revoke_tokens() {
+    let remote = common::remote().await;
+    let controller = remote.controller();
+    let server = RestApi::new(&controller)
+        .auth(
+            BearerTokens::new()
+                .token("player-token", "players")
+                .admin("gm-token", "gm"),
+        )
+        .clients(ClientTracker::new().moderator("gm"))
+        .start("127.0.0.1:0")
+        .await
+        .unwrap();
+    let url = |path: &str| format!("http://{}{}", server.local_addr(), path);
+    let client = reqwest::Client::new();
+    let send = |method: reqwest::Method, path: &str, token: &'static str| {
+        let request = client.request(method, url(path)).bearer_auth(token).send();
+        async move { request.await.unwrap().status() }
+    };
+
+    assert_eq!(
+        send(reqwest::Method::PUT, "/admin/automation", "player-token").await,
+        StatusCode::FORBIDDEN
+    );
+    assert_eq!(
+        send(reqwest::Method::PUT, "/admin/automation", "gm-token").await,
+        StatusCode::NO_CONTENT
+    );
+    assert!(controller.automation_paused());
+
+    let paused: Value = client
+        .get(url("/admin/automation"))
+        .bearer_auth("gm-token")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(paused, json!({"paused": true}));
+
+    assert_eq!(
+        send(reqwest::Method::DELETE, "/admin/automation", "gm-token").await,
+        StatusCode::NO_CONTENT
+    );
+    assert!(!controller.automation_paused());
+
+    assert_eq!(
+        send(reqwest::Method::GET, "/playlist", "player-token").await,
+        StatusCode::OK
+    );
+    for expected in [StatusCode::NO_CONTENT, StatusCode::NOT_FOUND] {
+        assert_eq!(
+            send(reqwest::Method::DELETE, "/admin/tokens/players", "gm-token").await,
+            expected
+        );
+    }
+    assert_eq!(
+        send(reqwest::Method::GET, "/playlist", "player-token").await,
+        StatusCode::UNAUTHORIZED
+    );
+}
+
+#[tokio::test]
+async fn discord_members_are_let_in_and_remembered() {
+    let remote = common::remote().await;
+    let (api, asked) = discord().await;
+    let server = RestApi::new(&remote.controller())
+        .auth(DiscordGuild::new("777").api_base(api).lookups_per_minute(3))
+        .start("127.0.0.1:0")
+        .await
+        .unwrap();
+    let url = format!("http://{}/playlist", server.local_addr());
+    let client = reqwest::Client::new();
+    let status = |token: &'static str| {
+        let request = client.get(&url).bearer_auth(token).send();
+        async move { request.await.unwrap().status() }
+    };
+
+    for _ in 0..2 {
+        assert_eq!(status("member-token").await, StatusCode::OK);
+    }
+    assert_eq!(asked.load(Ordering::SeqCst), 1);
+
+    // Rejected tokens are asked about again, until the lookups of the minute are used up.
+    for _ in 0..3 {
+        assert_eq!(status("stranger-token").await, StatusCode::UNAUTHORIZED);
+    }
+    assert_eq!(asked.load(Ordering::SeqCst), 3);
+
+    assert_eq!(status("member-token").await, StatusCode::OK);
+}
+
+#[cfg(feature = "log-filter")]
+#[tokio::test]
+async fn moderators_change_the_log_filter() {
+    use kenku_control::log_filter::LogFilter;
+
+    let remote = common::remote().await;
+    let (_layer, filter) = LogFilter::layer("warn").unwrap();
+    let server = RestApi::new(&remote.controller())
+        .auth(ProxyHeader::new("X-Forwarded-User").admins(["ana", "gm"]))
+        .clients(ClientTracker::new().moderator("gm"))
+        .log_filter(filter.clone())
+        .start("127.0.0.1:0")
+        .await
+        .unwrap();
+    let url = format!("http://{}/admin/log-filter", server.local_addr());
+    let client = reqwest::Client::new();
+    let change = |user: &'static str, directives: &'static str| {
+        client
+            .put(&url)
+            .header("x-forwarded-user", user)
+            .json(&json!({ "directives": directives }))
+            .send()
+    };
+
+    let forbidden = change("ana", "debug").await.unwrap();
+    let rejected = change("gm", "kenku_control=loud").await.unwrap();
+    let changed = change("gm", "kenku_control=debug").await.unwrap();
+    assert_eq!(forbidden.status(), StatusCode::FORBIDDEN);
+    assert_eq!(rejected.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(changed.status(), StatusCode::OK);
+
+    let current: Value = client
+        .get(&url)
+        .header("x-forwarded-user", "gm")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(current, json!({"directives": "kenku_control=debug"}));
+    assert_eq!(filter.directives().unwrap(), "kenku_control=debug");
+}
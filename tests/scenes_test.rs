@@ -0,0 +1,60 @@
+//! Scenes activated against the remote, checked through the playback it reports afterwards.
+#![cfg(feature = "scenes")]
+mod common;
+
+use kenku_control::{playlist::Repeat, scenes::Scene, KenkuError, Volume};
+
+#[tokio::test]
+async fn activate_a_scene() {
+    let remote = common::remote().await;
+    let controller = remote.controller();
+    let playlists = controller
+        .get_playlist()
+        .await
+        .expect("failed to get kenku playlists");
+    let soundboard = controller
+        .get_soundboard()
+        .await
+        .expect("failed to get kenku soundboards");
+    let sound = soundboard.sounds[0].id.clone();
+
+    let scene = Scene::new("tavern")
+        .track(playlists.tracks[0].id.clone())
+        .volume(Volume::from_percent(30))
+        .repeat(Repeat::Track)
+        .sound(sound.clone());
+
+    assert!(scene.activate(&controller).await.unwrap().is_success());
+
+    let playback = controller.snapshot().await.unwrap();
+    assert_eq!(playback.playlist.volume_level(), Volume::from_percent(30));
+    assert_eq!(playback.playlist.repeat, Repeat::Track);
+    assert!(playback.soundboard.is_playing(&sound));
+
+    assert!(scene
+        .activate(&controller)
+        .await
+        .unwrap()
+        .results
+        .is_empty());
+}
+
+#[tokio::test]
+async fn scenes_with_missing_sounds_change_nothing() {
+    let remote = common::remote().await;
+    let controller = remote.controller();
+    let before = controller.snapshot().await.unwrap();
+
+    let scene = Scene::new("ambush")
+        .volume(Volume::from_percent(10))
+        .sound("missing");
+
+    assert!(matches!(
+        scene.activate(&controller).await,
+        Err(KenkuError::NotFound(_))
+    ));
+    assert_eq!(
+        controller.snapshot().await.unwrap().playlist.volume,
+        before.playlist.volume
+    );
+}
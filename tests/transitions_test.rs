@@ -0,0 +1,91 @@
+//! Scene transitions, checked against the playback the remote reports afterwards.
+#![cfg(feature = "transitions")]
+
+mod common;
+
+use kenku_control::{
+    scenes::Scene,
+    transitions::{Transition, TransitionEngine, TransitionOutcome},
+    Volume,
+};
+use std::time::Duration;
+
+#[tokio::test]
+async fn transitions_swap_the_sounds_of_scenes() {
+    let remote = common::remote().await;
+    let controller = remote.controller();
+    let playlists = controller
+        .get_playlist()
+        .await
+        .expect("failed to get kenku playlists");
+    let soundboard = controller
+        .get_soundboard()
+        .await
+        .expect("failed to get kenku soundboards");
+    let (fire, drums) = (
+        soundboard.sounds[0].id.clone(),
+        soundboard.sounds[1].id.clone(),
+    );
+
+    let tavern = Scene::new("tavern")
+        .track(playlists.tracks[0].id.clone())
+        .volume(Volume::from_percent(60))
+        .sound(fire.clone());
+    let ambush = Scene::new("ambush")
+        .track(playlists.tracks[1].id.clone())
+        .sound(drums.clone());
+
+    let engine = TransitionEngine::new(&controller);
+    let fade = Transition::fade(Duration::from_millis(100)).overlap(Duration::from_millis(50));
+
+    let outcome = engine.transition_to(tavern, fade).wait().await.unwrap();
+    assert_eq!(outcome, TransitionOutcome::Completed);
+
+    let outcome = engine.transition_to(ambush, fade).wait().await.unwrap();
+    assert_eq!(outcome, TransitionOutcome::Completed);
+
+    let playback = controller.snapshot().await.unwrap();
+    assert_eq!(engine.current().unwrap().name, "ambush");
+    assert_eq!(playback.playlist.volume_level(), Volume::from_percent(60));
+    assert!(playback.soundboard.is_playing(&drums));
+    assert!(!playback.soundboard.is_playing(&fire));
+
+    controller.stop_sound_id(&drums).await.unwrap();
+}
+
+#[tokio::test]
+async fn a_new_transition_interrupts_the_running_one() {
+    let remote = common::remote().await;
+    let controller = remote.controller();
+    let soundboard = controller
+        .get_soundboard()
+        .await
+        .expect("failed to get kenku soundboards");
+    let (fire, drums) = (
+        soundboard.sounds[0].id.clone(),
+        soundboard.sounds[1].id.clone(),
+    );
+
+    let engine = TransitionEngine::new(&controller);
+    let slow = engine.transition_to(
+        Scene::new("tavern").sound(fire.clone()),
+        Transition::fade(Duration::from_secs(5)),
+    );
+    let quick = engine.transition_to(
+        Scene::new("ambush")
+            .volume(Volume::from_percent(50))
+            .sound(drums.clone()),
+        Transition::CUT,
+    );
+
+    assert_eq!(slow.wait().await.unwrap(), TransitionOutcome::Interrupted);
+    assert_eq!(quick.wait().await.unwrap(), TransitionOutcome::Completed);
+    assert!(!engine.is_running());
+
+    let playback = controller.snapshot().await.unwrap();
+    assert_eq!(playback.playlist.volume_level(), Volume::from_percent(50));
+    assert!(playback.soundboard.is_playing(&drums));
+    assert!(!playback.soundboard.is_playing(&fire));
+
+    controller.stop_sound_id(&drums).await.unwrap();
+}
@@ -0,0 +1,123 @@
+//! Webhooks, posted to a local receiver from events of the mock remote.
+#![cfg(all(feature = "webhooks", not(feature = "live-tests")))]
+
+mod common;
+
+use futures_util::StreamExt;
+use kenku_control::{events::KenkuEvent, webhooks::Webhooks, ControllerBuilder};
+use reqwest::header::{HeaderValue, AUTHORIZATION};
+use serde_json::Value;
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
+
+/// The head and JSON body of every request a receiver got.
+type Received = Arc<Mutex<Vec<(String, Value)>>>;
+
+/// Answers every request with `200 OK` after `delay`, and keeps the requests.
+async fn receiver(delay: Duration) -> (String, Received) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+    let bodies = Arc::new(Mutex::new(Vec::new()));
+    let received = bodies.clone();
+
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let mut request = Vec::new();
+            let mut buffer = [0; 4096];
+
+            let (head, body) = loop {
+                let read = stream.read(&mut buffer).await.unwrap();
+                request.extend_from_slice(&buffer[..read]);
+                let text = String::from_utf8_lossy(&request);
+
+                if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                    let length = head
+                        .lines()
+                        .find_map(|line| {
+                            line.to_lowercase()
+                                .strip_prefix("content-length: ")
+                                .map(str::to_string)
+                        })
+                        .and_then(|length| length.parse::<usize>().ok())
+                        .unwrap_or(0);
+
+                    if body.len() >= length {
+                        break (head.to_string(), body.to_string());
+                    }
+                }
+            };
+
+            received
+                .lock()
+                .unwrap()
+                .push((head, serde_json::from_str(&body).unwrap()));
+            tokio::time::sleep(delay).await;
+            let _ = stream
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\nconnection: close\r\n\r\n")
+                .await;
+        }
+    });
+
+    (url, bodies)
+}
+
+#[tokio::test]
+async fn events_are_posted_to_the_hooks_that_want_them() {
+    let remote = common::remote().await;
+    let controller = remote.controller();
+    let (sounds, sound_bodies) = receiver(Duration::ZERO).await;
+    let (tracks, track_bodies) = receiver(Duration::ZERO).await;
+    let hooks = Webhooks::new()
+        .on(&sounds, ["sound_started"])
+        .on(&tracks, ["track_changed"]);
+
+    let deliveries = controller.webhooks(hooks, Duration::from_millis(20));
+    let mut deliveries = std::pin::pin!(deliveries);
+
+    let player = controller.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        player.play_sound_id("s1").await.unwrap();
+    });
+
+    let delivery = tokio::time::timeout(Duration::from_secs(5), deliveries.next())
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(delivery.url, sounds);
+    assert_eq!(delivery.event, "sound_started");
+    assert!(delivery.result.is_ok());
+    assert_eq!(sound_bodies.lock().unwrap()[0].1["sound"]["id"], "s1");
+    assert!(track_bodies.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn webhooks_get_neither_the_kenku_headers_nor_its_timeout() {
+    let remote = common::remote().await;
+    let controller = ControllerBuilder::from_address(remote.address.clone())
+        .header(
+            AUTHORIZATION,
+            HeaderValue::from_static("Bearer kenku-secret"),
+        )
+        .build()
+        .unwrap();
+    // Slower than the 100 ms the controller gives the Kenku Remote.
+    let (url, received) = receiver(Duration::from_millis(300)).await;
+
+    let deliveries = controller
+        .deliver(&Webhooks::new().all(&url), &KenkuEvent::PlaybackPaused)
+        .await;
+
+    assert!(deliveries[0].result.is_ok(), "{:?}", deliveries[0].result);
+    let (head, body) = received.lock().unwrap()[0].clone();
+    assert_eq!(body["type"], "playback_paused");
+    assert!(!head.to_lowercase().contains("authorization"), "{}", head);
+    assert!(!head.contains("kenku-secret"), "{}", head);
+}
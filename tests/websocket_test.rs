@@ -0,0 +1,239 @@
+//! The WebSocket server, driven by a client against the mock remote.
+#![cfg(all(feature = "websocket", not(feature = "live-tests")))]
+
+mod common;
+
+use futures_util::{SinkExt, StreamExt};
+use kenku_control::{
+    auth::BearerTokens, clients::ClientTracker, journal::Journal, websocket::WebSocketServer,
+    KenkuError,
+};
+use serde_json::{json, Value};
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio_tungstenite::{
+    connect_async,
+    tungstenite::{protocol::frame::coding::CloseCode, Message},
+    MaybeTlsStream, WebSocketStream,
+};
+
+type Client = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Returns the next text message, as JSON.
+async fn receive(socket: &mut Client) -> Value {
+    loop {
+        let message = tokio::time::timeout(Duration::from_secs(5), socket.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+
+        if let Message::Text(text) = message {
+            return serde_json::from_str(&text).unwrap();
+        }
+    }
+}
+
+#[tokio::test]
+async fn clients_get_events_and_send_commands() {
+    let remote = common::remote().await;
+    let controller = remote.controller();
+    let server = WebSocketServer::start("127.0.0.1:0", &controller, Duration::from_millis(20))
+        .await
+        .unwrap();
+
+    let (mut socket, _) = connect_async(format!("ws://{}", server.local_addr()))
+        .await
+        .unwrap();
+
+    let hello = receive(&mut socket).await;
+    assert_eq!(hello["type"], json!("snapshot"));
+    assert_eq!(hello["playback"]["soundboard"]["sounds"], json!([]));
+
+    socket
+        .send(Message::text(json!({"PlaySound": "s1"}).to_string()))
+        .await
+        .unwrap();
+
+    let mut messages = Vec::new();
+    while !messages
+        .iter()
+        .any(|message: &Value| message["type"] == json!("sound_started"))
+    {
+        messages.push(receive(&mut socket).await);
+    }
+
+    assert!(messages.contains(&json!({"type": "result", "ok": true})));
+    assert_eq!(remote.mock().playing_sounds(), ["s1"]);
+}
+
+#[tokio::test]
+async fn subscribers_only_get_the_events_they_asked_for() {
+    let remote = common::remote().await;
+    let controller = remote.controller();
+    let server = WebSocketServer::start("127.0.0.1:0", &controller, Duration::from_millis(20))
+        .await
+        .unwrap();
+
+    let (mut socket, _) = connect_async(format!("ws://{}", server.local_addr()))
+        .await
+        .unwrap();
+    receive(&mut socket).await;
+
+    for message in [
+        json!({"subscribe": {"types": ["sound_started"], "board": "Dungeon"}}),
+        json!({"subscribe": {"types": ["sound_started"], "board": "Weather"}}),
+        json!({"Playback": {"PlaylistPlaybackVolume": 0.3}}),
+    ] {
+        socket
+            .send(Message::text(message.to_string()))
+            .await
+            .unwrap();
+    }
+
+    assert_eq!(receive(&mut socket).await["ok"], json!(false));
+    assert_eq!(
+        receive(&mut socket).await,
+        json!({"type": "result", "ok": true})
+    );
+    assert_eq!(
+        receive(&mut socket).await,
+        json!({"type": "result", "ok": true})
+    );
+
+    // Leave time for the volume change to be polled, then start a sound of the board.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    socket
+        .send(Message::text(json!({"PlaySound": "s2"}).to_string()))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        receive(&mut socket).await,
+        json!({"type": "result", "ok": true})
+    );
+    let event = receive(&mut socket).await;
+    assert_eq!(event["type"], json!("sound_started"));
+    assert_eq!(event["sound"]["id"], json!("s2"));
+}
+
+#[tokio::test]
+async fn reconnecting_clients_replay_the_journal_since_their_last_event() {
+    let remote = common::remote().await;
+    let path = std::env::temp_dir().join(format!(
+        "kenku_control_websocket_journal_{}.jsonl",
+        remote.address.port
+    ));
+    let _ = std::fs::remove_file(&path);
+    let mut journal = Journal::open(&path, 100).unwrap();
+    journal.append(json!({"type": "playback_paused"})).unwrap();
+
+    let server = WebSocketServer::start_with_journal(
+        "127.0.0.1:0",
+        &remote.controller(),
+        Duration::from_millis(20),
+        BearerTokens::new().admin("gm-token", "gm"),
+        &ClientTracker::new(),
+        journal,
+    )
+    .await
+    .unwrap();
+    let url = format!("ws://{}/?access_token=gm-token", server.local_addr());
+
+    let (mut socket, _) = connect_async(&url).await.unwrap();
+    receive(&mut socket).await;
+    socket
+        .send(Message::text(json!({"PlaySound": "s1"}).to_string()))
+        .await
+        .unwrap();
+
+    let started = loop {
+        let message = receive(&mut socket).await;
+
+        if message["type"] == json!("sound_started") {
+            break message;
+        }
+    };
+    let id = started["id"].as_u64().unwrap();
+    assert!(id > 1);
+    drop(socket);
+
+    let (mut socket, _) = connect_async(&url).await.unwrap();
+    receive(&mut socket).await;
+
+    for since in [id - 1, 0] {
+        socket
+            .send(Message::text(json!({ "since": since }).to_string()))
+            .await
+            .unwrap();
+    }
+
+    let replay = receive(&mut socket).await;
+    assert_eq!(replay["type"], json!("replay"));
+    assert_eq!(replay["events"][0], started);
+
+    let replay = receive(&mut socket).await;
+    assert_eq!(
+        replay["events"][0],
+        json!({"type": "playback_paused", "id": 1})
+    );
+    assert_eq!(replay["events"][id as usize - 1], started);
+
+    drop(server);
+    let reopened: Journal<Value> = Journal::open(&path, 100).unwrap();
+    assert_eq!(reopened.last_id(), id);
+    let _ = std::fs::remove_file(path);
+}
+
+#[tokio::test]
+async fn clients_without_a_token_are_closed_with_a_policy_violation() {
+    let remote = common::remote().await;
+    let server = WebSocketServer::start_with_auth(
+        "127.0.0.1:0",
+        &remote.controller(),
+        Duration::from_millis(20),
+        BearerTokens::new().token("overlay-token", "overlay"),
+    )
+    .await
+    .unwrap();
+
+    let (mut stranger, _) = connect_async(format!("ws://{}", server.local_addr()))
+        .await
+        .unwrap();
+    let closed = tokio::time::timeout(Duration::from_secs(5), stranger.next())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    assert!(matches!(
+        closed,
+        Message::Close(Some(frame)) if frame.code == CloseCode::Policy
+    ));
+
+    let (mut overlay, _) = connect_async(format!(
+        "ws://{}/?access_token=overlay-token",
+        server.local_addr()
+    ))
+    .await
+    .unwrap();
+    assert_eq!(receive(&mut overlay).await["type"], json!("snapshot"));
+}
+
+#[tokio::test]
+async fn servers_without_auth_only_listen_on_loopback() {
+    let remote = common::remote().await;
+    let controller = remote.controller();
+    let poll_interval = Duration::from_millis(20);
+
+    let open = WebSocketServer::start("0.0.0.0:0", &controller, poll_interval).await;
+    assert!(matches!(open, Err(KenkuError::InvalidInput(_))));
+
+    let guarded = WebSocketServer::start_with_auth(
+        "0.0.0.0:0",
+        &controller,
+        poll_interval,
+        BearerTokens::new().token("overlay-token", "overlay"),
+    )
+    .await;
+    assert!(guarded.is_ok());
+}